@@ -0,0 +1,246 @@
+use crate::{MonitorError, Result, monitor::PhysicalMonitor, selector::MonitorSelector};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+};
+
+type Job<M> = Box<dyn FnOnce(&M) + Send>;
+
+enum Message<M> {
+    Call(Job<M>),
+    Shutdown,
+}
+
+/// One monitor owned by a dedicated worker thread.
+///
+/// The worker opens the monitor, runs submitted calls one at a time, and releases the physical
+/// monitor handle when it exits. Callers on any thread reach it through cloned
+/// [`MonitorActorHandle`]s, so DDC/CI traffic to the monitor is never interleaved.
+///
+/// Dropping the actor stops the worker and waits for it: the call in progress finishes, calls
+/// still queued are rejected with [`MonitorError::Shutdown`], and so is any later call made
+/// through a handle.
+pub struct MonitorActor<M: 'static = PhysicalMonitor> {
+    handle: MonitorActorHandle<M>,
+    worker: Option<JoinHandle<()>>,
+    /// Set on drop, so calls already queued ahead of the shutdown message are rejected too
+    stopping: Arc<AtomicBool>,
+}
+
+/// Cheap, cloneable sender of calls to a [`MonitorActor`]
+pub struct MonitorActorHandle<M: 'static = PhysicalMonitor> {
+    sender: Sender<Message<M>>,
+}
+
+// Derived `Clone` would require `M: Clone`
+impl<M> Clone for MonitorActorHandle<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl MonitorActor {
+    /// Open the monitor chosen by `selector` on a new worker thread
+    pub fn spawn(selector: MonitorSelector) -> Result<Self> {
+        Self::spawn_with(move || selector.resolve())
+    }
+}
+
+impl<M: 'static> MonitorActor<M> {
+    /// Run `open` on a new worker thread and serve calls against the monitor it returns
+    pub(crate) fn spawn_with(open: impl FnOnce() -> Result<M> + Send + 'static) -> Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let stopping = Arc::new(AtomicBool::new(false));
+        let worker_stopping = Arc::clone(&stopping);
+
+        let worker = thread::Builder::new()
+            .name("monitor-actor".to_string())
+            .spawn(move || {
+                let mon = match open() {
+                    Ok(mon) => {
+                        let _ = ready_sender.send(Ok(()));
+                        mon
+                    }
+                    Err(e) => {
+                        let _ = ready_sender.send(Err(e));
+                        return;
+                    }
+                };
+                run(&mon, receiver, &worker_stopping);
+                // `mon` is dropped here, on the thread that owns it, releasing the handle
+            })?;
+
+        match ready_receiver.recv() {
+            Ok(Ok(())) => Ok(Self {
+                handle: MonitorActorHandle { sender },
+                worker: Some(worker),
+                stopping,
+            }),
+            Ok(Err(e)) => {
+                let _ = worker.join();
+                Err(e)
+            }
+            // The worker panicked before reporting back
+            Err(_) => {
+                let _ = worker.join();
+                Err(MonitorError::Shutdown)
+            }
+        }
+    }
+
+    /// A handle for submitting calls from other threads
+    pub fn handle(&self) -> MonitorActorHandle<M> {
+        self.handle.clone()
+    }
+
+    /// Run `f` on the worker thread and wait for its result
+    pub fn call<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&M) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        self.handle.call(f)
+    }
+}
+
+impl<M> MonitorActorHandle<M> {
+    /// Run `f` on the worker thread and wait for its result. Fails with
+    /// [`MonitorError::Shutdown`] if the actor is dropped before `f` gets to run.
+    pub fn call<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&M) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let (reply_sender, reply_receiver) = mpsc::channel();
+        let job: Job<M> = Box::new(move |mon| {
+            let _ = reply_sender.send(f(mon));
+        });
+
+        self.sender
+            .send(Message::Call(job))
+            .map_err(|_| MonitorError::Shutdown)?;
+        // A job dropped unrun (queued at shutdown, or panicked) drops its reply sender
+        reply_receiver.recv().map_err(|_| MonitorError::Shutdown)?
+    }
+}
+
+impl<M> Drop for MonitorActor<M> {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        // Wakes the worker if it is waiting for a call
+        let _ = self.handle.sender.send(Message::Shutdown);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run<M>(mon: &M, receiver: Receiver<Message<M>>, stopping: &AtomicBool) {
+    // Handles keep the channel open, so shutdown is an explicit message rather than a close
+    while let Ok(message) = receiver.recv() {
+        match message {
+            Message::Call(job) if !stopping.load(Ordering::SeqCst) => job(mon),
+            Message::Call(_) | Message::Shutdown => break,
+        }
+    }
+
+    // Reject everything still queued by dropping it unrun. Calls sent after the receiver is gone
+    // fail to send instead.
+    drop(receiver);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Stand-in for a physical monitor that records when its handle is released
+    struct FakeMonitor {
+        released: Arc<AtomicBool>,
+    }
+
+    impl Drop for FakeMonitor {
+        fn drop(&mut self) {
+            self.released.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn spawn_fake() -> (MonitorActor<FakeMonitor>, Arc<AtomicBool>) {
+        let released = Arc::new(AtomicBool::new(false));
+        let monitor_released = Arc::clone(&released);
+        let actor = MonitorActor::spawn_with(move || {
+            Ok(FakeMonitor {
+                released: monitor_released,
+            })
+        })
+        .unwrap();
+        (actor, released)
+    }
+
+    #[test]
+    fn calls_run_on_the_worker() {
+        let (actor, _) = spawn_fake();
+        let worker = actor.call(|_| Ok(thread::current().name().map(str::to_string)));
+        assert_eq!(worker.unwrap().as_deref(), Some("monitor-actor"));
+        assert!(matches!(
+            actor
+                .handle()
+                .call(|_| Err::<(), _>(MonitorError::VcpNotSupported)),
+            Err(MonitorError::VcpNotSupported)
+        ));
+    }
+
+    #[test]
+    fn failing_to_open_is_reported_by_spawn() {
+        let result = MonitorActor::<FakeMonitor>::spawn_with(|| {
+            Err(MonitorError::MonitorNotFound("DISPLAY9".to_string()))
+        });
+        assert!(matches!(result, Err(MonitorError::MonitorNotFound(_))));
+    }
+
+    #[test]
+    fn dropping_the_actor_releases_the_monitor_and_rejects_pending_calls() {
+        let (actor, released) = spawn_fake();
+        let handle = actor.handle();
+
+        // Keep the worker busy so the next call stays queued
+        let (unblock, blocked) = mpsc::channel::<()>();
+        let busy = {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                handle.call(move |_| {
+                    blocked.recv().unwrap();
+                    Ok(1)
+                })
+            })
+        };
+        let queued = {
+            let handle = handle.clone();
+            thread::spawn(move || handle.call(|_| Ok(2)))
+        };
+        thread::sleep(Duration::from_millis(50));
+
+        let dropper = thread::spawn(move || drop(actor));
+        thread::sleep(Duration::from_millis(50));
+        assert!(!released.load(Ordering::SeqCst));
+        unblock.send(()).unwrap();
+        dropper.join().unwrap();
+
+        assert!(released.load(Ordering::SeqCst));
+        // The call in progress finishes; the queued one and any later one are rejected
+        assert_eq!(busy.join().unwrap().unwrap(), 1);
+        assert!(matches!(
+            queued.join().unwrap(),
+            Err(MonitorError::Shutdown)
+        ));
+        assert!(matches!(
+            handle.call(|_| Ok(3)),
+            Err(MonitorError::Shutdown)
+        ));
+    }
+}
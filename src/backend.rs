@@ -0,0 +1,657 @@
+use crate::{
+    MonitorError, Result,
+    error::{is_channel_busy_error, is_vcp_unsupported_error},
+    native,
+    vcp::{VcpCodeType, VcpFeatureResponse, check_capabilities_length, table_unsupported},
+};
+use std::{thread, time::Duration};
+use windows_sys::Win32::Foundation::HANDLE;
+
+/// Transport for the VCP traffic of one monitor.
+///
+/// [`VcpMonitor`](crate::vcp::VcpMonitor) reaches the hardware only through this trait. The real
+/// implementation uses the Windows Monitor Configuration API (dxva2); with the `testing` feature,
+/// [`MockBackend`] stands in for a monitor in tests.
+pub trait Backend {
+    fn get_vcp_feature(&self, vcp_code: u8) -> Result<VcpFeatureResponse>;
+    fn set_vcp_feature(&self, vcp_code: u8, value: u32) -> Result<()>;
+    /// Raw MCCS capabilities string
+    fn capabilities(&self) -> Result<String>;
+    fn save_settings(&self) -> Result<()>;
+    fn restore_factory_defaults(&self) -> Result<()>;
+    fn restore_factory_color_defaults(&self) -> Result<()>;
+
+    // dxva2 only exposes single-value VCP get/set and has no Table Read/Table Write entry points,
+    // so by default table transfers can't reach the monitor.
+
+    /// Read one fragment of at most [`TABLE_FRAGMENT_SIZE`](crate::vcp::TABLE_FRAGMENT_SIZE)
+    /// bytes from a table-type code
+    fn read_table_fragment(&self, vcp_code: u8, _offset: u16) -> Result<Vec<u8>> {
+        Err(table_unsupported(vcp_code))
+    }
+
+    /// Write one fragment of at most [`TABLE_FRAGMENT_SIZE`](crate::vcp::TABLE_FRAGMENT_SIZE)
+    /// bytes to a table-type code
+    fn write_table_fragment(&self, vcp_code: u8, _offset: u16, _fragment: &[u8]) -> Result<()> {
+        Err(table_unsupported(vcp_code))
+    }
+}
+
+/// How often a dxva2 call that fails the way a contended I2C bus does is tried, and how long to
+/// pause in between so another application's transaction can finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in all, including the first; 0 is treated as 1
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Attempts when nothing is configured
+    pub const DEFAULT_ATTEMPTS: u32 = 3;
+
+    /// Pause between attempts when nothing is configured
+    pub const DEFAULT_DELAY: Duration = Duration::from_millis(50);
+
+    /// Try each call once, e.g. to measure how often single transfers fail
+    pub const ONCE: Self = Self {
+        attempts: 1,
+        delay: Duration::ZERO,
+    };
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: Self::DEFAULT_ATTEMPTS,
+            delay: Self::DEFAULT_DELAY,
+        }
+    }
+}
+
+/// Run `call`, retrying as `policy` says while it fails with one of the errors another
+/// application using the DDC/CI channel causes. If every attempt fails that way, the error
+/// becomes [`MonitorError::ChannelBusy`]; other results are returned as they are.
+fn retry_while_busy<T>(policy: RetryPolicy, mut call: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match call() {
+            Err(MonitorError::Win32 { function, code }) if is_channel_busy_error(code) => {
+                if attempt >= policy.attempts {
+                    return Err(MonitorError::ChannelBusy(function));
+                }
+                attempt += 1;
+                thread::sleep(policy.delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Backend for a physical monitor handle, using dxva2
+pub(crate) struct NativeBackend {
+    handle: HANDLE,
+    retry: RetryPolicy,
+}
+
+impl NativeBackend {
+    pub(crate) fn new(handle: HANDLE, retry: RetryPolicy) -> Self {
+        Self { handle, retry }
+    }
+}
+
+impl Backend for NativeBackend {
+    fn get_vcp_feature(&self, vcp_code: u8) -> Result<VcpFeatureResponse> {
+        let result = retry_while_busy(self.retry, || unsafe {
+            let mut code_type = 0u32;
+            let mut current_value = 0u32;
+            let mut maximum_value = 0u32;
+
+            let result = native::dxva2::GetVCPFeatureAndVCPFeatureReply(
+                self.handle,
+                vcp_code,
+                &mut code_type,
+                &mut current_value,
+                &mut maximum_value,
+            );
+
+            if result == 0 {
+                return Err(native::last_error("GetVCPFeatureAndVCPFeatureReply"));
+            }
+
+            Ok(VcpFeatureResponse {
+                vcp_code,
+                current_value,
+                maximum_value,
+                code_type: if code_type == 0 {
+                    VcpCodeType::SetParameter
+                } else {
+                    VcpCodeType::Momentary
+                },
+                reported_value: None,
+            })
+        });
+
+        match result {
+            Err(error) if error.os_code().is_some_and(is_vcp_unsupported_error) => {
+                Err(MonitorError::VcpNotSupported)
+            }
+            result => result,
+        }
+    }
+
+    fn set_vcp_feature(&self, vcp_code: u8, value: u32) -> Result<()> {
+        retry_while_busy(self.retry, || unsafe {
+            let result = native::dxva2::SetVCPFeature(self.handle, vcp_code, value);
+
+            if result == 0 {
+                return Err(native::last_error("SetVCPFeature"));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn capabilities(&self) -> Result<String> {
+        retry_while_busy(self.retry, || unsafe {
+            let mut length = 0u32;
+            let result = native::dxva2::GetCapabilitiesStringLength(self.handle, &mut length);
+
+            if result == 0 {
+                return Err(native::last_error("GetCapabilitiesStringLength"));
+            }
+
+            let mut buffer = vec![0u8; check_capabilities_length(length)?];
+            let result = native::dxva2::CapabilitiesRequestAndCapabilitiesReply(
+                self.handle,
+                buffer.as_mut_ptr(),
+                length,
+            );
+
+            if result == 0 {
+                return Err(native::last_error(
+                    "CapabilitiesRequestAndCapabilitiesReply",
+                ));
+            }
+
+            // Remove null terminators and convert to String
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            Ok(String::from_utf8_lossy(&buffer[..end]).to_string())
+        })
+    }
+
+    fn save_settings(&self) -> Result<()> {
+        retry_while_busy(self.retry, || unsafe {
+            let result = native::dxva2::SaveCurrentMonitorSettings(self.handle);
+
+            if result == 0 {
+                return Err(native::last_error("SaveCurrentMonitorSettings"));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn restore_factory_defaults(&self) -> Result<()> {
+        retry_while_busy(self.retry, || unsafe {
+            let result = native::dxva2::RestoreMonitorFactoryDefaults(self.handle);
+
+            if result == 0 {
+                return Err(native::last_error("RestoreMonitorFactoryDefaults"));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn restore_factory_color_defaults(&self) -> Result<()> {
+        retry_while_busy(self.retry, || unsafe {
+            let result = native::dxva2::RestoreMonitorFactoryColorDefaults(self.handle);
+
+            if result == 0 {
+                return Err(native::last_error("RestoreMonitorFactoryColorDefaults"));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+pub use mock::MockBackend;
+
+#[cfg(any(test, feature = "testing"))]
+mod mock {
+    use super::Backend;
+    use crate::{
+        MonitorError, Result,
+        vcp::{TABLE_FRAGMENT_SIZE, VcpCodeType, VcpFeatureResponse, table_unsupported},
+    };
+    use std::{
+        cell::RefCell,
+        collections::BTreeMap,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    #[derive(Debug, Default)]
+    struct State {
+        features: BTreeMap<u8, VcpFeatureResponse>,
+        capabilities: Option<String>,
+        ignore_writes: bool,
+        writes: Vec<(u8, u32)>,
+        /// Win32 error code that reads and writes of a code fail with
+        failures: BTreeMap<u8, u32>,
+        tables: BTreeMap<u8, Vec<u8>>,
+        table_writes: Vec<(u8, u16, usize)>,
+        /// Fail table fragments the way the dxva2 backend does
+        no_table_transfers: bool,
+        /// How long a read of a code takes to answer
+        read_delays: BTreeMap<u8, Duration>,
+        reads: Vec<u8>,
+        settle_time: Duration,
+        /// Value each code reported before its last write, and when that write happened
+        settling: BTreeMap<u8, (u32, Instant)>,
+    }
+
+    /// In-memory monitor for tests. **Only available with the `testing` feature; not for
+    /// production use.**
+    ///
+    /// Clones share state, so keep a clone to inspect the monitor after handing one to
+    /// [`VcpMonitor::with_backend`](crate::vcp::VcpMonitor::with_backend):
+    ///
+    /// ```
+    /// use monitorconfig::{MockBackend, vcp::{VcpMonitor, codes}};
+    ///
+    /// let mock = MockBackend::new().with_feature(codes::BRIGHTNESS, 40, 100);
+    /// let monitor = VcpMonitor::with_backend(mock.clone());
+    ///
+    /// monitor.set_vcp_feature(codes::BRIGHTNESS, 75).unwrap();
+    /// assert_eq!(mock.value(codes::BRIGHTNESS), Some(75));
+    /// assert!(monitor.get_vcp_feature(codes::CONTRAST).is_err());
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct MockBackend {
+        state: Rc<RefCell<State>>,
+    }
+
+    impl MockBackend {
+        /// A monitor that supports no VCP codes
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Support `code` as a settable value with the given current and maximum value
+        pub fn with_feature(self, code: u8, current: u32, maximum: u32) -> Self {
+            self.insert(code, current, maximum, VcpCodeType::SetParameter)
+        }
+
+        /// Support `code` as a momentary action (e.g. 0x04 Restore Factory Defaults)
+        pub fn with_action(self, code: u8) -> Self {
+            self.insert(code, 0, 1, VcpCodeType::Momentary)
+        }
+
+        pub fn with_capabilities(self, capabilities: &str) -> Self {
+            self.state.borrow_mut().capabilities = Some(capabilities.to_string());
+            self
+        }
+
+        /// Fail reads and writes of `code` with Win32 error `os_code`, like a monitor whose
+        /// DDC/CI channel errors out
+        pub fn failing_with(self, code: u8, os_code: u32) -> Self {
+            self.state.borrow_mut().failures.insert(code, os_code);
+            self
+        }
+
+        /// Support `code` as a table-type code holding `data`, readable and writable through
+        /// table fragments
+        pub fn with_table(self, code: u8, data: &[u8]) -> Self {
+            self.state.borrow_mut().tables.insert(code, data.to_vec());
+            self
+        }
+
+        /// Fail every table read and write as unsupported, like the dxva2 backend, which has no
+        /// table transfers
+        pub fn without_table_transfers(self) -> Self {
+            self.state.borrow_mut().no_table_transfers = true;
+            self
+        }
+
+        /// Take `delay` to answer each read of `code`, like a monitor that stalls on some codes
+        pub fn slow_to_read(self, code: u8, delay: Duration) -> Self {
+            self.state.borrow_mut().read_delays.insert(code, delay);
+            self
+        }
+
+        /// Keep reporting a code's previous value for `delay` after each write, like slow
+        /// monitors that take a while to apply a change
+        pub fn settling_after(self, delay: Duration) -> Self {
+            self.state.borrow_mut().settle_time = delay;
+            self
+        }
+
+        /// Acknowledge writes without changing any value, like monitors that silently ignore
+        /// features they don't implement
+        pub fn ignoring_writes(self) -> Self {
+            self.state.borrow_mut().ignore_writes = true;
+            self
+        }
+
+        /// Current value of `code`, if supported
+        pub fn value(&self, code: u8) -> Option<u32> {
+            self.state
+                .borrow()
+                .features
+                .get(&code)
+                .map(|f| f.current_value)
+        }
+
+        /// Every code read, in order, whether or not the read succeeded
+        pub fn reads(&self) -> Vec<u8> {
+            self.state.borrow().reads.clone()
+        }
+
+        /// Every accepted write, in order, including ones ignored by [`Self::ignoring_writes`]
+        pub fn writes(&self) -> Vec<(u8, u32)> {
+            self.state.borrow().writes.clone()
+        }
+
+        /// Contents of table-type `code`, if supported
+        pub fn table(&self, code: u8) -> Option<Vec<u8>> {
+            self.state.borrow().tables.get(&code).cloned()
+        }
+
+        /// Every table fragment written, in order, as `(code, offset, length)`
+        pub fn table_writes(&self) -> Vec<(u8, u16, usize)> {
+            self.state.borrow().table_writes.clone()
+        }
+
+        fn insert(self, code: u8, current: u32, maximum: u32, code_type: VcpCodeType) -> Self {
+            self.state.borrow_mut().features.insert(
+                code,
+                VcpFeatureResponse {
+                    vcp_code: code,
+                    current_value: current,
+                    maximum_value: maximum,
+                    code_type,
+                    reported_value: None,
+                },
+            );
+            self
+        }
+    }
+
+    impl Backend for MockBackend {
+        fn get_vcp_feature(&self, vcp_code: u8) -> Result<VcpFeatureResponse> {
+            self.state.borrow_mut().reads.push(vcp_code);
+            let state = self.state.borrow();
+            if let Some(&delay) = state.read_delays.get(&vcp_code) {
+                std::thread::sleep(delay);
+            }
+            if let Some(&code) = state.failures.get(&vcp_code) {
+                return Err(MonitorError::Win32 {
+                    function: "GetVCPFeatureAndVCPFeatureReply",
+                    code,
+                });
+            }
+            let mut response = state
+                .features
+                .get(&vcp_code)
+                .cloned()
+                .ok_or(MonitorError::VcpNotSupported)?;
+            if let Some(&(previous, written)) = state.settling.get(&vcp_code)
+                && written.elapsed() < state.settle_time
+            {
+                response.current_value = previous;
+            }
+            Ok(response)
+        }
+
+        fn set_vcp_feature(&self, vcp_code: u8, value: u32) -> Result<()> {
+            let mut state = self.state.borrow_mut();
+            if let Some(&code) = state.failures.get(&vcp_code) {
+                return Err(MonitorError::Win32 {
+                    function: "SetVCPFeature",
+                    code,
+                });
+            }
+            let ignore_writes = state.ignore_writes;
+            let feature = state
+                .features
+                .get_mut(&vcp_code)
+                .ok_or(MonitorError::VcpNotSupported)?;
+            let previous = feature.current_value;
+            if !ignore_writes && feature.code_type == VcpCodeType::SetParameter {
+                feature.current_value = value;
+            }
+            state.settling.insert(vcp_code, (previous, Instant::now()));
+            state.writes.push((vcp_code, value));
+            Ok(())
+        }
+
+        fn capabilities(&self) -> Result<String> {
+            self.state
+                .borrow()
+                .capabilities
+                .clone()
+                .ok_or_else(|| MonitorError::UnsupportedOperation("no capabilities".to_string()))
+        }
+
+        fn save_settings(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn restore_factory_defaults(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn restore_factory_color_defaults(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_table_fragment(&self, vcp_code: u8, offset: u16) -> Result<Vec<u8>> {
+            let state = self.state.borrow();
+            if state.no_table_transfers {
+                return Err(table_unsupported(vcp_code));
+            }
+            let table = state
+                .tables
+                .get(&vcp_code)
+                .ok_or(MonitorError::VcpNotSupported)?;
+            let start = (offset as usize).min(table.len());
+            let end = (start + TABLE_FRAGMENT_SIZE).min(table.len());
+            Ok(table[start..end].to_vec())
+        }
+
+        fn write_table_fragment(&self, vcp_code: u8, offset: u16, fragment: &[u8]) -> Result<()> {
+            if self.state.borrow().no_table_transfers {
+                return Err(table_unsupported(vcp_code));
+            }
+            if fragment.len() > TABLE_FRAGMENT_SIZE {
+                return Err(MonitorError::InvalidValue(format!(
+                    "table fragment of {} bytes exceeds {}",
+                    fragment.len(),
+                    TABLE_FRAGMENT_SIZE
+                )));
+            }
+            let mut state = self.state.borrow_mut();
+            let table = state
+                .tables
+                .get_mut(&vcp_code)
+                .ok_or(MonitorError::VcpNotSupported)?;
+            let start = offset as usize;
+            if table.len() < start + fragment.len() {
+                table.resize(start + fragment.len(), 0);
+            }
+            table[start..start + fragment.len()].copy_from_slice(fragment);
+            state.table_writes.push((vcp_code, offset, fragment.len()));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcp::{VcpMonitor, codes};
+    use windows_sys::Win32::Foundation::{
+        ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH, ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA,
+    };
+
+    #[test]
+    fn mock_rejects_unsupported_codes() {
+        let mock = MockBackend::new().with_feature(codes::BRIGHTNESS, 40, 100);
+
+        assert!(matches!(
+            mock.get_vcp_feature(codes::CONTRAST),
+            Err(MonitorError::VcpNotSupported)
+        ));
+        assert!(matches!(
+            mock.set_vcp_feature(codes::CONTRAST, 50),
+            Err(MonitorError::VcpNotSupported)
+        ));
+        assert!(mock.writes().is_empty());
+        assert!(mock.capabilities().is_err());
+    }
+
+    #[test]
+    fn mock_applies_and_logs_writes() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 40, 100)
+            .with_feature(codes::CONTRAST, 50, 100)
+            .with_action(codes::RESTORE_LUMINANCE_CONTRAST);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        monitor.set_vcp_feature(codes::BRIGHTNESS, 75).unwrap();
+        monitor.set_vcp_feature(codes::CONTRAST, 20).unwrap();
+        monitor
+            .set_vcp_feature(codes::RESTORE_LUMINANCE_CONTRAST, 1)
+            .unwrap();
+
+        assert_eq!(mock.value(codes::BRIGHTNESS), Some(75));
+        assert_eq!(mock.value(codes::CONTRAST), Some(20));
+        // Momentary codes trigger an action; they don't store the written value
+        assert_eq!(mock.value(codes::RESTORE_LUMINANCE_CONTRAST), Some(0));
+        assert_eq!(
+            mock.writes(),
+            vec![
+                (codes::BRIGHTNESS, 75),
+                (codes::CONTRAST, 20),
+                (codes::RESTORE_LUMINANCE_CONTRAST, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn mock_ignoring_writes_keeps_values_but_logs_them() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 40, 100)
+            .ignoring_writes();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(!monitor.set_and_verify(codes::BRIGHTNESS, 75).unwrap());
+        assert_eq!(mock.value(codes::BRIGHTNESS), Some(40));
+        assert_eq!(mock.writes(), vec![(codes::BRIGHTNESS, 75)]);
+    }
+
+    #[test]
+    fn mock_returns_capabilities() {
+        let mock = MockBackend::new().with_capabilities("(vcp(10 12))");
+        assert_eq!(mock.capabilities().unwrap(), "(vcp(10 12))");
+    }
+
+    #[test]
+    fn retry_while_busy_gives_up_with_channel_busy() {
+        let mut calls = 0;
+        let result: Result<()> = retry_while_busy(RetryPolicy::default(), || {
+            calls += 1;
+            Err(MonitorError::Win32 {
+                function: "SetVCPFeature",
+                code: ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA as u32,
+            })
+        });
+
+        assert!(matches!(
+            result,
+            Err(MonitorError::ChannelBusy("SetVCPFeature"))
+        ));
+        assert_eq!(calls, RetryPolicy::DEFAULT_ATTEMPTS);
+    }
+
+    #[test]
+    fn retry_while_busy_stops_at_first_success_or_other_error() {
+        let mut calls = 0;
+        let result = retry_while_busy(RetryPolicy::default(), || {
+            calls += 1;
+            if calls < 2 {
+                Err(MonitorError::Win32 {
+                    function: "SetVCPFeature",
+                    code: ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA as u32,
+                })
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 2);
+
+        let mut calls = 0;
+        let result: Result<()> = retry_while_busy(RetryPolicy::default(), || {
+            calls += 1;
+            Err(MonitorError::VcpNotSupported)
+        });
+        assert!(matches!(result, Err(MonitorError::VcpNotSupported)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn retry_while_busy_does_not_retry_null_replies() {
+        let mut calls = 0;
+        let result: Result<()> = retry_while_busy(RetryPolicy::default(), || {
+            calls += 1;
+            Err(MonitorError::Win32 {
+                function: "GetVCPFeatureAndVCPFeatureReply",
+                code: ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH as u32,
+            })
+        });
+        assert!(
+            result
+                .unwrap_err()
+                .os_code()
+                .is_some_and(is_vcp_unsupported_error)
+        );
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn retry_while_busy_follows_the_policy() {
+        let busy = || MonitorError::Win32 {
+            function: "SetVCPFeature",
+            code: ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA as u32,
+        };
+        let policy = RetryPolicy {
+            attempts: 5,
+            delay: Duration::from_millis(10),
+        };
+
+        let mut calls = 0;
+        let started = std::time::Instant::now();
+        let result: Result<()> = retry_while_busy(policy, || {
+            calls += 1;
+            Err(busy())
+        });
+        assert!(matches!(result, Err(MonitorError::ChannelBusy(_))));
+        assert_eq!(calls, 5);
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        for attempts in [0, 1] {
+            let mut calls = 0;
+            let policy = RetryPolicy { attempts, ..policy };
+            let result: Result<()> = retry_while_busy(policy, || {
+                calls += 1;
+                Err(busy())
+            });
+            assert!(matches!(result, Err(MonitorError::ChannelBusy(_))));
+            assert_eq!(calls, 1);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{
+    MonitorError, Result,
+    vcp::{self, codes},
+};
+use serde::{Deserialize, Serialize};
+
+/// A VCP code advertised in the capabilities string, with its allowed values if it is discrete
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VcpCapability {
+    pub code: u8,
+    pub values: Vec<u8>,
+}
+
+/// Parsed MCCS capabilities string
+///
+/// Example input:
+/// `(prot(monitor)type(lcd)model(U2723DE)cmds(01 02 03 0C E3 F3)vcp(10 12 14(05 08 0B) 60(0F 11))mccs_ver(2.1))`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub protocol: Option<String>,
+    pub display_type: Option<String>,
+    pub model: Option<String>,
+    pub commands: Vec<u8>,
+    pub vcp_codes: Vec<VcpCapability>,
+    pub mccs_version: Option<String>,
+}
+
+impl Capabilities {
+    pub fn vcp_code(&self, code: u8) -> Option<&VcpCapability> {
+        self.vcp_codes.iter().find(|c| c.code == code)
+    }
+
+    pub fn supports(&self, code: u8) -> bool {
+        self.vcp_code(code).is_some()
+    }
+
+    /// How many of the known VCP codes this monitor advertises, and which [`IMPORTANT_CODES`]
+    /// it lacks. Advertised codes missing from the known table, such as manufacturer codes,
+    /// don't count.
+    pub fn coverage(&self) -> Coverage {
+        let known = vcp::KNOWN_VCP_CODES.len();
+        let supported = vcp::KNOWN_VCP_CODES
+            .iter()
+            .filter(|info| self.supports(info.code))
+            .count();
+        let percentage = if known == 0 {
+            0.0
+        } else {
+            supported as f64 * 100.0 / known as f64
+        };
+
+        Coverage {
+            supported,
+            known,
+            percentage,
+            missing_important: IMPORTANT_CODES
+                .iter()
+                .filter(|(code, _)| !self.supports(*code))
+                .map(|&(_, name)| name)
+                .collect(),
+        }
+    }
+}
+
+/// Controls most tools and users rely on, with the names [`Coverage`] reports them by
+pub const IMPORTANT_CODES: &[(u8, &str)] = &[
+    (codes::BRIGHTNESS, "brightness"),
+    (codes::CONTRAST, "contrast"),
+    (codes::INPUT_SOURCE, "input"),
+    (codes::AUDIO_VOLUME, "volume"),
+    (codes::POWER_MODE, "power"),
+];
+
+/// Share of the known VCP codes a monitor advertises, see [`Capabilities::coverage`]
+#[derive(Debug, Clone, Serialize)]
+pub struct Coverage {
+    /// Known codes the monitor advertises
+    pub supported: usize,
+    /// Codes in the known table
+    pub known: usize,
+    /// `supported` as a percentage of `known`
+    pub percentage: f64,
+    /// Names of the [`IMPORTANT_CODES`] the monitor doesn't advertise
+    pub missing_important: Vec<&'static str>,
+}
+
+/// Parse a capabilities string as reported by the monitor.
+///
+/// The string comes straight from the device and is often malformed, so this never panics:
+/// truncated sections and stray closing parentheses are tolerated, and anything else that
+/// can't be read is reported as [`MonitorError::CapabilitiesParse`].
+pub fn parse_capabilities(caps: &str) -> Result<Capabilities> {
+    let mut body = caps.trim();
+    if let Some(stripped) = body.strip_prefix('(') {
+        // Some monitors omit the closing parenthesis of the outer group
+        body = stripped.strip_suffix(')').unwrap_or(stripped);
+    }
+
+    let mut result = Capabilities::default();
+    for (name, value) in split_sections(body)? {
+        match name.as_str() {
+            "prot" => result.protocol = Some(value.trim().to_string()),
+            "type" => result.display_type = Some(value.trim().to_string()),
+            "model" => result.model = Some(value.trim().to_string()),
+            "cmds" => result.commands = parse_hex_list(value)?,
+            "vcp" => result.vcp_codes = parse_vcp_section(value)?,
+            "mccs_ver" => result.mccs_version = Some(value.trim().to_string()),
+            // Vendor sections (mswhql, asset_eep, vcpname, ...) are not interpreted
+            _ => {}
+        }
+    }
+
+    Ok(result)
+}
+
+/// Split `name(value)name(value)...` into pairs, honoring nested parentheses
+fn split_sections(body: &str) -> Result<Vec<(String, &str)>> {
+    let mut sections = Vec::new();
+    let mut rest = body;
+
+    loop {
+        // Some monitors close a group more often than they open one
+        rest = rest.trim_start_matches(|c: char| c == ')' || c.is_whitespace());
+        if rest.is_empty() {
+            break;
+        }
+
+        let open = rest.find('(').ok_or_else(|| {
+            MonitorError::CapabilitiesParse(format!("expected '(' after '{}'", rest.trim()))
+        })?;
+        let name = rest[..open].trim().to_ascii_lowercase();
+
+        let mut depth = 0usize;
+        let mut close = None;
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        close = Some(open + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match close {
+            Some(close) => {
+                sections.push((name, &rest[open + 1..close]));
+                rest = &rest[close + 1..];
+            }
+            None => {
+                // Truncated string: keep what we have for the last section
+                sections.push((name, &rest[open + 1..]));
+                rest = "";
+            }
+        }
+    }
+
+    Ok(sections)
+}
+
+fn parse_vcp_section(section: &str) -> Result<Vec<VcpCapability>> {
+    let mut codes: Vec<VcpCapability> = Vec::new();
+    let mut rest = section.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('(') {
+            let close = after.find(')').unwrap_or(after.len());
+            let values = parse_hex_list(&after[..close])?;
+            let last = codes.last_mut().ok_or_else(|| {
+                MonitorError::CapabilitiesParse("value list without a VCP code".to_string())
+            })?;
+            last.values = values;
+            rest = after.get(close + 1..).unwrap_or("");
+        } else {
+            let (code, remaining) = take_hex_byte(rest)?;
+            codes.push(VcpCapability {
+                code,
+                values: Vec::new(),
+            });
+            rest = remaining;
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(codes)
+}
+
+fn parse_hex_list(list: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut rest = list.trim_start();
+
+    while !rest.is_empty() {
+        let (byte, remaining) = take_hex_byte(rest)?;
+        bytes.push(byte);
+        rest = remaining.trim_start();
+    }
+
+    Ok(bytes)
+}
+
+/// Read one two-digit hex byte; codes are usually space separated but some monitors pack them
+fn take_hex_byte(s: &str) -> Result<(u8, &str)> {
+    // Counted in bytes so the slice below always ends on a character boundary
+    let digits = s.bytes().take(2).take_while(u8::is_ascii_hexdigit).count();
+
+    if digits == 0 {
+        return Err(MonitorError::CapabilitiesParse(format!(
+            "expected hex byte at '{}'",
+            s.chars().take(10).collect::<String>()
+        )));
+    }
+
+    let byte = u8::from_str_radix(&s[..digits], 16)
+        .map_err(|e| MonitorError::CapabilitiesParse(e.to_string()))?;
+    Ok((byte, &s[digits..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const DELL: &str = "(prot(monitor)type(lcd)model(U2723DE)cmds(01 02 03 0C E3 F3)vcp(10 12 14(05 08 0B) 60(0F 11))mccs_ver(2.1))";
+
+    /// Capabilities strings saved with `get-capabilities --save`
+    const SAVED_DELL: &str = include_str!("../tests/data/capabilities/dell_u2723qe.txt");
+    const SAVED_LG: &str = include_str!("../tests/data/capabilities/lg_27gl850.txt");
+
+    #[test]
+    fn parses_a_well_formed_string() {
+        let caps = parse_capabilities(DELL).unwrap();
+        assert_eq!(caps.protocol.as_deref(), Some("monitor"));
+        assert_eq!(caps.display_type.as_deref(), Some("lcd"));
+        assert_eq!(caps.model.as_deref(), Some("U2723DE"));
+        assert_eq!(caps.commands, vec![0x01, 0x02, 0x03, 0x0C, 0xE3, 0xF3]);
+        assert_eq!(caps.mccs_version.as_deref(), Some("2.1"));
+        assert_eq!(
+            caps.vcp_codes
+                .iter()
+                .map(|cap| cap.code)
+                .collect::<Vec<_>>(),
+            vec![0x10, 0x12, 0x14, 0x60]
+        );
+        assert_eq!(caps.vcp_code(0x14).unwrap().values, vec![0x05, 0x08, 0x0B]);
+        assert!(caps.supports(0x60));
+        assert!(!caps.supports(0x62));
+    }
+
+    #[test]
+    fn tolerates_missing_outer_parenthesis() {
+        let caps = parse_capabilities("(prot(monitor)vcp(10 12)").unwrap();
+        assert_eq!(caps.vcp_codes.len(), 2);
+    }
+
+    #[test]
+    fn tolerates_stray_closing_parentheses() {
+        let caps = parse_capabilities("(prot(monitor))) vcp(10 12)))mccs_ver(2.2))").unwrap();
+        assert_eq!(caps.vcp_codes.len(), 2);
+        assert_eq!(caps.mccs_version.as_deref(), Some("2.2"));
+    }
+
+    #[test]
+    fn keeps_a_truncated_last_section() {
+        let caps = parse_capabilities("(prot(monitor)vcp(10 12 14(05 08").unwrap();
+        assert_eq!(caps.vcp_codes.len(), 3);
+        assert_eq!(caps.vcp_code(0x14).unwrap().values, vec![0x05, 0x08]);
+    }
+
+    #[test]
+    fn reads_packed_codes() {
+        let caps = parse_capabilities("(vcp(101214(0508)60))").unwrap();
+        assert_eq!(
+            caps.vcp_codes
+                .iter()
+                .map(|cap| cap.code)
+                .collect::<Vec<_>>(),
+            vec![0x10, 0x12, 0x14, 0x60]
+        );
+        assert_eq!(caps.vcp_code(0x14).unwrap().values, vec![0x05, 0x08]);
+    }
+
+    #[test]
+    fn ignores_vendor_sections() {
+        let caps = parse_capabilities("(prot(monitor)mswhql(1)asset_eep(40)vcp(10))").unwrap();
+        assert_eq!(caps.vcp_codes.len(), 1);
+    }
+
+    #[test]
+    fn empty_string_has_no_capabilities() {
+        let caps = parse_capabilities("").unwrap();
+        assert!(caps.vcp_codes.is_empty());
+        assert!(caps.protocol.is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_input_with_an_error() {
+        for caps in [
+            "garbage",
+            "(vcp(10 zz))",
+            "(vcp((05 08)))",
+            "(cmds(01 0g))",
+            "(vcp(10 é))",
+            "(vcp(1€))",
+        ] {
+            assert!(
+                matches!(
+                    parse_capabilities(caps),
+                    Err(MonitorError::CapabilitiesParse(_))
+                ),
+                "{}",
+                caps
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics_on_arbitrary_bytes(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+            let _ = parse_capabilities(&String::from_utf8_lossy(&bytes));
+        }
+
+        #[test]
+        fn never_panics_on_capabilities_like_text(caps in "[()a-zA-Z0-9_. é€]{0,256}") {
+            let _ = parse_capabilities(&caps);
+        }
+
+        #[test]
+        fn parses_every_code_of_a_generated_vcp_section(
+            codes in prop::collection::vec(any::<u8>(), 0..32)
+        ) {
+            let list: Vec<String> = codes.iter().map(|code| format!("{:02X}", code)).collect();
+            let caps = parse_capabilities(&format!("(vcp({}))", list.join(" "))).unwrap();
+            let parsed: Vec<u8> = caps.vcp_codes.iter().map(|cap| cap.code).collect();
+            prop_assert_eq!(parsed, codes);
+        }
+    }
+
+    #[test]
+    fn parses_saved_capabilities_files() {
+        let dell = parse_capabilities(SAVED_DELL).unwrap();
+        assert_eq!(dell.model.as_deref(), Some("U2723QE"));
+        assert_eq!(dell.mccs_version.as_deref(), Some("2.1"));
+        assert_eq!(dell.vcp_code(0x60).unwrap().values, vec![0x0F, 0x11, 0x1B]);
+        assert_eq!(dell.vcp_code(0xD6).unwrap().values, vec![0x01, 0x04, 0x05]);
+        assert!(dell.supports(0xFD));
+
+        // Saved with a trailing newline, and a space inside the 0x60 value list
+        let lg = parse_capabilities(SAVED_LG).unwrap();
+        assert_eq!(lg.model.as_deref(), Some("27GL850"));
+        assert_eq!(
+            lg.vcp_code(0x60).unwrap().values,
+            vec![0x11, 0x12, 0x0F, 0x10]
+        );
+        assert!(lg.vcp_code(0x62).unwrap().values.is_empty());
+        assert!(lg.supports(0xFF));
+    }
+
+    #[test]
+    fn coverage_counts_known_codes_and_lists_missing_important_ones() {
+        // 0x07 isn't in the known table, so it doesn't count
+        let coverage = parse_capabilities("(vcp(10 12 60 07))").unwrap().coverage();
+        assert_eq!(coverage.supported, 3);
+        assert_eq!(coverage.known, vcp::KNOWN_VCP_CODES.len());
+        assert!((coverage.percentage - 300.0 / coverage.known as f64).abs() < 1e-9);
+        assert_eq!(coverage.missing_important, ["volume", "power"]);
+
+        let coverage = parse_capabilities(DELL).unwrap().coverage();
+        assert_eq!(coverage.supported, 4);
+        assert_eq!(coverage.missing_important, ["volume", "power"]);
+
+        let coverage = parse_capabilities("(vcp(10 12 60 62 D6))")
+            .unwrap()
+            .coverage();
+        assert!(coverage.missing_important.is_empty());
+    }
+}
@@ -1,5 +1,25 @@
-use crate::{Result, monitor, monitor::Monitor, vcp};
-use clap::{Parser, Subcommand};
+use crate::{
+    MonitorError, Result, RetryPolicy, capabilities,
+    config::Config,
+    curve::BrightnessCurve,
+    decode, monitor,
+    monitor::{BrightnessControl, LevelValue, Monitor},
+    profile,
+    selector::MonitorSelector,
+    selftest, server,
+    table::Table,
+    template, vcp,
+};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::{
+    cell::Cell,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 
 #[derive(Parser)]
 #[command(name = "monitorconfig")]
@@ -9,14 +29,98 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub silent: bool,
 
+    /// Configuration file (defaults to %APPDATA%\monitorconfig\config.json)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Refuse any command that would change monitor settings; reads work normally
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Skip the confirmation prompt for commands that reset monitor settings
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Send each set-brightness/set-contrast/set-vcp write this many times, for monitors that
+    /// ignore the first attempt (defaults to the configured `repeat`, normally 1)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub repeat: Option<u32>,
+
+    /// Print the monitor the command resolved to (device, friendly and instance name) to stderr
+    /// before acting
+    #[arg(long, global = true)]
+    pub show_target: bool,
+
+    /// Match --device only by the exact device name, friendly name or stable ID, without
+    /// ignoring case or accepting part of a name
+    #[arg(long, global = true)]
+    pub exact: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// Monitor targeting options shared by every single-monitor command
+#[derive(Args)]
+pub struct MonitorArgs {
+    /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
+    #[arg(short, long)]
+    device: Option<String>,
+
+    /// Use primary monitor
+    #[arg(short, long)]
+    primary: bool,
+
+    /// Monitor number as shown in Windows display settings (e.g. 2)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["device", "primary"])]
+    display_number: Option<u32>,
+
+    /// Raw HMONITOR in hex (e.g. 0x10001), bypassing enumeration
+    #[arg(long, value_parser = parse_handle, conflicts_with_all = ["device", "primary", "display_number"])]
+    hmonitor: Option<usize>,
+
+    /// Set from the global `--exact` once parsed; a `Cell` so it can be set through
+    /// [`Commands::target`]
+    #[arg(skip)]
+    exact: Cell<bool>,
+}
+
+impl MonitorArgs {
+    /// `--hmonitor` and `--display-number` are exclusive; otherwise `--primary` wins over
+    /// `--device`, and with neither the primary monitor is used
+    pub fn selector(&self) -> MonitorSelector {
+        if let Some(handle) = self.hmonitor {
+            return MonitorSelector::Handle(handle);
+        }
+        if let Some(number) = self.display_number {
+            return MonitorSelector::DisplayNumber(number);
+        }
+        match (&self.device, self.primary) {
+            (Some(device), false) if self.exact.get() => {
+                MonitorSelector::ExactDevice(device.clone())
+            }
+            (Some(device), false) => MonitorSelector::Device(device.clone()),
+            _ => MonitorSelector::Primary,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// List all available monitors
     List {
+        /// Order monitors by friendly name, device name, or primary first
+        #[arg(long, value_enum)]
+        sort: Option<ListSort>,
+
+        /// Only list monitors on this adapter (GPU); matches part of the name, e.g. "nvidia"
+        #[arg(long)]
+        adapter: Option<String>,
+
+        /// Group the monitors under the adapter (GPU) they are attached to
+        #[arg(long)]
+        tree: bool,
+
         /// Output in JSON format
         #[arg(short, long)]
         json: bool,
@@ -24,13 +128,20 @@ pub enum Commands {
 
     /// Get brightness level of a monitor
     GetBrightness {
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
 
-        /// Use primary monitor
-        #[arg(short, long)]
-        primary: bool,
+        /// Report every monitor
+        #[arg(short, long, conflicts_with_all = ["device", "primary", "display_number", "hmonitor"])]
+        all: bool,
+
+        /// Report brightness as 0.0-1.0 of the monitor's range
+        #[arg(short, long, conflicts_with = "all")]
+        normalized: bool,
+
+        /// Custom output, e.g. "{current}/{max}" (fields: current, min, max)
+        #[arg(long, conflicts_with_all = ["all", "normalized", "json"])]
+        template: Option<String>,
 
         /// Output in JSON format
         #[arg(short, long)]
@@ -39,27 +150,93 @@ pub enum Commands {
 
     /// Set brightness level of a monitor
     SetBrightness {
-        /// Brightness value (0-100)
+        /// Brightness value (0-100), or a notch of the on-screen scale such as 7n
+        #[arg(required_unless_present = "from_lux", conflicts_with = "from_lux")]
+        value: Option<LevelValue>,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Ambient light reading in lux, mapped to a brightness through --curve
+        #[arg(long, value_name = "LUX", requires = "curve")]
+        from_lux: Option<f64>,
+
+        /// JSON file of lux-to-brightness points for --from-lux
+        #[arg(long, value_name = "PATH", requires = "from_lux")]
+        curve: Option<PathBuf>,
+
+        /// Set the white backlight level (VCP 0x6B) instead of luminance (VCP 0x10). Used
+        /// automatically when the monitor doesn't support luminance.
+        #[arg(long)]
+        backlight: bool,
+    },
+
+    /// Wait until the monitor reports a brightness, e.g. after a fade
+    WaitBrightness {
+        /// Brightness to wait for
         value: u32,
 
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
 
-        /// Use primary monitor
-        #[arg(short, long)]
-        primary: bool,
+        /// Accept readings this far from the value in either direction
+        #[arg(long, default_value_t = 0)]
+        tolerance: u32,
+
+        /// Seconds to wait before giving up
+        #[arg(short, long, default_value_t = 10.0)]
+        timeout: f64,
+
+        /// Milliseconds between reads
+        #[arg(short, long, default_value_t = 250)]
+        interval: u64,
+    },
+
+    /// Wait until the monitor switches to an input, e.g. after a KVM switch
+    WaitInput {
+        /// Input to wait for (e.g. hdmi1, dp1, dvi) or a raw VCP 0x60 value
+        input: decode::InputSource,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Seconds to wait before giving up
+        #[arg(short, long, default_value_t = 10.0)]
+        timeout: f64,
+
+        /// Milliseconds between reads
+        #[arg(short, long, default_value_t = 250)]
+        interval: u64,
+    },
+
+    /// Turn DDC/CI on using the OEM-specific code and value from the config file
+    EnableDdc {
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Turn the ambient light sensor's automatic brightness on or off (VCP 0x66)
+    SetAutoBrightness {
+        /// on or off
+        #[arg(value_enum)]
+        state: Switch,
+
+        #[command(flatten)]
+        target: MonitorArgs,
     },
 
     /// Get contrast level of a monitor
     GetContrast {
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
 
-        /// Use primary monitor
-        #[arg(short, long)]
-        primary: bool,
+        /// Report every monitor
+        #[arg(short, long, conflicts_with_all = ["device", "primary", "display_number", "hmonitor"])]
+        all: bool,
+
+        /// Custom output, e.g. "{current}/{max}" (fields: current, min, max)
+        #[arg(long, conflicts_with_all = ["all", "json"])]
+        template: Option<String>,
 
         /// Output in JSON format
         #[arg(short, long)]
@@ -68,16 +245,52 @@ pub enum Commands {
 
     /// Set contrast level of a monitor
     SetContrast {
-        /// Contrast value (0-100)
-        value: u32,
+        /// Contrast value (0-100), or a notch of the on-screen scale such as 7n
+        value: LevelValue,
 
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Apply to all monitors
+        #[arg(short, long, conflicts_with_all = ["device", "primary", "display_number", "hmonitor"])]
+        all: bool,
+    },
 
-        /// Use primary monitor
+    /// Get the speaker volume of a monitor (VCP 0x62)
+    GetVolume {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
         #[arg(short, long)]
-        primary: bool,
+        json: bool,
+    },
+
+    /// Set the speaker volume of a monitor (VCP 0x62)
+    SetVolume {
+        /// Volume value (0 to the monitor's maximum, usually 100)
+        value: u32,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Ramp to the value over this many milliseconds instead of jumping
+        #[arg(long, value_name = "MS")]
+        fade: Option<u64>,
+
+        /// Number of writes a fade is split into
+        #[arg(long, default_value_t = 10, requires = "fade", value_parser = clap::value_parser!(u32).range(1..))]
+        steps: u32,
+    },
+
+    /// Adjust contrast of a monitor relative to its current level
+    AdjustContrast {
+        /// Amount to add to the current contrast (e.g., 10 or -10)
+        #[arg(allow_negative_numbers = true)]
+        delta: i32,
+
+        #[command(flatten)]
+        target: MonitorArgs,
     },
 
     /// Get VCP feature value
@@ -86,13 +299,23 @@ pub enum Commands {
         #[arg(value_parser = parse_hex)]
         code: u8,
 
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Custom output, e.g. "{name}: {current}" (fields: code, name, current, max, type, decoded)
+        #[arg(long, conflicts_with = "json")]
+        template: Option<String>,
 
-        /// Use primary monitor
+        /// Output in JSON format
         #[arg(short, long)]
-        primary: bool,
+        json: bool,
+    },
+
+    /// List the monitors that support a VCP code, with their current values
+    FindSupporting {
+        /// VCP code (e.g., 0x60 for input source)
+        #[arg(value_parser = parse_hex)]
+        code: u8,
 
         /// Output in JSON format
         #[arg(short, long)]
@@ -108,17 +331,26 @@ pub enum Commands {
         /// Value to set
         value: u32,
 
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
 
-        /// Use primary monitor
-        #[arg(short, long)]
-        primary: bool,
+        /// Read the value back after each write and write again until it sticks, up to this
+        /// many writes; replaces --repeat
+        #[arg(long, value_name = "ATTEMPTS", value_parser = clap::value_parser!(u32).range(1..))]
+        until_stable: Option<u32>,
+
+        /// With --until-stable, accept a read-back this far from the value
+        #[arg(long, default_value_t = 0, requires = "until_stable")]
+        tolerance: u32,
     },
 
     /// List all VCP codes
     ListVcp {
+        /// Only list codes in this category (color, geometry, audio, power, image, info, oem,
+        /// control)
+        #[arg(short, long)]
+        category: Option<vcp::VcpCategory>,
+
         /// Output in JSON format
         #[arg(short, long)]
         json: bool,
@@ -126,347 +358,4312 @@ pub enum Commands {
 
     /// Scan monitor for all supported VCP codes
     ScanVcp {
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+        #[command(flatten)]
+        target: MonitorArgs,
 
-        /// Use primary monitor
+        /// Only show codes with an adjustable value (no actions or read-only status codes)
         #[arg(short, long)]
-        primary: bool,
+        writable_only: bool,
+
+        /// Order codes by code, name, or current value (default: code)
+        #[arg(long, value_enum)]
+        sort: Option<ScanSort>,
+
+        /// Report how long the scan and each code read took
+        #[arg(long)]
+        timings: bool,
+
+        /// Instead of a full scan, read the advertised codes and a set of common ones, and list
+        /// where the monitor and its capabilities string disagree
+        #[arg(long, conflicts_with_all = ["all", "resume", "timings", "writable_only", "sort"])]
+        reconcile: bool,
+
+        /// Save progress to this file after every code and continue from it if it exists, so an
+        /// interrupted scan picks up where it stopped
+        #[arg(long, value_name = "STATEFILE")]
+        resume: Option<PathBuf>,
+
+        /// Scan every monitor
+        #[arg(short, long, conflicts_with_all = ["device", "primary", "display_number", "hmonitor", "resume", "timings"])]
+        all: bool,
+
+        /// With --all, how many monitors to scan at once. Monitors on one graphics adapter may
+        /// share an I2C bus, where parallel scans slow each other down or fail.
+        #[arg(long, default_value_t = DEFAULT_SCAN_JOBS, requires = "all", value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
 
         /// Output in JSON format
         #[arg(short, long)]
         json: bool,
     },
 
-    /// Get monitor capabilities string
-    GetCapabilities {
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+    /// Compare two saved scans and list the codes whose value changed (no monitor needed)
+    ScanDiff {
+        /// Scan saved earlier with scan-vcp --json (or a --resume state file)
+        #[arg(long)]
+        before: PathBuf,
+
+        /// Scan saved later
+        #[arg(long)]
+        after: PathBuf,
 
-        /// Use primary monitor
+        /// Output in JSON format
         #[arg(short, long)]
-        primary: bool,
+        json: bool,
     },
 
-    /// Save current monitor settings
-    SaveSettings {
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+    /// Switch the monitor to another input (VCP 0x60)
+    SetInput {
+        /// Input name (e.g. hdmi1, dp1, dvi) or a raw VCP 0x60 value
+        input: decode::InputSource,
 
-        /// Use primary monitor
-        #[arg(short, long)]
-        primary: bool,
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Restore the current brightness and contrast after the switch
+        #[arg(long)]
+        preserve: bool,
+
+        /// Milliseconds to wait for the switch before restoring (with --preserve)
+        #[arg(long, default_value_t = 2000, requires = "preserve")]
+        settle: u64,
     },
 
-    /// Reset monitor to factory defaults
-    ResetDefaults {
-        /// Device name or friendly name (e.g., \\.\DISPLAY1 or "Dell U2723DE") or use --primary
-        #[arg(short, long)]
-        device: Option<String>,
+    /// Show or change the active output on displays with multiple outputs (VCP 0xD0)
+    OutputSelect {
+        /// Output to select (same names as set-input); omit to show the current output
+        output: Option<decode::InputSource>,
 
-        /// Use primary monitor
-        #[arg(short, long)]
-        primary: bool,
+        #[command(flatten)]
+        target: MonitorArgs,
 
-        /// Only reset color settings
-        #[arg(short, long)]
-        color_only: bool,
+        /// Output in JSON format
+        #[arg(short, long, conflicts_with = "output")]
+        json: bool,
     },
-}
 
-fn parse_hex(s: &str) -> std::result::Result<u8, String> {
-    if let Some(stripped) = s.strip_prefix("0x") {
-        u8::from_str_radix(stripped, 16).map_err(|e| e.to_string())
-    } else {
-        s.parse::<u8>().map_err(|e| e.to_string())
-    }
-}
+    /// Show or change overscan/underscan on displays that support it (VCP 0xDA)
+    ScanMode {
+        /// Scan mode: normal, underscan, overscan, letterbox, or a raw VCP 0xDA value; omit to
+        /// show the current mode
+        mode: Option<decode::ScanMode>,
 
-pub fn run() -> Result<()> {
-    let cli = Cli::parse();
-    let silent = cli.silent;
+        #[command(flatten)]
+        target: MonitorArgs,
 
-    match cli.command {
-        Commands::List { json } => list_monitors(json, silent),
-        Commands::GetBrightness {
-            device,
-            primary,
-            json,
-        } => get_brightness(device, primary, json, silent),
-        Commands::SetBrightness {
-            value,
-            device,
-            primary,
-        } => set_brightness(value, device, primary, silent),
-        Commands::GetContrast {
-            device,
-            primary,
-            json,
-        } => get_contrast(device, primary, json, silent),
-        Commands::SetContrast {
-            value,
-            device,
-            primary,
-        } => set_contrast(value, device, primary, silent),
-        Commands::GetVcp {
-            code,
-            device,
-            primary,
-            json,
-        } => get_vcp(code, device, primary, json, silent),
-        Commands::SetVcp {
-            code,
-            value,
-            device,
-            primary,
-        } => set_vcp(code, value, device, primary, silent),
-        Commands::ListVcp { json } => list_vcp(json, silent),
-        Commands::ScanVcp {
-            device,
-            primary,
-            json,
-        } => scan_vcp(device, primary, json, silent),
-        Commands::GetCapabilities { device, primary } => get_capabilities(device, primary, silent),
-        Commands::SaveSettings { device, primary } => save_settings(device, primary, silent),
-        Commands::ResetDefaults {
-            device,
-            primary,
-            color_only,
-        } => reset_defaults(device, primary, color_only, silent),
-    }
-}
+        /// Output in JSON format
+        #[arg(short, long, conflicts_with = "mode")]
+        json: bool,
+    },
 
-fn get_monitor(device: Option<String>, primary: bool) -> Result<monitor::PhysicalMonitor> {
-    if primary {
-        monitor::get_primary_monitor()
-    } else if let Some(device_name) = device {
-        monitor::find_monitor(&device_name)
-    } else {
-        monitor::get_primary_monitor()
-    }
-}
+    /// Show or change which set of speakers is active (VCP 0x63)
+    SpeakerSelect {
+        /// Speakers: front, side, rear, center (or subwoofer), or a raw VCP 0x63 value; omit to
+        /// show the active ones
+        speakers: Option<decode::SpeakerSelect>,
 
-fn list_monitors(json: bool, silent: bool) -> Result<()> {
-    let monitors = monitor::enumerate_monitors()?;
+        #[command(flatten)]
+        target: MonitorArgs,
 
-    if !silent {
-        if json {
-            let info: Vec<_> = monitors.iter().map(|m| m.info()).collect();
-            println!("{}", serde_json::to_string_pretty(&info)?);
-        } else {
-            println!(
-                "{:<20} {:<30} {}",
-                "Device Name", "Friendly Name", "Primary"
-            );
-            println!("{}", "-".repeat(70));
-            for mon in &monitors {
-                let info = mon.info();
-                println!(
-                    "{:<20} {:<30} {}",
-                    info.device_name,
-                    info.friendly_name,
-                    if info.is_primary { "Yes" } else { "" }
-                );
-            }
-        }
-    }
+        /// Output in JSON format
+        #[arg(short, long, conflicts_with = "speakers")]
+        json: bool,
+    },
 
-    Ok(())
-}
+    /// Set a custom color temperature through VCP 0x0C, in the steps the monitor reports in
+    /// VCP 0x0B
+    SetUserColorTemp {
+        /// Color temperature in kelvin, e.g. 6500; set to the nearest step the monitor supports
+        kelvin: u32,
 
-fn get_brightness(device: Option<String>, primary: bool, json: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    let brightness = mon.get_brightness()?;
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
 
-    if !silent {
-        if json {
-            println!("{}", serde_json::to_string_pretty(&brightness)?);
-        } else {
-            println!(
-                "Current brightness: {} (min: {}, max: {})",
-                brightness.current, brightness.minimum, brightness.maximum
-            );
-        }
-    }
+    /// Set the red, green and blue gains (VCP 0x16, 0x18, 0x1A)
+    SetRgbGain {
+        /// Red gain
+        #[arg(required_unless_present = "hex", requires_all = ["green", "blue"])]
+        red: Option<u32>,
 
-    Ok(())
-}
+        /// Green gain
+        green: Option<u32>,
 
-fn set_brightness(value: u32, device: Option<String>, primary: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    mon.set_brightness(value)?;
-    if !silent {
-        println!("Brightness set to {}", value);
-    }
-    Ok(())
-}
+        /// Blue gain
+        blue: Option<u32>,
 
-fn get_contrast(device: Option<String>, primary: bool, json: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    let contrast = mon.get_contrast()?;
+        /// All three gains as a hex triplet like #FFF0E8, each byte scaled to the channel's
+        /// maximum
+        #[arg(long, value_name = "#RRGGBB", conflicts_with_all = ["red", "green", "blue"])]
+        hex: Option<decode::HexTriplet>,
 
-    if !silent {
-        if json {
-            println!("{}", serde_json::to_string_pretty(&contrast)?);
-        } else {
-            println!(
-                "Current contrast: {} (min: {}, max: {})",
-                contrast.current, contrast.minimum, contrast.maximum
-            );
-        }
-    }
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
 
-    Ok(())
-}
+    /// Show or change the performance-preservation features (VCP 0x54)
+    PerformancePreservation {
+        #[command(flatten)]
+        target: MonitorArgs,
 
-fn set_contrast(value: u32, device: Option<String>, primary: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    mon.set_contrast(value)?;
-    if !silent {
-        println!("Contrast set to {}", value);
-    }
-    Ok(())
-}
+        /// Enable a feature by bit number (0-15); may be repeated
+        #[arg(long, value_name = "BIT", value_parser = clap::value_parser!(u8).range(0..16))]
+        enable: Vec<u8>,
 
-fn get_vcp(
-    code: u8,
-    device: Option<String>,
-    primary: bool,
-    json: bool,
-    silent: bool,
-) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
-    let response = vcp_mon.get_vcp_feature(code)?;
+        /// Disable a feature by bit number (0-15); may be repeated
+        #[arg(long, value_name = "BIT", value_parser = clap::value_parser!(u8).range(0..16))]
+        disable: Vec<u8>,
 
-    if !silent {
-        if json {
-            println!("{}", serde_json::to_string_pretty(&response)?);
-        } else {
-            let info = vcp::get_vcp_code_info(code);
-            if let Some(info) = info {
-                println!("VCP Code: 0x{:02X} - {}", code, info.name);
-                println!("Description: {}", info.description);
-            } else {
-                println!("VCP Code: 0x{:02X}", code);
-            }
-            println!(
-                "Current value: {} (max: {})",
-                response.current_value, response.maximum_value
-            );
-            println!("Type: {:?}", response.code_type);
-        }
-    }
+        /// Write the whole bitmask at once (e.g. 0x0005)
+        #[arg(long, value_parser = parse_hex_u16, conflicts_with_all = ["enable", "disable"])]
+        raw: Option<u16>,
 
-    Ok(())
-}
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
 
-fn set_vcp(
-    code: u8,
-    value: u32,
-    device: Option<String>,
-    primary: bool,
-    silent: bool,
-) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
-    vcp_mon.set_vcp_feature(code, value)?;
-    if !silent {
-        println!("VCP code 0x{:02X} set to {}", code, value);
-    }
-    Ok(())
-}
+    /// Show or change six-axis color saturation (VCP 0x59-0x5E) or hue (VCP 0x9B-0xA0)
+    SixAxis {
+        /// Which six-axis control to use
+        #[arg(value_enum)]
+        control: SixAxisControl,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        #[command(flatten)]
+        values: SixAxisArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Answer JSON requests on a named pipe, keeping monitor handles open between requests
+    Serve {
+        /// Named pipe to listen on
+        #[arg(long, default_value = server::DEFAULT_PIPE_NAME)]
+        pipe: String,
+    },
+
+    /// Show brightness, contrast, and input together, e.g. for dashboards
+    Snapshot {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Snapshot all monitors
+        #[arg(short, long, conflicts_with_all = ["device", "primary", "display_number", "hmonitor"])]
+        all: bool,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Show which input the monitor is currently displaying
+    CurrentInput {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Report power-on hours and whether the monitor is past the configured maintenance threshold
+    Health {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Measure how reliably the monitor applies brightness writes; the brightness is restored
+    /// afterwards
+    SelfTest {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Number of write/verify cycles
+        #[arg(short = 'n', long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        cycles: u32,
+
+        /// Milliseconds to wait for each write to be reported back
+        #[arg(long, default_value_t = 1000)]
+        timeout: u64,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Measure how the monitor handles writes and save a settle delay and write count for it
+    Tune {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Number of write/verify cycles to measure
+        #[arg(short = 'n', long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+        cycles: u32,
+
+        /// Milliseconds to wait for each write to be reported back
+        #[arg(long, default_value_t = 1000)]
+        timeout: u64,
+
+        /// Show the recommended settings without writing the configuration file
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Show what Windows and the monitor report, for troubleshooting
+    Diagnose {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Also check the GDI device context, which gamma-ramp control needs
+        #[arg(long)]
+        device_context: bool,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Show the DVI link status (VCP 0xBE), for diagnosing flaky connections
+    LinkStatus {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Show the video mode and packet status of a DPVL display (VCP 0xB7)
+    MonitorStatus {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Switch the monitor's power mode (on, standby, suspend, off)
+    Power {
+        /// Power mode: on, standby, suspend, off, hard-off, or a raw VCP 0xD6 value
+        mode: decode::PowerMode,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Allow blanking the primary monitor (standby, suspend, off or hard-off)
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Write the monitor's asset tag (VCP 0xD2)
+    SetAssetTag {
+        /// Asset tag text (printable ASCII)
+        tag: String,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Write the monitor's display descriptor (VCP 0xC3)
+    SetDisplayDescriptor {
+        /// Descriptor text (printable ASCII, up to the length the monitor reports in VCP 0xC2)
+        descriptor: String,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Read or change the display descriptor (VCP 0xC3) and whether it is shown without a
+    /// signal (VCP 0xC4)
+    Descriptor {
+        #[command(subcommand)]
+        action: DescriptorAction,
+    },
+
+    /// Show the size of the monitor's auxiliary display (VCP 0xCE) or write text to it (VCP 0xCF)
+    AuxDisplay {
+        #[command(subcommand)]
+        action: AuxDisplayAction,
+    },
+
+    /// Get monitor capabilities string
+    GetCapabilities {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Write the raw string to a file instead of printing it
+        #[arg(long)]
+        save: Option<PathBuf>,
+
+        /// Report how many known VCP codes the monitor advertises and which important controls
+        /// (brightness, contrast, input, volume, power) it lacks
+        #[arg(long, conflicts_with = "save")]
+        coverage: bool,
+
+        /// Output the coverage report in JSON format
+        #[arg(short, long, requires = "coverage")]
+        json: bool,
+    },
+
+    /// Show the monitor's EDID: identity, native resolution, and CEA-861 audio/video support
+    GetEdid {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Parse a capabilities string saved with `get-capabilities --save` (no monitor needed)
+    ParseCapabilities {
+        /// File containing a raw capabilities string
+        path: PathBuf,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Save current monitor settings
+    SaveSettings {
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Check whether the monitor keeps a brightness set over DDC/CI when switched off and on;
+    /// the brightness is restored afterwards
+    PersistenceTest {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Switch the monitor off and on through its power mode (VCP 0xD6) instead of asking you
+        /// to do it
+        #[arg(long)]
+        power_cycle: bool,
+
+        /// Milliseconds to leave the monitor off with --power-cycle
+        #[arg(long, default_value_t = 5000, requires = "power_cycle")]
+        off_time: u64,
+
+        /// Allow --power-cycle to switch off the primary monitor
+        #[arg(short, long, requires = "power_cycle")]
+        force: bool,
+
+        /// Milliseconds to wait for the monitor to answer again once it is back on
+        #[arg(long, default_value_t = 30000)]
+        timeout: u64,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Save current VCP values to a profile file
+    SaveProfile {
+        /// Profile file to write
+        path: PathBuf,
+
+        /// Comma-separated VCP codes to capture (defaults to brightness, contrast, color preset and RGB gains)
+        #[arg(long, value_delimiter = ',', value_parser = parse_hex)]
+        codes: Vec<u8>,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Apply the VCP values from a profile file
+    LoadProfile {
+        /// Profile file to apply
+        path: PathBuf,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Read each code first and write only the values that differ
+        #[arg(long)]
+        minimal: bool,
+
+        /// Apply only if the monitor is on this input (e.g. hdmi1, dp1); otherwise skip
+        #[arg(long, value_name = "INPUT")]
+        only_if_input: Option<decode::InputSource>,
+    },
+
+    /// Check whether a profile fits a monitor, without applying it
+    ValidateProfile {
+        /// Profile file to check
+        path: PathBuf,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// List settings that differ from a factory baseline profile
+    Drift {
+        /// Profile saved right after a factory reset
+        baseline: PathBuf,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Write one code back to the value saved in a baseline profile
+    RevertVcp {
+        /// VCP code to revert (e.g., 0x10 for brightness)
+        #[arg(value_parser = parse_hex)]
+        code: u8,
+
+        /// Profile holding the value to restore, e.g. one saved right after a factory reset
+        #[arg(long)]
+        baseline: PathBuf,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Save a profile and the identity of every monitor to one file
+    BackupAll {
+        /// Backup file to write
+        path: PathBuf,
+
+        /// Comma-separated VCP codes to capture (defaults to brightness, contrast, color preset and RGB gains)
+        #[arg(long, value_delimiter = ',', value_parser = parse_hex)]
+        codes: Vec<u8>,
+    },
+
+    /// Re-apply a backup-all file to the monitors it was taken from
+    RestoreAll {
+        /// Backup file to apply
+        path: PathBuf,
+    },
+
+    /// Watch for input changes and apply the profile configured for the new input
+    WatchInput {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Seconds between input checks
+        #[arg(short, long, default_value_t = 2)]
+        interval: u64,
+
+        /// Hold back a profile while the monitor reports its OSD open (VCP 0xCA), so it doesn't
+        /// fight changes made in the menu
+        #[arg(long)]
+        pause_for_osd: bool,
+    },
+
+    /// Copy the selected monitor's brightness to all other monitors whenever it changes
+    MirrorBrightness {
+        // The selected monitor is the one followed
+        #[command(flatten)]
+        source: MonitorArgs,
+
+        /// Milliseconds between brightness reads
+        #[arg(short, long, default_value_t = 500)]
+        interval: u64,
+
+        /// Milliseconds a new brightness must hold before it is copied
+        #[arg(long, default_value_t = 1000)]
+        debounce: u64,
+
+        /// Hold back writes to a monitor while it reports its OSD open (VCP 0xCA), so they don't
+        /// fight changes made in its menu
+        #[arg(long)]
+        pause_for_osd: bool,
+    },
+
+    /// Reset monitor to factory defaults
+    ResetDefaults {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Only reset color settings
+        #[arg(short, long)]
+        color_only: bool,
+
+        /// Reset every monitor, asking about each one unless --yes is given
+        #[arg(long, conflicts_with_all = ["device", "primary", "display_number", "hmonitor"])]
+        all_monitors: bool,
+
+        /// Output the per-monitor results in JSON format (with --all-monitors)
+        #[arg(short, long, requires = "all_monitors")]
+        json: bool,
+    },
+
+    /// Reset only brightness and contrast to factory defaults (VCP 0x05)
+    ResetBrightnessContrast {
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Switch {
+    On,
+    Off,
+}
+
+impl Commands {
+    /// Whether the command writes to the monitor (VCP writes, saving or resetting settings).
+    /// Writing local files such as saved profiles doesn't count.
+    pub fn changes_monitor(&self) -> bool {
+        match self {
+            Commands::SetBrightness { .. }
+            | Commands::SetAutoBrightness { .. }
+            | Commands::EnableDdc { .. }
+            | Commands::SetContrast { .. }
+            | Commands::AdjustContrast { .. }
+            | Commands::SetVolume { .. }
+            | Commands::SetVcp { .. }
+            | Commands::SetInput { .. }
+            | Commands::Power { .. }
+            | Commands::SetAssetTag { .. }
+            | Commands::SetUserColorTemp { .. }
+            | Commands::SetRgbGain { .. }
+            | Commands::SetDisplayDescriptor { .. }
+            | Commands::SaveSettings { .. }
+            | Commands::PersistenceTest { .. }
+            | Commands::LoadProfile { .. }
+            | Commands::RevertVcp { .. }
+            | Commands::RestoreAll { .. }
+            | Commands::WatchInput { .. }
+            | Commands::MirrorBrightness { .. }
+            | Commands::SelfTest { .. }
+            | Commands::Tune { .. }
+            | Commands::ResetDefaults { .. }
+            | Commands::ResetBrightnessContrast { .. } => true,
+            Commands::OutputSelect { output, .. } => output.is_some(),
+            Commands::ScanMode { mode, .. } => mode.is_some(),
+            Commands::SpeakerSelect { speakers, .. } => speakers.is_some(),
+            Commands::Descriptor { action } => !matches!(action, DescriptorAction::Get { .. }),
+            Commands::AuxDisplay { action } => matches!(action, AuxDisplayAction::Set { .. }),
+            Commands::SixAxis { values, .. } => !values.values().is_empty(),
+            Commands::PerformancePreservation {
+                enable,
+                disable,
+                raw,
+                ..
+            } => !enable.is_empty() || !disable.is_empty() || raw.is_some(),
+            Commands::List { .. }
+            | Commands::GetBrightness { .. }
+            | Commands::GetContrast { .. }
+            | Commands::GetVolume { .. }
+            | Commands::GetVcp { .. }
+            | Commands::ListVcp { .. }
+            | Commands::ScanVcp { .. }
+            | Commands::ScanDiff { .. }
+            | Commands::Snapshot { .. }
+            | Commands::FindSupporting { .. }
+            | Commands::Serve { .. }
+            | Commands::WaitBrightness { .. }
+            | Commands::WaitInput { .. }
+            | Commands::CurrentInput { .. }
+            | Commands::Health { .. }
+            | Commands::Diagnose { .. }
+            | Commands::LinkStatus { .. }
+            | Commands::MonitorStatus { .. }
+            | Commands::GetCapabilities { .. }
+            | Commands::GetEdid { .. }
+            | Commands::ParseCapabilities { .. }
+            | Commands::SaveProfile { .. }
+            | Commands::BackupAll { .. }
+            | Commands::ValidateProfile { .. }
+            | Commands::Drift { .. } => false,
+        }
+    }
+
+    /// Whether the command resets settings the user may not be able to restore, and so needs
+    /// confirmation or `--yes`
+    pub fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            Commands::ResetDefaults { .. } | Commands::ResetBrightnessContrast { .. }
+        )
+    }
+
+    /// The single monitor the command acts on, for `--show-target`. `None` for commands that
+    /// don't target one, or that act on every monitor because of `--all`/`--all-monitors`.
+    pub fn target(&self) -> Option<&MonitorArgs> {
+        match self {
+            Commands::GetBrightness { target, all, .. }
+            | Commands::GetContrast { target, all, .. }
+            | Commands::SetContrast { target, all, .. }
+            | Commands::ScanVcp { target, all, .. }
+            | Commands::Snapshot { target, all, .. } => (!*all).then_some(target),
+            Commands::ResetDefaults {
+                target,
+                all_monitors,
+                ..
+            } => (!*all_monitors).then_some(target),
+            Commands::Descriptor { action } => match action {
+                DescriptorAction::Get { target, .. }
+                | DescriptorAction::Set { target, .. }
+                | DescriptorAction::Enable { target }
+                | DescriptorAction::Disable { target } => Some(target),
+            },
+            Commands::AuxDisplay { action } => match action {
+                AuxDisplayAction::Get { target, .. } | AuxDisplayAction::Set { target, .. } => {
+                    Some(target)
+                }
+            },
+            Commands::MirrorBrightness { source, .. } => Some(source),
+            Commands::SetBrightness { target, .. }
+            | Commands::WaitBrightness { target, .. }
+            | Commands::WaitInput { target, .. }
+            | Commands::EnableDdc { target }
+            | Commands::SetAutoBrightness { target, .. }
+            | Commands::GetVolume { target, .. }
+            | Commands::SetVolume { target, .. }
+            | Commands::AdjustContrast { target, .. }
+            | Commands::GetVcp { target, .. }
+            | Commands::SetVcp { target, .. }
+            | Commands::SetInput { target, .. }
+            | Commands::OutputSelect { target, .. }
+            | Commands::ScanMode { target, .. }
+            | Commands::SpeakerSelect { target, .. }
+            | Commands::PerformancePreservation { target, .. }
+            | Commands::SixAxis { target, .. }
+            | Commands::CurrentInput { target, .. }
+            | Commands::Health { target, .. }
+            | Commands::SelfTest { target, .. }
+            | Commands::Tune { target, .. }
+            | Commands::Diagnose { target, .. }
+            | Commands::LinkStatus { target, .. }
+            | Commands::MonitorStatus { target, .. }
+            | Commands::Power { target, .. }
+            | Commands::SetAssetTag { target, .. }
+            | Commands::SetUserColorTemp { target, .. }
+            | Commands::SetRgbGain { target, .. }
+            | Commands::SetDisplayDescriptor { target, .. }
+            | Commands::GetCapabilities { target, .. }
+            | Commands::GetEdid { target, .. }
+            | Commands::SaveSettings { target }
+            | Commands::PersistenceTest { target, .. }
+            | Commands::SaveProfile { target, .. }
+            | Commands::LoadProfile { target, .. }
+            | Commands::ValidateProfile { target, .. }
+            | Commands::Drift { target, .. }
+            | Commands::RevertVcp { target, .. }
+            | Commands::WatchInput { target, .. }
+            | Commands::ResetBrightnessContrast { target } => Some(target),
+            Commands::List { .. }
+            | Commands::FindSupporting { .. }
+            | Commands::ListVcp { .. }
+            | Commands::Serve { .. }
+            | Commands::BackupAll { .. }
+            | Commands::RestoreAll { .. }
+            | Commands::ScanDiff { .. }
+            | Commands::ParseCapabilities { .. } => None,
+        }
+    }
+}
+
+/// Actions of the `descriptor` command
+#[derive(Subcommand)]
+pub enum DescriptorAction {
+    /// Show the descriptor and whether it is shown without a signal
+    Get {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Write the descriptor (same as set-display-descriptor)
+    Set {
+        /// Descriptor text (printable ASCII, up to the length the monitor reports in VCP 0xC2)
+        descriptor: String,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Show the descriptor while the monitor receives no video
+    Enable {
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+
+    /// Stop showing the descriptor while the monitor receives no video
+    Disable {
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuxDisplayAction {
+    /// Show the size of the auxiliary display
+    Get {
+        #[command(flatten)]
+        target: MonitorArgs,
+
+        /// Output in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Show text on the auxiliary display
+    Set {
+        /// Text to show (printable ASCII, up to rows x characters per row)
+        text: String,
+
+        #[command(flatten)]
+        target: MonitorArgs,
+    },
+}
+
+/// Controls for `six-axis`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SixAxisControl {
+    Saturation,
+    Hue,
+}
+
+/// Per-axis values for `six-axis`; none given means read
+#[derive(Args)]
+pub struct SixAxisArgs {
+    /// Red axis value
+    #[arg(long)]
+    red: Option<u32>,
+
+    /// Yellow axis value
+    #[arg(long)]
+    yellow: Option<u32>,
+
+    /// Green axis value
+    #[arg(long)]
+    green: Option<u32>,
+
+    /// Cyan axis value
+    #[arg(long)]
+    cyan: Option<u32>,
+
+    /// Blue axis value
+    #[arg(long)]
+    blue: Option<u32>,
+
+    /// Magenta axis value
+    #[arg(long)]
+    magenta: Option<u32>,
+}
+
+impl SixAxisArgs {
+    fn values(&self) -> vcp::SixAxisValues {
+        vcp::SixAxisValues {
+            red: self.red,
+            yellow: self.yellow,
+            green: self.green,
+            cyan: self.cyan,
+            blue: self.blue,
+            magenta: self.magenta,
+        }
+    }
+}
+
+/// Sort keys for `list --sort`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListSort {
+    Name,
+    Device,
+    Primary,
+}
+
+/// Sort keys for `scan-vcp --sort`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ScanSort {
+    Code,
+    Name,
+    Value,
+}
+
+fn parse_handle(s: &str) -> std::result::Result<usize, String> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    match usize::from_str_radix(digits, 16) {
+        Ok(0) => Err("handle must not be null".to_string()),
+        Ok(handle) => Ok(handle),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_hex(s: &str) -> std::result::Result<u8, String> {
+    if let Some(stripped) = s.strip_prefix("0x") {
+        u8::from_str_radix(stripped, 16).map_err(|e| e.to_string())
+    } else {
+        s.parse::<u8>().map_err(|e| e.to_string())
+    }
+}
+
+fn parse_hex_u16(s: &str) -> std::result::Result<u16, String> {
+    if let Some(stripped) = s.strip_prefix("0x") {
+        u16::from_str_radix(stripped, 16).map_err(|e| e.to_string())
+    } else {
+        s.parse::<u16>().map_err(|e| e.to_string())
+    }
+}
+
+pub fn run() -> Result<()> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let silent = cli.silent;
+    let config_path = cli.config.as_deref();
+    let repeat = cli.repeat;
+
+    if cli.read_only && cli.command.changes_monitor() {
+        let name = matches.subcommand_name().unwrap_or_default();
+        return Err(MonitorError::ReadOnlyMode(name.to_string()));
+    }
+    // --all-monitors asks about each monitor in turn instead
+    let per_monitor = matches!(
+        cli.command,
+        Commands::ResetDefaults {
+            all_monitors: true,
+            ..
+        }
+    );
+    if cli.command.is_destructive() && !cli.yes && !per_monitor {
+        let name = matches.subcommand_name().unwrap_or_default();
+        confirm_destructive(name, silent)?;
+    }
+    if cli.exact
+        && let Some(target) = cli.command.target()
+    {
+        target.exact.set(true);
+    }
+    if cli.show_target
+        && !silent
+        && let Some(target) = cli.command.target()
+    {
+        show_target(&target.selector())?;
+    }
+
+    match cli.command {
+        Commands::List {
+            sort,
+            adapter,
+            tree,
+            json,
+        } => list_monitors(sort, adapter.as_deref(), tree, json, silent),
+        Commands::GetBrightness {
+            target,
+            all,
+            normalized,
+            template,
+            json,
+        } => get_brightness(
+            config_path,
+            target.selector(),
+            all,
+            normalized,
+            template.as_deref(),
+            json,
+            silent,
+        ),
+        Commands::SetBrightness {
+            value,
+            target,
+            from_lux,
+            curve,
+            backlight,
+        } => {
+            let value = match (from_lux, curve) {
+                (Some(lux), Some(curve)) => {
+                    LevelValue::Raw(brightness_from_lux(lux, &curve, silent)?)
+                }
+                _ => value.ok_or_else(|| {
+                    MonitorError::InvalidValue(
+                        "a brightness value or --from-lux is required".to_string(),
+                    )
+                })?,
+            };
+            set_brightness(
+                config_path,
+                value,
+                target.selector(),
+                backlight,
+                repeat,
+                silent,
+            )
+        }
+        Commands::WaitBrightness {
+            value,
+            target,
+            tolerance,
+            timeout,
+            interval,
+        } => wait_brightness(
+            config_path,
+            value,
+            target.selector(),
+            tolerance,
+            timeout,
+            Duration::from_millis(interval),
+            silent,
+        ),
+        Commands::WaitInput {
+            input,
+            target,
+            timeout,
+            interval,
+        } => wait_input(
+            input,
+            target.selector(),
+            timeout,
+            Duration::from_millis(interval),
+            silent,
+        ),
+        Commands::EnableDdc { target } => enable_ddc(config_path, target.selector(), silent),
+        Commands::SetAutoBrightness { state, target } => {
+            set_auto_brightness(config_path, state, target.selector(), silent)
+        }
+        Commands::GetContrast {
+            target,
+            all,
+            template,
+            json,
+        } => get_contrast(
+            config_path,
+            target.selector(),
+            all,
+            template.as_deref(),
+            json,
+            silent,
+        ),
+        Commands::GetVolume { target, json } => {
+            get_volume(config_path, target.selector(), json, silent)
+        }
+        Commands::SetVolume {
+            value,
+            target,
+            fade,
+            steps,
+        } => set_volume(config_path, value, target.selector(), fade, steps, silent),
+        Commands::SetContrast { value, target, all } => {
+            set_contrast(config_path, value, target.selector(), all, repeat, silent)
+        }
+        Commands::AdjustContrast { delta, target } => {
+            adjust_contrast(config_path, delta, target.selector(), silent)
+        }
+        Commands::GetVcp {
+            code,
+            target,
+            template,
+            json,
+        } => get_vcp(
+            config_path,
+            code,
+            target.selector(),
+            template.as_deref(),
+            json,
+            silent,
+        ),
+        Commands::SetVcp {
+            code,
+            value,
+            target,
+            until_stable: Some(attempts),
+            tolerance,
+        } => set_vcp_until_stable(
+            config_path,
+            code,
+            value,
+            target.selector(),
+            attempts,
+            tolerance,
+            silent,
+        ),
+        Commands::SetVcp {
+            code,
+            value,
+            target,
+            until_stable: None,
+            ..
+        } => set_vcp(config_path, code, value, target.selector(), repeat, silent),
+        Commands::FindSupporting { code, json } => find_supporting(config_path, code, json, silent),
+        Commands::ListVcp { category, json } => list_vcp(category, json, silent),
+        Commands::ScanVcp {
+            writable_only,
+            sort,
+            all: true,
+            jobs,
+            json,
+            ..
+        } => scan_all(
+            config_path,
+            writable_only,
+            sort,
+            jobs as usize,
+            json,
+            silent,
+        ),
+        Commands::ScanVcp {
+            target,
+            reconcile: true,
+            json,
+            ..
+        } => reconcile_scan(config_path, target.selector(), json, silent),
+        Commands::ScanVcp {
+            target,
+            writable_only,
+            sort,
+            timings,
+            resume,
+            all: false,
+            json,
+            ..
+        } => scan_vcp(
+            &resolve_configured(config_path, &target.selector())?,
+            writable_only,
+            sort,
+            timings,
+            resume.as_deref(),
+            json,
+            silent,
+        ),
+        Commands::ScanDiff {
+            before,
+            after,
+            json,
+        } => scan_diff(&before, &after, json, silent),
+        Commands::SetInput {
+            input,
+            target,
+            preserve,
+            settle,
+        } => set_input(
+            config_path,
+            input,
+            target.selector(),
+            preserve,
+            settle,
+            silent,
+        ),
+        Commands::OutputSelect {
+            output,
+            target,
+            json,
+        } => output_select(output, target.selector(), json, silent),
+        Commands::ScanMode { mode, target, json } => {
+            scan_mode(mode, target.selector(), json, silent)
+        }
+        Commands::SpeakerSelect {
+            speakers,
+            target,
+            json,
+        } => speaker_select(speakers, target.selector(), json, silent),
+        Commands::PerformancePreservation {
+            target,
+            enable,
+            disable,
+            raw,
+            json,
+        } => performance_preservation(target.selector(), &enable, &disable, raw, json, silent),
+        Commands::SixAxis {
+            control,
+            target,
+            values,
+            json,
+        } => six_axis(
+            config_path,
+            control,
+            target.selector(),
+            values.values(),
+            json,
+            silent,
+        ),
+        Commands::Serve { pipe } => {
+            // Write requests are checked against --read-only one by one
+            let session = server::Session::new(cli.read_only, cli.exact)?;
+            server::serve(&pipe, &session, silent)
+        }
+        Commands::Snapshot { target, all, json } => {
+            snapshot(config_path, target.selector(), all, json, silent)
+        }
+        Commands::CurrentInput { target, json } => current_input(target.selector(), json, silent),
+        Commands::Health { target, json } => health(config_path, target.selector(), json, silent),
+        Commands::SelfTest {
+            target,
+            cycles,
+            timeout,
+            json,
+        } => self_test(
+            config_path,
+            target.selector(),
+            cycles,
+            Duration::from_millis(timeout),
+            json,
+            silent,
+        ),
+        Commands::Tune {
+            target,
+            cycles,
+            timeout,
+            dry_run,
+            json,
+        } => tune(
+            config_path,
+            target.selector(),
+            cycles,
+            Duration::from_millis(timeout),
+            dry_run,
+            json,
+            silent,
+        ),
+        Commands::Diagnose {
+            target,
+            device_context,
+            json,
+        } => diagnose(target.selector(), device_context, json, silent),
+        Commands::LinkStatus { target, json } => link_status(target.selector(), json, silent),
+        Commands::MonitorStatus { target, json } => monitor_status(target.selector(), json, silent),
+        Commands::Power {
+            mode,
+            target,
+            force,
+        } => power(config_path, mode, target.selector(), force, silent),
+        Commands::SetAssetTag { tag, target } => set_asset_tag(&tag, target.selector(), silent),
+        Commands::SetUserColorTemp { kelvin, target } => {
+            set_user_color_temp(kelvin, target.selector(), silent)
+        }
+        Commands::SetRgbGain {
+            red,
+            green,
+            blue,
+            hex,
+            target,
+        } => {
+            let gains = match (red, green, blue) {
+                (Some(red), Some(green), Some(blue)) => Some([red, green, blue]),
+                _ => None,
+            };
+            set_rgb_gain(gains, hex, target.selector(), silent)
+        }
+        Commands::SetDisplayDescriptor { descriptor, target } => {
+            set_display_descriptor(&descriptor, target.selector(), silent)
+        }
+        Commands::Descriptor { action } => match action {
+            DescriptorAction::Get { target, json } => {
+                get_descriptor(target.selector(), json, silent)
+            }
+            DescriptorAction::Set { descriptor, target } => {
+                set_display_descriptor(&descriptor, target.selector(), silent)
+            }
+            DescriptorAction::Enable { target } => {
+                set_descriptor_enabled(true, target.selector(), silent)
+            }
+            DescriptorAction::Disable { target } => {
+                set_descriptor_enabled(false, target.selector(), silent)
+            }
+        },
+        Commands::AuxDisplay { action } => match action {
+            AuxDisplayAction::Get { target, json } => {
+                get_aux_display(target.selector(), json, silent)
+            }
+            AuxDisplayAction::Set { text, target } => {
+                set_aux_display(&text, target.selector(), silent)
+            }
+        },
+        Commands::GetCapabilities {
+            target,
+            save,
+            coverage: false,
+            ..
+        } => get_capabilities(target.selector(), save.as_deref(), silent),
+        Commands::GetCapabilities { target, json, .. } => {
+            capability_coverage(target.selector(), json, silent)
+        }
+        Commands::GetEdid { target, json } => get_edid(target.selector(), json, silent),
+        Commands::ParseCapabilities { path, json } => parse_capabilities(&path, json, silent),
+        Commands::SaveSettings { target } => save_settings(target.selector(), silent),
+        Commands::PersistenceTest {
+            target,
+            power_cycle,
+            off_time,
+            force,
+            timeout,
+            json,
+        } => persistence_test(
+            config_path,
+            target.selector(),
+            power_cycle.then(|| Duration::from_millis(off_time)),
+            force,
+            Duration::from_millis(timeout),
+            json,
+            silent,
+        ),
+        Commands::SaveProfile {
+            path,
+            codes,
+            target,
+        } => save_profile(&path, codes, target.selector(), silent),
+        Commands::LoadProfile {
+            path,
+            target,
+            minimal,
+            only_if_input,
+        } => load_profile(
+            config_path,
+            &path,
+            target.selector(),
+            minimal,
+            only_if_input,
+            silent,
+        ),
+        Commands::ValidateProfile { path, target, json } => {
+            validate_profile(config_path, &path, target.selector(), json, silent)
+        }
+        Commands::Drift {
+            baseline,
+            target,
+            json,
+        } => drift(&baseline, target.selector(), json, silent),
+        Commands::RevertVcp {
+            code,
+            baseline,
+            target,
+        } => revert_vcp(config_path, code, &baseline, target.selector(), silent),
+        Commands::BackupAll { path, codes } => backup_all(&path, codes, silent),
+        Commands::RestoreAll { path } => restore_all(&path, silent),
+        Commands::WatchInput {
+            target,
+            interval,
+            pause_for_osd,
+        } => watch_input(
+            config_path,
+            target.selector(),
+            interval,
+            pause_for_osd,
+            silent,
+        ),
+        Commands::MirrorBrightness {
+            source,
+            interval,
+            debounce,
+            pause_for_osd,
+        } => mirror_brightness(
+            config_path,
+            source.selector(),
+            Duration::from_millis(interval),
+            Duration::from_millis(debounce),
+            pause_for_osd,
+            silent,
+        ),
+        Commands::ResetDefaults {
+            color_only,
+            all_monitors: true,
+            json,
+            ..
+        } => reset_all_monitors(config_path, color_only, cli.yes, json, silent),
+        Commands::ResetDefaults {
+            target, color_only, ..
+        } => reset_defaults(config_path, target.selector(), color_only, silent),
+        Commands::ResetBrightnessContrast { target } => {
+            reset_brightness_contrast(target.selector(), silent)
+        }
+    }
+}
+
+/// Ask on the terminal before a destructive command. Without a terminal to ask on (scheduled
+/// tasks, pipes, `--silent`), refuse instead of blocking or guessing.
+fn confirm_destructive(name: &str, silent: bool) -> Result<()> {
+    let question = format!(
+        "'{}' resets monitor settings to factory defaults. Continue?",
+        name
+    );
+    if ask_confirmation(name, &question, silent)? {
+        Ok(())
+    } else {
+        Err(MonitorError::ConfirmationRequired(name.to_string()))
+    }
+}
+
+/// Ask a yes/no `question` on the terminal; anything but yes is no. Fails with
+/// [`MonitorError::ConfirmationRequired`] for command `name` when there is no terminal to ask on.
+fn ask_confirmation(name: &str, question: &str, silent: bool) -> Result<bool> {
+    let stdin = io::stdin();
+    if silent || !stdin.is_terminal() {
+        return Err(MonitorError::ConfirmationRequired(name.to_string()));
+    }
+
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// Run `f` against every monitor, reporting failures per monitor instead of stopping at the first
+fn for_each_monitor(
+    config_path: Option<&Path>,
+    silent: bool,
+    mut f: impl FnMut(&monitor::PhysicalMonitor) -> Result<()>,
+) -> Result<()> {
+    let monitors = enumerate_configured(config_path)?;
+    let mut failed = 0;
+
+    for mon in &monitors {
+        if let Err(e) = f(mon) {
+            failed += 1;
+            if !silent {
+                eprintln!("{}: {}", mon.info().friendly_name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(MonitorError::UnsupportedOperation(format!(
+            "{} of {} monitors failed",
+            failed,
+            monitors.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// [`collect_each`] over every monitor, with its configured overrides applied
+fn collect_from_monitors<T>(
+    config_path: Option<&Path>,
+    f: impl FnMut(&monitor::PhysicalMonitor) -> Result<T>,
+) -> Result<Vec<(monitor::MonitorInfo, Option<T>)>> {
+    Ok(collect_each(&enumerate_configured(config_path)?, f))
+}
+
+/// Run `f` against each of `monitors`, keeping `None` for monitors where it fails
+fn collect_each<M: Monitor, T>(
+    monitors: &[M],
+    mut f: impl FnMut(&M) -> Result<T>,
+) -> Vec<(monitor::MonitorInfo, Option<T>)> {
+    monitors
+        .iter()
+        .map(|mon| (mon.info().clone(), f(mon).ok()))
+        .collect()
+}
+
+/// The monitors of a [`collect_each`] result that answered, in order
+fn answered_only<T>(
+    results: Vec<(monitor::MonitorInfo, Option<T>)>,
+) -> Vec<(monitor::MonitorInfo, T)> {
+    results
+        .into_iter()
+        .filter_map(|(info, value)| value.map(|value| (info, value)))
+        .collect()
+}
+
+/// Every monitor, with the maximum overrides and settle delay the configuration file sets for it
+fn enumerate_configured(config_path: Option<&Path>) -> Result<Vec<monitor::PhysicalMonitor>> {
+    let config = Config::load(config_path)?;
+    Ok(monitor::enumerate_monitors()?
+        .into_iter()
+        .map(|mon| configure(&config, mon))
+        .collect())
+}
+
+/// The monitor `target` selects, with the maximum overrides and settle delay the configuration
+/// file sets for it
+fn resolve_configured(
+    config_path: Option<&Path>,
+    target: &MonitorSelector,
+) -> Result<monitor::PhysicalMonitor> {
+    let config = Config::load(config_path)?;
+    Ok(configure(&config, target.resolve()?))
+}
+
+fn configure(config: &Config, mon: monitor::PhysicalMonitor) -> monitor::PhysicalMonitor {
+    let overrides = config.max_overrides(mon.info());
+    let settle_delay = config.settle_delay(mon.info());
+    let retry = config.retry(mon.info());
+    mon.with_max_overrides(overrides)
+        .with_settle_delay(settle_delay)
+        .with_retry(retry)
+}
+
+/// Write count for `monitor`: `--repeat` if given, otherwise the configured one
+fn configured_repeat(
+    config_path: Option<&Path>,
+    repeat: Option<u32>,
+    monitor: &monitor::MonitorInfo,
+) -> Result<u32> {
+    match repeat {
+        Some(repeat) => Ok(repeat),
+        None => Ok(Config::load(config_path)?.repeat(monitor)),
+    }
+}
+
+/// VCP access to `mon` that keeps its maximum overrides, settle delay and retry policy
+fn configured_vcp(mon: &monitor::PhysicalMonitor) -> vcp::VcpMonitor {
+    vcp::VcpMonitor::with_retry(mon.handle(), mon.retry())
+        .with_max_overrides(mon.max_overrides().clone())
+        .with_settle_delay(mon.settle_delay())
+}
+
+/// Brightness or contrast reading used by the `--all` reports
+#[derive(Clone, Copy, Serialize)]
+struct Level {
+    current: u32,
+    minimum: u32,
+    maximum: u32,
+}
+
+impl From<monitor::BrightnessInfo> for Level {
+    fn from(b: monitor::BrightnessInfo) -> Self {
+        Level {
+            current: b.current,
+            minimum: b.minimum,
+            maximum: b.maximum,
+        }
+    }
+}
+
+impl From<monitor::ContrastInfo> for Level {
+    fn from(c: monitor::ContrastInfo) -> Self {
+        Level {
+            current: c.current,
+            minimum: c.minimum,
+            maximum: c.maximum,
+        }
+    }
+}
+
+/// Print readings from every monitor, noting the ones that don't support the control
+fn print_levels(
+    label: &str,
+    levels: &[(monitor::MonitorInfo, Option<Level>)],
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let output: Vec<serde_json::Map<_, _>> = levels
+            .iter()
+            .map(|(info, level)| {
+                let mut entry = serde_json::Map::new();
+                entry.insert("device_name".into(), info.device_name.clone().into());
+                entry.insert("friendly_name".into(), info.friendly_name.clone().into());
+                entry.insert(label.into(), serde_json::to_value(level)?);
+                Ok(entry)
+            })
+            .collect::<Result<_>>()?;
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        let mut table = Table::new(["Device Name", "Friendly Name", "Current", "Min", "Max"]);
+        for (info, level) in levels {
+            match level {
+                Some(level) => table.add_row([
+                    info.device_name.clone(),
+                    info.friendly_name.clone(),
+                    level.current.to_string(),
+                    level.minimum.to_string(),
+                    level.maximum.to_string(),
+                ]),
+                None => table.add_row([
+                    info.device_name.clone(),
+                    info.friendly_name.clone(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    format!("({} not supported)", label),
+                ]),
+            }
+        }
+        print!("{}", table);
+    }
+
+    Ok(())
+}
+
+/// Apply a signed `delta` to `current`, clamped to the monitor-reported range
+fn adjusted_level(current: u32, minimum: u32, maximum: u32, delta: i32) -> u32 {
+    let target = i64::from(current) + i64::from(delta);
+    target.min(i64::from(maximum)).max(i64::from(minimum)) as u32
+}
+
+/// Stable sort, so monitors that compare equal keep their enumeration order
+fn sort_monitors(monitors: &mut [monitor::MonitorInfo], sort: ListSort) {
+    match sort {
+        ListSort::Name => monitors.sort_by(|a, b| a.friendly_name.cmp(&b.friendly_name)),
+        ListSort::Device => monitors.sort_by(|a, b| a.device_name.cmp(&b.device_name)),
+        ListSort::Primary => monitors.sort_by_key(|m| !m.is_primary),
+    }
+}
+
+/// Stable sort, so entries that compare equal stay in code order
+fn sort_scan_entries(entries: &mut [vcp::ScanEntry], sort: ScanSort) {
+    let name = |entry: &vcp::ScanEntry| {
+        vcp::get_vcp_code_info(entry.response.vcp_code).map_or("Unknown", |info| info.name)
+    };
+    match sort {
+        ScanSort::Code => entries.sort_by_key(|e| e.response.vcp_code),
+        ScanSort::Name => entries.sort_by(|a, b| name(a).cmp(name(b))),
+        ScanSort::Value => entries.sort_by_key(|e| e.response.current_value),
+    }
+}
+
+/// `list --json` entry: the monitor info plus its derived stable ID
+#[derive(Serialize)]
+struct ListedMonitor<'a> {
+    #[serde(flatten)]
+    info: &'a monitor::MonitorInfo,
+    stable_id: String,
+}
+
+impl<'a> ListedMonitor<'a> {
+    fn new(info: &'a monitor::MonitorInfo) -> Self {
+        Self {
+            stable_id: info.stable_id(),
+            info,
+        }
+    }
+}
+
+/// `list --tree --json` entry: an adapter and the monitors attached to it
+#[derive(Serialize)]
+struct ListedAdapter<'a> {
+    adapter_name: &'a str,
+    monitors: Vec<ListedMonitor<'a>>,
+}
+
+fn list_monitors(
+    sort: Option<ListSort>,
+    adapter: Option<&str>,
+    tree: bool,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    // Listing needs no DDC/CI, so skip opening physical monitor handles
+    let mut monitors = monitor::list_monitor_info()?;
+    if let Some(adapter) = adapter {
+        monitors.retain(|info| info.on_adapter(adapter));
+    }
+    if let Some(sort) = sort {
+        sort_monitors(&mut monitors, sort);
+    }
+
+    if silent {
+        return Ok(());
+    }
+
+    if tree {
+        print_monitor_tree(&monitors, json)?;
+    } else if json {
+        let info: Vec<_> = monitors.iter().map(ListedMonitor::new).collect();
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        let mut table = Table::new(["Device Name", "Friendly Name", "Native", "Primary"]);
+        for info in &monitors {
+            table.add_row(monitor_row(info, "Yes"));
+        }
+        print!("{}", table);
+    }
+
+    Ok(())
+}
+
+/// `list` columns for one monitor, with `primary` in the last column of the primary monitor
+fn monitor_row(info: &monitor::MonitorInfo, primary: &str) -> [String; 4] {
+    let native = info
+        .edid
+        .as_ref()
+        .and_then(|edid| edid.native_resolution)
+        .map(|res| res.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    [
+        info.device_name.clone(),
+        info.friendly_name.clone(),
+        native,
+        if info.is_primary { primary } else { "" }.to_string(),
+    ]
+}
+
+/// `list --tree`: each adapter followed by its monitors, indented and in columns that line up
+/// across adapters
+fn print_monitor_tree(monitors: &[monitor::MonitorInfo], json: bool) -> Result<()> {
+    let groups = monitor::group_by_adapter(monitors);
+    if json {
+        let adapters: Vec<_> = groups
+            .into_iter()
+            .map(|(adapter_name, members)| ListedAdapter {
+                adapter_name,
+                monitors: members.into_iter().map(ListedMonitor::new).collect(),
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&adapters)?);
+        return Ok(());
+    }
+
+    let mut table = Table::new([""; 4]);
+    for (_, members) in &groups {
+        for info in members {
+            table.add_row(monitor_row(info, "Primary"));
+        }
+    }
+    let mut lines = table.row_lines().into_iter();
+    for (adapter_name, members) in &groups {
+        if adapter_name.is_empty() {
+            println!("Unknown adapter");
+        } else {
+            println!("{}", adapter_name);
+        }
+        for line in lines.by_ref().take(members.len()) {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Fields available to `--template` on get-brightness and get-contrast
+const LEVEL_TEMPLATE_FIELDS: &[&str] = &["current", "min", "max"];
+
+/// Fields available to `--template` on get-vcp
+const VCP_TEMPLATE_FIELDS: &[&str] = &["code", "name", "current", "max", "type", "decoded"];
+
+fn level_template_fields(current: u32, minimum: u32, maximum: u32) -> [(&'static str, String); 3] {
+    [
+        ("current", current.to_string()),
+        ("min", minimum.to_string()),
+        ("max", maximum.to_string()),
+    ]
+}
+
+fn get_brightness(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    all: bool,
+    normalized: bool,
+    output_template: Option<&str>,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    if let Some(output_template) = output_template {
+        template::validate(output_template, LEVEL_TEMPLATE_FIELDS)?;
+    }
+
+    if all {
+        let levels =
+            collect_from_monitors(config_path, |mon| mon.get_brightness().map(Level::from))?;
+        return print_levels("brightness", &levels, json, silent);
+    }
+
+    let mon = resolve_configured(config_path, &target)?;
+
+    if normalized {
+        let value = mon.get_brightness_normalized()?;
+        if !silent {
+            if json {
+                println!("{}", serde_json::json!({ "normalized": value }));
+            } else {
+                println!("Current brightness: {:.2}", value);
+            }
+        }
+        return Ok(());
+    }
+
+    let brightness = mon.get_brightness()?;
+
+    if !silent {
+        if !json {
+            warn_if_clamped("brightness", brightness.reported, brightness.maximum);
+        }
+        if let Some(output_template) = output_template {
+            let fields =
+                level_template_fields(brightness.current, brightness.minimum, brightness.maximum);
+            println!("{}", template::render(output_template, &fields)?);
+        } else if json {
+            println!("{}", serde_json::to_string_pretty(&brightness)?);
+        } else {
+            println!(
+                "Current brightness: {} (min: {}, max: {})",
+                brightness.current, brightness.minimum, brightness.maximum
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Tell the user on stderr that the monitor reported `reported` for `what`, above its maximum, and
+/// the maximum is shown instead. JSON output carries the reported value as a field.
+fn warn_if_clamped(what: &str, reported: Option<u32>, maximum: u32) {
+    if let Some(reported) = reported {
+        eprintln!(
+            "Warning: monitor reported {} for {}, above its maximum of {}; using {}",
+            reported, what, maximum, maximum
+        );
+    }
+}
+
+/// Raw level for `value`. A notch is scaled to the range `range` reports, using the notch count
+/// configured for `code`.
+fn resolve_level(
+    config_path: Option<&Path>,
+    info: &monitor::MonitorInfo,
+    code: u8,
+    value: LevelValue,
+    range: impl FnOnce() -> Result<(u32, u32)>,
+) -> Result<u32> {
+    let notch = match value {
+        LevelValue::Raw(value) => return Ok(value),
+        LevelValue::Notch(notch) => notch,
+    };
+
+    let config = Config::load(config_path)?;
+    let notches = config.notches(info, code).ok_or_else(|| {
+        MonitorError::Config(format!(
+            "no notch count configured for VCP code 0x{:02X} on {}",
+            code, info.friendly_name
+        ))
+    })?;
+    let (minimum, maximum) = range()?;
+    monitor::level_from_notch(notch, notches, minimum, maximum)
+}
+
+fn set_brightness(
+    config_path: Option<&Path>,
+    value: LevelValue,
+    target: MonitorSelector,
+    backlight: bool,
+    repeat: Option<u32>,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    let repeat = configured_repeat(config_path, repeat, mon.info())?;
+    // Probe through VCP reads, which tell a missing control apart from a busy channel
+    let control = BrightnessControl::select(
+        backlight,
+        || vcp_mon.get_vcp_feature(vcp::codes::BRIGHTNESS).map(drop),
+        || vcp_mon.get_backlight_white().map(drop),
+    )?
+    .ok_or_else(|| {
+        MonitorError::ControlUnavailable(if backlight {
+            format!(
+                "{} does not report a backlight white level (VCP 0x6B)",
+                mon.info().friendly_name
+            )
+        } else {
+            format!(
+                "{} reports neither brightness (VCP 0x10) nor a backlight white level (VCP 0x6B)",
+                mon.info().friendly_name
+            )
+        })
+    })?;
+
+    let value = resolve_level(
+        config_path,
+        mon.info(),
+        control.code(),
+        value,
+        || match control {
+            BrightnessControl::Luminance => {
+                let brightness = mon.get_brightness()?;
+                Ok((brightness.minimum, brightness.maximum))
+            }
+            BrightnessControl::BacklightWhite => {
+                Ok((0, vcp_mon.max_for_code(vcp::codes::BACKLIGHT_WHITE)?))
+            }
+        },
+    )?;
+    match control {
+        BrightnessControl::Luminance => {
+            monitor::repeat_write(repeat, || mon.set_brightness(value))?;
+            if !silent {
+                println!("Brightness set to {}", value);
+            }
+        }
+        BrightnessControl::BacklightWhite => {
+            monitor::repeat_write(repeat, || vcp_mon.set_backlight_white(value))?;
+            if !silent {
+                println!("Backlight white level (VCP 0x6B) set to {}", value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn brightness_from_lux(lux: f64, curve_path: &Path, silent: bool) -> Result<u32> {
+    let curve = BrightnessCurve::load(curve_path)?;
+    let value = curve.brightness_for(lux)?;
+    if !silent {
+        println!("{} lux maps to brightness {}", lux, value);
+    }
+    Ok(value)
+}
+
+fn wait_brightness(
+    config_path: Option<&Path>,
+    value: u32,
+    target: MonitorSelector,
+    tolerance: u32,
+    timeout: f64,
+    interval: Duration,
+    silent: bool,
+) -> Result<()> {
+    let timeout = Duration::try_from_secs_f64(timeout)
+        .map_err(|_| MonitorError::InvalidValue(format!("timeout {} is not valid", timeout)))?;
+    let mon = resolve_configured(config_path, &target)?;
+    // Give a write that started the transition time to register before the first read
+    thread::sleep(mon.settle_delay());
+    let reached = monitor::wait_for_brightness(&mon, value, tolerance, timeout, interval)?;
+    if !silent {
+        println!("Brightness reached {}", reached);
+    }
+    Ok(())
+}
+
+fn wait_input(
+    input: decode::InputSource,
+    target: MonitorSelector,
+    timeout: f64,
+    interval: Duration,
+    silent: bool,
+) -> Result<()> {
+    let timeout = Duration::try_from_secs_f64(timeout)
+        .map_err(|_| MonitorError::InvalidValue(format!("timeout {} is not valid", timeout)))?;
+    let mon = target.resolve()?;
+    let reached = vcp::VcpMonitor::new(mon.handle()).wait_for_input(input, timeout, interval)?;
+    if !silent {
+        println!("Input switched to {}", reached);
+    }
+    Ok(())
+}
+
+fn enable_ddc(config_path: Option<&Path>, target: MonitorSelector, silent: bool) -> Result<()> {
+    let config = Config::load(config_path)?;
+    let mon = target.resolve()?;
+    let info = mon.info();
+    let write = write_enable_ddc(&config, info, &vcp::VcpMonitor::new(mon.handle()))?;
+    if !silent {
+        println!(
+            "Wrote {} to VCP code 0x{:02X} on {} to enable DDC/CI",
+            write.value, write.code, info.friendly_name
+        );
+    }
+    Ok(())
+}
+
+/// Send the DDC/CI enable write configured for `info`, returning what was written
+fn write_enable_ddc(
+    config: &Config,
+    info: &monitor::MonitorInfo,
+    vcp_mon: &vcp::VcpMonitor,
+) -> Result<crate::config::VcpWrite> {
+    let write = config.enable_ddc(info).ok_or_else(|| {
+        MonitorError::Config(format!(
+            "no enable_ddc code configured for {} ({}); the code is manufacturer-specific and \
+             must be set in the config file",
+            info.friendly_name,
+            info.stable_id()
+        ))
+    })?;
+    vcp_mon.set_vcp_feature(write.code, write.value)?;
+    Ok(write)
+}
+
+fn set_auto_brightness(
+    config_path: Option<&Path>,
+    state: Switch,
+    target: MonitorSelector,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    let enabled = state == Switch::On;
+    let label = if enabled { "on" } else { "off" };
+
+    if !vcp_mon.set_auto_brightness(enabled)? {
+        return Err(MonitorError::UnsupportedOperation(format!(
+            "monitor accepted the write but did not turn auto brightness {} (VCP 0x66)",
+            label
+        )));
+    }
+
+    if !silent {
+        println!("Auto brightness turned {}", label);
+    }
+    Ok(())
+}
+
+fn get_contrast(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    all: bool,
+    output_template: Option<&str>,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    if let Some(output_template) = output_template {
+        template::validate(output_template, LEVEL_TEMPLATE_FIELDS)?;
+    }
+
+    if all {
+        let levels = collect_from_monitors(config_path, |mon| mon.get_contrast().map(Level::from))?;
+        return print_levels("contrast", &levels, json, silent);
+    }
+
+    let mon = resolve_configured(config_path, &target)?;
+    let contrast = mon.get_contrast()?;
+
+    if !silent {
+        if !json {
+            warn_if_clamped("contrast", contrast.reported, contrast.maximum);
+        }
+        if let Some(output_template) = output_template {
+            let fields =
+                level_template_fields(contrast.current, contrast.minimum, contrast.maximum);
+            println!("{}", template::render(output_template, &fields)?);
+        } else if json {
+            println!("{}", serde_json::to_string_pretty(&contrast)?);
+        } else {
+            println!(
+                "Current contrast: {} (min: {}, max: {})",
+                contrast.current, contrast.minimum, contrast.maximum
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn get_volume(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let volume = configured_vcp(&mon).get_volume()?;
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "current": volume.current_value,
+                "maximum": volume.maximum_value,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!(
+                "Current volume: {} (max: {})",
+                volume.current_value, volume.maximum_value
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn set_volume(
+    config_path: Option<&Path>,
+    value: u32,
+    target: MonitorSelector,
+    fade: Option<u64>,
+    steps: u32,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    match fade {
+        Some(fade) => vcp_mon.fade_volume(value, Duration::from_millis(fade), steps)?,
+        None => vcp_mon.set_volume(value)?,
+    }
+    if !silent {
+        println!("Volume set to {}", value);
+    }
+    Ok(())
+}
+
+fn set_contrast(
+    config_path: Option<&Path>,
+    value: LevelValue,
+    target: MonitorSelector,
+    all: bool,
+    repeat: Option<u32>,
+    silent: bool,
+) -> Result<()> {
+    let resolve = |mon: &monitor::PhysicalMonitor| {
+        resolve_level(config_path, mon.info(), vcp::codes::CONTRAST, value, || {
+            let contrast = mon.get_contrast()?;
+            Ok((contrast.minimum, contrast.maximum))
+        })
+    };
+
+    if all {
+        return for_each_monitor(config_path, silent, |mon| {
+            let value = resolve(mon)?;
+            let repeat = configured_repeat(config_path, repeat, mon.info())?;
+            monitor::repeat_write(repeat, || mon.set_contrast(value))?;
+            if !silent {
+                println!("{}: Contrast set to {}", mon.info().friendly_name, value);
+            }
+            Ok(())
+        });
+    }
+
+    let mon = resolve_configured(config_path, &target)?;
+    let value = resolve(&mon)?;
+    let repeat = configured_repeat(config_path, repeat, mon.info())?;
+    monitor::repeat_write(repeat, || mon.set_contrast(value))?;
+    if !silent {
+        println!("Contrast set to {}", value);
+    }
+    Ok(())
+}
+
+fn adjust_contrast(
+    config_path: Option<&Path>,
+    delta: i32,
+    target: MonitorSelector,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let contrast = mon.get_contrast()?;
+    monitor::check_range(contrast.minimum, contrast.maximum)?;
+    let value = adjusted_level(contrast.current, contrast.minimum, contrast.maximum, delta);
+    mon.set_contrast(value)?;
+    if !silent {
+        println!("Contrast adjusted from {} to {}", contrast.current, value);
+    }
+    Ok(())
+}
+
+fn get_vcp(
+    config_path: Option<&Path>,
+    code: u8,
+    target: MonitorSelector,
+    output_template: Option<&str>,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    if let Some(output_template) = output_template {
+        template::validate(output_template, VCP_TEMPLATE_FIELDS)?;
+    }
+
+    let mon = resolve_configured(config_path, &target)?;
+    let response = configured_vcp(&mon).get_vcp_feature(code)?;
+
+    if !silent {
+        if !json {
+            warn_if_clamped(
+                &format!("VCP code 0x{:02X}", code),
+                response.reported_value,
+                response.maximum_value,
+            );
+        }
+        if let Some(output_template) = output_template {
+            let fields = [
+                ("code", format!("0x{:02X}", code)),
+                (
+                    "name",
+                    vcp::get_vcp_code_info(code)
+                        .map_or("Unknown", |info| info.name)
+                        .to_string(),
+                ),
+                ("current", response.current_value.to_string()),
+                ("max", response.maximum_value.to_string()),
+                ("type", format!("{:?}", response.code_type)),
+                (
+                    "decoded",
+                    decode::decode_value(code, response.current_value).unwrap_or_default(),
+                ),
+            ];
+            println!("{}", template::render(output_template, &fields)?);
+        } else if json {
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        } else {
+            let info = vcp::get_vcp_code_info(code);
+            if let Some(info) = info {
+                println!("VCP Code: 0x{:02X} - {}", code, info.name);
+                println!("Description: {}", info.description);
+            } else {
+                println!("VCP Code: 0x{:02X}", code);
+            }
+            match decode::decode_value(code, response.current_value) {
+                Some(decoded) => println!(
+                    "Current value: {} [{}] (max: {})",
+                    response.current_value, decoded, response.maximum_value
+                ),
+                None => println!(
+                    "Current value: {} (max: {})",
+                    response.current_value, response.maximum_value
+                ),
+            }
+            println!("Type: {:?}", response.code_type);
+        }
+    }
+
+    Ok(())
+}
+
+fn set_vcp(
+    config_path: Option<&Path>,
+    code: u8,
+    value: u32,
+    target: MonitorSelector,
+    repeat: Option<u32>,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    let repeat = configured_repeat(config_path, repeat, mon.info())?;
+    monitor::repeat_write(repeat, || vcp_mon.set_vcp_feature(code, value))?;
+    if !silent {
+        println!("VCP code 0x{:02X} set to {}", code, value);
+    }
+    Ok(())
+}
+
+fn set_vcp_until_stable(
+    config_path: Option<&Path>,
+    code: u8,
+    value: u32,
+    target: MonitorSelector,
+    attempts: u32,
+    tolerance: u32,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    // Give the monitor at least the usual gap between writes before reading back
+    let settle = mon.settle_delay().max(monitor::REPEAT_DELAY);
+    let reported =
+        configured_vcp(&mon).set_vcp_until_stable(code, value, attempts, settle, tolerance)?;
+
+    if !monitor::within_tolerance(reported, value, tolerance) {
+        return Err(MonitorError::ControlUnavailable(format!(
+            "VCP code 0x{:02X} still reads {} after {} writes of {}",
+            code, reported, attempts, value
+        )));
+    }
+    if !silent {
+        println!(
+            "VCP code 0x{:02X} set to {} (reads {})",
+            code, value, reported
+        );
+    }
+    Ok(())
+}
+
+fn find_supporting(config_path: Option<&Path>, code: u8, json: bool, silent: bool) -> Result<()> {
+    let results =
+        collect_from_monitors(config_path, |mon| configured_vcp(mon).get_vcp_feature(code))?;
+    let total = results.len();
+    let supporting = answered_only(results);
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let output: Vec<_> = supporting
+            .iter()
+            .map(|(info, response)| {
+                serde_json::json!({
+                    "device_name": info.device_name,
+                    "friendly_name": info.friendly_name,
+                    "stable_id": info.stable_id(),
+                    "current_value": response.current_value,
+                    "maximum_value": response.maximum_value,
+                    "decoded": decode::decode_value(code, response.current_value),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let name = vcp::get_vcp_code_info(code).map_or("Unknown", |info| info.name);
+    eprintln!(
+        "{} of {} monitors support VCP code 0x{:02X} ({})\n",
+        supporting.len(),
+        total,
+        code,
+        name
+    );
+    if supporting.is_empty() {
+        return Ok(());
+    }
+
+    let mut table = Table::new(["Device Name", "Friendly Name", "Current", "Max"]);
+    for (info, response) in &supporting {
+        let current = match decode::decode_value(code, response.current_value) {
+            Some(decoded) => format!("{} [{}]", response.current_value, decoded),
+            None => response.current_value.to_string(),
+        };
+        table.add_row([
+            info.device_name.clone(),
+            info.friendly_name.clone(),
+            current,
+            response.maximum_value.to_string(),
+        ]);
+    }
+    print!("{}", table);
+
+    Ok(())
+}
+
+fn list_vcp(category: Option<vcp::VcpCategory>, json: bool, silent: bool) -> Result<()> {
+    if !silent {
+        let codes: Vec<&vcp::VcpFeatureInfo> = match category {
+            Some(category) => vcp::known_codes_in(category).collect(),
+            None => vcp::KNOWN_VCP_CODES.iter().collect(),
+        };
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&codes)?);
+        } else {
+            let mut table = Table::new(["Code", "Name", "Category", "Description"]);
+            for info in codes {
+                table.add_row([
+                    format!("0x{:02X}", info.code),
+                    info.name.to_string(),
+                    info.category.to_string(),
+                    info.description.to_string(),
+                ]);
+            }
+            print!("{}", table);
+        }
+    }
+    Ok(())
+}
+
+fn scan_vcp(
+    mon: &monitor::PhysicalMonitor,
+    writable_only: bool,
+    sort: Option<ScanSort>,
+    timings: bool,
+    resume: Option<&Path>,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let vcp_mon = configured_vcp(mon);
+
+    if !json && !silent {
+        eprintln!("Scanning monitor for supported VCP codes...");
+    }
+
+    let report = match resume {
+        Some(path) => scan_resumable(&vcp_mon, mon.info(), path, json || silent)?,
+        None if json || silent => vcp_mon.scan_report(),
+        None => {
+            let report = vcp_mon.scan_report_with_progress(print_progress);
+            eprintln!();
+            report
+        }
+    };
+    let mut entries = report.entries();
+    if writable_only {
+        entries.retain(|entry| entry.class.is_writable());
+    }
+    if let Some(sort) = sort {
+        sort_scan_entries(&mut entries, sort);
+    }
+
+    if !silent {
+        if json && timings {
+            let output = serde_json::json!({
+                "entries": entries,
+                "probe_time_ms": report.probe_time().as_secs_f64() * 1000.0,
+                "probes": report.probes,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else if json {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        } else {
+            let kind = if writable_only {
+                "adjustable"
+            } else {
+                "supported"
+            };
+            eprintln!("Found {} {} VCP codes\n", entries.len(), kind);
+            print_scan_entries(&entries);
+
+            if timings {
+                print_scan_timings(&report);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn reconcile_scan(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    if !json && !silent {
+        eprintln!("Comparing the capabilities string with the codes the monitor answers...");
+    }
+    let reconciliation = configured_vcp(&mon).reconcile_capabilities()?;
+
+    if silent {
+        return Ok(());
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reconciliation)?);
+        return Ok(());
+    }
+
+    let list = |codes: &[u8]| {
+        if codes.is_empty() {
+            return "none".to_string();
+        }
+        codes
+            .iter()
+            .map(|code| {
+                let name = vcp::get_vcp_code_info(*code).map_or("Unknown", |info| info.name);
+                format!("0x{:02X} {}", code, name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    println!(
+        "Advertised and answering:  {} codes",
+        reconciliation.confirmed.len()
+    );
+    println!(
+        "Advertised, no answer:     {}",
+        list(&reconciliation.advertised_unresponsive)
+    );
+    println!(
+        "Answering, not advertised: {}",
+        list(&reconciliation.responsive_unadvertised)
+    );
+    if reconciliation.is_consistent() {
+        println!("The capabilities string matches the monitor");
+    }
+    Ok(())
+}
+
+/// Codes whose value differs between two scans, leaving out read-only codes: counters like
+/// usage time change on their own and would show up in every diff
+fn scan_changes(
+    before: &[vcp::VcpFeatureResponse],
+    after: &[vcp::VcpFeatureResponse],
+) -> Vec<profile::ProfileChange> {
+    profile::MonitorProfile::from_responses(before)
+        .diff(&profile::MonitorProfile::from_responses(after))
+        .into_iter()
+        .filter(|change| !vcp::READ_ONLY_CODES.contains(&change.code))
+        .collect()
+}
+
+fn scan_diff(before: &Path, after: &Path, json: bool, silent: bool) -> Result<()> {
+    let changes = scan_changes(&vcp::load_scan(before)?, &vcp::load_scan(after)?);
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let output: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                serde_json::json!({
+                    "code": change.code,
+                    "before": change.baseline,
+                    "after": change.current,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if changes.is_empty() {
+        println!("No changes between the two scans");
+    } else {
+        let show = |code: u8, value: Option<u32>| match value {
+            Some(value) => match decode::decode_value(code, value) {
+                Some(decoded) => format!("{} ({})", value, decoded),
+                None => value.to_string(),
+            },
+            None => "-".to_string(),
+        };
+        let mut table = Table::new(["Code", "Name", "Before", "After"]);
+        for change in &changes {
+            let name = vcp::get_vcp_code_info(change.code).map_or("Unknown", |info| info.name);
+            table.add_row([
+                format!("0x{:02X}", change.code),
+                name.to_string(),
+                show(change.code, change.baseline),
+                show(change.code, change.current),
+            ]);
+        }
+        print!("{}", table);
+        println!("{} codes changed", changes.len());
+    }
+
+    Ok(())
+}
+
+/// Print the monitor `target` resolves to, for `--show-target`. Goes to stderr so JSON and
+/// template output stay parseable.
+fn show_target(target: &MonitorSelector) -> Result<()> {
+    let mon = target.resolve()?;
+    eprintln!("{}", target_line(mon.info()));
+    Ok(())
+}
+
+/// The `--show-target` line for `info`
+fn target_line(info: &monitor::MonitorInfo) -> String {
+    format!(
+        "Target: {} ({}) {}",
+        info.device_name, info.friendly_name, info.instance_name
+    )
+}
+
+/// Overwrite the current stderr line with `done/total`
+fn print_progress(done: usize, total: usize) {
+    eprint!("\r{}/{}", done, total);
+}
+
+fn print_scan_entries(entries: &[vcp::ScanEntry]) {
+    let mut table = Table::new([
+        "Code",
+        "Name",
+        "CurrentValue",
+        "MaxValue",
+        "Class",
+        "Description",
+    ]);
+
+    for entry in entries {
+        let response = &entry.response;
+        let info = vcp::get_vcp_code_info(response.vcp_code);
+        let name = info.map(|i| i.name).unwrap_or("Unknown");
+        let description = info.map(|i| i.description).unwrap_or("");
+
+        table.add_row([
+            format!("0x{:02X}", response.vcp_code),
+            name.to_string(),
+            response.current_value.to_string(),
+            response.maximum_value.to_string(),
+            entry.class.to_string(),
+            description.to_string(),
+        ]);
+    }
+    print!("{}", table);
+}
+
+/// Monitors scanned at once by `scan-vcp --all` unless `--jobs` says otherwise. One at a time
+/// is slowest but never contends for a shared I2C bus.
+const DEFAULT_SCAN_JOBS: u32 = 1;
+
+/// `scan-vcp --all`: scan every monitor, at most `jobs` at a time
+fn scan_all(
+    config_path: Option<&Path>,
+    writable_only: bool,
+    sort: Option<ScanSort>,
+    jobs: usize,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let config = Config::load(config_path)?;
+    let monitors: Vec<_> = monitor::enumerate_monitors()?
+        .into_iter()
+        .map(|mon| configure(&config, mon))
+        .collect();
+    if !json && !silent {
+        eprintln!(
+            "Scanning {} monitors for supported VCP codes ({} at a time)...",
+            monitors.len(),
+            jobs
+        );
+    }
+
+    let scans = monitor::run_bounded(monitors, jobs, |mon| {
+        let report = configured_vcp(&mon).scan_report();
+        let mut entries = report.entries();
+        if writable_only {
+            entries.retain(|entry| entry.class.is_writable());
+        }
+        if let Some(sort) = sort {
+            sort_scan_entries(&mut entries, sort);
+        }
+        (mon.info().clone(), entries)
+    });
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let output: Vec<_> = scans
+            .iter()
+            .map(|(info, entries)| {
+                serde_json::json!({
+                    "device_name": info.device_name,
+                    "friendly_name": info.friendly_name,
+                    "stable_id": info.stable_id(),
+                    "entries": entries,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    for (i, (info, entries)) in scans.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!(
+            "{} ({}): {} codes",
+            info.friendly_name,
+            info.device_name,
+            entries.len()
+        );
+        if !entries.is_empty() {
+            print_scan_entries(entries);
+        }
+    }
+
+    Ok(())
+}
+
+/// `scan-vcp --resume`: continue from the state file if there is one, save after every code,
+/// and remove the file once the scan completes
+fn scan_resumable(
+    vcp_mon: &vcp::VcpMonitor,
+    info: &monitor::MonitorInfo,
+    path: &Path,
+    quiet: bool,
+) -> Result<vcp::ScanReport> {
+    let stable_id = info.stable_id();
+    let mut state = match vcp::ScanState::load(path)? {
+        Some(state) if state.monitor != stable_id => {
+            return Err(MonitorError::InvalidValue(format!(
+                "scan state {} belongs to monitor {}, not {}",
+                path.display(),
+                state.monitor,
+                stable_id
+            )));
+        }
+        Some(state) => {
+            if !quiet && !state.is_complete() {
+                eprintln!(
+                    "Resuming at code 0x{:02X} ({} codes left)",
+                    state.next_code,
+                    256 - state.next_code
+                );
+            }
+            state
+        }
+        None => vcp::ScanState::new(stable_id),
+    };
+
+    let report = vcp_mon.scan_report_resumable(&mut state, |state| state.save(path))?;
+    fs::remove_file(path)?;
+    Ok(report)
+}
+
+/// Number of probes listed by `scan-vcp --timings`
+const SLOWEST_PROBES: usize = 10;
+
+fn print_scan_timings(report: &vcp::ScanReport) {
+    println!(
+        "\nRead {} codes in {:.0} ms; slowest reads:",
+        report.probes.len(),
+        report.probe_time().as_secs_f64() * 1000.0
+    );
+    let mut table = Table::new(["Code", "Name", "Supported", "Time (ms)"]).align_right(3);
+    for probe in report.slowest_probes(SLOWEST_PROBES) {
+        let name = vcp::get_vcp_code_info(probe.code).map_or("Unknown", |info| info.name);
+        table.add_row([
+            format!("0x{:02X}", probe.code),
+            name.to_string(),
+            if probe.supported { "Yes" } else { "No" }.to_string(),
+            format!("{:.1}", probe.elapsed.as_secs_f64() * 1000.0),
+        ]);
+    }
+    print!("{}", table);
+}
+
+fn set_input(
+    config_path: Option<&Path>,
+    input: decode::InputSource,
+    target: MonitorSelector,
+    preserve: bool,
+    settle: u64,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+
+    if !preserve {
+        vcp_mon.set_input_source(input)?;
+        if !silent {
+            println!("Input switched to {}", input);
+        }
+        return Ok(());
+    }
+
+    let restored =
+        profile::switch_input_preserving(&vcp_mon, input, Duration::from_millis(settle))?;
+    if !silent {
+        println!("Input switched to {}", input);
+        for value in &restored.values {
+            let name = vcp::get_vcp_code_info(value.code).map_or("Unknown", |info| info.name);
+            println!("Restored {} to {}", name, value.value);
+        }
+    }
+    Ok(())
+}
+
+fn output_select(
+    output: Option<decode::InputSource>,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+
+    if let Some(output) = output {
+        vcp_mon.set_output_select(output)?;
+        if !silent {
+            println!("Output set to {}", output);
+        }
+        return Ok(());
+    }
+
+    let output = vcp_mon.get_output_select()?;
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "output": output,
+                "name": output.to_string(),
+                "value": output.value(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!("Current output: {} (0x{:02X})", output, output.value());
+        }
+    }
+
+    Ok(())
+}
+
+fn scan_mode(
+    mode: Option<decode::ScanMode>,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+
+    if let Some(mode) = mode {
+        vcp_mon.set_scan_mode(mode)?;
+        if !silent {
+            println!("Scan mode set to {}", mode);
+        }
+        return Ok(());
+    }
+
+    let mode = vcp_mon.get_scan_mode()?;
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "mode": mode,
+                "name": mode.to_string(),
+                "value": mode.value(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!("Scan mode: {} (0x{:02X})", mode, mode.value());
+        }
+    }
+
+    Ok(())
+}
+
+fn speaker_select(
+    speakers: Option<decode::SpeakerSelect>,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+
+    if let Some(speakers) = speakers {
+        vcp_mon.set_speaker_select(speakers)?;
+        if !silent {
+            println!("Speakers set to {}", speakers);
+        }
+        return Ok(());
+    }
+
+    let speakers = vcp_mon.get_speaker_select()?;
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "speakers": speakers,
+                "name": speakers.to_string(),
+                "value": speakers.value(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!("Speakers: {} (0x{:02X})", speakers, speakers.value());
+        }
+    }
+
+    Ok(())
+}
+
+fn set_user_color_temp(kelvin: u32, target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let temperature = vcp::VcpMonitor::new(mon.handle()).set_user_color_temperature(kelvin)?;
+    if !silent {
+        println!(
+            "User color temperature set to {} (multiplier {} of {} K steps)",
+            temperature, temperature.multiplier, temperature.increment
+        );
+    }
+    Ok(())
+}
+
+fn set_rgb_gain(
+    gains: Option<[u32; 3]>,
+    hex: Option<decode::HexTriplet>,
+    target: MonitorSelector,
+    silent: bool,
+) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    // clap guarantees either all three gains or the triplet
+    let gains = match (gains, hex) {
+        (Some(gains), _) => gains,
+        (None, Some(triplet)) => vcp_mon.rgb_gains_for(triplet)?,
+        (None, None) => unreachable!("clap requires the gains or --hex"),
+    };
+    vcp_mon.set_rgb_gains(gains)?;
+    if !silent {
+        let [red, green, blue] = gains;
+        match hex {
+            Some(triplet) => println!(
+                "RGB gains set to red {}, green {}, blue {} (from {})",
+                red, green, blue, triplet
+            ),
+            None => println!(
+                "RGB gains set to red {}, green {}, blue {}",
+                red, green, blue
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn performance_preservation(
+    target: MonitorSelector,
+    enable: &[u8],
+    disable: &[u8],
+    raw: Option<u16>,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+
+    // Individual flags are changed read-modify-write so the other OEM features keep their state
+    let mut features = vcp_mon.get_performance_preservation()?;
+    let changing = raw.is_some() || !enable.is_empty() || !disable.is_empty();
+    if let Some(raw) = raw {
+        features = decode::PerformancePreservation::from_bits(raw);
+    }
+    for &bit in enable {
+        features.set(bit, true)?;
+    }
+    for &bit in disable {
+        features.set(bit, false)?;
+    }
+    if changing {
+        vcp_mon.set_performance_preservation(features)?;
+    }
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "value": features.bits(),
+                "enabled": features.enabled(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            let prefix = if changing {
+                "Performance preservation set to"
+            } else {
+                "Performance preservation:"
+            };
+            println!("{} 0x{:04X} ({})", prefix, features.bits(), features);
+        }
+    }
+
+    Ok(())
+}
+
+fn six_axis(
+    config_path: Option<&Path>,
+    control: SixAxisControl,
+    target: MonitorSelector,
+    values: vcp::SixAxisValues,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+
+    if values.is_empty() {
+        let current = match control {
+            SixAxisControl::Saturation => vcp_mon.get_six_axis_saturation()?,
+            SixAxisControl::Hue => vcp_mon.get_six_axis_hue()?,
+        };
+        if !silent {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&current)?);
+            } else {
+                for axis in vcp::ColorAxis::ALL {
+                    let value = current
+                        .get(axis)
+                        .map_or_else(|| "-".to_string(), |value| value.to_string());
+                    println!("{:<10} {}", axis, value);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let writes = match control {
+        SixAxisControl::Saturation => vcp_mon.set_six_axis_saturation(&values),
+        SixAxisControl::Hue => vcp_mon.set_six_axis_hue(&values),
+    };
+
+    if !silent {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&writes)?);
+        } else {
+            for write in &writes {
+                match (&write.error, write.written) {
+                    (Some(e), _) => eprintln!("{}: {}", write.axis, e),
+                    (None, Some(value)) if write.clamped() => println!(
+                        "{}: set to {} (clamped from {})",
+                        write.axis, value, write.requested
+                    ),
+                    (None, _) => println!("{}: set to {}", write.axis, write.requested),
+                }
+            }
+        }
+    }
+
+    let failed = writes.iter().filter(|write| write.error.is_some()).count();
+    if failed > 0 {
+        return Err(MonitorError::UnsupportedOperation(format!(
+            "{} of {} axes failed",
+            failed,
+            writes.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn snapshot(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    all: bool,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let snapshots = if all {
+        collect_from_monitors(config_path, |mon| mon.snapshot())?
+    } else {
+        let mon = resolve_configured(config_path, &target)?;
+        vec![(mon.info().clone(), Some(mon.snapshot()?))]
+    };
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let output: Vec<_> = snapshots
+            .iter()
+            .map(|(info, snapshot)| {
+                serde_json::json!({
+                    "device_name": info.device_name,
+                    "friendly_name": info.friendly_name,
+                    "snapshot": snapshot,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let show = |value: Option<u32>| value.map_or("-".to_string(), |v| v.to_string());
+    let mut table = Table::new([
+        "Device Name",
+        "Friendly Name",
+        "Brightness",
+        "Contrast",
+        "Input",
+    ]);
+    for (info, snapshot) in &snapshots {
+        match snapshot {
+            Some(snapshot) => table.add_row([
+                info.device_name.clone(),
+                info.friendly_name.clone(),
+                show(snapshot.brightness.as_ref().map(|b| b.current)),
+                show(snapshot.contrast.as_ref().map(|c| c.current)),
+                snapshot
+                    .input
+                    .map_or("-".to_string(), |input| input.to_string()),
+            ]),
+            None => table.add_row([
+                info.device_name.clone(),
+                info.friendly_name.clone(),
+                "-".to_string(),
+                "-".to_string(),
+                "(no response over DDC/CI)".to_string(),
+            ]),
+        }
+    }
+    print!("{}", table);
+
+    Ok(())
+}
+
+fn current_input(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    let input = vcp_mon.get_input_source()?;
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "input": input,
+                "name": input.to_string(),
+                "value": input.value(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!("Current input: {} (0x{:02X})", input, input.value());
+        }
+    }
+
+    Ok(())
+}
+
+/// Advisory state of a monitor's power-on hours relative to the configured threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum UsageHealth {
+    Ok,
+    /// Within the last 10% before the threshold
+    Approaching,
+    Exceeded,
+}
+
+impl UsageHealth {
+    fn assess(hours: u32, threshold: u32) -> Self {
+        if hours >= threshold {
+            UsageHealth::Exceeded
+        } else if u64::from(hours) * 10 >= u64::from(threshold) * 9 {
+            UsageHealth::Approaching
+        } else {
+            UsageHealth::Ok
+        }
+    }
+
+    fn message(self, threshold: u32) -> String {
+        match self {
+            UsageHealth::Ok => format!("OK: below the {}-hour maintenance threshold", threshold),
+            UsageHealth::Approaching => format!(
+                "Note: approaching the {}-hour maintenance threshold",
+                threshold
+            ),
+            UsageHealth::Exceeded => format!(
+                "Warning: past the {}-hour maintenance threshold; expect reduced backlight \
+                 brightness and color drift, and check whether the warranty still applies",
+                threshold
+            ),
+        }
+    }
+}
+
+fn health(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let config = Config::load(config_path)?;
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    let hours = vcp_mon.get_usage_hours()?;
+    let status = UsageHealth::assess(hours, config.usage_warning_hours);
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "power_on_hours": hours,
+                "threshold_hours": config.usage_warning_hours,
+                "status": status,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!("Power-on time: {} hours", hours);
+            println!("{}", status.message(config.usage_warning_hours));
+        }
+    }
+
+    Ok(())
+}
+
+fn self_test(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    cycles: u32,
+    timeout: Duration,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    if !silent && !json {
+        eprintln!(
+            "Testing {} with {} brightness writes...",
+            mon.info().friendly_name,
+            cycles
+        );
+    }
+    let report = selftest::run(&configured_vcp(&mon), cycles, timeout)?;
+
+    if silent {
+        return Ok(());
+    }
+
+    let millis = |time: Option<Duration>| time.map(|time| time.as_millis() as u64);
+    if json {
+        let output = serde_json::json!({
+            "cycles": report.cycles,
+            "verified": report.verified(),
+            "success_rate": report.success_rate(),
+            "typical_settle_ms": millis(report.typical_settle()),
+            "slowest_settle_ms": millis(report.slowest_settle()),
+            "restored_brightness": report.original,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let mut table = Table::new(["Cycle", "Result"]).align_right(0);
+    for (index, cycle) in report.cycles.iter().enumerate() {
+        let result = match cycle {
+            selftest::CycleOutcome::Verified { settle_ms } => {
+                format!("Verified after {} ms", settle_ms)
+            }
+            selftest::CycleOutcome::NotApplied { reported } => {
+                format!("Not applied (monitor reported {})", reported)
+            }
+            selftest::CycleOutcome::Failed { error } => format!("Failed: {}", error),
+        };
+        table.add_row([(index + 1).to_string(), result]);
+    }
+    print!("{}", table);
+    println!();
+    println!(
+        "Success rate:    {:.0}% ({} of {})",
+        report.success_rate() * 100.0,
+        report.verified(),
+        report.cycles.len()
+    );
+    if let (Some(typical), Some(slowest)) = (
+        millis(report.typical_settle()),
+        millis(report.slowest_settle()),
+    ) {
+        println!(
+            "Settle time:     {} ms typical, {} ms slowest",
+            typical, slowest
+        );
+    }
+    println!("Brightness restored to {}", report.original);
+    Ok(())
+}
+
+fn tune(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    cycles: u32,
+    timeout: Duration,
+    dry_run: bool,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    let info = mon.info();
+    if !silent && !json {
+        eprintln!(
+            "Tuning {} with {} brightness writes...",
+            info.friendly_name, cycles
+        );
+    }
+    // Without retries, each failed transfer shows up in the report and sizes the retry count
+    let vcp_mon = vcp::VcpMonitor::with_retry(mon.handle(), RetryPolicy::ONCE)
+        .with_max_overrides(mon.max_overrides().clone())
+        .with_settle_delay(mon.settle_delay());
+    let report = selftest::run(&vcp_mon, cycles, timeout)?;
+    let tuning = report.recommend().ok_or_else(|| {
+        MonitorError::ControlUnavailable(format!(
+            "{} never reported a written brightness back, so there is nothing to tune; \
+             run self-test for details",
+            info.friendly_name
+        ))
+    })?;
+
+    let stable_id = info.stable_id();
+    let saved = if dry_run {
+        None
+    } else {
+        let settings = serde_json::to_value(tuning)?
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        Some(Config::update_monitor(config_path, &stable_id, settings)?)
+    };
+
+    if silent {
+        return Ok(());
+    }
+
+    let millis = |time: Option<Duration>| time.map(|time| time.as_millis() as u64);
+    if json {
+        let output = serde_json::json!({
+            "stable_id": stable_id,
+            "verified": report.verified(),
+            "success_rate": report.success_rate(),
+            "typical_settle_ms": millis(report.typical_settle()),
+            "slowest_settle_ms": millis(report.slowest_settle()),
+            "failure_rate": report.failure_rate(),
+            "slowest_read_ms": report.slowest_read_ms,
+            "settle_delay_ms": tuning.settle_delay_ms,
+            "repeat": tuning.repeat,
+            "retry": tuning.retry,
+            "retry_delay_ms": tuning.retry_delay_ms,
+            "config": saved,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!(
+        "Success rate:    {:.0}% ({} of {})",
+        report.success_rate() * 100.0,
+        report.verified(),
+        report.cycles.len()
+    );
+    if let (Some(typical), Some(slowest)) = (
+        millis(report.typical_settle()),
+        millis(report.slowest_settle()),
+    ) {
+        println!(
+            "Settle time:     {} ms typical, {} ms slowest",
+            typical, slowest
+        );
+    }
+    println!(
+        "Failed:          {:.0}% of cycles, slowest read {} ms",
+        report.failure_rate() * 100.0,
+        report.slowest_read_ms
+    );
+    println!(
+        "Recommended:     settle_delay_ms {}, repeat {}, retry {}, retry_delay_ms {}",
+        tuning.settle_delay_ms, tuning.repeat, tuning.retry, tuning.retry_delay_ms
+    );
+    match saved {
+        Some(path) => println!("Saved for {} in {}", stable_id, path.display()),
+        None => println!("Not saved (--dry-run)"),
+    }
+    Ok(())
+}
+
+fn diagnose(target: MonitorSelector, device_context: bool, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let diagnosis = Diagnosis {
+        info: mon.info(),
+        controls: mon.probe_capabilities(),
+        device_context: device_context.then(|| monitor::DeviceContextInfo::probe(mon.info())),
+    };
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diagnosis.to_json()?)?);
+    } else {
+        print!("{}", diagnosis);
+    }
+    Ok(())
+}
+
+/// Everything `diagnose` reports about one monitor
+struct Diagnosis<'a> {
+    info: &'a monitor::MonitorInfo,
+    controls: monitor::ControlCapabilities,
+    /// Only probed with `--device-context`
+    device_context: Option<monitor::DeviceContextInfo>,
+}
+
+impl Diagnosis<'_> {
+    fn to_json(&self) -> Result<serde_json::Value> {
+        let mut output = serde_json::json!({
+            "monitor": self.info,
+            "stable_id": self.info.stable_id(),
+            "controls": self.controls,
+        });
+        if let Some(dc) = &self.device_context {
+            output["device_context"] = serde_json::to_value(dc)?;
+        }
+        Ok(output)
+    }
+}
+
+impl std::fmt::Display for Diagnosis<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let yes_no = |value: bool| if value { "Yes" } else { "No" };
+        let info = self.info;
+
+        writeln!(f, "Monitor")?;
+        writeln!(f, "  Device name:    {}", info.device_name)?;
+        writeln!(f, "  Friendly name:  {}", info.friendly_name)?;
+        writeln!(f, "  Instance:       {}", info.instance_name)?;
+        writeln!(f, "  Adapter:        {}", info.adapter_name)?;
+        writeln!(f, "  Stable ID:      {}", info.stable_id())?;
+        writeln!(f, "  Primary:        {}", yes_no(info.is_primary))?;
+        writeln!(f)?;
+        writeln!(f, "DDC/CI")?;
+        writeln!(f, "  VCP reads:      {}", yes_no(self.controls.vcp))?;
+        writeln!(f, "  Brightness:     {}", yes_no(self.controls.brightness))?;
+        writeln!(f, "  Contrast:       {}", yes_no(self.controls.contrast))?;
+
+        if let Some(dc) = &self.device_context {
+            writeln!(f)?;
+            writeln!(f, "Device context")?;
+            writeln!(f, "  GDI device:     {}", dc.device_name)?;
+            match &dc.error {
+                Some(e) => writeln!(f, "  CreateDCW:      Failed ({})", e)?,
+                None => writeln!(f, "  CreateDCW:      OK")?,
+            }
+            writeln!(f, "  Raster display: {}", yes_no(dc.valid))?;
+            writeln!(f, "  Gamma ramp:     {}", yes_no(dc.gamma_ramp))?;
+        }
+        Ok(())
+    }
+}
+
+fn link_status(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    // Most monitors without a DVI input don't implement 0xBE; that's an answer, not a failure
+    let status = match vcp_mon.get_link_status() {
+        Ok(status) => Some(status),
+        Err(MonitorError::UnsupportedOperation(_)) => None,
+        Err(e) => return Err(e),
+    };
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "supported": status.is_some(),
+                "status": status,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            match status {
+                Some(status) => println!("Link status: {}", status),
+                None => println!("Link status not reported by this monitor (VCP 0xBE)"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn monitor_status(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    // Only DPVL displays implement 0xB7, so ordinary monitors answer "not reported"
+    let status = match vcp_mon.get_monitor_status() {
+        Ok(status) => Some(status),
+        Err(MonitorError::UnsupportedOperation(_)) => None,
+        Err(e) => return Err(e),
+    };
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "supported": status.is_some(),
+                "status": status,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            match status {
+                Some(status) => println!("Monitor status: {}", status),
+                None => println!("Monitor status not reported by this monitor (VCP 0xB7)"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Blanking the primary monitor can leave a session with no visible screen to recover from,
+/// so it requires `--force`
+fn check_power_guard(
+    mode: decode::PowerMode,
+    info: &monitor::MonitorInfo,
+    force: bool,
+    silent: bool,
+) -> Result<()> {
+    if !mode.blanks_screen() || !info.is_primary {
+        return Ok(());
+    }
+
+    if !force {
+        return Err(MonitorError::UnsupportedOperation(format!(
+            "refusing to switch the primary monitor ({}) to {}; pass --force to do it anyway",
+            info.friendly_name, mode
+        )));
+    }
+
+    if !silent {
+        eprintln!(
+            "Warning: switching the primary monitor ({}) to {}",
+            info.friendly_name, mode
+        );
+    }
+    Ok(())
+}
+
+fn power(
+    config_path: Option<&Path>,
+    mode: decode::PowerMode,
+    target: MonitorSelector,
+    force: bool,
+    silent: bool,
+) -> Result<()> {
+    let mon = resolve_configured(config_path, &target)?;
+    check_power_guard(mode, mon.info(), force, silent)?;
+
+    let vcp_mon = configured_vcp(&mon);
+    vcp_mon.set_power_mode(mode)?;
+
+    if !silent {
+        println!("Power mode set to {}", mode);
+    }
+
+    Ok(())
+}
+
+fn set_asset_tag(tag: &str, target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    vcp_mon.set_asset_tag(tag)?;
+    if !silent {
+        println!("Asset tag set to \"{}\"", tag);
+    }
+    Ok(())
+}
+
+fn set_display_descriptor(descriptor: &str, target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    vcp_mon.set_display_descriptor(descriptor)?;
+    if !silent {
+        println!("Display descriptor set to \"{}\"", descriptor);
+    }
+    Ok(())
+}
+
+fn get_descriptor(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    // The text needs a table read, which can fail where the plain 0xC4 flag still works
+    let descriptor = vcp_mon.get_display_descriptor();
+    let enabled = vcp_mon.get_display_descriptor_enabled().ok();
+    let descriptor = match (descriptor, enabled) {
+        (Err(e), None) => return Err(e),
+        (descriptor, _) => descriptor,
+    };
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "descriptor": descriptor.as_ref().ok(),
+                "enabled": enabled,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            match &descriptor {
+                Ok(text) => println!("Display descriptor: \"{}\"", text),
+                Err(e) => println!("Display descriptor: unavailable ({})", e),
+            }
+            match enabled {
+                Some(true) => println!("Shown without a signal: Yes"),
+                Some(false) => println!("Shown without a signal: No"),
+                None => println!("Shown without a signal: not reported (VCP 0xC4)"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_aux_display(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let size = vcp::VcpMonitor::new(mon.handle()).get_aux_display_size()?;
+
+    if !silent {
+        if json {
+            let output = serde_json::json!({
+                "rows": size.rows,
+                "characters_per_row": size.characters_per_row,
+                "capacity": size.capacity(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            println!("Auxiliary display: {}", size);
+        }
+    }
+    Ok(())
+}
+
+fn set_aux_display(text: &str, target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    vcp::VcpMonitor::new(mon.handle()).set_aux_display_text(text)?;
+    if !silent {
+        println!("Auxiliary display set to \"{}\"", text);
+    }
+    Ok(())
+}
+
+fn set_descriptor_enabled(enabled: bool, target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    vcp_mon.set_display_descriptor_enabled(enabled)?;
+    if !silent {
+        if enabled {
+            println!("Display descriptor will be shown when there is no signal");
+        } else {
+            println!("Display descriptor will not be shown when there is no signal");
+        }
+    }
+    Ok(())
+}
+
+fn get_capabilities(target: MonitorSelector, save: Option<&Path>, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    let caps = vcp_mon.get_capabilities()?;
+
+    if let Some(path) = save {
+        fs::write(path, &caps)?;
+        if !silent {
+            println!("Capabilities saved to {}", path.display());
+        }
+    } else if !silent {
+        println!("{}", caps);
+    }
+    Ok(())
+}
+
+fn capability_coverage(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let caps = vcp::VcpMonitor::new(mon.handle()).get_capabilities()?;
+    let coverage = capabilities::parse_capabilities(&caps)?.coverage();
+
+    if silent {
+        return Ok(());
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&coverage)?);
+        return Ok(());
+    }
+
+    println!(
+        "Coverage:  {:.1}% of known VCP codes ({} of {})",
+        coverage.percentage, coverage.supported, coverage.known
+    );
+    if coverage.missing_important.is_empty() {
+        println!("Missing:   no important controls");
+    } else {
+        println!("Missing:   {}", coverage.missing_important.join(", "));
+    }
+    Ok(())
+}
+
+fn get_edid(target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let info = mon.info();
+    let edid = info.edid.as_ref().ok_or_else(|| {
+        MonitorError::UnsupportedOperation(format!(
+            "Windows has no EDID cached for {}",
+            info.friendly_name
+        ))
+    })?;
+
+    // Not part of the EDID, but what font-rendering tools look for next to it; few monitors
+    // report it
+    let subpixel_layout = vcp::VcpMonitor::new(mon.handle())
+        .get_subpixel_layout()
+        .ok();
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let mut output = serde_json::to_value(edid)?;
+        if let (Some(layout), Some(object)) = (subpixel_layout, output.as_object_mut()) {
+            object.insert("subpixel_layout".into(), serde_json::to_value(layout)?);
+        }
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let field = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    println!("Manufacturer:      {}", edid.manufacturer);
+    println!("Product code:      0x{:04X}", edid.product_code);
+    println!("Serial number:     {}", edid.serial_number);
+    println!("Serial:            {}", field(edid.serial.clone()));
+    println!("Name:              {}", field(edid.name.clone()));
+    println!(
+        "Manufactured:      {}",
+        field(edid.manufacture_date.map(|d| d.to_string()))
+    );
+    println!(
+        "Native resolution: {}",
+        field(edid.native_resolution.map(|r| r.to_string()))
+    );
+    println!("Extension blocks:  {}", edid.extension_count);
+    println!(
+        "Sub-pixel layout:  {}",
+        field(subpixel_layout.map(|layout| layout.to_string()))
+    );
+
+    if let Some(cea) = &edid.cea {
+        let yes_no = |flag: bool| if flag { "Yes" } else { "No" };
+        let join = |items: Vec<String>| {
+            if items.is_empty() {
+                "-".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+
+        println!("\nCEA-861 (revision {})", cea.revision);
+        println!("  HDMI:            {}", yes_no(cea.hdmi));
+        println!("  HDMI Forum:      {}", yes_no(cea.hdmi_forum));
+        println!("  Basic audio:     {}", yes_no(cea.basic_audio));
+        println!(
+            "  Audio formats:   {}",
+            join(cea.audio_formats.iter().map(|a| a.to_string()).collect())
+        );
+        println!(
+            "  Video modes:     {}",
+            join(
+                cea.video_modes
+                    .iter()
+                    .map(|m| if m.native {
+                        format!("{} [native]", m)
+                    } else {
+                        m.to_string()
+                    })
+                    .collect()
+            )
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_capabilities(path: &Path, json: bool, silent: bool) -> Result<()> {
+    let raw = fs::read_to_string(path)?;
+    let caps = capabilities::parse_capabilities(&raw)?;
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&caps)?);
+        return Ok(());
+    }
+
+    let field = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let hex_list = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    println!("Protocol:     {}", field(&caps.protocol));
+    println!("Type:         {}", field(&caps.display_type));
+    println!("Model:        {}", field(&caps.model));
+    println!("MCCS version: {}", field(&caps.mccs_version));
+    println!("Commands:     {}", hex_list(&caps.commands));
+    println!("VCP codes:    {}\n", caps.vcp_codes.len());
+
+    let mut table = Table::new(["Code", "Name", "Values"]);
+    for code in &caps.vcp_codes {
+        let name = vcp::get_vcp_code_info(code.code).map_or("Unknown", |info| info.name);
+        table.add_row([
+            format!("0x{:02X}", code.code),
+            name.to_string(),
+            hex_list(&code.values),
+        ]);
+    }
+    print!("{}", table);
+
+    Ok(())
+}
+
+fn save_settings(target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    vcp_mon.save_settings()?;
+    if !silent {
+        println!("Monitor settings saved");
+    }
+    Ok(())
+}
+
+/// Pause between attempts to reach the monitor again after a power cycle
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+
+fn persistence_test(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    power_cycle: Option<Duration>,
+    force: bool,
+    timeout: Duration,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    if power_cycle.is_none() && (silent || !io::stdin().is_terminal()) {
+        return Err(MonitorError::UnsupportedOperation(
+            "persistence-test needs a terminal to ask for the power cycle on; \
+             pass --power-cycle to use the monitor's power mode instead"
+                .to_string(),
+        ));
+    }
+
+    let mon = resolve_configured(config_path, &target)?;
+    if power_cycle.is_some() {
+        check_power_guard(decode::PowerMode::Off, mon.info(), force, silent)?;
+    }
+    let name = mon.info().friendly_name.clone();
+    let vcp_mon = configured_vcp(&mon);
+    let response = vcp_mon.get_vcp_feature(vcp::codes::BRIGHTNESS)?;
+    let original = response.current_value;
+    let test_value = selftest::test_levels(original, response.maximum_value)?[0];
+
+    vcp_mon.set_vcp_feature(vcp::codes::BRIGHTNESS, test_value)?;
+    let applied = selftest::wait_for_brightness(&vcp_mon, test_value, Duration::from_secs(1));
+    let cycled = match applied {
+        Ok(true) => cycle_power(&vcp_mon, &name, power_cycle),
+        Ok(false) => Err(MonitorError::ControlUnavailable(format!(
+            "{} didn't apply the test brightness {}, so there is nothing to test",
+            name, test_value
+        ))),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = cycled {
+        return Err(restore_after_failure(&vcp_mon, &name, original, e, silent));
+    }
+
+    // Switching a monitor off can take it away from Windows, leaving the old handle unusable, so
+    // read back through a fresh one
+    drop(vcp_mon);
+    drop(mon);
+    let (mon, reported) = reconnect(config_path, &target, timeout).map_err(|e| {
+        MonitorError::ControlUnavailable(format!(
+            "{} didn't answer after the power cycle ({}); its brightness was {} before the test",
+            name, e, original
+        ))
+    })?;
+    configured_vcp(&mon).set_vcp_feature(vcp::codes::BRIGHTNESS, original)?;
+
+    let report = selftest::PersistenceReport {
+        original,
+        test_value,
+        reported,
+    };
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        let output = serde_json::json!({
+            "original": report.original,
+            "test_value": report.test_value,
+            "reported": report.reported,
+            "persisted": report.persisted(),
+            "save_settings_needed": !report.persisted(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!(
+        "Test brightness: {} (was {})",
+        report.test_value, report.original
+    );
+    println!("After power on:  {}", report.reported);
+    println!(
+        "Persisted:       {}",
+        if report.persisted() { "yes" } else { "no" }
+    );
+    println!("{}", report.advice());
+    println!("Brightness restored to {}", report.original);
+    Ok(())
+}
+
+/// `error`, after trying to write the `original` brightness back. A failed restore is only
+/// reported on stderr, so it doesn't hide why the test stopped.
+fn restore_after_failure(
+    vcp_mon: &vcp::VcpMonitor,
+    name: &str,
+    original: u32,
+    error: MonitorError,
+    silent: bool,
+) -> MonitorError {
+    if let Err(e) = vcp_mon.set_vcp_feature(vcp::codes::BRIGHTNESS, original)
+        && !silent
+    {
+        eprintln!(
+            "Warning: couldn't restore the brightness of {} to {}: {}",
+            name, original, e
+        );
+    }
+    error
+}
+
+/// Switch the monitor off and on again: through its power mode after `off_time`, or by asking
+/// the user to do it when `off_time` is `None`
+fn cycle_power(vcp_mon: &vcp::VcpMonitor, name: &str, off_time: Option<Duration>) -> Result<()> {
+    let Some(off_time) = off_time else {
+        eprint!(
+            "Switch {} off with its power button, switch it back on, then press Enter...",
+            name
+        );
+        io::stderr().flush()?;
+        io::stdin().read_line(&mut String::new())?;
+        return Ok(());
+    };
+
+    vcp_mon.set_power_mode(decode::PowerMode::Off)?;
+    thread::sleep(off_time);
+    vcp_mon.set_power_mode(decode::PowerMode::On)
+}
+
+/// Resolve `target` again and read its brightness, retrying until the monitor answers or
+/// `timeout` passes
+fn reconnect(
+    config_path: Option<&Path>,
+    target: &MonitorSelector,
+    timeout: Duration,
+) -> Result<(monitor::PhysicalMonitor, u32)> {
+    let started = Instant::now();
+    loop {
+        let attempt = resolve_configured(config_path, target).and_then(|mon| {
+            let brightness = configured_vcp(&mon).get_vcp_feature(vcp::codes::BRIGHTNESS)?;
+            Ok((mon, brightness.current_value))
+        });
+        match attempt {
+            Ok(answer) => return Ok(answer),
+            Err(e) if started.elapsed() >= timeout => return Err(e),
+            Err(_) => thread::sleep(RECONNECT_INTERVAL),
+        }
+    }
+}
+
+fn save_profile(path: &Path, codes: Vec<u8>, target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    let codes = if codes.is_empty() {
+        profile::DEFAULT_PROFILE_CODES.to_vec()
+    } else {
+        codes
+    };
+
+    let profile = profile::MonitorProfile::capture(&vcp_mon, &codes).with_monitor(mon.info());
+    profile.save(path)?;
+
+    if !silent {
+        println!(
+            "Saved {} of {} VCP values to {}",
+            profile.values.len(),
+            codes.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Say on stderr when a profile was saved from another monitor than the one it is used on.
+/// Only a note: profiles are meant to be shareable between monitors.
+fn note_profile_source(
+    path: &Path,
+    profile: &profile::MonitorProfile,
+    info: &monitor::MonitorInfo,
+    silent: bool,
+) {
+    if let Some(source) = &profile.monitor
+        && !silent
+        && profile.from_other_monitor(info)
+    {
+        eprintln!(
+            "Note: {} was saved from {} ({}), not {}",
+            path.display(),
+            source.friendly_name,
+            source.stable_id(),
+            info.friendly_name
+        );
+    }
+}
+
+/// The input the monitor is on, if it isn't `required`; for `load-profile --only-if-input`
+fn other_input(
+    vcp_mon: &vcp::VcpMonitor,
+    required: decode::InputSource,
+) -> Result<Option<decode::InputSource>> {
+    let current = vcp_mon.get_input_source()?;
+    Ok((current != required).then_some(current))
+}
+
+fn load_profile(
+    config_path: Option<&Path>,
+    path: &Path,
+    target: MonitorSelector,
+    minimal: bool,
+    only_if_input: Option<decode::InputSource>,
+    silent: bool,
+) -> Result<()> {
+    let profile = profile::MonitorProfile::load(path)?;
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    note_profile_source(path, &profile, mon.info(), silent);
+
+    if let Some(required) = only_if_input
+        && let Some(current) = other_input(&vcp_mon, required)?
+    {
+        if !silent {
+            println!(
+                "Skipped {}: {} is on {}, not {}",
+                path.display(),
+                mon.info().friendly_name,
+                current,
+                required
+            );
+        }
+        return Ok(());
+    }
+
+    if minimal {
+        let written = profile.apply_minimal(&vcp_mon)?;
+        if !silent {
+            println!(
+                "Applied {} of {} VCP values from {}; the rest already matched",
+                written.values.len(),
+                profile.values.len(),
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    profile.apply(&vcp_mon)?;
+    if !silent {
+        println!(
+            "Applied {} VCP values from {}",
+            profile.values.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+fn validate_profile(
+    config_path: Option<&Path>,
+    path: &Path,
+    target: MonitorSelector,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    let profile = profile::MonitorProfile::load(path)?;
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    note_profile_source(path, &profile, mon.info(), silent || json);
+    let issues = profile.validate(&vcp_mon);
+
+    if !silent {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&issues)?);
+        } else if issues.is_empty() {
+            println!(
+                "All {} values in {} fit {}",
+                profile.values.len(),
+                path.display(),
+                mon.info().friendly_name
+            );
+        } else {
+            for issue in &issues {
+                let name = vcp::get_vcp_code_info(issue.code()).map_or("Unknown", |info| info.name);
+                println!("0x{:02X}   {:<30} {}", issue.code(), name, issue);
+            }
+        }
+    }
+
+    if !issues.is_empty() {
+        return Err(MonitorError::Profile(format!(
+            "{} of {} values in {} don't fit this monitor",
+            issues.len(),
+            profile.values.len(),
+            path.display()
+        )));
+    }
 
-fn list_vcp(json: bool, silent: bool) -> Result<()> {
-    if !silent {
-        if json {
-            println!("{}", serde_json::to_string_pretty(&vcp::KNOWN_VCP_CODES)?);
-        } else {
-            println!("{:<6} {:<30} {}", "Code", "Name", "Description");
-            println!("{}", "-".repeat(80));
-            for info in vcp::KNOWN_VCP_CODES {
-                println!(
-                    "0x{:02X}   {:<30} {}",
-                    info.code, info.name, info.description
-                );
-            }
-        }
-    }
     Ok(())
 }
 
-fn scan_vcp(device: Option<String>, primary: bool, json: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
+fn drift(baseline: &Path, target: MonitorSelector, json: bool, silent: bool) -> Result<()> {
+    let baseline = profile::MonitorProfile::load(baseline)?;
+    let mon = target.resolve()?;
     let vcp_mon = vcp::VcpMonitor::new(mon.handle());
 
-    if !json && !silent {
-        eprintln!("Scanning monitor for supported VCP codes...");
+    let codes = baseline.adjustable_codes();
+    let changes = baseline.drift(&vcp_mon);
+
+    if silent {
+        return Ok(());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else if changes.is_empty() {
+        println!("No drift: all {} settings match the baseline", codes.len());
+    } else {
+        let show = |value: Option<u32>| value.map_or("-".to_string(), |v| v.to_string());
+        let mut table = Table::new(["Code", "Name", "Baseline", "Current"])
+            .align_right(2)
+            .align_right(3);
+        for change in &changes {
+            let name = vcp::get_vcp_code_info(change.code).map_or("Unknown", |info| info.name);
+            table.add_row([
+                format!("0x{:02X}", change.code),
+                name.to_string(),
+                show(change.baseline),
+                show(change.current),
+            ]);
+        }
+        print!("{}", table);
+        println!("{} of {} settings differ", changes.len(), codes.len());
+    }
+
+    Ok(())
+}
+
+fn revert_vcp(
+    config_path: Option<&Path>,
+    code: u8,
+    baseline: &Path,
+    target: MonitorSelector,
+    silent: bool,
+) -> Result<()> {
+    let profile = profile::MonitorProfile::load(baseline)?;
+    let no_value = || {
+        MonitorError::Profile(format!(
+            "{} has no saved value for 0x{:02X}",
+            baseline.display(),
+            code
+        ))
+    };
+    if profile.value(code).is_none() {
+        return Err(no_value());
     }
 
-    let features = vcp_mon.scan_vcp_features();
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
+    let value = profile.revert(&vcp_mon, code)?.ok_or_else(no_value)?;
 
     if !silent {
-        if json {
-            println!("{}", serde_json::to_string_pretty(&features)?);
-        } else {
-            eprintln!("Found {} supported VCP codes\n", features.len());
-            println!(
-                "{:<6} {:<35} {:<12} {:<8} {}",
-                "Code", "Name", "CurrentValue", "MaxValue", "Description"
-            );
-            println!("{}", "-".repeat(120));
-
-            for response in features {
-                let info = vcp::get_vcp_code_info(response.vcp_code);
-                let name = info.map(|i| i.name).unwrap_or("Unknown");
-                let description = info.map(|i| i.description).unwrap_or("");
-
-                println!(
-                    "0x{:02X}   {:<35} {:<12} {:<8} {}",
-                    response.vcp_code,
-                    name,
-                    response.current_value,
-                    response.maximum_value,
-                    description
-                );
+        println!(
+            "Reverted 0x{:02X} to {} from {}",
+            code,
+            value,
+            baseline.display()
+        );
+    }
+    Ok(())
+}
+
+fn backup_all(path: &Path, codes: Vec<u8>, silent: bool) -> Result<()> {
+    let codes = if codes.is_empty() {
+        profile::DEFAULT_PROFILE_CODES.to_vec()
+    } else {
+        codes
+    };
+
+    let mut backup = profile::Backup::default();
+    for mon in monitor::enumerate_monitors()? {
+        let info = mon.info();
+        // A cloned or MST-chained monitor shows up once per device name; save it once
+        if backup
+            .monitors
+            .iter()
+            .any(|saved| saved.info.same_panel_as(info))
+        {
+            continue;
+        }
+
+        let profile = profile::MonitorProfile::capture(&vcp::VcpMonitor::new(mon.handle()), &codes);
+        // Built-in laptop panels and monitors with DDC/CI off answer nothing
+        if profile.values.is_empty() {
+            if !silent {
+                eprintln!("Skipped {}: no response over DDC/CI", info.friendly_name);
             }
+            continue;
         }
+        backup.monitors.push(profile::MonitorBackup {
+            stable_id: info.stable_id(),
+            info: info.clone(),
+            profile,
+        });
+    }
+
+    if backup.monitors.is_empty() {
+        return Err(MonitorError::ControlUnavailable(
+            "no monitor answered over DDC/CI; nothing to back up".to_string(),
+        ));
     }
+    backup.save(path)?;
 
+    if !silent {
+        println!(
+            "Saved {} monitors to {}",
+            backup.monitors.len(),
+            path.display()
+        );
+    }
     Ok(())
 }
 
-fn get_capabilities(device: Option<String>, primary: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
-    let caps = vcp_mon.get_capabilities()?;
+fn restore_all(path: &Path, silent: bool) -> Result<()> {
+    let backup = profile::Backup::load(path)?;
+    let monitors = monitor::enumerate_monitors()?;
+    let infos: Vec<_> = monitors.iter().map(|mon| mon.info().clone()).collect();
+    let (matched, unmatched) = backup.match_monitors(&infos);
+
+    let mut failed = 0;
+    for (saved, index) in &matched {
+        let mon = &monitors[*index];
+        let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+        match saved.profile.apply(&vcp_mon) {
+            Ok(()) if !silent => println!(
+                "{}: applied {} VCP values",
+                mon.info().friendly_name,
+                saved.profile.values.len()
+            ),
+            Ok(()) => {}
+            Err(e) => {
+                failed += 1;
+                if !silent {
+                    eprintln!("{}: {}", mon.info().friendly_name, e);
+                }
+            }
+        }
+    }
     if !silent {
-        println!("{}", caps);
+        for saved in &unmatched {
+            eprintln!(
+                "{}: not connected ({})",
+                saved.info.friendly_name, saved.stable_id
+            );
+        }
+    }
+
+    let not_restored = failed + unmatched.len();
+    if not_restored > 0 {
+        return Err(MonitorError::UnsupportedOperation(format!(
+            "{} of {} monitors in {} were not restored",
+            not_restored,
+            backup.monitors.len(),
+            path.display()
+        )));
     }
     Ok(())
 }
 
-fn save_settings(device: Option<String>, primary: bool, silent: bool) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
+/// Whether writes to `vcp_mon` should wait because the user is in its OSD menu. Only asked with
+/// `--pause-for-osd`; a monitor that doesn't report its OSD state never holds writes back.
+fn osd_open(vcp_mon: &vcp::VcpMonitor, pause_for_osd: bool) -> bool {
+    pause_for_osd && vcp_mon.is_osd_active().unwrap_or(false)
+}
+
+fn watch_input(
+    config_path: Option<&Path>,
+    target: MonitorSelector,
+    interval: u64,
+    pause_for_osd: bool,
+    silent: bool,
+) -> Result<()> {
+    let config = Config::load(config_path)?;
+    let mut profiles = profile::InputProfileMap::from_config(&config)?;
+    let mon = target.resolve()?;
     let vcp_mon = vcp::VcpMonitor::new(mon.handle());
-    vcp_mon.save_settings()?;
+
     if !silent {
-        println!("Monitor settings saved");
+        eprintln!("Watching for input changes (Ctrl+C to stop)...");
+    }
+
+    // Input whose profile is still to be applied, held back while the OSD is open
+    let mut pending = None;
+    loop {
+        match vcp_mon.get_input_source() {
+            Ok(input) => {
+                if pending.is_some_and(|pending| pending != input) {
+                    pending = None;
+                }
+                if profiles.observe(input).is_some() {
+                    pending = Some(input);
+                }
+            }
+            Err(e) if e.is_disconnect() || matches!(e, MonitorError::UnsupportedOperation(_)) => {
+                return Err(e);
+            }
+            // The monitor may be asleep or switched away; keep polling until it answers again
+            Err(_) => {}
+        }
+
+        if let Some(input) = pending
+            && !osd_open(&vcp_mon, pause_for_osd)
+            && let Some(profile) = profiles.profile_for(input)
+        {
+            pending = None;
+            match profile.apply(&vcp_mon) {
+                Ok(()) if !silent => println!("Input changed to {}, profile applied", input),
+                Err(e) if !silent => eprintln!("Input changed to {}: {}", input, e),
+                _ => {}
+            }
+        }
+
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+fn mirror_brightness(
+    config_path: Option<&Path>,
+    source: MonitorSelector,
+    interval: Duration,
+    debounce: Duration,
+    pause_for_osd: bool,
+    silent: bool,
+) -> Result<()> {
+    let source = resolve_configured(config_path, &source)?;
+    let targets: Vec<_> = enumerate_configured(config_path)?
+        .into_iter()
+        .filter(|mon| !mon.info().same_panel_as(source.info()))
+        .collect();
+    if targets.is_empty() {
+        return Err(MonitorError::MonitorNotFound(
+            "no other monitors to mirror brightness to".to_string(),
+        ));
+    }
+
+    if !silent {
+        eprintln!(
+            "Mirroring brightness of {} to {} monitor(s) (Ctrl+C to stop)...",
+            source.info().friendly_name,
+            targets.len()
+        );
+    }
+
+    let mut debouncer = monitor::Debouncer::new(debounce);
+    // Latest brightness to copy with the source's range, and the targets still to receive it
+    let mut latest = None;
+    let mut behind: Vec<&monitor::PhysicalMonitor> = Vec::new();
+    loop {
+        // The source may stop answering while it is asleep; keep polling until it answers again
+        if let Ok(brightness) = source.get_brightness()
+            && let Some(current) = debouncer.observe(brightness.current, Instant::now())
+        {
+            latest = Some((current, (brightness.minimum, brightness.maximum)));
+            behind = targets.iter().collect();
+        }
+
+        if let Some((current, source_range)) = latest {
+            // Targets whose OSD is open stay behind and are retried on the next poll
+            behind.retain(|target| {
+                if osd_open(&vcp::VcpMonitor::new(target.handle()), pause_for_osd) {
+                    return true;
+                }
+                match monitor::mirror_brightness_to(*target, current, source_range) {
+                    Ok(level) if !silent => {
+                        println!("{}: brightness {}", target.info().friendly_name, level)
+                    }
+                    Err(e) if !silent => eprintln!("{}: {}", target.info().friendly_name, e),
+                    _ => {}
+                }
+                false
+            });
+        }
+
+        thread::sleep(interval);
     }
-    Ok(())
 }
 
 fn reset_defaults(
-    device: Option<String>,
-    primary: bool,
+    config_path: Option<&Path>,
+    target: MonitorSelector,
     color_only: bool,
     silent: bool,
 ) -> Result<()> {
-    let mon = get_monitor(device, primary)?;
-    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    let mon = resolve_configured(config_path, &target)?;
+    let vcp_mon = configured_vcp(&mon);
 
     if color_only {
         vcp_mon.restore_factory_color_defaults()?;
@@ -482,3 +4679,886 @@ fn reset_defaults(
 
     Ok(())
 }
+
+/// What happened to one monitor during `reset-defaults --all-monitors`
+#[derive(Debug, PartialEq)]
+enum ResetOutcome {
+    Reset,
+    /// Declined at the prompt
+    Skipped,
+    Failed(String),
+}
+
+fn reset_all_monitors(
+    config_path: Option<&Path>,
+    color_only: bool,
+    yes: bool,
+    json: bool,
+    silent: bool,
+) -> Result<()> {
+    const NAME: &str = "reset-defaults";
+    // Refuse up front rather than after enumerating, when there is no way to ask
+    if !yes && (silent || !io::stdin().is_terminal()) {
+        return Err(MonitorError::ConfirmationRequired(NAME.to_string()));
+    }
+
+    let config = Config::load(config_path)?;
+    let monitors: Vec<_> = monitor::enumerate_monitors()?
+        .into_iter()
+        .map(|mon| configure(&config, mon))
+        .collect();
+    let confirm = |info: &monitor::MonitorInfo| {
+        let question = format!(
+            "Reset {} ({}) to factory {}?",
+            info.friendly_name,
+            info.device_name,
+            if color_only {
+                "color defaults"
+            } else {
+                "defaults"
+            }
+        );
+        Ok(yes || ask_confirmation(NAME, &question, silent)?)
+    };
+    let results = reset_each(&monitors, confirm, |mon| {
+        let vcp_mon = configured_vcp(mon);
+        if color_only {
+            vcp_mon.restore_factory_color_defaults()
+        } else {
+            vcp_mon.restore_factory_defaults()
+        }
+    })?;
+
+    if !silent {
+        if json {
+            let output: Vec<_> = results
+                .iter()
+                .map(|(info, outcome)| {
+                    let (result, error) = match outcome {
+                        ResetOutcome::Reset => ("reset", None),
+                        ResetOutcome::Skipped => ("skipped", None),
+                        ResetOutcome::Failed(e) => ("failed", Some(e)),
+                    };
+                    serde_json::json!({
+                        "device_name": info.device_name,
+                        "friendly_name": info.friendly_name,
+                        "result": result,
+                        "error": error,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            let mut table = Table::new(["Device Name", "Friendly Name", "Result"]);
+            for (info, outcome) in &results {
+                let status = match outcome {
+                    ResetOutcome::Reset => "Reset".to_string(),
+                    ResetOutcome::Skipped => "Skipped".to_string(),
+                    ResetOutcome::Failed(e) => format!("Failed: {}", e),
+                };
+                table.add_row([info.device_name.clone(), info.friendly_name.clone(), status]);
+            }
+            print!("{}", table);
+        }
+    }
+
+    let failed = results
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, ResetOutcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        return Err(MonitorError::UnsupportedOperation(format!(
+            "{} of {} monitors failed",
+            failed,
+            results.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Ask `confirm` about each of `monitors` in turn and `reset` the ones it agrees to, collecting
+/// every monitor's outcome. A failed reset doesn't stop the others; an error from `confirm` does.
+fn reset_each<M: Monitor>(
+    monitors: &[M],
+    mut confirm: impl FnMut(&monitor::MonitorInfo) -> Result<bool>,
+    mut reset: impl FnMut(&M) -> Result<()>,
+) -> Result<Vec<(&monitor::MonitorInfo, ResetOutcome)>> {
+    let mut results = Vec::with_capacity(monitors.len());
+    for mon in monitors {
+        let outcome = if !confirm(mon.info())? {
+            ResetOutcome::Skipped
+        } else {
+            match reset(mon) {
+                Ok(()) => ResetOutcome::Reset,
+                Err(e) => ResetOutcome::Failed(e.to_string()),
+            }
+        };
+        results.push((mon.info(), outcome));
+    }
+    Ok(results)
+}
+
+fn reset_brightness_contrast(target: MonitorSelector, silent: bool) -> Result<()> {
+    let mon = target.resolve()?;
+    let vcp_mon = vcp::VcpMonitor::new(mon.handle());
+    vcp_mon.reset_luminance_contrast()?;
+    if !silent {
+        println!("Brightness and contrast reset to factory defaults");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("monitorconfig").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn adjusted_level_clamps_to_reported_range() {
+        assert_eq!(adjusted_level(50, 0, 100, 10), 60);
+        assert_eq!(adjusted_level(50, 0, 100, -10), 40);
+        assert_eq!(adjusted_level(95, 0, 100, 10), 100);
+        assert_eq!(adjusted_level(5, 0, 100, -10), 0);
+        assert_eq!(adjusted_level(25, 20, 80, -10), 20);
+        assert_eq!(adjusted_level(75, 20, 80, i32::MAX), 80);
+        assert_eq!(adjusted_level(0, 0, 100, i32::MIN), 0);
+    }
+
+    #[test]
+    fn adjust_contrast_accepts_negative_delta() {
+        let cli = parse(&["adjust-contrast", "-10"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::AdjustContrast { delta: -10, .. }
+        ));
+        assert!(parse(&["set-contrast", "50", "--all"]).is_ok());
+    }
+
+    #[test]
+    fn power_guard_applies_to_the_primary_monitor_only() {
+        use decode::PowerMode;
+
+        let mut primary = monitor::MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        primary.is_primary = true;
+        let secondary = monitor::MonitorInfo::fake("\\\\.\\DISPLAY2", "Dell P2422H");
+
+        for mode in [
+            PowerMode::Standby,
+            PowerMode::Suspend,
+            PowerMode::Off,
+            PowerMode::HardOff,
+        ] {
+            assert!(matches!(
+                check_power_guard(mode, &primary, false, true),
+                Err(MonitorError::UnsupportedOperation(_))
+            ));
+            assert!(check_power_guard(mode, &primary, true, true).is_ok());
+            assert!(check_power_guard(mode, &secondary, false, true).is_ok());
+        }
+        assert!(check_power_guard(PowerMode::On, &primary, false, true).is_ok());
+    }
+
+    fn selector(args: &[&str]) -> MonitorSelector {
+        let cli = parse(args).unwrap();
+        let target = cli.command.target().unwrap();
+        target.exact.set(cli.exact);
+        target.selector()
+    }
+
+    #[test]
+    fn selector_precedence() {
+        assert_eq!(selector(&["get-brightness"]), MonitorSelector::Primary);
+        assert_eq!(
+            selector(&["get-brightness", "--device", "dell"]),
+            MonitorSelector::Device("dell".to_string())
+        );
+        assert_eq!(
+            selector(&["get-brightness", "--device", "dell", "--exact"]),
+            MonitorSelector::ExactDevice("dell".to_string())
+        );
+        // --primary wins over --device
+        assert_eq!(
+            selector(&["get-brightness", "--device", "dell", "--primary"]),
+            MonitorSelector::Primary
+        );
+        assert_eq!(
+            selector(&["get-brightness", "--hmonitor", "0x10001"]),
+            MonitorSelector::Handle(0x10001)
+        );
+        assert!(
+            parse(&[
+                "get-brightness",
+                "--hmonitor",
+                "0x10001",
+                "--device",
+                "dell"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn list_sort_orders_by_each_key() {
+        let mut primary = monitor::MonitorInfo::fake("\\\\.\\DISPLAY3", "Dell U2723QE");
+        primary.is_primary = true;
+        let mut monitors = vec![
+            monitor::MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850"),
+            primary,
+            monitor::MonitorInfo::fake("\\\\.\\DISPLAY1", "Acer XB271HU"),
+        ];
+        let devices = |monitors: &[monitor::MonitorInfo]| -> Vec<String> {
+            monitors
+                .iter()
+                .map(|m| m.device_name.trim_start_matches("\\\\.\\").to_string())
+                .collect()
+        };
+
+        sort_monitors(&mut monitors, ListSort::Name);
+        assert_eq!(devices(&monitors), ["DISPLAY1", "DISPLAY3", "DISPLAY2"]);
+        sort_monitors(&mut monitors, ListSort::Device);
+        assert_eq!(devices(&monitors), ["DISPLAY1", "DISPLAY2", "DISPLAY3"]);
+        // Stable: the others keep their device order behind the primary
+        sort_monitors(&mut monitors, ListSort::Primary);
+        assert_eq!(devices(&monitors), ["DISPLAY3", "DISPLAY1", "DISPLAY2"]);
+    }
+
+    #[test]
+    fn scan_sort_orders_by_each_key() {
+        let entry = |code: u8, value: u32| vcp::ScanEntry {
+            response: vcp::VcpFeatureResponse {
+                vcp_code: code,
+                current_value: value,
+                maximum_value: 100,
+                code_type: vcp::VcpCodeType::SetParameter,
+                reported_value: None,
+            },
+            class: vcp::CodeClass::Continuous,
+            decoded: None,
+        };
+        let mut entries = vec![
+            entry(vcp::codes::INPUT_SOURCE, 15),
+            entry(vcp::codes::CONTRAST, 80),
+            entry(vcp::codes::AUDIO_VOLUME, 30),
+            entry(vcp::codes::BRIGHTNESS, 50),
+        ];
+        let codes = |entries: &[vcp::ScanEntry]| -> Vec<u8> {
+            entries.iter().map(|e| e.response.vcp_code).collect()
+        };
+
+        sort_scan_entries(&mut entries, ScanSort::Code);
+        assert_eq!(codes(&entries), [0x10, 0x12, 0x60, 0x62]);
+        // Audio: Speaker Volume, Contrast, Input Select, Luminance
+        sort_scan_entries(&mut entries, ScanSort::Name);
+        assert_eq!(codes(&entries), [0x62, 0x12, 0x60, 0x10]);
+        sort_scan_entries(&mut entries, ScanSort::Value);
+        assert_eq!(codes(&entries), [0x60, 0x62, 0x10, 0x12]);
+    }
+
+    #[test]
+    fn usage_health_compares_against_the_threshold() {
+        assert_eq!(UsageHealth::assess(0, 30_000), UsageHealth::Ok);
+        assert_eq!(UsageHealth::assess(26_999, 30_000), UsageHealth::Ok);
+        assert_eq!(
+            UsageHealth::assess(27_000, 30_000),
+            UsageHealth::Approaching
+        );
+        assert_eq!(
+            UsageHealth::assess(29_999, 30_000),
+            UsageHealth::Approaching
+        );
+        assert_eq!(UsageHealth::assess(30_000, 30_000), UsageHealth::Exceeded);
+        assert_eq!(
+            UsageHealth::assess(u32::MAX, u32::MAX - 1),
+            UsageHealth::Exceeded
+        );
+        assert_eq!(Config::default().usage_warning_hours, 30_000);
+    }
+
+    #[test]
+    fn usage_health_message_matches_the_status() {
+        assert!(
+            UsageHealth::Ok
+                .message(30_000)
+                .starts_with("OK: below the 30000-hour")
+        );
+        assert!(
+            UsageHealth::Approaching
+                .message(30_000)
+                .starts_with("Note: approaching")
+        );
+        let exceeded = UsageHealth::Exceeded.message(20_000);
+        assert!(exceeded.starts_with("Warning: past the 20000-hour"));
+        assert!(exceeded.contains("warranty"));
+    }
+
+    #[test]
+    fn parse_capabilities_reads_saved_files() {
+        let saved = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/capabilities");
+        for file in ["dell_u2723qe.txt", "lg_27gl850.txt"] {
+            parse_capabilities(&saved.join(file), false, true).unwrap();
+        }
+        assert!(parse_capabilities(&saved.join("missing.txt"), false, true).is_err());
+    }
+
+    #[test]
+    fn hmonitor_is_parsed_as_non_null_hex() {
+        assert_eq!(parse_handle("0x10001"), Ok(0x10001));
+        assert_eq!(parse_handle("0X1a2B"), Ok(0x1A2B));
+        assert_eq!(parse_handle("10001"), Ok(0x10001));
+        assert!(parse_handle("0x0").is_err());
+        assert!(parse_handle("0xZZ").is_err());
+        assert!(parse(&["get-brightness", "--hmonitor", "0"]).is_err());
+    }
+
+    #[test]
+    fn read_only_blocks_only_commands_that_change_the_monitor() {
+        let changes = |args: &[&str]| parse(args).unwrap().command.changes_monitor();
+
+        for args in [
+            &["set-brightness", "50"][..],
+            &["set-vcp", "0x10", "50"],
+            &["save-settings"],
+            &["power", "standby"],
+            &["load-profile", "work.json"],
+            &["output-select", "hdmi1"],
+            &["descriptor", "set", "Room 3"],
+            &["descriptor", "enable"],
+            &["aux-display", "set", "BUILD"],
+        ] {
+            assert!(changes(args), "{:?}", args);
+        }
+        for args in [
+            &["get-brightness"][..],
+            &["get-vcp", "0x10"],
+            &["scan-vcp"],
+            &["current-input"],
+            &["link-status"],
+            &["save-profile", "work.json"],
+            &["output-select"],
+            &["descriptor", "get"],
+            &["aux-display", "get"],
+        ] {
+            assert!(!changes(args), "{:?}", args);
+        }
+
+        let cli = parse(&["set-brightness", "50", "--read-only"]).unwrap();
+        assert!(cli.read_only);
+    }
+
+    #[test]
+    fn all_reports_keep_monitors_without_the_control() {
+        let monitors = [
+            monitor::VcpControls::new(
+                "\\\\.\\DISPLAY1",
+                crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 40, 100),
+            ),
+            monitor::VcpControls::new("\\\\.\\DISPLAY2", crate::backend::MockBackend::new()),
+        ];
+
+        let levels = collect_each(&monitors, |mon| mon.get_brightness().map(Level::from));
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].0.device_name, "\\\\.\\DISPLAY1");
+        let level = levels[0].1.unwrap();
+        assert_eq!((level.current, level.minimum, level.maximum), (40, 0, 100));
+        assert_eq!(levels[1].0.device_name, "\\\\.\\DISPLAY2");
+        assert!(levels[1].1.is_none());
+    }
+
+    #[test]
+    fn set_input_settle_requires_preserve() {
+        assert!(parse(&["set-input", "hdmi1", "--settle", "500"]).is_err());
+        let cli = parse(&["set-input", "hdmi1", "--preserve", "--settle", "500"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::SetInput {
+                preserve: true,
+                settle: 500,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reset_brightness_contrast_needs_confirmation() {
+        let cli = parse(&["reset-brightness-contrast", "--primary"]).unwrap();
+        assert!(cli.command.is_destructive());
+        assert!(cli.command.changes_monitor());
+        assert!(!parse(&["get-brightness"]).unwrap().command.is_destructive());
+
+        // Without a terminal to ask on, the reset is refused rather than run
+        assert!(matches!(
+            confirm_destructive("reset-brightness-contrast", true),
+            Err(MonitorError::ConfirmationRequired(name)) if name == "reset-brightness-contrast"
+        ));
+    }
+
+    #[test]
+    fn level_templates_render_every_advertised_field() {
+        let fields = level_template_fields(40, 0, 100);
+        let names: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, LEVEL_TEMPLATE_FIELDS);
+        assert_eq!(
+            template::render("{current}/{max} (min {min})", &fields).unwrap(),
+            "40/100 (min 0)"
+        );
+        assert!(template::validate("{value}", LEVEL_TEMPLATE_FIELDS).is_err());
+    }
+
+    #[test]
+    fn find_supporting_keeps_only_monitors_answering_the_code() {
+        use crate::backend::MockBackend;
+        use monitor::VcpControls;
+
+        let monitors = [
+            VcpControls::new(
+                "\\\\.\\DISPLAY1",
+                MockBackend::new().with_feature(vcp::codes::INPUT_SOURCE, 0x0F, 0x1B),
+            ),
+            VcpControls::new("\\\\.\\DISPLAY2", MockBackend::new()),
+            VcpControls::new(
+                "\\\\.\\DISPLAY3",
+                MockBackend::new().with_feature(vcp::codes::INPUT_SOURCE, 0x11, 0x1B),
+            ),
+        ];
+
+        let results = collect_each(&monitors, |mon| {
+            mon.vcp.get_vcp_feature(vcp::codes::INPUT_SOURCE)
+        });
+        assert_eq!(results.len(), 3);
+        let supporting: Vec<(String, u32)> = answered_only(results)
+            .into_iter()
+            .map(|(info, response)| (info.device_name, response.current_value))
+            .collect();
+        assert_eq!(
+            supporting,
+            [
+                ("\\\\.\\DISPLAY1".to_string(), 0x0F),
+                ("\\\\.\\DISPLAY3".to_string(), 0x11),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnosis_includes_the_device_context_section_when_probed() {
+        let info = monitor::MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        let mut diagnosis = Diagnosis {
+            info: &info,
+            controls: monitor::ControlCapabilities::default(),
+            device_context: None,
+        };
+        assert!(!diagnosis.to_string().contains("Device context"));
+        assert!(diagnosis.to_json().unwrap().get("device_context").is_none());
+
+        diagnosis.device_context = Some(monitor::DeviceContextInfo {
+            device_name: info.device_name.clone(),
+            created: false,
+            error: Some("CreateDCW failed: Invalid handle (0x00000006)".to_string()),
+            valid: false,
+            gamma_ramp: false,
+        });
+        let text = diagnosis.to_string();
+        assert!(text.contains("\nDevice context\n  GDI device:     \\\\.\\DISPLAY1\n"));
+        assert!(text.contains("  CreateDCW:      Failed (CreateDCW failed: Invalid handle"));
+        assert!(text.contains("  Gamma ramp:     No"));
+
+        let json = diagnosis.to_json().unwrap();
+        assert_eq!(json["device_context"]["created"], false);
+        assert_eq!(json["device_context"]["device_name"], "\\\\.\\DISPLAY1");
+    }
+
+    #[test]
+    fn reset_all_monitors_asks_about_each_one() {
+        use crate::backend::MockBackend;
+        use monitor::VcpControls;
+        // Restore Factory Defaults
+        const RESET: u8 = 0x04;
+
+        let monitors: Vec<VcpControls> = (1..=3)
+            .map(|i| {
+                let mock = MockBackend::new().with_action(RESET);
+                let mock = if i == 3 {
+                    mock.failing_with(RESET, 0xC0262582)
+                } else {
+                    mock
+                };
+                VcpControls::new(&format!("\\\\.\\DISPLAY{}", i), mock)
+            })
+            .collect();
+
+        let mut asked = Vec::new();
+        let results = reset_each(
+            &monitors,
+            |info| {
+                asked.push(info.device_name.clone());
+                Ok(info.device_name != "\\\\.\\DISPLAY2")
+            },
+            |mon| mon.vcp.set_vcp_feature(RESET, 1),
+        )
+        .unwrap();
+
+        assert_eq!(asked.len(), 3);
+        let outcomes: Vec<&ResetOutcome> = results.iter().map(|(_, outcome)| outcome).collect();
+        assert_eq!(outcomes[0], &ResetOutcome::Reset);
+        assert_eq!(outcomes[1], &ResetOutcome::Skipped);
+        assert!(matches!(outcomes[2], ResetOutcome::Failed(e) if e.contains("SetVCPFeature")));
+    }
+
+    #[test]
+    fn reset_all_monitors_stops_when_it_cant_ask() {
+        let monitors = [monitor::VcpControls::new(
+            "\\\\.\\DISPLAY1",
+            crate::backend::MockBackend::new(),
+        )];
+        let result = reset_each(
+            &monitors,
+            |_| {
+                Err(MonitorError::ConfirmationRequired(
+                    "reset-defaults".to_string(),
+                ))
+            },
+            |_| unreachable!(),
+        );
+        assert!(matches!(result, Err(MonitorError::ConfirmationRequired(_))));
+
+        // Without --yes and without a terminal, nothing is enumerated at all
+        assert!(matches!(
+            reset_all_monitors(None, false, false, false, true),
+            Err(MonitorError::ConfirmationRequired(_))
+        ));
+        assert!(parse(&["reset-defaults", "--json"]).is_err());
+        assert!(parse(&["reset-defaults", "--all-monitors", "--primary"]).is_err());
+    }
+
+    #[test]
+    fn enable_ddc_writes_the_configured_code() {
+        let info = monitor::MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        let other = monitor::MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850");
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "enable_ddc": {"code": 0xE1, "value": 1},
+            "monitors": { (other.stable_id()): { "enable_ddc": {"code": 0xF0, "value": 2} } },
+        }))
+        .unwrap();
+
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(0xE1, 0, 1)
+            .with_feature(0xF0, 0, 2);
+        let vcp_mon = vcp::VcpMonitor::with_backend(mock.clone());
+        write_enable_ddc(&config, &info, &vcp_mon).unwrap();
+        write_enable_ddc(&config, &other, &vcp_mon).unwrap();
+        assert_eq!(mock.writes(), vec![(0xE1, 1), (0xF0, 2)]);
+    }
+
+    #[test]
+    fn enable_ddc_without_a_configured_code_writes_nothing() {
+        let info = monitor::MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        let mock = crate::backend::MockBackend::new().with_feature(0xE1, 0, 1);
+        let vcp_mon = vcp::VcpMonitor::with_backend(mock.clone());
+
+        let err = write_enable_ddc(&Config::default(), &info, &vcp_mon).unwrap_err();
+        assert!(matches!(err, MonitorError::Config(_)));
+        assert!(err.to_string().contains(&info.stable_id()));
+        assert!(mock.writes().is_empty());
+    }
+
+    #[test]
+    fn show_target_names_the_resolved_monitor() {
+        let mut info = monitor::MonitorInfo::fake("\\\\.\\DISPLAY2", "Dell P2422H");
+        info.instance_name = "DISPLAY\\DELA0F1\\5&1a2b3c&0&UID4353".to_string();
+        assert_eq!(
+            target_line(&info),
+            "Target: \\\\.\\DISPLAY2 (Dell P2422H) DISPLAY\\DELA0F1\\5&1a2b3c&0&UID4353"
+        );
+
+        // Only commands acting on a single monitor have a target to show
+        let cli = parse(&["set-contrast", "50", "--device", "dell"]).unwrap();
+        assert!(matches!(
+            cli.command.target().map(MonitorArgs::selector),
+            Some(MonitorSelector::Device(name)) if name == "dell"
+        ));
+        let cli = parse(&["set-contrast", "50", "--all"]).unwrap();
+        assert!(cli.command.target().is_none());
+        let cli = parse(&["list"]).unwrap();
+        assert!(cli.command.target().is_none());
+    }
+
+    #[test]
+    fn only_if_input_skips_profiles_on_other_inputs() {
+        let mock =
+            crate::backend::MockBackend::new().with_feature(vcp::codes::INPUT_SOURCE, 0x11, 0xFF);
+        let vcp_mon = vcp::VcpMonitor::with_backend(mock);
+
+        assert_eq!(
+            other_input(&vcp_mon, decode::InputSource::Hdmi1).unwrap(),
+            None
+        );
+        assert_eq!(
+            other_input(&vcp_mon, decode::InputSource::DisplayPort1).unwrap(),
+            Some(decode::InputSource::Hdmi1)
+        );
+
+        // A monitor that can't report its input isn't assumed to be on the required one
+        let silent = vcp::VcpMonitor::with_backend(crate::backend::MockBackend::new());
+        assert!(other_input(&silent, decode::InputSource::Hdmi1).is_err());
+    }
+
+    #[test]
+    fn until_stable_needs_at_least_one_write_and_owns_tolerance() {
+        assert!(
+            parse(&[
+                "set-vcp",
+                "0x10",
+                "50",
+                "--until-stable",
+                "3",
+                "--tolerance",
+                "2"
+            ])
+            .is_ok()
+        );
+        assert!(parse(&["set-vcp", "0x10", "50", "--until-stable", "0"]).is_err());
+        assert!(parse(&["set-vcp", "0x10", "50", "--tolerance", "2"]).is_err());
+    }
+
+    #[test]
+    fn scan_changes_skip_read_only_counters() {
+        let response = |code: u8, value: u32| vcp::VcpFeatureResponse {
+            vcp_code: code,
+            current_value: value,
+            maximum_value: 100,
+            code_type: vcp::VcpCodeType::SetParameter,
+            reported_value: None,
+        };
+        let before = [
+            response(vcp::codes::BRIGHTNESS, 50),
+            response(vcp::codes::CONTRAST, 70),
+            response(vcp::codes::USAGE_TIME, 1200),
+            response(vcp::codes::AUDIO_VOLUME, 30),
+        ];
+        let after = [
+            response(vcp::codes::BRIGHTNESS, 60),
+            response(vcp::codes::CONTRAST, 70),
+            response(vcp::codes::USAGE_TIME, 1230),
+            response(vcp::codes::INPUT_SOURCE, 0x11),
+        ];
+
+        let changes: Vec<_> = scan_changes(&before, &after)
+            .iter()
+            .map(|change| (change.code, change.baseline, change.current))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (vcp::codes::BRIGHTNESS, Some(50), Some(60)),
+                (vcp::codes::AUDIO_VOLUME, Some(30), None),
+                (vcp::codes::INPUT_SOURCE, None, Some(0x11)),
+            ]
+        );
+        assert!(scan_changes(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn exact_turns_device_into_an_exact_selector() {
+        let cli = parse(&["--exact", "get-contrast", "--device", "dell"]).unwrap();
+        assert!(cli.exact);
+        let target = cli.command.target().unwrap();
+        assert_eq!(
+            target.selector(),
+            MonitorSelector::Device("dell".to_string())
+        );
+
+        // As `run` does once the arguments are parsed
+        target.exact.set(true);
+        assert_eq!(
+            target.selector(),
+            MonitorSelector::ExactDevice("dell".to_string())
+        );
+
+        // --primary still wins over --device
+        let cli = parse(&["get-contrast", "--device", "dell", "--primary", "--exact"]).unwrap();
+        let target = cli.command.target().unwrap();
+        target.exact.set(true);
+        assert_eq!(target.selector(), MonitorSelector::Primary);
+    }
+
+    #[test]
+    fn power_cycling_switches_the_monitor_off_then_on() {
+        let mock =
+            crate::backend::MockBackend::new().with_feature(vcp::codes::POWER_MODE, 0x01, 0x05);
+        let vcp_mon = vcp::VcpMonitor::with_backend(mock.clone());
+
+        cycle_power(&vcp_mon, "Dell U2723QE", Some(Duration::ZERO)).unwrap();
+        assert_eq!(
+            mock.writes(),
+            vec![
+                (vcp::codes::POWER_MODE, 0x04),
+                (vcp::codes::POWER_MODE, 0x01)
+            ]
+        );
+
+        assert!(parse(&["persistence-test", "--power-cycle", "--off-time", "100"]).is_ok());
+        assert!(parse(&["persistence-test", "--off-time", "100"]).is_err());
+        assert!(parse(&["persistence-test", "--power-cycle", "--force"]).is_ok());
+        assert!(parse(&["persistence-test", "--force"]).is_err());
+    }
+
+    #[test]
+    fn failed_power_cycles_return_their_error_whether_or_not_the_restore_works() {
+        let cycle_error = || MonitorError::ControlUnavailable("no power mode".to_string());
+
+        let mock = crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 40, 100);
+        let vcp_mon = vcp::VcpMonitor::with_backend(mock.clone());
+        let error = restore_after_failure(&vcp_mon, "Dell U2723QE", 50, cycle_error(), true);
+        assert!(matches!(error, MonitorError::ControlUnavailable(_)));
+        assert_eq!(mock.value(vcp::codes::BRIGHTNESS), Some(50));
+
+        // The restore fails too, yet the cycle's error is the one returned
+        let vcp_mon = vcp::VcpMonitor::with_backend(crate::backend::MockBackend::new());
+        let error = restore_after_failure(&vcp_mon, "Dell U2723QE", 50, cycle_error(), true);
+        assert!(
+            matches!(error, MonitorError::ControlUnavailable(message) if message == "no power mode")
+        );
+    }
+
+    #[test]
+    fn list_rows_mark_only_the_primary_monitor() {
+        let mut primary = monitor::MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        primary.is_primary = true;
+        let other = monitor::MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850");
+
+        assert_eq!(
+            monitor_row(&primary, "Primary"),
+            ["\\\\.\\DISPLAY1", "Dell U2723QE", "-", "Primary"]
+        );
+        assert_eq!(monitor_row(&other, "Yes")[3], "");
+
+        let listed = serde_json::to_value(ListedAdapter {
+            adapter_name: "NVIDIA GeForce RTX 3080",
+            monitors: vec![ListedMonitor::new(&other)],
+        })
+        .unwrap();
+        assert_eq!(listed["adapter_name"], "NVIDIA GeForce RTX 3080");
+        assert_eq!(listed["monitors"][0]["friendly_name"], "LG 27GL850");
+        assert_eq!(listed["monitors"][0]["stable_id"], "device:\\\\.\\DISPLAY2");
+    }
+
+    #[test]
+    fn osd_holds_writes_back_only_when_asked_and_reported_open() {
+        use crate::backend::MockBackend;
+
+        let open = vcp::VcpMonitor::with_backend(MockBackend::new().with_feature(
+            vcp::codes::OSD,
+            0x02,
+            0xFF,
+        ));
+        assert!(osd_open(&open, true));
+        assert!(!osd_open(&open, false));
+
+        let closed = vcp::VcpMonitor::with_backend(MockBackend::new().with_feature(
+            vcp::codes::OSD,
+            0x01,
+            0xFF,
+        ));
+        assert!(!osd_open(&closed, true));
+
+        // Monitors that don't report the OSD state never hold writes back
+        assert!(!osd_open(
+            &vcp::VcpMonitor::with_backend(MockBackend::new()),
+            true
+        ));
+    }
+
+    #[test]
+    fn pause_for_osd_flags_parse() {
+        let cli = parse(&["watch-input", "--pause-for-osd"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::WatchInput {
+                pause_for_osd: true,
+                ..
+            }
+        ));
+        let cli = parse(&["mirror-brightness"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::MirrorBrightness {
+                pause_for_osd: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn set_rgb_gain_takes_three_gains_or_a_hex_triplet() {
+        let cli = parse(&["set-rgb-gain", "90", "95", "100"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::SetRgbGain {
+                red: Some(90),
+                green: Some(95),
+                blue: Some(100),
+                hex: None,
+                ..
+            }
+        ));
+        let cli = parse(&["set-rgb-gain", "--hex", "#FFF0E8"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::SetRgbGain {
+                red: None,
+                hex: Some(_),
+                ..
+            }
+        ));
+
+        assert!(parse(&["set-rgb-gain"]).is_err());
+        assert!(parse(&["set-rgb-gain", "90", "95"]).is_err());
+        assert!(parse(&["set-rgb-gain", "90", "95", "100", "--hex", "#FFF0E8"]).is_err());
+        assert!(parse(&["set-rgb-gain", "--hex", "#FFF"]).is_err());
+    }
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn all_conflicts_with_display_number() {
+        for command in ["get-brightness", "get-contrast"] {
+            assert!(parse(&[command, "--all", "--display-number", "2"]).is_err());
+            assert!(parse(&[command, "--all"]).is_ok());
+        }
+        assert!(parse(&["set-contrast", "50", "--all", "--display-number", "2"]).is_err());
+        assert!(parse(&["scan-vcp", "--all", "--display-number", "2"]).is_err());
+    }
+
+    #[test]
+    fn display_number_selects_by_number() {
+        let cli = parse(&["get-brightness", "--display-number", "2"]).unwrap();
+        let target = cli.command.target().unwrap();
+        assert!(matches!(
+            target.selector(),
+            MonitorSelector::DisplayNumber(2)
+        ));
+        assert!(parse(&["get-brightness", "--display-number", "0"]).is_err());
+        assert!(parse(&["get-brightness", "--display-number", "2", "--primary"]).is_err());
+    }
+
+    #[test]
+    fn snapshot_all_conflicts_with_selectors() {
+        for selector in [
+            &["--device", "X"][..],
+            &["--primary"],
+            &["--display-number", "2"],
+            &["--hmonitor", "0x10001"],
+        ] {
+            let mut args = vec!["snapshot", "--all"];
+            args.extend_from_slice(selector);
+            assert!(parse(&args).is_err(), "{:?}", selector);
+        }
+        assert!(parse(&["snapshot", "--all", "--json"]).is_ok());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::{MonitorError, Result, RetryPolicy, monitor::MonitorInfo};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// User configuration, read from `%APPDATA%\monitorconfig\config.json` unless `--config` is given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Input name (e.g. "hdmi1") to profile file, used by `watch-input`
+    pub input_profiles: BTreeMap<String, PathBuf>,
+
+    /// Power-on hours after which `health` suggests the monitor is due for a check
+    pub usage_warning_hours: u32,
+
+    /// Milliseconds to wait after a write before reading the value back to verify it
+    pub settle_delay_ms: u64,
+
+    /// Times to send each set-brightness/set-contrast/set-vcp write when `--repeat` isn't given
+    pub repeat: u32,
+
+    /// Attempts at a VCP call that fails on a busy DDC/CI channel, including the first
+    pub retry: u32,
+
+    /// Milliseconds to wait between those attempts
+    pub retry_delay_ms: u64,
+
+    /// Steps of the monitor's on-screen scale per VCP code, for values like `7n`
+    pub notches: BTreeMap<u8, u32>,
+
+    /// Known maximum per VCP code, for monitors that report a wrong one
+    pub max_overrides: BTreeMap<u8, u32>,
+
+    /// OEM-specific VCP write that turns DDC/CI on, used by `enable-ddc`
+    pub enable_ddc: Option<VcpWrite>,
+
+    /// Per-monitor settings, keyed by `MonitorInfo::stable_id`
+    pub monitors: BTreeMap<String, MonitorSettings>,
+
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            input_profiles: BTreeMap::new(),
+            // Roughly where LCD backlights are commonly rated to reach half brightness
+            usage_warning_hours: 30_000,
+            settle_delay_ms: 0,
+            repeat: 1,
+            retry: RetryPolicy::DEFAULT_ATTEMPTS,
+            retry_delay_ms: RetryPolicy::DEFAULT_DELAY.as_millis() as u64,
+            notches: BTreeMap::new(),
+            max_overrides: BTreeMap::new(),
+            enable_ddc: None,
+            monitors: BTreeMap::new(),
+            base_dir: PathBuf::new(),
+        }
+    }
+}
+
+/// Overrides of the global settings for one monitor; unset fields use the global value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorSettings {
+    pub settle_delay_ms: Option<u64>,
+    pub repeat: Option<u32>,
+    pub retry: Option<u32>,
+    pub retry_delay_ms: Option<u64>,
+    pub notches: BTreeMap<u8, u32>,
+    pub max_overrides: BTreeMap<u8, u32>,
+    pub enable_ddc: Option<VcpWrite>,
+}
+
+/// A single VCP value to write, e.g. `{"code": 225, "value": 1}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VcpWrite {
+    pub code: u8,
+    pub value: u32,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("APPDATA")
+            .map(|dir| PathBuf::from(dir).join("monitorconfig").join("config.json"))
+    }
+
+    /// Load the config from `path`, or from the default location if `path` is `None`.
+    /// A missing default config yields the defaults; an explicitly given one must exist.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| MonitorError::Config(format!("{}: {}", path.display(), e)))?;
+        let mut config: Config = serde_json::from_str(&contents)
+            .map_err(|e| MonitorError::Config(format!("{}: {}", path.display(), e)))?;
+        config.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        Ok(config)
+    }
+
+    /// Settle delay for `monitor`: its own override if configured, otherwise the global one
+    pub fn settle_delay(&self, monitor: &MonitorInfo) -> Duration {
+        let delay = self
+            .monitors
+            .get(&monitor.stable_id())
+            .and_then(|settings| settings.settle_delay_ms)
+            .unwrap_or(self.settle_delay_ms);
+        Duration::from_millis(delay)
+    }
+
+    /// Write count for `monitor`: its own override if configured, otherwise the global one
+    pub fn repeat(&self, monitor: &MonitorInfo) -> u32 {
+        self.monitors
+            .get(&monitor.stable_id())
+            .and_then(|settings| settings.repeat)
+            .unwrap_or(self.repeat)
+    }
+
+    /// Busy-channel retries for `monitor`: its own attempts and delay where configured, otherwise
+    /// the global ones
+    pub fn retry(&self, monitor: &MonitorInfo) -> RetryPolicy {
+        let settings = self.monitors.get(&monitor.stable_id());
+        RetryPolicy {
+            attempts: settings
+                .and_then(|settings| settings.retry)
+                .unwrap_or(self.retry),
+            delay: Duration::from_millis(
+                settings
+                    .and_then(|settings| settings.retry_delay_ms)
+                    .unwrap_or(self.retry_delay_ms),
+            ),
+        }
+    }
+
+    /// Notch count for `code` on `monitor`: its own if configured, otherwise the global one
+    pub fn notches(&self, monitor: &MonitorInfo, code: u8) -> Option<u32> {
+        self.monitors
+            .get(&monitor.stable_id())
+            .and_then(|settings| settings.notches.get(&code))
+            .or_else(|| self.notches.get(&code))
+            .copied()
+    }
+
+    /// Maximum overrides for `monitor`: the global ones, with its own taking precedence per code
+    pub fn max_overrides(&self, monitor: &MonitorInfo) -> BTreeMap<u8, u32> {
+        let mut overrides = self.max_overrides.clone();
+        if let Some(settings) = self.monitors.get(&monitor.stable_id()) {
+            overrides.extend(&settings.max_overrides);
+        }
+        overrides
+    }
+
+    /// DDC/CI enable write for `monitor`: its own if configured, otherwise the global one
+    pub fn enable_ddc(&self, monitor: &MonitorInfo) -> Option<VcpWrite> {
+        self.monitors
+            .get(&monitor.stable_id())
+            .and_then(|settings| settings.enable_ddc)
+            .or(self.enable_ddc)
+    }
+
+    /// Set `settings` (named as the [`MonitorSettings`] fields) in the `monitors` entry for
+    /// `stable_id` of the config file at `path`, or the default location if `path` is `None`.
+    /// The file is created if needed; everything else in it is kept as written. Returns the path
+    /// written.
+    pub fn update_monitor(
+        path: Option<&Path>,
+        stable_id: &str,
+        settings: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<PathBuf> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::default_path().ok_or_else(|| {
+                MonitorError::Config("APPDATA is not set; pass --config".to_string())
+            })?,
+        };
+        let config_error =
+            |e: &dyn std::fmt::Display| MonitorError::Config(format!("{}: {}", path.display(), e));
+
+        let mut document = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| config_error(&e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::json!({}),
+            Err(e) => return Err(config_error(&e)),
+        };
+        document
+            .as_object_mut()
+            .ok_or_else(|| config_error(&"not a JSON object"))?
+            .entry("monitors")
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or_else(|| config_error(&"`monitors` is not a JSON object"))?
+            .entry(stable_id)
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or_else(|| config_error(&format!("`monitors.{}` is not a JSON object", stable_id)))?
+            .extend(settings);
+        // Refuse to write a file the next command couldn't load
+        serde_json::from_value::<Config>(document.clone()).map_err(|e| config_error(&e))?;
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| config_error(&e))?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&document)?).map_err(|e| config_error(&e))?;
+        Ok(path)
+    }
+
+    /// Resolve a path written in the config file relative to the config file's directory
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        self.base_dir.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeat_prefers_the_monitor_setting_over_the_global_one() {
+        let info = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        let other = MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850");
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "repeat": 2,
+            "monitors": { (info.stable_id()): { "repeat": 4 } },
+        }))
+        .unwrap();
+
+        assert_eq!(config.repeat(&info), 4);
+        assert_eq!(config.repeat(&other), 2);
+        assert_eq!(Config::default().repeat(&info), 1);
+    }
+
+    #[test]
+    fn retry_takes_each_monitor_setting_over_the_global_one() {
+        let info = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        let other = MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850");
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "retry": 4,
+            "retry_delay_ms": 80,
+            "monitors": { (info.stable_id()): { "retry_delay_ms": 120 } },
+        }))
+        .unwrap();
+
+        let policy = |attempts, delay_ms| RetryPolicy {
+            attempts,
+            delay: Duration::from_millis(delay_ms),
+        };
+        assert_eq!(config.retry(&info), policy(4, 120));
+        assert_eq!(config.retry(&other), policy(4, 80));
+        assert_eq!(Config::default().retry(&info), RetryPolicy::default());
+    }
+
+    #[test]
+    fn notches_prefer_the_monitor_setting_over_the_global_one() {
+        let info = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        let other = MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850");
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "notches": { "16": 10, "18": 20 },
+            "monitors": { (info.stable_id()): { "notches": { "16": 5 } } },
+        }))
+        .unwrap();
+
+        assert_eq!(config.notches(&info, 0x10), Some(5));
+        assert_eq!(config.notches(&info, 0x12), Some(20));
+        assert_eq!(config.notches(&other, 0x10), Some(10));
+        assert_eq!(config.notches(&other, 0x62), None);
+    }
+
+    #[test]
+    fn update_monitor_merges_into_the_file_and_keeps_the_rest() {
+        let dir = std::env::temp_dir().join(format!("monitorconfig-tune-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("config.json");
+        let settings = |json: serde_json::Value| json.as_object().unwrap().clone();
+
+        // Created along with its directory when missing
+        let written = Config::update_monitor(
+            Some(&path),
+            "DEL-4211-ABC",
+            settings(serde_json::json!({ "settle_delay_ms": 60, "repeat": 2 })),
+        )
+        .unwrap();
+        assert_eq!(written, path);
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.monitors["DEL-4211-ABC"].settle_delay_ms, Some(60));
+        assert_eq!(config.monitors["DEL-4211-ABC"].repeat, Some(2));
+
+        fs::write(
+            &path,
+            r#"{ "usage_warning_hours": 20000, "monitors": { "DEL-4211-ABC": { "repeat": 2, "notches": { "16": 10 } } } }"#,
+        )
+        .unwrap();
+        Config::update_monitor(
+            Some(&path),
+            "DEL-4211-ABC",
+            settings(serde_json::json!({ "repeat": 3 })),
+        )
+        .unwrap();
+        let document: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            document,
+            serde_json::json!({
+                "usage_warning_hours": 20000,
+                "monitors": { "DEL-4211-ABC": { "repeat": 3, "notches": { "16": 10 } } }
+            })
+        );
+
+        // Settings the config couldn't load are refused, leaving the file as it was
+        assert!(
+            Config::update_monitor(
+                Some(&path),
+                "DEL-4211-ABC",
+                settings(serde_json::json!({ "repeat": "often" })),
+            )
+            .is_err()
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&path).unwrap()).unwrap(),
+            document
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{MonitorError, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// One point of a [`BrightnessCurve`]: at `lux`, use `brightness`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurvePoint {
+    pub lux: f64,
+    pub brightness: u32,
+}
+
+/// Piecewise linear mapping from an ambient light reading to a brightness level, used by
+/// `set-brightness --from-lux`.
+///
+/// Example file:
+/// `{"points": [{"lux": 0, "brightness": 10}, {"lux": 200, "brightness": 50}, {"lux": 1000, "brightness": 100}]}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrightnessCurve {
+    points: Vec<CurvePoint>,
+}
+
+impl BrightnessCurve {
+    /// Build a curve from points given in order of increasing lux
+    pub fn new(points: Vec<CurvePoint>) -> Result<Self> {
+        check_points(&points).map_err(MonitorError::InvalidValue)?;
+        Ok(Self { points })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| MonitorError::Config(format!("{}: {}", path.display(), e)))?;
+        let curve: BrightnessCurve = serde_json::from_str(&contents)
+            .map_err(|e| MonitorError::Config(format!("{}: {}", path.display(), e)))?;
+        check_points(&curve.points)
+            .map_err(|e| MonitorError::Config(format!("{}: {}", path.display(), e)))?;
+        Ok(curve)
+    }
+
+    pub fn points(&self) -> &[CurvePoint] {
+        &self.points
+    }
+
+    /// Brightness for a lux reading: interpolated between the two surrounding points, and
+    /// clamped to the first or last point's brightness outside the curve
+    pub fn brightness_for(&self, lux: f64) -> Result<u32> {
+        if !lux.is_finite() || lux < 0.0 {
+            return Err(MonitorError::InvalidValue(format!(
+                "lux {} must be a non-negative number",
+                lux
+            )));
+        }
+
+        // Construction guarantees at least one point
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if lux <= first.lux {
+            return Ok(first.brightness);
+        }
+        if lux >= last.lux {
+            return Ok(last.brightness);
+        }
+
+        let upper = self.points.partition_point(|p| p.lux <= lux);
+        let (low, high) = (self.points[upper - 1], self.points[upper]);
+        let fraction = (lux - low.lux) / (high.lux - low.lux);
+        let brightness =
+            low.brightness as f64 + fraction * (high.brightness as f64 - low.brightness as f64);
+        Ok(brightness.round() as u32)
+    }
+}
+
+fn check_points(points: &[CurvePoint]) -> std::result::Result<(), String> {
+    if points.is_empty() {
+        return Err("brightness curve has no points".to_string());
+    }
+    if let Some(point) = points.iter().find(|p| !p.lux.is_finite() || p.lux < 0.0) {
+        return Err(format!(
+            "curve point lux {} must be a non-negative number",
+            point.lux
+        ));
+    }
+    if let Some(pair) = points.windows(2).find(|pair| pair[1].lux <= pair[0].lux) {
+        return Err(format!(
+            "curve points must be in increasing lux order ({} follows {})",
+            pair[1].lux, pair[0].lux
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> BrightnessCurve {
+        BrightnessCurve::new(vec![
+            CurvePoint {
+                lux: 10.0,
+                brightness: 10,
+            },
+            CurvePoint {
+                lux: 200.0,
+                brightness: 50,
+            },
+            CurvePoint {
+                lux: 1000.0,
+                brightness: 100,
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn brightness_at_curve_points() {
+        let curve = curve();
+        assert_eq!(curve.brightness_for(10.0).unwrap(), 10);
+        assert_eq!(curve.brightness_for(200.0).unwrap(), 50);
+        assert_eq!(curve.brightness_for(1000.0).unwrap(), 100);
+    }
+
+    #[test]
+    fn brightness_between_curve_points_is_interpolated() {
+        let curve = curve();
+        assert_eq!(curve.brightness_for(105.0).unwrap(), 30);
+        assert_eq!(curve.brightness_for(600.0).unwrap(), 75);
+        // Rounded to the nearest level
+        assert_eq!(curve.brightness_for(216.0).unwrap(), 51);
+    }
+
+    #[test]
+    fn brightness_beyond_the_curve_is_clamped() {
+        let curve = curve();
+        assert_eq!(curve.brightness_for(0.0).unwrap(), 10);
+        assert_eq!(curve.brightness_for(50_000.0).unwrap(), 100);
+        assert!(curve.brightness_for(-1.0).is_err());
+        assert!(curve.brightness_for(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn curves_must_have_increasing_points() {
+        let point = |lux, brightness| CurvePoint { lux, brightness };
+        assert!(BrightnessCurve::new(Vec::new()).is_err());
+        assert!(BrightnessCurve::new(vec![point(100.0, 50), point(100.0, 60)]).is_err());
+        assert!(BrightnessCurve::new(vec![point(-5.0, 10)]).is_err());
+
+        let single = BrightnessCurve::new(vec![point(100.0, 40)]).unwrap();
+        assert_eq!(single.brightness_for(0.0).unwrap(), 40);
+        assert_eq!(single.brightness_for(1e6).unwrap(), 40);
+    }
+}
@@ -0,0 +1,1136 @@
+use crate::{MonitorError, vcp::codes};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Input source values for VCP code 0x60 (MCCS 2.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputSource {
+    Vga1,
+    Vga2,
+    Dvi1,
+    Dvi2,
+    Composite1,
+    Composite2,
+    SVideo1,
+    SVideo2,
+    Tuner1,
+    Tuner2,
+    Tuner3,
+    Component1,
+    Component2,
+    Component3,
+    DisplayPort1,
+    DisplayPort2,
+    Hdmi1,
+    Hdmi2,
+    /// Value outside the MCCS table (often OEM-defined, e.g. USB-C)
+    Other(u8),
+}
+
+impl InputSource {
+    const ALL: [InputSource; 18] = [
+        InputSource::Vga1,
+        InputSource::Vga2,
+        InputSource::Dvi1,
+        InputSource::Dvi2,
+        InputSource::Composite1,
+        InputSource::Composite2,
+        InputSource::SVideo1,
+        InputSource::SVideo2,
+        InputSource::Tuner1,
+        InputSource::Tuner2,
+        InputSource::Tuner3,
+        InputSource::Component1,
+        InputSource::Component2,
+        InputSource::Component3,
+        InputSource::DisplayPort1,
+        InputSource::DisplayPort2,
+        InputSource::Hdmi1,
+        InputSource::Hdmi2,
+    ];
+
+    /// Decode a 0x60 reply; only the low byte identifies the input
+    pub fn from_value(value: u32) -> Self {
+        let value = (value & 0xFF) as u8;
+        Self::ALL
+            .into_iter()
+            .find(|source| source.value() == value)
+            .unwrap_or(InputSource::Other(value))
+    }
+
+    pub fn value(self) -> u8 {
+        match self {
+            InputSource::Vga1 => 0x01,
+            InputSource::Vga2 => 0x02,
+            InputSource::Dvi1 => 0x03,
+            InputSource::Dvi2 => 0x04,
+            InputSource::Composite1 => 0x05,
+            InputSource::Composite2 => 0x06,
+            InputSource::SVideo1 => 0x07,
+            InputSource::SVideo2 => 0x08,
+            InputSource::Tuner1 => 0x09,
+            InputSource::Tuner2 => 0x0A,
+            InputSource::Tuner3 => 0x0B,
+            InputSource::Component1 => 0x0C,
+            InputSource::Component2 => 0x0D,
+            InputSource::Component3 => 0x0E,
+            InputSource::DisplayPort1 => 0x0F,
+            InputSource::DisplayPort2 => 0x10,
+            InputSource::Hdmi1 => 0x11,
+            InputSource::Hdmi2 => 0x12,
+            InputSource::Other(value) => value,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            InputSource::Vga1 => "VGA 1",
+            InputSource::Vga2 => "VGA 2",
+            InputSource::Dvi1 => "DVI 1",
+            InputSource::Dvi2 => "DVI 2",
+            InputSource::Composite1 => "Composite 1",
+            InputSource::Composite2 => "Composite 2",
+            InputSource::SVideo1 => "S-Video 1",
+            InputSource::SVideo2 => "S-Video 2",
+            InputSource::Tuner1 => "Tuner 1",
+            InputSource::Tuner2 => "Tuner 2",
+            InputSource::Tuner3 => "Tuner 3",
+            InputSource::Component1 => "Component 1",
+            InputSource::Component2 => "Component 2",
+            InputSource::Component3 => "Component 3",
+            InputSource::DisplayPort1 => "DisplayPort 1",
+            InputSource::DisplayPort2 => "DisplayPort 2",
+            InputSource::Hdmi1 => "HDMI 1",
+            InputSource::Hdmi2 => "HDMI 2",
+            InputSource::Other(_) => "Other",
+        }
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Other(value) => f.pad(&format!("Other (0x{:02X})", value)),
+            source => f.pad(source.name()),
+        }
+    }
+}
+
+impl FromStr for InputSource {
+    type Err = MonitorError;
+
+    /// Accepts names like `hdmi1`, `HDMI-1`, `dp2` or a raw value like `0x1B`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        let alias = match normalized.as_str() {
+            "vga" => Some(InputSource::Vga1),
+            "dvi" => Some(InputSource::Dvi1),
+            "dp" | "dp1" => Some(InputSource::DisplayPort1),
+            "dp2" => Some(InputSource::DisplayPort2),
+            "hdmi" => Some(InputSource::Hdmi1),
+            _ => None,
+        };
+        let named = alias.or_else(|| {
+            Self::ALL.into_iter().find(|source| {
+                source.name().replace([' ', '-'], "").to_ascii_lowercase() == normalized
+            })
+        });
+        if let Some(source) = named {
+            return Ok(source);
+        }
+
+        let raw = match normalized.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16),
+            None => normalized.parse::<u8>(),
+        };
+        raw.map(|value| Self::from_value(u32::from(value)))
+            .map_err(|_| MonitorError::InvalidValue(format!("unknown input source '{}'", s)))
+    }
+}
+
+/// Power mode values for VCP code 0xD6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerMode {
+    On,
+    Standby,
+    Suspend,
+    Off,
+    /// Off via the power button; cannot be woken over DDC/CI
+    HardOff,
+    Other(u8),
+}
+
+impl PowerMode {
+    pub fn from_value(value: u32) -> Self {
+        match value & 0xFF {
+            0x01 => PowerMode::On,
+            0x02 => PowerMode::Standby,
+            0x03 => PowerMode::Suspend,
+            0x04 => PowerMode::Off,
+            0x05 => PowerMode::HardOff,
+            other => PowerMode::Other(other as u8),
+        }
+    }
+
+    pub fn value(self) -> u8 {
+        match self {
+            PowerMode::On => 0x01,
+            PowerMode::Standby => 0x02,
+            PowerMode::Suspend => 0x03,
+            PowerMode::Off => 0x04,
+            PowerMode::HardOff => 0x05,
+            PowerMode::Other(value) => value,
+        }
+    }
+
+    /// Whether the monitor stops showing a picture in this mode: the sleep modes as well as off
+    pub fn blanks_screen(self) -> bool {
+        matches!(
+            self,
+            PowerMode::Standby | PowerMode::Suspend | PowerMode::Off | PowerMode::HardOff
+        )
+    }
+}
+
+impl fmt::Display for PowerMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerMode::On => f.pad("On"),
+            PowerMode::Standby => f.pad("Standby"),
+            PowerMode::Suspend => f.pad("Suspend"),
+            PowerMode::Off => f.pad("Off"),
+            PowerMode::HardOff => f.pad("Off (power button)"),
+            PowerMode::Other(value) => f.pad(&format!("Other (0x{:02X})", value)),
+        }
+    }
+}
+
+impl FromStr for PowerMode {
+    type Err = MonitorError;
+
+    /// Accepts `on`, `standby`, `suspend`, `off`, `hard-off` or a raw value like `0x04`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.to_ascii_lowercase().replace(['-', '_', ' '], "");
+        let mode = match normalized.as_str() {
+            "on" => PowerMode::On,
+            "standby" => PowerMode::Standby,
+            "suspend" => PowerMode::Suspend,
+            "off" => PowerMode::Off,
+            "hardoff" => PowerMode::HardOff,
+            _ => {
+                let raw = match normalized.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => normalized.parse::<u8>(),
+                };
+                raw.map(|value| Self::from_value(u32::from(value)))
+                    .map_err(|_| {
+                        MonitorError::InvalidValue(format!("unknown power mode '{}'", s))
+                    })?
+            }
+        };
+        Ok(mode)
+    }
+}
+
+/// Scan mode values for VCP code 0xDA, mainly set on TVs used as monitors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanMode {
+    /// The display's default scanning (0x00)
+    Normal,
+    /// The whole picture is shown, with a border around it
+    Underscan,
+    /// The picture is enlarged and its edges cropped
+    Overscan,
+    /// 16:9 letterbox scanning (MCCS calls it widescreen)
+    Letterbox,
+    /// Manufacturer-specific value
+    Other(u8),
+}
+
+impl ScanMode {
+    pub fn from_value(value: u32) -> Self {
+        match value & 0xFF {
+            0x00 => ScanMode::Normal,
+            0x01 => ScanMode::Underscan,
+            0x02 => ScanMode::Overscan,
+            0x03 => ScanMode::Letterbox,
+            other => ScanMode::Other(other as u8),
+        }
+    }
+
+    pub fn value(self) -> u8 {
+        match self {
+            ScanMode::Normal => 0x00,
+            ScanMode::Underscan => 0x01,
+            ScanMode::Overscan => 0x02,
+            ScanMode::Letterbox => 0x03,
+            ScanMode::Other(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for ScanMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanMode::Normal => f.pad("Normal"),
+            ScanMode::Underscan => f.pad("Underscan"),
+            ScanMode::Overscan => f.pad("Overscan"),
+            ScanMode::Letterbox => f.pad("Letterbox"),
+            ScanMode::Other(value) => f.pad(&format!("Other (0x{:02X})", value)),
+        }
+    }
+}
+
+impl FromStr for ScanMode {
+    type Err = MonitorError;
+
+    /// Accepts `normal`, `underscan`, `overscan`, `letterbox` (or `widescreen`) or a raw value
+    /// like `0x02`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.to_ascii_lowercase().replace(['-', '_', ' '], "");
+        let mode = match normalized.as_str() {
+            "normal" => ScanMode::Normal,
+            "underscan" => ScanMode::Underscan,
+            "overscan" => ScanMode::Overscan,
+            "letterbox" | "widescreen" => ScanMode::Letterbox,
+            _ => {
+                let raw = match normalized.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => normalized.parse::<u8>(),
+                };
+                raw.map(|value| Self::from_value(u32::from(value)))
+                    .map_err(|_| MonitorError::InvalidValue(format!("unknown scan mode '{}'", s)))?
+            }
+        };
+        Ok(mode)
+    }
+}
+
+/// Speaker set values for VCP code 0x63, on displays with more than one set of speakers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeakerSelect {
+    /// Front left/right pair (0x00)
+    Front,
+    /// Side left/right pair
+    Side,
+    /// Rear left/right pair
+    Rear,
+    /// Center speaker and subwoofer
+    Center,
+    /// Manufacturer-specific value
+    Other(u8),
+}
+
+impl SpeakerSelect {
+    pub fn from_value(value: u32) -> Self {
+        match value & 0xFF {
+            0x00 => SpeakerSelect::Front,
+            0x01 => SpeakerSelect::Side,
+            0x02 => SpeakerSelect::Rear,
+            0x03 => SpeakerSelect::Center,
+            other => SpeakerSelect::Other(other as u8),
+        }
+    }
+
+    pub fn value(self) -> u8 {
+        match self {
+            SpeakerSelect::Front => 0x00,
+            SpeakerSelect::Side => 0x01,
+            SpeakerSelect::Rear => 0x02,
+            SpeakerSelect::Center => 0x03,
+            SpeakerSelect::Other(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for SpeakerSelect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpeakerSelect::Front => f.pad("Front L/R"),
+            SpeakerSelect::Side => f.pad("Side L/R"),
+            SpeakerSelect::Rear => f.pad("Rear L/R"),
+            SpeakerSelect::Center => f.pad("Center/Subwoofer"),
+            SpeakerSelect::Other(value) => f.pad(&format!("Other (0x{:02X})", value)),
+        }
+    }
+}
+
+impl FromStr for SpeakerSelect {
+    type Err = MonitorError;
+
+    /// Accepts `front`, `side`, `rear`, `center` (or `subwoofer`) or a raw value like `0x02`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.to_ascii_lowercase().replace(['-', '_', ' '], "");
+        let speakers = match normalized.as_str() {
+            "front" => SpeakerSelect::Front,
+            "side" => SpeakerSelect::Side,
+            "rear" => SpeakerSelect::Rear,
+            "center" | "centre" | "subwoofer" => SpeakerSelect::Center,
+            _ => {
+                let raw = match normalized.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => normalized.parse::<u8>(),
+                };
+                raw.map(|value| Self::from_value(u32::from(value)))
+                    .map_err(|_| {
+                        MonitorError::InvalidValue(format!("unknown speaker selection '{}'", s))
+                    })?
+            }
+        };
+        Ok(speakers)
+    }
+}
+
+/// On-screen display state reported by VCP code 0xCA, in the low byte. The high byte, which
+/// MCCS 2.2 uses for button control, isn't decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OsdState {
+    /// 0x01
+    Disabled,
+    /// 0x02
+    Enabled,
+    /// The display can't report its OSD state (0xFF)
+    NotReported,
+    Other(u8),
+}
+
+impl OsdState {
+    pub fn from_value(value: u32) -> Self {
+        match value & 0xFF {
+            0x01 => OsdState::Disabled,
+            0x02 => OsdState::Enabled,
+            0xFF => OsdState::NotReported,
+            other => OsdState::Other(other as u8),
+        }
+    }
+}
+
+impl fmt::Display for OsdState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OsdState::Disabled => f.pad("OSD disabled"),
+            OsdState::Enabled => f.pad("OSD enabled"),
+            OsdState::NotReported => f.pad("OSD state not reported"),
+            OsdState::Other(value) => f.pad(&format!("Other (0x{:02X})", value)),
+        }
+    }
+}
+
+/// DVI link status for VCP code 0xBE; only bit 0 is defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkStatus {
+    /// The monitor keeps the link up when the source stops sending
+    ShutdownDisabled,
+    /// The monitor may shut the link down, which some sources handle poorly
+    ShutdownEnabled,
+}
+
+impl LinkStatus {
+    pub fn from_value(value: u32) -> Self {
+        if value & 0x01 == 0 {
+            LinkStatus::ShutdownDisabled
+        } else {
+            LinkStatus::ShutdownEnabled
+        }
+    }
+}
+
+impl fmt::Display for LinkStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkStatus::ShutdownDisabled => f.pad("Link shutdown disabled"),
+            LinkStatus::ShutdownEnabled => f.pad("Link shutdown enabled"),
+        }
+    }
+}
+
+/// Video mode and packet status of a DPVL (Digital Packet Video Link) display, VCP code 0xB7.
+/// Bits 3-7 are reserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorStatus {
+    /// Bit 0: the display is in DPVL mode rather than raster scan mode
+    pub dpvl_mode: bool,
+    /// Bit 1: the display can't accept the next packet yet
+    pub busy: bool,
+    /// Bit 2: an error was detected in the last packet received
+    pub packet_error: bool,
+}
+
+impl MonitorStatus {
+    pub fn from_value(value: u32) -> Self {
+        Self {
+            dpvl_mode: value & 0x01 != 0,
+            busy: value & 0x02 != 0,
+            packet_error: value & 0x04 != 0,
+        }
+    }
+}
+
+impl fmt::Display for MonitorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.dpvl_mode {
+            "DPVL mode"
+        } else {
+            "Raster scan mode"
+        };
+        let ready = if self.busy {
+            "busy"
+        } else {
+            "ready for next packet"
+        };
+        let error = if self.packet_error {
+            "error in last packet"
+        } else {
+            "last packet OK"
+        };
+        f.pad(&format!("{}, {}, {}", mode, ready, error))
+    }
+}
+
+/// Size of the auxiliary alphanumeric display reported by VCP code 0xCE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuxDisplaySize {
+    pub rows: u8,
+    pub characters_per_row: u8,
+}
+
+impl AuxDisplaySize {
+    /// Decode a 0xCE reply: rows in bits 7-6 and characters per row in bits 5-0 of the low byte
+    pub fn from_value(value: u32) -> Self {
+        Self {
+            rows: ((value >> 6) & 0x03) as u8,
+            characters_per_row: (value & 0x3F) as u8,
+        }
+    }
+
+    /// Number of characters the display holds
+    pub fn capacity(&self) -> usize {
+        usize::from(self.rows) * usize::from(self.characters_per_row)
+    }
+}
+
+impl fmt::Display for AuxDisplaySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!(
+            "{} x {} characters",
+            self.rows, self.characters_per_row
+        ))
+    }
+}
+
+/// Color temperature VCP 0x0C counts from: the requested temperature is this plus the
+/// multiplier times the increment from VCP 0x0B
+pub const USER_COLOR_TEMPERATURE_BASE: u32 = 3000;
+
+/// Custom color temperature set through VCP 0x0C, in steps of the increment the monitor reports
+/// through VCP 0x0B
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserColorTemperature {
+    /// Kelvin per step (VCP 0x0B)
+    pub increment: u32,
+    /// Current number of steps above 3000 K (VCP 0x0C)
+    pub multiplier: u32,
+    /// Highest multiplier the monitor accepts (maximum of VCP 0x0C)
+    pub maximum: u32,
+}
+
+impl UserColorTemperature {
+    /// Temperature the monitor is set to: 3000 K + multiplier × increment
+    pub fn kelvin(&self) -> u32 {
+        self.kelvin_at(self.multiplier)
+    }
+
+    /// Highest temperature the monitor can be set to
+    pub fn max_kelvin(&self) -> u32 {
+        self.kelvin_at(self.maximum)
+    }
+
+    /// Multiplier for the temperature nearest `kelvin` the monitor can show. Temperatures outside
+    /// 3000 K to [`max_kelvin`](Self::max_kelvin) are rejected rather than clamped.
+    pub fn multiplier_for(&self, kelvin: u32) -> Result<u32, MonitorError> {
+        if self.increment == 0 {
+            return Err(MonitorError::InvalidValue(
+                "monitor reports a color temperature increment (VCP 0x0B) of 0".to_string(),
+            ));
+        }
+        if !(USER_COLOR_TEMPERATURE_BASE..=self.max_kelvin()).contains(&kelvin) {
+            return Err(MonitorError::InvalidValue(format!(
+                "color temperature {} K is outside the monitor's range of {}-{} K",
+                kelvin,
+                USER_COLOR_TEMPERATURE_BASE,
+                self.max_kelvin()
+            )));
+        }
+        let steps = kelvin - USER_COLOR_TEMPERATURE_BASE;
+        Ok((steps + self.increment / 2) / self.increment)
+    }
+
+    fn kelvin_at(&self, multiplier: u32) -> u32 {
+        USER_COLOR_TEMPERATURE_BASE.saturating_add(multiplier.saturating_mul(self.increment))
+    }
+}
+
+impl fmt::Display for UserColorTemperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("{} K", self.kelvin()))
+    }
+}
+
+/// Red, green and blue gains written as a `#RRGGBB` hex triplet, as calibration tools show them.
+/// Each byte is a fraction of 255 that [`scale`](Self::scale) maps onto the channel's maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HexTriplet {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl HexTriplet {
+    /// Scale a byte of the triplet to a gain out of `maximum`, rounding to the nearest step
+    pub fn scale(byte: u8, maximum: u32) -> u32 {
+        let scaled = (u64::from(byte) * u64::from(maximum) + 127) / 255;
+        scaled as u32
+    }
+}
+
+impl fmt::Display for HexTriplet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!(
+            "#{:02X}{:02X}{:02X}",
+            self.red, self.green, self.blue
+        ))
+    }
+}
+
+impl FromStr for HexTriplet {
+    type Err = MonitorError;
+
+    /// Accepts six hex digits, with or without a leading `#`, e.g. `#FFF0E8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            MonitorError::InvalidValue(format!("'{}' is not a hex triplet like #RRGGBB", s.trim()))
+        };
+        let digits = s.trim();
+        let digits = digits.strip_prefix('#').unwrap_or(digits);
+        if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid());
+        Ok(HexTriplet {
+            red: byte(0)?,
+            green: byte(2)?,
+            blue: byte(4)?,
+        })
+    }
+}
+
+/// LCD sub-pixel arrangement reported by VCP code 0xB2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubPixelLayout {
+    /// The display doesn't say (0x00)
+    Undefined,
+    RgbVertical,
+    RgbHorizontal,
+    BgrVertical,
+    BgrHorizontal,
+    /// 2x2 quad: red top left, blue bottom right, green in the other two
+    QuadRedTopLeft,
+    /// 2x2 quad: red bottom left, blue top right, green in the other two
+    QuadRedBottomLeft,
+    Delta,
+    Mosaic,
+    Other(u8),
+}
+
+impl SubPixelLayout {
+    /// Decode a 0xB2 reply; only the low byte is defined
+    pub fn from_value(value: u32) -> Self {
+        match value & 0xFF {
+            0x00 => SubPixelLayout::Undefined,
+            0x01 => SubPixelLayout::RgbVertical,
+            0x02 => SubPixelLayout::RgbHorizontal,
+            0x03 => SubPixelLayout::BgrVertical,
+            0x04 => SubPixelLayout::BgrHorizontal,
+            0x05 => SubPixelLayout::QuadRedTopLeft,
+            0x06 => SubPixelLayout::QuadRedBottomLeft,
+            0x07 => SubPixelLayout::Delta,
+            0x08 => SubPixelLayout::Mosaic,
+            other => SubPixelLayout::Other(other as u8),
+        }
+    }
+}
+
+impl fmt::Display for SubPixelLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SubPixelLayout::Undefined => "Undefined",
+            SubPixelLayout::RgbVertical => "RGB vertical stripe",
+            SubPixelLayout::RgbHorizontal => "RGB horizontal stripe",
+            SubPixelLayout::BgrVertical => "BGR vertical stripe",
+            SubPixelLayout::BgrHorizontal => "BGR horizontal stripe",
+            SubPixelLayout::QuadRedTopLeft => "Quad pixel (red top left)",
+            SubPixelLayout::QuadRedBottomLeft => "Quad pixel (red bottom left)",
+            SubPixelLayout::Delta => "Delta (triad)",
+            SubPixelLayout::Mosaic => "Mosaic",
+            SubPixelLayout::Other(value) => return f.pad(&format!("Other (0x{:02X})", value)),
+        };
+        f.pad(name)
+    }
+}
+
+/// Performance-preservation feature bitmask for VCP code 0x54.
+///
+/// MCCS reserves 16 bits for features that preserve display performance (e.g. pixel orbiting
+/// against burn-in), but leaves their meaning to the manufacturer, so features are addressed by
+/// bit number (0-15) rather than by name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PerformancePreservation(u16);
+
+impl PerformancePreservation {
+    /// Number of feature bits MCCS defines
+    pub const FEATURE_COUNT: u8 = 16;
+
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    /// Decode a 0x54 reply; only the low two bytes are defined
+    pub fn from_value(value: u32) -> Self {
+        Self((value & 0xFFFF) as u16)
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Whether feature `bit` (0-15) is enabled
+    pub fn is_enabled(self, bit: u8) -> bool {
+        bit < Self::FEATURE_COUNT && self.0 & (1 << bit) != 0
+    }
+
+    /// Enable or disable feature `bit` (0-15)
+    pub fn set(&mut self, bit: u8, enabled: bool) -> Result<(), MonitorError> {
+        if bit >= Self::FEATURE_COUNT {
+            return Err(MonitorError::InvalidValue(format!(
+                "performance preservation feature {} is out of range (0-15)",
+                bit
+            )));
+        }
+        if enabled {
+            self.0 |= 1 << bit;
+        } else {
+            self.0 &= !(1 << bit);
+        }
+        Ok(())
+    }
+
+    /// Bit numbers of the enabled features, lowest first
+    pub fn enabled(self) -> Vec<u8> {
+        (0..Self::FEATURE_COUNT)
+            .filter(|&bit| self.is_enabled(bit))
+            .collect()
+    }
+}
+
+impl fmt::Display for PerformancePreservation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enabled = self.enabled();
+        if enabled.is_empty() {
+            return f.pad("No features enabled");
+        }
+        let list: Vec<_> = enabled.iter().map(u8::to_string).collect();
+        f.pad(&format!("Features {} enabled", list.join(", ")))
+    }
+}
+
+/// Power-on hours from a 0xC0 reply. The hours span four bytes: the low word arrives as the
+/// current value and the high word as the maximum value.
+pub fn usage_hours(current_value: u32, maximum_value: u32) -> u32 {
+    ((maximum_value & 0xFFFF) << 16) | (current_value & 0xFFFF)
+}
+
+fn color_preset_name(value: u32) -> Option<&'static str> {
+    let name = match value & 0xFF {
+        0x01 => "sRGB",
+        0x02 => "Display Native",
+        0x03 => "4000K",
+        0x04 => "5000K",
+        0x05 => "6500K",
+        0x06 => "7500K",
+        0x07 => "8200K",
+        0x08 => "9300K",
+        0x09 => "10000K",
+        0x0A => "11500K",
+        0x0B => "User 1",
+        0x0C => "User 2",
+        0x0D => "User 3",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Human-readable interpretation of a raw value, for codes that have a known interpreter
+pub fn decode_value(code: u8, value: u32) -> Option<String> {
+    match code {
+        codes::INPUT_SOURCE | codes::OUTPUT_SELECT => {
+            Some(InputSource::from_value(value).to_string())
+        }
+        codes::POWER_MODE => Some(PowerMode::from_value(value).to_string()),
+        codes::SCAN_MODE => Some(ScanMode::from_value(value).to_string()),
+        codes::SPEAKER_SELECT => Some(SpeakerSelect::from_value(value).to_string()),
+        codes::LINK_CONTROL => Some(LinkStatus::from_value(value).to_string()),
+        codes::OSD => Some(OsdState::from_value(value).to_string()),
+        codes::SUBPIXEL_LAYOUT => Some(SubPixelLayout::from_value(value).to_string()),
+        codes::AUX_DISPLAY_SIZE => Some(AuxDisplaySize::from_value(value).to_string()),
+        codes::MONITOR_STATUS => Some(MonitorStatus::from_value(value).to_string()),
+        codes::PERFORMANCE_PRESERVATION => {
+            Some(PerformancePreservation::from_value(value).to_string())
+        }
+        codes::COLOR_TEMPERATURE => color_preset_name(value).map(str::to_string),
+        codes::AUDIO_MUTE => match value {
+            1 => Some("Muted".to_string()),
+            2 => Some("Unmuted".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_value_interprets_known_codes() {
+        assert_eq!(
+            decode_value(codes::INPUT_SOURCE, 0x11).as_deref(),
+            Some("HDMI 1")
+        );
+        assert_eq!(
+            decode_value(codes::INPUT_SOURCE, 0x1B).as_deref(),
+            Some("Other (0x1B)")
+        );
+        assert_eq!(
+            decode_value(codes::POWER_MODE, 0x04).as_deref(),
+            Some("Off")
+        );
+        assert_eq!(
+            decode_value(codes::COLOR_TEMPERATURE, 0x05).as_deref(),
+            Some("6500K")
+        );
+        assert_eq!(decode_value(codes::AUDIO_MUTE, 1).as_deref(), Some("Muted"));
+    }
+
+    #[test]
+    fn decode_value_is_absent_without_an_interpreter() {
+        assert_eq!(decode_value(codes::BRIGHTNESS, 50), None);
+        assert_eq!(decode_value(codes::COLOR_TEMPERATURE, 0x40), None);
+        assert_eq!(decode_value(codes::AUDIO_MUTE, 0), None);
+    }
+
+    #[test]
+    fn input_source_decodes_the_low_byte() {
+        assert_eq!(InputSource::from_value(0x11), InputSource::Hdmi1);
+        assert_eq!(
+            InputSource::from_value(0x0100_000F),
+            InputSource::DisplayPort1
+        );
+        assert_eq!(InputSource::from_value(0x1B), InputSource::Other(0x1B));
+        assert_eq!(InputSource::Other(0x1B).value(), 0x1B);
+        assert_eq!(InputSource::DisplayPort2.to_string(), "DisplayPort 2");
+    }
+
+    #[test]
+    fn input_source_parses_names_aliases_and_raw_values() {
+        assert_eq!("HDMI-1".parse::<InputSource>().unwrap(), InputSource::Hdmi1);
+        assert_eq!(
+            "dp".parse::<InputSource>().unwrap(),
+            InputSource::DisplayPort1
+        );
+        assert_eq!(
+            "s_video_2".parse::<InputSource>().unwrap(),
+            InputSource::SVideo2
+        );
+        assert_eq!("0x12".parse::<InputSource>().unwrap(), InputSource::Hdmi2);
+        assert_eq!("27".parse::<InputSource>().unwrap(), InputSource::Other(27));
+        assert!("thunderbolt".parse::<InputSource>().is_err());
+    }
+
+    #[test]
+    fn link_status_decodes_the_shutdown_bit() {
+        assert_eq!(LinkStatus::from_value(0x00), LinkStatus::ShutdownDisabled);
+        assert_eq!(LinkStatus::from_value(0x01), LinkStatus::ShutdownEnabled);
+        // Only bit 0 is defined
+        assert_eq!(LinkStatus::from_value(0xFE), LinkStatus::ShutdownDisabled);
+        assert_eq!(
+            decode_value(codes::LINK_CONTROL, 1).as_deref(),
+            Some("Link shutdown enabled")
+        );
+    }
+
+    #[test]
+    fn usage_hours_join_the_high_and_low_words() {
+        assert_eq!(usage_hours(12_345, 0), 12_345);
+        assert_eq!(usage_hours(0x0001, 0x0002), 0x0002_0001);
+        assert_eq!(usage_hours(0xFFFF_1234, 0xFFFF_0001), 0x0001_1234);
+    }
+
+    #[test]
+    fn sub_pixel_layout_maps_every_defined_value() {
+        let layouts: Vec<SubPixelLayout> = (0..=8).map(SubPixelLayout::from_value).collect();
+        assert_eq!(
+            layouts,
+            [
+                SubPixelLayout::Undefined,
+                SubPixelLayout::RgbVertical,
+                SubPixelLayout::RgbHorizontal,
+                SubPixelLayout::BgrVertical,
+                SubPixelLayout::BgrHorizontal,
+                SubPixelLayout::QuadRedTopLeft,
+                SubPixelLayout::QuadRedBottomLeft,
+                SubPixelLayout::Delta,
+                SubPixelLayout::Mosaic,
+            ]
+        );
+        assert_eq!(
+            SubPixelLayout::from_value(0x09),
+            SubPixelLayout::Other(0x09)
+        );
+        // Only the low byte is defined
+        assert_eq!(
+            SubPixelLayout::from_value(0x0101),
+            SubPixelLayout::RgbVertical
+        );
+    }
+
+    #[test]
+    fn sub_pixel_layout_names() {
+        assert_eq!(SubPixelLayout::Undefined.to_string(), "Undefined");
+        assert_eq!(
+            SubPixelLayout::BgrVertical.to_string(),
+            "BGR vertical stripe"
+        );
+        assert_eq!(SubPixelLayout::Other(0x2A).to_string(), "Other (0x2A)");
+        assert_eq!(
+            decode_value(codes::SUBPIXEL_LAYOUT, 0x01).as_deref(),
+            Some("RGB vertical stripe")
+        );
+    }
+
+    #[test]
+    fn performance_preservation_bits_round_trip() {
+        let mut features = PerformancePreservation::default();
+        features.set(0, true).unwrap();
+        features.set(15, true).unwrap();
+        features.set(3, true).unwrap();
+        features.set(3, false).unwrap();
+        assert_eq!(features.bits(), 0x8001);
+        assert_eq!(features.enabled(), [0, 15]);
+        assert!(features.is_enabled(15) && !features.is_enabled(3));
+        assert!(!features.is_enabled(16));
+        assert!(features.set(16, true).is_err());
+
+        assert_eq!(
+            PerformancePreservation::from_bits(features.bits()),
+            features
+        );
+        // Only the low two bytes of a reply are defined
+        assert_eq!(PerformancePreservation::from_value(0x0001_8001), features);
+        assert_eq!(serde_json::to_value(features).unwrap(), 0x8001);
+    }
+
+    #[test]
+    fn performance_preservation_names_enabled_features() {
+        assert_eq!(
+            PerformancePreservation::from_bits(0b101).to_string(),
+            "Features 0, 2 enabled"
+        );
+        assert_eq!(
+            PerformancePreservation::default().to_string(),
+            "No features enabled"
+        );
+    }
+
+    #[test]
+    fn monitor_status_decodes_each_bit() {
+        assert_eq!(
+            MonitorStatus::from_value(0),
+            MonitorStatus {
+                dpvl_mode: false,
+                busy: false,
+                packet_error: false
+            }
+        );
+        let status = MonitorStatus::from_value(0b101);
+        assert!(status.dpvl_mode && !status.busy && status.packet_error);
+        assert!(MonitorStatus::from_value(0b010).busy);
+        // Reserved bits are ignored
+        assert_eq!(
+            MonitorStatus::from_value(0xF8),
+            MonitorStatus::from_value(0)
+        );
+
+        assert_eq!(
+            status.to_string(),
+            "DPVL mode, ready for next packet, error in last packet"
+        );
+        assert_eq!(
+            decode_value(codes::MONITOR_STATUS, 0x02).as_deref(),
+            Some("Raster scan mode, busy, last packet OK")
+        );
+    }
+
+    #[test]
+    fn scan_modes_round_trip_and_parse_names_or_raw_values() {
+        for value in 0..=0xFF {
+            assert_eq!(ScanMode::from_value(u32::from(value)).value(), value);
+        }
+        assert_eq!(ScanMode::from_value(0x102), ScanMode::Overscan);
+
+        assert_eq!(
+            "Under-Scan".parse::<ScanMode>().unwrap(),
+            ScanMode::Underscan
+        );
+        assert_eq!(
+            "widescreen".parse::<ScanMode>().unwrap(),
+            ScanMode::Letterbox
+        );
+        assert_eq!("0x02".parse::<ScanMode>().unwrap(), ScanMode::Overscan);
+        assert_eq!("16".parse::<ScanMode>().unwrap(), ScanMode::Other(0x10));
+        assert!("zoom".parse::<ScanMode>().is_err());
+        assert!("0x100".parse::<ScanMode>().is_err());
+
+        assert_eq!(ScanMode::Other(0x10).to_string(), "Other (0x10)");
+        assert_eq!(
+            decode_value(codes::SCAN_MODE, 0x01).as_deref(),
+            Some("Underscan")
+        );
+    }
+
+    #[test]
+    fn aux_display_size_splits_rows_and_columns() {
+        let size = AuxDisplaySize::from_value(0b10_010000);
+        assert_eq!((size.rows, size.characters_per_row), (2, 16));
+        assert_eq!(size.capacity(), 32);
+        assert_eq!(size.to_string(), "2 x 16 characters");
+        assert_eq!(AuxDisplaySize::from_value(0).capacity(), 0);
+    }
+
+    #[test]
+    fn user_color_temperature_counts_steps_from_3000k() {
+        let temperature = UserColorTemperature {
+            increment: 50,
+            multiplier: 70,
+            maximum: 140,
+        };
+        assert_eq!(temperature.kelvin(), 6500);
+        assert_eq!(temperature.max_kelvin(), 10_000);
+        assert_eq!(temperature.to_string(), "6500 K");
+
+        assert_eq!(temperature.multiplier_for(3000).unwrap(), 0);
+        assert_eq!(temperature.multiplier_for(6500).unwrap(), 70);
+        // Rounded to the nearest step
+        assert_eq!(temperature.multiplier_for(6524).unwrap(), 70);
+        assert_eq!(temperature.multiplier_for(6525).unwrap(), 71);
+        assert_eq!(temperature.multiplier_for(10_000).unwrap(), 140);
+
+        for kelvin in [2999, 10_001] {
+            assert!(matches!(
+                temperature.multiplier_for(kelvin),
+                Err(MonitorError::InvalidValue(_))
+            ));
+        }
+        let no_increment = UserColorTemperature {
+            increment: 0,
+            ..temperature
+        };
+        assert!(no_increment.multiplier_for(3000).is_err());
+    }
+
+    #[test]
+    fn speaker_selections_round_trip_and_parse_names_or_raw_values() {
+        for value in 0..=0xFF {
+            assert_eq!(SpeakerSelect::from_value(u32::from(value)).value(), value);
+        }
+        assert_eq!(
+            "Rear".parse::<SpeakerSelect>().unwrap(),
+            SpeakerSelect::Rear
+        );
+        assert_eq!(
+            "subwoofer".parse::<SpeakerSelect>().unwrap(),
+            SpeakerSelect::Center
+        );
+        assert_eq!(
+            "centre".parse::<SpeakerSelect>().unwrap(),
+            SpeakerSelect::Center
+        );
+        assert_eq!(
+            "0x01".parse::<SpeakerSelect>().unwrap(),
+            SpeakerSelect::Side
+        );
+        assert_eq!(
+            "9".parse::<SpeakerSelect>().unwrap(),
+            SpeakerSelect::Other(9)
+        );
+        assert!("left".parse::<SpeakerSelect>().is_err());
+
+        assert_eq!(
+            decode_value(codes::SPEAKER_SELECT, 0x03).as_deref(),
+            Some("Center/Subwoofer")
+        );
+        assert_eq!(SpeakerSelect::Other(0x10).to_string(), "Other (0x10)");
+    }
+
+    #[test]
+    fn osd_state_reads_only_the_low_byte() {
+        assert_eq!(OsdState::from_value(0x01), OsdState::Disabled);
+        assert_eq!(OsdState::from_value(0x02), OsdState::Enabled);
+        assert_eq!(OsdState::from_value(0xFF), OsdState::NotReported);
+        assert_eq!(OsdState::from_value(0x07), OsdState::Other(0x07));
+        // Button control in the high byte doesn't change the OSD state
+        assert_eq!(OsdState::from_value(0x0302), OsdState::Enabled);
+
+        assert_eq!(OsdState::Enabled.to_string(), "OSD enabled");
+        assert_eq!(OsdState::Other(0x07).to_string(), "Other (0x07)");
+        assert_eq!(
+            decode_value(codes::OSD, 0x01).as_deref(),
+            Some("OSD disabled")
+        );
+    }
+
+    #[test]
+    fn hex_triplets_parse_with_or_without_the_hash() {
+        let triplet: HexTriplet = "#FFF0e8".parse().unwrap();
+        assert_eq!(
+            triplet,
+            HexTriplet {
+                red: 0xFF,
+                green: 0xF0,
+                blue: 0xE8
+            }
+        );
+        assert_eq!("FFF0E8".parse::<HexTriplet>().unwrap(), triplet);
+        assert_eq!(triplet.to_string(), "#FFF0E8");
+
+        for bad in ["#FFF", "#FFF0E8A", "#GGF0E8", "", "#+1F0E8"] {
+            assert!(bad.parse::<HexTriplet>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn hex_triplet_bytes_scale_to_the_channel_maximum() {
+        assert_eq!(HexTriplet::scale(0xFF, 100), 100);
+        assert_eq!(HexTriplet::scale(0x00, 100), 0);
+        // 128/255 of 100 is 50.2, 127/255 is 49.8
+        assert_eq!(HexTriplet::scale(0x80, 100), 50);
+        assert_eq!(HexTriplet::scale(0x7F, 100), 50);
+        assert_eq!(HexTriplet::scale(0xF0, 255), 0xF0);
+        assert_eq!(HexTriplet::scale(0xFF, u32::MAX), u32::MAX);
+    }
+}
@@ -0,0 +1,543 @@
+use crate::{MonitorError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+const BLOCK_SIZE: usize = 128;
+/// Offsets of the four 18-byte descriptors in the base block
+const DESCRIPTOR_OFFSETS: [usize; 4] = [54, 72, 90, 108];
+
+const DESCRIPTOR_SERIAL: u8 = 0xFF;
+const DESCRIPTOR_NAME: u8 = 0xFC;
+
+const EXTENSION_CEA: u8 = 0x02;
+const CEA_BLOCK_AUDIO: u8 = 1;
+const CEA_BLOCK_VIDEO: u8 = 2;
+const CEA_BLOCK_VENDOR: u8 = 3;
+/// IEEE OUIs of the HDMI Licensing and HDMI Forum vendor-specific data blocks
+const OUI_HDMI: [u8; 3] = [0x03, 0x0C, 0x00];
+const OUI_HDMI_FORUM: [u8; 3] = [0xD8, 0x5D, 0xC4];
+
+/// Horizontal and vertical active pixels of a video mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("{}x{}", self.width, self.height))
+    }
+}
+
+/// Week and year of manufacture from bytes 16-17 of the base block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManufactureDate {
+    /// Week of the year (1-54); `None` when the monitor leaves it unspecified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub week: Option<u8>,
+    pub year: u16,
+    /// `year` is the model year rather than the year the panel was built
+    #[serde(default)]
+    pub model_year: bool,
+}
+
+impl fmt::Display for ManufactureDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self.week {
+            Some(week) => format!("week {} of {}", week, self.year),
+            None if self.model_year => format!("{} (model year)", self.year),
+            None => self.year.to_string(),
+        };
+        f.pad(&text)
+    }
+}
+
+/// Raw EDID as reported by the display, with accessors for the fields we interpret
+#[derive(Debug, Clone)]
+pub struct Edid {
+    data: Vec<u8>,
+}
+
+impl Edid {
+    /// Validate the header and length of the base block; extension blocks are kept as-is
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < BLOCK_SIZE {
+            return Err(MonitorError::InvalidValue(format!(
+                "EDID is {} bytes, expected at least {}",
+                data.len(),
+                BLOCK_SIZE
+            )));
+        }
+        if data[..8] != HEADER {
+            return Err(MonitorError::InvalidValue(
+                "EDID header is missing".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            data: data.to_vec(),
+        })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Three-letter PNP manufacturer ID (e.g. "DEL")
+    pub fn manufacturer(&self) -> String {
+        let id = u16::from_be_bytes([self.data[8], self.data[9]]);
+        [10, 5, 0]
+            .iter()
+            .map(|shift| char::from(b'@' + ((id >> shift) & 0x1F) as u8))
+            .collect()
+    }
+
+    pub fn product_code(&self) -> u16 {
+        u16::from_le_bytes([self.data[10], self.data[11]])
+    }
+
+    /// Numeric serial from the base block; many monitors leave it at zero and use the
+    /// serial string descriptor instead
+    pub fn serial_number(&self) -> u32 {
+        u32::from_le_bytes([self.data[12], self.data[13], self.data[14], self.data[15]])
+    }
+
+    /// Manufacture date; a week of 0xFF flags byte 17 as the model year instead
+    pub fn manufacture_date(&self) -> ManufactureDate {
+        let week = self.data[16];
+        ManufactureDate {
+            week: (week != 0 && week != 0xFF).then_some(week),
+            year: 1990 + u16::from(self.data[17]),
+            model_year: week == 0xFF,
+        }
+    }
+
+    pub fn serial(&self) -> Option<String> {
+        self.descriptor_text(DESCRIPTOR_SERIAL)
+    }
+
+    pub fn name(&self) -> Option<String> {
+        self.descriptor_text(DESCRIPTOR_NAME)
+    }
+
+    /// Preferred mode, taken from the first detailed timing descriptor
+    pub fn native_resolution(&self) -> Option<Resolution> {
+        DESCRIPTOR_OFFSETS
+            .iter()
+            .map(|&offset| &self.data[offset..offset + 18])
+            // A zero pixel clock marks a display descriptor rather than a timing
+            .find(|d| d[0] != 0 || d[1] != 0)
+            .map(|d| Resolution {
+                width: u32::from(d[2]) | (u32::from(d[4] & 0xF0) << 4),
+                height: u32::from(d[5]) | (u32::from(d[7] & 0xF0) << 4),
+            })
+    }
+
+    /// Number of extension blocks announced in byte 126 of the base block
+    pub fn extension_count(&self) -> u8 {
+        self.data[126]
+    }
+
+    /// Extension blocks that are actually present; a truncated EDID yields fewer than
+    /// [`Edid::extension_count`]
+    pub fn extensions(&self) -> impl Iterator<Item = &[u8]> {
+        self.data[BLOCK_SIZE..]
+            .chunks_exact(BLOCK_SIZE)
+            .take(usize::from(self.extension_count()))
+    }
+
+    /// Audio and video capabilities from all CEA-861 extension blocks, if there are any
+    pub fn cea(&self) -> Option<CeaInfo> {
+        let mut blocks = self
+            .extensions()
+            .filter(|block| block[0] == EXTENSION_CEA)
+            .peekable();
+        blocks.peek()?;
+
+        let mut info = CeaInfo::default();
+        for block in blocks {
+            info.add_block(block);
+        }
+        Some(info)
+    }
+
+    pub fn summary(&self) -> EdidSummary {
+        EdidSummary {
+            manufacturer: self.manufacturer(),
+            product_code: self.product_code(),
+            serial_number: self.serial_number(),
+            serial: self.serial(),
+            name: self.name(),
+            manufacture_date: Some(self.manufacture_date()),
+            native_resolution: self.native_resolution(),
+            extension_count: self.extension_count(),
+            cea: self.cea(),
+        }
+    }
+
+    /// Text of the first display descriptor with `tag`, trimmed of its newline and padding
+    fn descriptor_text(&self, tag: u8) -> Option<String> {
+        DESCRIPTOR_OFFSETS
+            .iter()
+            .map(|&offset| &self.data[offset..offset + 18])
+            .find(|d| d[..3] == [0, 0, 0] && d[3] == tag)
+            .map(|d| {
+                let text = &d[5..];
+                let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+                String::from_utf8_lossy(&text[..end]).trim().to_string()
+            })
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Decoded EDID fields, as shown by `list` and `get-edid`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdidSummary {
+    pub manufacturer: String,
+    pub product_code: u16,
+    pub serial_number: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manufacture_date: Option<ManufactureDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_resolution: Option<Resolution>,
+    #[serde(default)]
+    pub extension_count: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cea: Option<CeaInfo>,
+}
+
+/// Short audio descriptor from a CEA-861 audio data block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioFormat {
+    /// Audio format code (1 = LPCM, 2 = AC-3, ...)
+    pub code: u8,
+    pub max_channels: u8,
+    /// Supported sample rates in Hz
+    pub sample_rates: Vec<u32>,
+}
+
+impl AudioFormat {
+    pub fn name(&self) -> &'static str {
+        match self.code {
+            1 => "LPCM",
+            2 => "AC-3",
+            3 => "MPEG-1",
+            4 => "MP3",
+            5 => "MPEG-2",
+            6 => "AAC LC",
+            7 => "DTS",
+            8 => "ATRAC",
+            9 => "DSD",
+            10 => "E-AC-3",
+            11 => "DTS-HD",
+            12 => "Dolby TrueHD",
+            13 => "DST",
+            14 => "WMA Pro",
+            _ => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("{} ({} ch)", self.name(), self.max_channels))
+    }
+}
+
+/// Video mode from a CEA-861 video data block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoMode {
+    /// Video Identification Code
+    pub vic: u8,
+    /// Marked as a native mode by the display
+    pub native: bool,
+}
+
+impl VideoMode {
+    /// Timing for the common VICs; `None` for the rest
+    pub fn timing(&self) -> Option<&'static str> {
+        let timing = match self.vic {
+            1 => "640x480p60",
+            2 | 3 => "720x480p60",
+            4 => "1280x720p60",
+            5 => "1920x1080i60",
+            16 => "1920x1080p60",
+            17 | 18 => "720x576p50",
+            19 => "1280x720p50",
+            20 => "1920x1080i50",
+            31 => "1920x1080p50",
+            32 => "1920x1080p24",
+            33 => "1920x1080p25",
+            34 => "1920x1080p30",
+            63 => "1920x1080p120",
+            64 => "1920x1080p100",
+            93 => "3840x2160p24",
+            94 => "3840x2160p25",
+            95 => "3840x2160p30",
+            96 => "3840x2160p50",
+            97 => "3840x2160p60",
+            98 => "4096x2160p24",
+            99 => "4096x2160p25",
+            100 => "4096x2160p30",
+            101 => "4096x2160p50",
+            102 => "4096x2160p60",
+            117 => "3840x2160p100",
+            118 => "3840x2160p120",
+            _ => return None,
+        };
+        Some(timing)
+    }
+}
+
+impl fmt::Display for VideoMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self.timing() {
+            Some(timing) => format!("{} (VIC {})", timing, self.vic),
+            None => format!("VIC {}", self.vic),
+        };
+        f.pad(&text)
+    }
+}
+
+/// Capabilities collected from the CEA-861 extension blocks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CeaInfo {
+    pub revision: u8,
+    /// Basic audio (2-channel LPCM) support flag
+    pub basic_audio: bool,
+    pub audio_formats: Vec<AudioFormat>,
+    pub video_modes: Vec<VideoMode>,
+    /// Has an HDMI vendor-specific data block (absent on DVI/DisplayPort-only sinks)
+    pub hdmi: bool,
+    /// Has an HDMI Forum data block (HDMI 2.x features)
+    pub hdmi_forum: bool,
+}
+
+impl CeaInfo {
+    fn add_block(&mut self, block: &[u8]) {
+        self.revision = self.revision.max(block[1]);
+        // Byte 2 is where the detailed timings start, which ends the data block collection.
+        // Revision 1 blocks have no data blocks at all.
+        let data_end = usize::from(block[2]).clamp(4, BLOCK_SIZE - 1);
+        if block[1] >= 2 {
+            self.basic_audio |= block[3] & 0x40 != 0;
+        }
+        if block[1] < 3 {
+            return;
+        }
+
+        let mut offset = 4;
+        while offset < data_end {
+            let tag = block[offset] >> 5;
+            let len = usize::from(block[offset] & 0x1F);
+            let end = (offset + 1 + len).min(data_end);
+            let payload = &block[offset + 1..end];
+
+            match tag {
+                CEA_BLOCK_AUDIO => self
+                    .audio_formats
+                    .extend(payload.chunks_exact(3).map(|sad| {
+                        AudioFormat {
+                            code: (sad[0] >> 3) & 0x0F,
+                            max_channels: (sad[0] & 0x07) + 1,
+                            sample_rates: [
+                                32_000, 44_100, 48_000, 88_200, 96_000, 176_400, 192_000,
+                            ]
+                            .iter()
+                            .enumerate()
+                            .filter(|(bit, _)| sad[1] & (1 << bit) != 0)
+                            .map(|(_, &rate)| rate)
+                            .collect(),
+                        }
+                    })),
+                CEA_BLOCK_VIDEO => self.video_modes.extend(payload.iter().map(|&svd| {
+                    // Bit 7 marks a native mode only for VICs 1-64; above that it is part of the VIC
+                    if svd & 0x80 != 0 && (svd & 0x7F) <= 64 {
+                        VideoMode {
+                            vic: svd & 0x7F,
+                            native: true,
+                        }
+                    } else {
+                        VideoMode {
+                            vic: svd,
+                            native: false,
+                        }
+                    }
+                })),
+                CEA_BLOCK_VENDOR if payload.len() >= 3 => {
+                    self.hdmi |= payload[..3] == OUI_HDMI;
+                    self.hdmi_forum |= payload[..3] == OUI_HDMI_FORUM;
+                }
+                _ => {}
+            }
+
+            offset = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Base block of a Dell U2723QE, whose preferred mode is 3840x2160 at 60 Hz
+    fn u2723qe() -> Vec<u8> {
+        let mut data = vec![0u8; BLOCK_SIZE];
+        data[..8].copy_from_slice(&HEADER);
+        data[8..10].copy_from_slice(&[0x10, 0xAC]);
+        data[10..12].copy_from_slice(&0x4290u16.to_le_bytes());
+        // 533.25 MHz pixel clock, 3840 (0xF00) x 2160 (0x870) active
+        data[54..62].copy_from_slice(&[0x4D, 0xD0, 0x00, 0xA0, 0xF0, 0x70, 0x3E, 0x80]);
+        data[72..77].copy_from_slice(&[0, 0, 0, DESCRIPTOR_NAME, 0]);
+        data[77..90].copy_from_slice(b"DELL U2723QE\n");
+        data
+    }
+
+    #[test]
+    fn native_resolution_comes_from_the_first_detailed_timing() {
+        let edid = Edid::parse(&u2723qe()).unwrap();
+        assert_eq!(
+            edid.native_resolution(),
+            Some(Resolution {
+                width: 3840,
+                height: 2160
+            })
+        );
+        assert_eq!(edid.native_resolution().unwrap().to_string(), "3840x2160");
+        assert_eq!(edid.manufacturer(), "DEL");
+        assert_eq!(edid.name().as_deref(), Some("DELL U2723QE"));
+    }
+
+    #[test]
+    fn native_resolution_skips_display_descriptors() {
+        let mut data = u2723qe();
+        // Move the timing behind the name descriptor
+        let timing: Vec<u8> = data[54..72].to_vec();
+        let name: Vec<u8> = data[72..90].to_vec();
+        data[54..72].copy_from_slice(&name);
+        data[72..90].copy_from_slice(&timing);
+        let edid = Edid::parse(&data).unwrap();
+        assert_eq!(edid.native_resolution().map(|r| r.width), Some(3840));
+
+        data[72..90].fill(0);
+        assert_eq!(Edid::parse(&data).unwrap().native_resolution(), None);
+    }
+
+    #[test]
+    fn parse_rejects_short_or_headerless_data() {
+        assert!(Edid::parse(&u2723qe()[..100]).is_err());
+        let mut data = u2723qe();
+        data[0] = 0xFF;
+        assert!(Edid::parse(&data).is_err());
+    }
+
+    /// CEA-861 revision 3 extension: basic audio, LPCM 2ch at 32/44.1/48 kHz, AC-3 6ch at
+    /// 48 kHz, VIC 16 (native) and 97, and the HDMI and HDMI Forum vendor blocks
+    fn cea_extension() -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        block[..4].copy_from_slice(&[EXTENSION_CEA, 3, 0, 0x40]);
+        let data_blocks = [
+            0x26, 0x09, 0x07, 0x07, 0x15, 0x04, 0x50, // audio: 2 short audio descriptors
+            0x42, 0x90, 0x61, // video: VIC 16 native, VIC 97
+            0x65, 0x03, 0x0C, 0x00, 0x10, 0x00, // HDMI vendor block
+            0x64, 0xD8, 0x5D, 0xC4, 0x01, // HDMI Forum vendor block
+        ];
+        block[4..4 + data_blocks.len()].copy_from_slice(&data_blocks);
+        block[2] = (4 + data_blocks.len()) as u8;
+        block
+    }
+
+    #[test]
+    fn cea_extension_lists_audio_video_and_hdmi_support() {
+        let mut data = u2723qe();
+        data[126] = 1;
+        data.extend(cea_extension());
+        let edid = Edid::parse(&data).unwrap();
+        assert_eq!(edid.extension_count(), 1);
+
+        let cea = edid.cea().unwrap();
+        assert_eq!(cea.revision, 3);
+        assert!(cea.basic_audio);
+        assert_eq!(cea.audio_formats.len(), 2);
+        assert_eq!(cea.audio_formats[0].name(), "LPCM");
+        assert_eq!(cea.audio_formats[0].max_channels, 2);
+        assert_eq!(
+            cea.audio_formats[0].sample_rates,
+            vec![32_000, 44_100, 48_000]
+        );
+        assert_eq!(cea.audio_formats[1].to_string(), "AC-3 (6 ch)");
+        assert_eq!(
+            cea.video_modes,
+            vec![
+                VideoMode {
+                    vic: 16,
+                    native: true
+                },
+                VideoMode {
+                    vic: 97,
+                    native: false
+                },
+            ]
+        );
+        assert_eq!(cea.video_modes[1].to_string(), "3840x2160p60 (VIC 97)");
+        assert!(cea.hdmi);
+        assert!(cea.hdmi_forum);
+    }
+
+    #[test]
+    fn missing_extension_blocks_are_not_parsed() {
+        let mut data = u2723qe();
+        assert!(Edid::parse(&data).unwrap().cea().is_none());
+
+        // Announced but truncated
+        data[126] = 1;
+        let edid = Edid::parse(&data).unwrap();
+        assert_eq!(edid.extensions().count(), 0);
+        assert!(edid.cea().is_none());
+    }
+
+    #[test]
+    fn manufacture_date_reads_week_year_and_the_model_year_flag() {
+        let date = |week: u8, year: u8| {
+            let mut data = u2723qe();
+            data[16] = week;
+            data[17] = year;
+            Edid::parse(&data).unwrap().manufacture_date()
+        };
+
+        let built = date(12, 32);
+        assert_eq!(
+            built,
+            ManufactureDate {
+                week: Some(12),
+                year: 2022,
+                model_year: false
+            }
+        );
+        assert_eq!(built.to_string(), "week 12 of 2022");
+
+        let unspecified = date(0, 33);
+        assert_eq!(unspecified.week, None);
+        assert_eq!(unspecified.to_string(), "2023");
+
+        let model = date(0xFF, 34);
+        assert!(model.model_year);
+        assert_eq!(model.week, None);
+        assert_eq!(model.to_string(), "2024 (model year)");
+
+        assert_eq!(
+            serde_json::to_value(built).unwrap(),
+            serde_json::json!({ "week": 12, "year": 2022, "model_year": false })
+        );
+        assert_eq!(
+            serde_json::to_value(unspecified).unwrap(),
+            serde_json::json!({ "year": 2023, "model_year": false })
+        );
+    }
+}
@@ -1,13 +1,23 @@
 use thiserror::Error;
+use windows_sys::Win32::Foundation::*;
 
 #[derive(Error, Debug)]
 pub enum MonitorError {
     #[error("Windows API error: {0}")]
     WindowsApi(String),
 
+    #[error("{function} failed: {}", describe_win32_error(*code))]
+    Win32 { function: &'static str, code: u32 },
+
     #[error("Monitor not found: {0}")]
     MonitorNotFound(String),
 
+    #[error("Ambiguous monitor name: {0}")]
+    AmbiguousMonitor(String),
+
+    #[error("Monitor disconnected: {0} is no longer connected; refresh the monitor list")]
+    MonitorDisconnected(String),
+
     #[error("Invalid VCP code: {0:#x}")]
     InvalidVcpCode(u8),
 
@@ -31,6 +41,298 @@ pub enum MonitorError {
 
     #[error("VCP feature not supported")]
     VcpNotSupported,
+
+    #[error("Failed to parse capabilities string: {0}")]
+    CapabilitiesParse(String),
+
+    #[error("Capabilities string too large: monitor reported {0} bytes")]
+    CapabilitiesTooLarge(u32),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Profile error: {0}")]
+    Profile(String),
+
+    #[error("Control unavailable: {0}")]
+    ControlUnavailable(String),
+
+    #[error("Read-only mode: '{0}' would change monitor settings")]
+    ReadOnlyMode(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error(
+        "DDC/CI channel busy: {0} kept failing. Another application (e.g. the monitor vendor's utility) may be using the monitor; close it and try again"
+    )]
+    ChannelBusy(&'static str),
+
+    #[error("Monitor worker has shut down")]
+    Shutdown,
+
+    #[error("Confirmation required: '{0}' resets monitor settings; pass --yes to proceed")]
+    ConfirmationRequired(String),
 }
 
 pub type Result<T> = std::result::Result<T, MonitorError>;
+
+impl MonitorError {
+    /// Win32 error code captured when the failing call returned, if this error came from one
+    pub fn os_code(&self) -> Option<u32> {
+        match self {
+            MonitorError::Win32 { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Whether this error means the monitor behind the handle has gone away, so retrying with
+    /// the same handle can't succeed
+    pub fn is_disconnect(&self) -> bool {
+        self.os_code().is_some_and(is_disconnected_error)
+    }
+
+    /// Whether the monitor answered that it doesn't implement the VCP code, as opposed to the
+    /// read failing to reach it
+    pub fn is_not_supported(&self) -> bool {
+        matches!(self, MonitorError::VcpNotSupported)
+    }
+}
+
+/// I2C/DDC/CI errors that appear intermittently when another application talks to the monitor at
+/// the same time: transfers collide on the bus, or replies arrive garbled
+const CHANNEL_BUSY_ERRORS: &[u32] = &[
+    ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32,
+    ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA as u32,
+    ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_CHECKSUM as u32,
+];
+
+/// Whether Win32 error `code` is one a contended DDC/CI channel produces; such calls are worth
+/// retrying before giving up with [`MonitorError::ChannelBusy`]
+pub fn is_channel_busy_error(code: u32) -> bool {
+    CHANNEL_BUSY_ERRORS.contains(&code)
+}
+
+/// Errors the Monitor Configuration API returns when the monitor answers a VCP read but doesn't
+/// implement the code: an explicit unsupported reply, or a reply with no usable value. Some
+/// monitors answer unknown codes with a null message, which dxva2 reports as a bad length.
+const VCP_UNSUPPORTED_ERRORS: &[u32] = &[
+    ERROR_GRAPHICS_DDCCI_VCP_NOT_SUPPORTED as u32,
+    ERROR_GRAPHICS_DDCCI_INVALID_DATA as u32,
+    ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH as u32,
+];
+
+/// Whether Win32 error `code` means the monitor doesn't implement the VCP code that was read
+pub fn is_vcp_unsupported_error(code: u32) -> bool {
+    VCP_UNSUPPORTED_ERRORS.contains(&code)
+}
+
+/// Errors the Monitor Configuration API returns for a physical monitor handle whose monitor was
+/// unplugged or switched off at the wall after the handle was opened
+const DISCONNECTED_ERRORS: &[u32] = &[
+    ERROR_GRAPHICS_INVALID_PHYSICAL_MONITOR_HANDLE as u32,
+    ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS as u32,
+];
+
+/// Whether Win32 error `code` is one a stale physical monitor handle produces
+pub fn is_disconnected_error(code: u32) -> bool {
+    DISCONNECTED_ERRORS.contains(&code)
+}
+
+/// Descriptions for the I2C/DDC/CI errors returned by the Monitor Configuration API
+const WIN32_ERROR_DESCRIPTIONS: &[(u32, &str)] = &[
+    (ERROR_ACCESS_DENIED, "Access denied"),
+    (ERROR_INVALID_HANDLE, "Invalid handle"),
+    (ERROR_INVALID_PARAMETER, "Invalid parameter"),
+    (
+        ERROR_GRAPHICS_I2C_NOT_SUPPORTED as u32,
+        "The monitor's I2C bus is not supported by the display driver",
+    ),
+    (
+        ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST as u32,
+        "No I2C device responded (is DDC/CI enabled in the monitor's OSD?)",
+    ),
+    (
+        ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32,
+        "Error sending data to the monitor over I2C",
+    ),
+    (
+        ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA as u32,
+        "Error receiving data from the monitor over I2C",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_VCP_NOT_SUPPORTED as u32,
+        "The monitor does not support this VCP code",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_INVALID_DATA as u32,
+        "The monitor returned invalid DDC/CI data",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_MONITOR_RETURNED_INVALID_TIMING_STATUS_BYTE as u32,
+        "The monitor returned an invalid timing status byte",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_INVALID_CAPABILITIES_STRING as u32,
+        "The monitor returned an invalid capabilities string",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_INTERNAL_ERROR as u32,
+        "Internal error in the Monitor Configuration API",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_COMMAND as u32,
+        "The monitor replied with an invalid DDC/CI command",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH as u32,
+        "The monitor replied with an invalid DDC/CI message length",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_CHECKSUM as u32,
+        "The monitor replied with an invalid DDC/CI checksum",
+    ),
+    (
+        ERROR_GRAPHICS_INVALID_PHYSICAL_MONITOR_HANDLE as u32,
+        "Invalid physical monitor handle",
+    ),
+    (
+        ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS as u32,
+        "The monitor is no longer connected",
+    ),
+    (
+        ERROR_GRAPHICS_DDCCI_CURRENT_CURRENT_VALUE_GREATER_THAN_MAXIMUM_VALUE as u32,
+        "The monitor reported a current value greater than its maximum",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_INVALID_VCP_VERSION as u32,
+        "The monitor reported an invalid VCP version",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_MONITOR_VIOLATES_MCCS_SPECIFICATION as u32,
+        "The monitor violates the MCCS specification",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_MCCS_VERSION_MISMATCH as u32,
+        "The monitor's MCCS version does not match its capabilities string",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_UNSUPPORTED_MCCS_VERSION as u32,
+        "Unsupported MCCS version",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_INVALID_TECHNOLOGY_TYPE_RETURNED as u32,
+        "The monitor returned an invalid display technology type",
+    ),
+    (
+        ERROR_GRAPHICS_MCA_UNSUPPORTED_COLOR_TEMPERATURE as u32,
+        "Unsupported color temperature",
+    ),
+    (
+        ERROR_GRAPHICS_ONLY_CONSOLE_SESSION_SUPPORTED as u32,
+        "Monitor control is only available from the console session (not over Remote Desktop)",
+    ),
+];
+
+/// Human-readable form of a Win32 error code; unknown codes are shown as hex
+pub fn describe_win32_error(code: u32) -> String {
+    match WIN32_ERROR_DESCRIPTIONS.iter().find(|(c, _)| *c == code) {
+        Some((_, description)) => format!("{} (0x{:08X})", description, code),
+        None => format!("error 0x{:08X}", code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn os_code_is_kept_only_for_win32_errors() {
+        let error = MonitorError::Win32 {
+            function: "SetVCPFeature",
+            code: ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS as u32,
+        };
+        assert_eq!(
+            error.os_code(),
+            Some(ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS as u32)
+        );
+        assert!(error.is_disconnect());
+        assert!(!error.is_not_supported());
+
+        assert_eq!(MonitorError::VcpNotSupported.os_code(), None);
+        assert!(MonitorError::VcpNotSupported.is_not_supported());
+    }
+
+    #[test]
+    fn win32_codes_are_classified() {
+        assert!(is_channel_busy_error(
+            ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32
+        ));
+        assert!(is_vcp_unsupported_error(
+            ERROR_GRAPHICS_DDCCI_VCP_NOT_SUPPORTED as u32
+        ));
+        // A null reply to an unknown code is answered at once, not retried as contention
+        assert!(is_vcp_unsupported_error(
+            ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH as u32
+        ));
+        assert!(!is_channel_busy_error(
+            ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH as u32
+        ));
+        assert!(!is_vcp_unsupported_error(ERROR_ACCESS_DENIED));
+        assert!(describe_win32_error(ERROR_ACCESS_DENIED).starts_with("Access denied"));
+        assert_eq!(describe_win32_error(0x1234), "error 0x00001234");
+    }
+
+    #[test]
+    fn ddc_errors_are_described_by_name() {
+        assert_eq!(
+            describe_win32_error(ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST as u32),
+            "No I2C device responded (is DDC/CI enabled in the monitor's OSD?) (0xC0262581)"
+        );
+        assert_eq!(
+            describe_win32_error(ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32),
+            "Error sending data to the monitor over I2C (0xC0262582)"
+        );
+        assert!(
+            describe_win32_error(ERROR_GRAPHICS_ONLY_CONSOLE_SESSION_SUPPORTED as u32)
+                .contains("Remote Desktop")
+        );
+        assert_eq!(describe_win32_error(0xC026_25FF), "error 0xC02625FF");
+    }
+
+    #[test]
+    fn win32_errors_display_the_function_and_description() {
+        let error = MonitorError::Win32 {
+            function: "GetMonitorBrightness",
+            code: ERROR_INVALID_HANDLE,
+        };
+        assert_eq!(
+            error.to_string(),
+            "GetMonitorBrightness failed: Invalid handle (0x00000006)"
+        );
+    }
+
+    #[test]
+    fn bus_contention_codes_map_to_channel_busy_only() {
+        for code in [
+            ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA,
+            ERROR_GRAPHICS_I2C_ERROR_RECEIVING_DATA,
+            ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_CHECKSUM,
+        ] {
+            assert!(is_channel_busy_error(code as u32), "0x{:08X}", code);
+        }
+        for code in [
+            ERROR_GRAPHICS_DDCCI_VCP_NOT_SUPPORTED as u32,
+            ERROR_GRAPHICS_DDCCI_INVALID_MESSAGE_LENGTH as u32,
+            ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS as u32,
+            ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST as u32,
+            ERROR_ACCESS_DENIED,
+        ] {
+            assert!(!is_channel_busy_error(code), "0x{:08X}", code);
+        }
+
+        let message = MonitorError::ChannelBusy("SetVCPFeature").to_string();
+        assert!(message.contains("SetVCPFeature kept failing"));
+        assert!(message.contains("close it and try again"));
+    }
+}
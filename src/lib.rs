@@ -1,7 +1,27 @@
+pub mod actor;
+mod backend;
+pub mod capabilities;
 pub mod cli;
+pub mod config;
+pub mod curve;
+pub mod decode;
+pub mod edid;
 pub mod error;
 pub mod monitor;
 pub mod native;
+pub mod profile;
+pub mod registry;
+pub mod selector;
+pub mod selftest;
+pub mod server;
+pub mod table;
+pub mod template;
 pub mod vcp;
 
+pub use actor::{MonitorActor, MonitorActorHandle};
+pub use backend::RetryPolicy;
 pub use error::{MonitorError, Result};
+pub use registry::MonitorRegistry;
+
+#[cfg(feature = "testing")]
+pub use backend::{Backend, MockBackend};
@@ -1,12 +1,34 @@
-use crate::{Result, native};
+use crate::{
+    MonitorError, Result, RetryPolicy,
+    decode::InputSource,
+    edid::{Edid, EdidSummary},
+    native,
+    selector::MonitorSelector,
+    vcp,
+};
 use serde::{Deserialize, Serialize};
-use windows_sys::Win32::{Foundation::HANDLE, Graphics::Gdi::HMONITOR};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+use windows_sys::Win32::{
+    Foundation::{HANDLE, RECT},
+    Graphics::Gdi::{HMONITOR, MONITORINFOEXW},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrightnessInfo {
     pub minimum: u32,
     pub current: u32,
     pub maximum: u32,
+    /// Level the monitor reported when it was above the maximum; `current` then holds the
+    /// maximum instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +36,40 @@ pub struct ContrastInfo {
     pub minimum: u32,
     pub current: u32,
     pub maximum: u32,
+    /// Level the monitor reported when it was above the maximum; `current` then holds the
+    /// maximum instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported: Option<u32>,
+}
+
+/// Rectangle in virtual-screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl MonitorRect {
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+}
+
+impl From<RECT> for MonitorRect {
+    fn from(rect: RECT) -> Self {
+        Self {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +78,546 @@ pub struct MonitorInfo {
     pub friendly_name: String,
     pub instance_name: String,
     pub is_primary: bool,
+    /// Adapter (GPU) the monitor is attached to, e.g. `NVIDIA GeForce RTX 3080`
+    #[serde(default)]
+    pub adapter_name: String,
+    /// Full bounds of the monitor
+    pub monitor_rect: MonitorRect,
+    /// Bounds excluding the taskbar and docked app bars
+    pub work_area: MonitorRect,
+    /// Decoded EDID, if Windows has one cached for the monitor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edid: Option<EdidSummary>,
+}
+
+/// The display path of the `index`-th monitor on GDI device `device_name`, with device
+/// instance ID `instance_name` (empty if unknown), among the active paths `targets`.
+///
+/// The instance ID is matched against each target's device interface path first, since it
+/// identifies the monitor itself. Failing that, the monitor is taken to be the `index`-th
+/// target of its source device, which holds as long as Windows lists cloned monitors in the same
+/// order in both places.
+pub fn correlate_target<'a>(
+    targets: &'a [native::DisplayConfigTarget],
+    device_name: &str,
+    instance_name: &str,
+    index: u32,
+) -> Option<&'a native::DisplayConfigTarget> {
+    let by_instance = targets.iter().find(|target| {
+        !instance_name.is_empty()
+            && native::instance_id_from_interface(&target.device_path)
+                .is_some_and(|id| id.eq_ignore_ascii_case(instance_name))
+    });
+
+    by_instance.or_else(|| {
+        targets
+            .iter()
+            .filter(|target| target.source_device == device_name)
+            .nth(index as usize)
+    })
+}
+
+impl MonitorInfo {
+    /// Info for the `index`-th monitor of a display monitor; reads the cached EDID but doesn't
+    /// talk to the monitor.
+    ///
+    /// When the monitor is found among `targets`, the device name and friendly name come from
+    /// the display configuration; otherwise from `szDevice` and `friendly_name` (the monitor
+    /// device description).
+    fn from_native(
+        monitor_info: &MONITORINFOEXW,
+        index: u32,
+        friendly_name: String,
+        targets: &[native::DisplayConfigTarget],
+    ) -> Self {
+        let device_name = native::display_device_name(monitor_info);
+        let instance_name =
+            native::get_monitor_instance_id(&monitor_info.szDevice, index).unwrap_or_default();
+        let edid = Some(instance_name.as_str())
+            .filter(|id| !id.is_empty())
+            .and_then(|id| native::read_edid(id).ok())
+            .and_then(|data| Edid::parse(&data).ok())
+            .map(|edid| edid.summary());
+        let target = correlate_target(targets, &device_name, &instance_name, index);
+
+        Self {
+            device_name: target.map_or(device_name, |target| target.source_device.clone()),
+            friendly_name: target
+                .map(|target| target.friendly_name.clone())
+                .filter(|name| !name.is_empty())
+                .unwrap_or(friendly_name),
+            instance_name,
+            // MONITORINFOF_PRIMARY
+            is_primary: (monitor_info.monitorInfo.dwFlags & 1) != 0,
+            adapter_name: native::get_adapter_name(&monitor_info.szDevice).unwrap_or_default(),
+            monitor_rect: monitor_info.monitorInfo.rcMonitor.into(),
+            work_area: monitor_info.monitorInfo.rcWork.into(),
+            edid,
+        }
+    }
+
+    /// Whether this monitor is identified by `name` (device name, friendly name, or
+    /// [stable ID](Self::stable_id))
+    pub fn matches(&self, name: &str) -> bool {
+        self.device_name == name || self.friendly_name == name || self.stable_id() == name
+    }
+
+    /// Looser form of [`Self::matches`]: device name and stable ID compared case-insensitively,
+    /// and `name` found anywhere in the friendly name, ignoring case (so `dell` matches
+    /// `DELL U2720Q`)
+    pub fn matches_partially(&self, name: &str) -> bool {
+        self.device_name.eq_ignore_ascii_case(name)
+            || self.stable_id().eq_ignore_ascii_case(name)
+            || self
+                .friendly_name
+                .to_lowercase()
+                .contains(&name.to_lowercase())
+    }
+
+    /// Whether the monitor is attached to the adapter `adapter`, matched case-insensitively
+    /// against part of the adapter name (so `nvidia` matches `NVIDIA GeForce RTX 3080`)
+    pub fn on_adapter(&self, adapter: &str) -> bool {
+        self.adapter_name
+            .to_lowercase()
+            .contains(&adapter.to_lowercase())
+    }
+
+    /// Identifier for keying saved per-monitor state, stable across reboots where possible.
+    ///
+    /// In order of preference:
+    /// 1. `edid:<manufacturer><product>:<serial>` from the EDID serial string, or the numeric
+    ///    serial if the string is missing. Survives reconnecting to another port or GPU.
+    /// 2. `instance:<device instance ID>`. Stable across reboots, but changes when the monitor is
+    ///    plugged into a different port.
+    /// 3. `device:<device name>` (e.g. `\\.\DISPLAY1`). Can be reassigned after a reboot or
+    ///    display change.
+    pub fn stable_id(&self) -> String {
+        self.edid_id()
+            .map(|id| format!("edid:{}", id))
+            .unwrap_or_else(|| {
+                if self.instance_name.is_empty() {
+                    format!("device:{}", self.device_name)
+                } else {
+                    format!("instance:{}", self.instance_name)
+                }
+            })
+    }
+
+    /// Whether `other` is the same physical panel, e.g. the two entries a cloned or MST-chained
+    /// monitor can show up as under different device names.
+    ///
+    /// Compares the EDID manufacturer, product and serial when both sides have a serial, and the
+    /// device instance IDs otherwise. Without either, the panels can't be told apart and are
+    /// reported as different.
+    pub fn same_panel_as(&self, other: &MonitorInfo) -> bool {
+        if let (Some(id), Some(other_id)) = (self.edid_id(), other.edid_id()) {
+            return id == other_id;
+        }
+        !self.instance_name.is_empty() && self.instance_name == other.instance_name
+    }
+
+    /// `<manufacturer><product>:<serial>` from the EDID, if it has a serial
+    fn edid_id(&self) -> Option<String> {
+        let edid = self.edid.as_ref()?;
+        let serial = edid.serial.clone().or_else(|| {
+            // Zero means the manufacturer didn't program a serial
+            (edid.serial_number != 0).then(|| edid.serial_number.to_string())
+        })?;
+        Some(format!(
+            "{}{:04X}:{}",
+            edid.manufacturer, edid.product_code, serial
+        ))
+    }
+}
+
+/// Which controls a monitor accepts, so a GUI can enable or disable sliders up front
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ControlCapabilities {
+    pub brightness: bool,
+    pub contrast: bool,
+    /// Whether raw VCP reads work at all
+    pub vcp: bool,
+}
+
+impl ControlCapabilities {
+    /// Probe `monitor`'s brightness and contrast controls, and raw VCP reads over `vcp`
+    pub fn probe(monitor: &(impl Monitor + ?Sized), vcp: &vcp::VcpMonitor) -> Self {
+        let vcp = vcp.get_vcp_feature(vcp::codes::VCP_VERSION).is_ok()
+            || vcp.get_vcp_feature(vcp::codes::BRIGHTNESS).is_ok();
+
+        // A zero range means the control is listed but can't actually be adjusted
+        ControlCapabilities {
+            brightness: monitor
+                .get_brightness()
+                .is_ok_and(|b| check_range(b.minimum, b.maximum).is_ok()),
+            contrast: monitor
+                .get_contrast()
+                .is_ok_and(|c| check_range(c.minimum, c.maximum).is_ok()),
+            vcp,
+        }
+    }
+}
+
+/// GDI device context of a monitor's display device, for diagnosing gamma-ramp problems
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceContextInfo {
+    /// GDI device the context was created for, e.g. `\\.\DISPLAY1`
+    pub device_name: String,
+    /// Whether `CreateDCW` returned a device context
+    pub created: bool,
+    /// Why `CreateDCW` failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether the context is a raster display, as a monitor's should be
+    pub valid: bool,
+    /// Whether the driver accepts gamma ramps on this device
+    pub gamma_ramp: bool,
+}
+
+impl DeviceContextInfo {
+    pub fn probe(info: &MonitorInfo) -> Self {
+        let device_name = info.device_name.clone();
+        match native::probe_device_context(&device_name) {
+            Ok(caps) => Self {
+                device_name,
+                created: true,
+                error: None,
+                valid: caps.is_raster_display(),
+                gamma_ramp: caps.supports_gamma_ramp(),
+            },
+            Err(e) => Self {
+                device_name,
+                created: false,
+                error: Some(e.to_string()),
+                valid: false,
+                gamma_ramp: false,
+            },
+        }
+    }
+}
+
+/// Current brightness, contrast, and input of one monitor, e.g. for a dashboard. A reading the
+/// monitor doesn't answer is `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitorSnapshot {
+    pub brightness: Option<BrightnessInfo>,
+    pub contrast: Option<ContrastInfo>,
+    pub input: Option<InputSource>,
+}
+
+impl MonitorSnapshot {
+    /// Read `monitor`'s brightness and contrast, and the input over `vcp`. Fails only if none of
+    /// them is answered.
+    pub fn read(monitor: &(impl Monitor + ?Sized), vcp: &vcp::VcpMonitor) -> Result<Self> {
+        let snapshot = MonitorSnapshot {
+            brightness: monitor.get_brightness().ok(),
+            contrast: monitor.get_contrast().ok(),
+            input: vcp.get_input_source().ok(),
+        };
+
+        if snapshot.brightness.is_none() && snapshot.contrast.is_none() && snapshot.input.is_none()
+        {
+            return Err(MonitorError::ControlUnavailable(format!(
+                "{} did not answer any reading",
+                monitor.info().friendly_name
+            )));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Reject a zero range: some monitors report `min == max` (usually both 0) for a control they
+/// don't really implement, and scaling against that range would divide by zero
+pub fn check_range(minimum: u32, maximum: u32) -> Result<()> {
+    if maximum <= minimum {
+        return Err(MonitorError::ControlUnavailable(format!(
+            "monitor reports a zero range (min: {}, max: {})",
+            minimum, maximum
+        )));
+    }
+    Ok(())
+}
+
+/// Pause between repeated writes, so the monitor has finished processing the previous one
+pub const REPEAT_DELAY: Duration = Duration::from_millis(50);
+
+/// Run `write` `times` times (at least once), pausing [`REPEAT_DELAY`] in between. A workaround
+/// for monitors that only apply a write after receiving it several times; stops at the first
+/// error.
+pub fn repeat_write(times: u32, mut write: impl FnMut() -> Result<()>) -> Result<()> {
+    for attempt in 0..times.max(1) {
+        if attempt > 0 {
+            thread::sleep(REPEAT_DELAY);
+        }
+        write()?;
+    }
+    Ok(())
+}
+
+/// Run `f` on every item using at most `jobs` threads (at least one), returning the results in
+/// item order. Each item is handed to exactly one thread, so work on one monitor handle is never
+/// interleaved; `jobs` only limits how many monitors are talked to at once, e.g. to keep traffic
+/// down on an I2C bus shared by several monitors.
+pub fn run_bounded<T: Send, R: Send>(
+    items: Vec<T>,
+    jobs: usize,
+    f: impl Fn(T) -> R + Sync,
+) -> Vec<R> {
+    let count = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate().collect::<VecDeque<_>>());
+    let results = Mutex::new((0..count).map(|_| None).collect::<Vec<Option<R>>>());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, count.max(1)) {
+            scope.spawn(|| {
+                loop {
+                    // Release the queue before running `f`, so other workers can take items
+                    let next = queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                    let Some((index, item)) = next else { break };
+                    let result = f(item);
+                    results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Whether `current` is within `tolerance` of `target`, in either direction
+pub fn within_tolerance(current: u32, target: u32, tolerance: u32) -> bool {
+    current.abs_diff(target) <= tolerance
+}
+
+/// Debouncing for polled values, as used by `mirror-brightness`: a reading is passed on only once
+/// it has held for `settle` and differs from the last value passed on, so a user stepping
+/// through several levels with the monitor's buttons causes one update rather than one per step
+#[derive(Debug, Clone)]
+pub struct Debouncer<T> {
+    settle: Duration,
+    pending: Option<(T, Instant)>,
+    applied: Option<T>,
+}
+
+impl<T: Copy + PartialEq> Debouncer<T> {
+    pub fn new(settle: Duration) -> Self {
+        Self {
+            settle,
+            pending: None,
+            applied: None,
+        }
+    }
+
+    /// Record a reading taken at `now`. Returns the value to pass on, if there is a new one that
+    /// has settled. The first reading is passed on once it settles, to bring everything in line.
+    pub fn observe(&mut self, value: T, now: Instant) -> Option<T> {
+        let since = match self.pending {
+            Some((pending, since)) if pending == value => since,
+            _ => {
+                self.pending = Some((value, now));
+                now
+            }
+        };
+
+        if now.duration_since(since) < self.settle || self.applied == Some(value) {
+            return None;
+        }
+        self.applied = Some(value);
+        Some(value)
+    }
+}
+
+/// Level on a `target_min..=target_max` control matching `current` on a
+/// `source_min..=source_max` one, at the same relative position
+pub fn mirrored_level(
+    current: u32,
+    (source_min, source_max): (u32, u32),
+    (target_min, target_max): (u32, u32),
+) -> Result<u32> {
+    level_from_normalized(
+        normalize_level(current, source_min, source_max)?,
+        target_min,
+        target_max,
+    )
+}
+
+/// Set `target`'s brightness to the level matching `current` on the source's range, skipping
+/// the write when it already matches. Returns the target's level.
+pub fn mirror_brightness_to(
+    target: &(impl Monitor + ?Sized),
+    current: u32,
+    source_range: (u32, u32),
+) -> Result<u32> {
+    let range = target.get_brightness()?;
+    let level = mirrored_level(current, source_range, (range.minimum, range.maximum))?;
+    if level != range.current {
+        target.set_brightness(level)?;
+    }
+    Ok(level)
+}
+
+/// Poll brightness every `interval` until it is within `tolerance` of `target`, e.g. while the
+/// monitor finishes a fade. Returns the last reading. Failed reads are retried, since monitors
+/// often stop answering mid-transition; the last reading is reported if `timeout` runs out.
+pub fn wait_for_brightness(
+    mon: &impl Monitor,
+    target: u32,
+    tolerance: u32,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<u32> {
+    let deadline = Instant::now() + timeout;
+    let mut last = None;
+
+    loop {
+        if let Ok(brightness) = mon.get_brightness() {
+            if within_tolerance(brightness.current, target, tolerance) {
+                return Ok(brightness.current);
+            }
+            last = Some(brightness.current);
+        }
+
+        if Instant::now() + interval > deadline {
+            let last = last.map_or("no reading".to_string(), |v| format!("last reading {}", v));
+            return Err(MonitorError::Timeout(format!(
+                "brightness did not reach {} ± {} within {:.1}s ({})",
+                target,
+                tolerance,
+                timeout.as_secs_f64(),
+                last
+            )));
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Position of `current` within `minimum..=maximum`, as 0.0-1.0
+pub fn normalize_level(current: u32, minimum: u32, maximum: u32) -> Result<f32> {
+    check_range(minimum, maximum)?;
+    let offset = current.saturating_sub(minimum) as f32;
+    Ok((offset / (maximum - minimum) as f32).clamp(0.0, 1.0))
+}
+
+/// Inverse of [`normalize_level`]: the raw level for `value`, which is clamped to 0.0-1.0.
+/// NaN maps to the minimum.
+pub fn level_from_normalized(value: f32, minimum: u32, maximum: u32) -> Result<u32> {
+    check_range(minimum, maximum)?;
+    let value = if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    };
+    Ok(minimum + (value * (maximum - minimum) as f32).round() as u32)
+}
+
+/// Raw level for step `notch` of an on-screen-display scale with `notches` steps, spread evenly
+/// over `minimum..=maximum`. E.g. notch 7 of 10 over 0-100 is 70.
+pub fn level_from_notch(notch: u32, notches: u32, minimum: u32, maximum: u32) -> Result<u32> {
+    check_range(minimum, maximum)?;
+    if notches == 0 {
+        return Err(MonitorError::InvalidValue(
+            "notch count must be at least 1".to_string(),
+        ));
+    }
+    if notch > notches {
+        return Err(MonitorError::InvalidValue(format!(
+            "notch {} is above the {} notches configured",
+            notch, notches
+        )));
+    }
+    let span = u64::from(maximum - minimum);
+    let offset = (u64::from(notch) * span * 2 + u64::from(notches)) / (u64::from(notches) * 2);
+    Ok(minimum + offset as u32)
+}
+
+/// The control brightness is adjusted through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrightnessControl {
+    /// Luminance (VCP 0x10), through the Monitor Configuration API's brightness functions
+    Luminance,
+    /// White backlight level (VCP 0x6B), which some monitors use instead of luminance
+    BacklightWhite,
+}
+
+impl BrightnessControl {
+    pub fn code(self) -> u8 {
+        match self {
+            BrightnessControl::Luminance => vcp::codes::BRIGHTNESS,
+            BrightnessControl::BacklightWhite => vcp::codes::BACKLIGHT_WHITE,
+        }
+    }
+
+    /// The first control the monitor has, checked in order: luminance (skipped when
+    /// `force_backlight` is set), then the white backlight level. A probe that fails because
+    /// the monitor lacks the control moves on to the next one; any other error, such as a busy
+    /// channel, is returned rather than switching controls. Controls after the chosen one aren't
+    /// probed. `None` if the monitor has neither.
+    pub fn select(
+        force_backlight: bool,
+        luminance: impl FnOnce() -> Result<()>,
+        backlight: impl FnOnce() -> Result<()>,
+    ) -> Result<Option<Self>> {
+        if !force_backlight && control_present(luminance())? {
+            Ok(Some(BrightnessControl::Luminance))
+        } else if control_present(backlight())? {
+            Ok(Some(BrightnessControl::BacklightWhite))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Whether a control probe found the control; errors other than the monitor lacking it are
+/// passed on
+fn control_present(probe: Result<()>) -> Result<bool> {
+    match probe {
+        Ok(()) => Ok(true),
+        Err(MonitorError::VcpNotSupported | MonitorError::UnsupportedOperation(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// A level given by the user: a raw value, or a notch of the monitor's on-screen scale
+/// written with an `n` suffix (`7n`), see [`level_from_notch`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelValue {
+    Raw(u32),
+    Notch(u32),
+}
+
+impl FromStr for LevelValue {
+    type Err = MonitorError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (digits, notch) = match s.strip_suffix(['n', 'N']) {
+            Some(digits) => (digits, true),
+            None => (s, false),
+        };
+        let value: u32 = digits.parse().map_err(|_| {
+            MonitorError::InvalidValue(format!("'{}' is not a level or a notch like 7n", s))
+        })?;
+        Ok(if notch {
+            LevelValue::Notch(value)
+        } else {
+            LevelValue::Raw(value)
+        })
+    }
+}
+
+impl fmt::Display for LevelValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelValue::Raw(value) => write!(f, "{}", value),
+            LevelValue::Notch(notch) => write!(f, "{}n", notch),
+        }
+    }
 }
 
 pub trait Monitor {
@@ -30,37 +626,123 @@ pub trait Monitor {
     fn get_contrast(&self) -> Result<ContrastInfo>;
     fn set_contrast(&self, level: u32) -> Result<()>;
     fn info(&self) -> &MonitorInfo;
+
+    /// Brightness as 0.0-1.0 of the monitor-reported range, e.g. for GUI sliders
+    fn get_brightness_normalized(&self) -> Result<f32> {
+        let brightness = self.get_brightness()?;
+        normalize_level(brightness.current, brightness.minimum, brightness.maximum)
+    }
+
+    /// Set brightness from 0.0-1.0 of the monitor-reported range; out-of-range values are clamped
+    fn set_brightness_normalized(&self, value: f32) -> Result<()> {
+        let brightness = self.get_brightness()?;
+        self.set_brightness(level_from_normalized(
+            value,
+            brightness.minimum,
+            brightness.maximum,
+        )?)
+    }
 }
 
 pub struct PhysicalMonitor {
     handle: HANDLE,
+    hmonitor: HMONITOR,
     info: MonitorInfo,
+    max_overrides: BTreeMap<u8, u32>,
+    settle_delay: Duration,
+    retry: RetryPolicy,
 }
 
+// SAFETY: the physical monitor handle is an opaque token owned exclusively by this struct and is
+// not tied to the thread that created it, so ownership may move between threads.
+unsafe impl Send for PhysicalMonitor {}
+
 impl PhysicalMonitor {
-    pub fn new(hmonitor: HMONITOR, physical_monitor: &native::PHYSICAL_MONITOR) -> Result<Self> {
+    /// `index` is the position of `physical_monitor` among the physical monitors of `hmonitor`;
+    /// `targets` are the active display paths from [`native::display_config_targets`], used to
+    /// name the monitor
+    pub fn new(
+        hmonitor: HMONITOR,
+        index: u32,
+        physical_monitor: &native::PHYSICAL_MONITOR,
+        targets: &[native::DisplayConfigTarget],
+    ) -> Result<Self> {
         let monitor_info = native::get_monitor_info(hmonitor)?;
 
-        // Note: windows-sys MONITORINFOEXW.szDevice is at offset after MONITORINFO
-        // For simplicity, use a placeholder device name based on handle address
-        let device_name = format!("DISPLAY_{:p}", hmonitor as *const ());
-
-        let is_primary = (monitor_info.monitorInfo.dwFlags & 1) != 0;
-
         Ok(Self {
             handle: physical_monitor.h_physical_monitor,
-            info: MonitorInfo {
-                device_name,
-                friendly_name: physical_monitor.description(),
-                instance_name: String::new(), // TODO: Get from display device
-                is_primary,
-            },
+            hmonitor,
+            info: MonitorInfo::from_native(
+                &monitor_info,
+                index,
+                physical_monitor.description(),
+                targets,
+            ),
+            max_overrides: BTreeMap::new(),
+            settle_delay: Duration::ZERO,
+            retry: RetryPolicy::default(),
         })
     }
 
+    /// Use these maximums, keyed by VCP code, in place of what the monitor reports for brightness
+    /// and contrast. For firmware that reports e.g. 0 or 255 for a 0-100 control.
+    pub fn with_max_overrides(mut self, overrides: BTreeMap<u8, u32>) -> Self {
+        self.max_overrides = overrides;
+        self
+    }
+
+    /// Maximums set with [`Self::with_max_overrides`]
+    pub fn max_overrides(&self) -> &BTreeMap<u8, u32> {
+        &self.max_overrides
+    }
+
+    /// Wait this long after a write before reading it back, for monitors that report the old
+    /// value for a while
+    pub fn with_settle_delay(mut self, delay: Duration) -> Self {
+        self.settle_delay = delay;
+        self
+    }
+
+    /// Delay set with [`Self::with_settle_delay`]
+    pub fn settle_delay(&self) -> Duration {
+        self.settle_delay
+    }
+
+    /// Retry VCP calls that fail on a busy DDC/CI channel as `retry` says
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Policy set with [`Self::with_retry`]
+    pub fn retry(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// The configured maximum for `code`, or `reported` if there is none
+    fn maximum(&self, code: u8, reported: u32) -> u32 {
+        self.max_overrides.get(&code).copied().unwrap_or(reported)
+    }
+
     pub fn handle(&self) -> HANDLE {
         self.handle
     }
+
+    /// Display monitor this physical monitor belongs to
+    pub fn hmonitor(&self) -> HMONITOR {
+        self.hmonitor
+    }
+
+    /// Probe brightness, contrast, and general VCP support in one pass over the same handle
+    pub fn probe_capabilities(&self) -> ControlCapabilities {
+        ControlCapabilities::probe(self, &vcp::VcpMonitor::new(self.handle))
+    }
+
+    /// Read brightness, contrast, and input in one go over this monitor's handle. Fails only if
+    /// the monitor answers none of them, which usually means DDC/CI is off or unreachable.
+    pub fn snapshot(&self) -> Result<MonitorSnapshot> {
+        MonitorSnapshot::read(self, &vcp::VcpMonitor::new(self.handle))
+    }
 }
 
 impl Monitor for PhysicalMonitor {
@@ -74,15 +756,16 @@ impl Monitor for PhysicalMonitor {
                 native::dxva2::GetMonitorBrightness(self.handle, &mut min, &mut current, &mut max);
 
             if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "GetMonitorBrightness failed".to_string(),
-                ));
+                return Err(native::last_error("GetMonitorBrightness"));
             }
+            let max = self.maximum(vcp::codes::BRIGHTNESS, max);
+            let (current, reported) = vcp::clamp_reported_level(current, max);
 
             Ok(BrightnessInfo {
                 minimum: min,
                 current,
                 maximum: max,
+                reported,
             })
         }
     }
@@ -92,9 +775,7 @@ impl Monitor for PhysicalMonitor {
             let result = native::dxva2::SetMonitorBrightness(self.handle, level);
 
             if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "SetMonitorBrightness failed".to_string(),
-                ));
+                return Err(native::last_error("SetMonitorBrightness"));
             }
 
             Ok(())
@@ -111,15 +792,16 @@ impl Monitor for PhysicalMonitor {
                 native::dxva2::GetMonitorContrast(self.handle, &mut min, &mut current, &mut max);
 
             if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "GetMonitorContrast failed".to_string(),
-                ));
+                return Err(native::last_error("GetMonitorContrast"));
             }
+            let max = self.maximum(vcp::codes::CONTRAST, max);
+            let (current, reported) = vcp::clamp_reported_level(current, max);
 
             Ok(ContrastInfo {
                 minimum: min,
                 current,
                 maximum: max,
+                reported,
             })
         }
     }
@@ -129,9 +811,7 @@ impl Monitor for PhysicalMonitor {
             let result = native::dxva2::SetMonitorContrast(self.handle, level);
 
             if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "SetMonitorContrast failed".to_string(),
-                ));
+                return Err(native::last_error("SetMonitorContrast"));
             }
 
             Ok(())
@@ -143,21 +823,125 @@ impl Monitor for PhysicalMonitor {
     }
 }
 
+#[cfg(test)]
+impl MonitorInfo {
+    /// Info for a monitor that exists only in tests, at the origin of the virtual screen
+    pub(crate) fn fake(device_name: &str, friendly_name: &str) -> Self {
+        let rect = MonitorRect {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        Self {
+            device_name: device_name.to_string(),
+            friendly_name: friendly_name.to_string(),
+            instance_name: String::new(),
+            is_primary: false,
+            adapter_name: String::new(),
+            monitor_rect: rect,
+            work_area: rect,
+            edid: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl PhysicalMonitor {
+    /// A monitor with no physical handle behind it, for tests that only need its info
+    pub(crate) fn fake(info: MonitorInfo) -> Self {
+        Self {
+            handle: std::ptr::null_mut(),
+            hmonitor: std::ptr::null_mut(),
+            info,
+            max_overrides: BTreeMap::new(),
+            settle_delay: Duration::ZERO,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Monitor whose brightness and contrast controls are plain VCP codes on a mock
+#[cfg(test)]
+pub(crate) struct VcpControls {
+    pub(crate) vcp: vcp::VcpMonitor,
+    info: MonitorInfo,
+}
+
+#[cfg(test)]
+impl VcpControls {
+    pub(crate) fn new(device_name: &str, mock: crate::backend::MockBackend) -> Self {
+        Self {
+            vcp: vcp::VcpMonitor::with_backend(mock),
+            info: MonitorInfo::fake(device_name, "Generic PnP Monitor"),
+        }
+    }
+
+    fn level(&self, code: u8) -> Result<(u32, u32)> {
+        let response = self.vcp.get_vcp_feature(code)?;
+        Ok((response.current_value, response.maximum_value))
+    }
+}
+
+#[cfg(test)]
+impl Monitor for VcpControls {
+    fn get_brightness(&self) -> Result<BrightnessInfo> {
+        let (current, maximum) = self.level(vcp::codes::BRIGHTNESS)?;
+        Ok(BrightnessInfo {
+            minimum: 0,
+            current,
+            maximum,
+            reported: None,
+        })
+    }
+
+    fn set_brightness(&self, level: u32) -> Result<()> {
+        self.vcp.set_vcp_feature(vcp::codes::BRIGHTNESS, level)
+    }
+
+    fn get_contrast(&self) -> Result<ContrastInfo> {
+        let (current, maximum) = self.level(vcp::codes::CONTRAST)?;
+        Ok(ContrastInfo {
+            minimum: 0,
+            current,
+            maximum,
+            reported: None,
+        })
+    }
+
+    fn set_contrast(&self, level: u32) -> Result<()> {
+        self.vcp.set_vcp_feature(vcp::codes::CONTRAST, level)
+    }
+
+    fn info(&self) -> &MonitorInfo {
+        &self.info
+    }
+}
+
 impl Drop for PhysicalMonitor {
     fn drop(&mut self) {
-        let _ = native::destroy_physical_monitor(self.handle);
+        if !self.handle.is_null() {
+            let _ = native::destroy_physical_monitor(self.handle);
+        }
     }
 }
 
+/// Active display paths for naming monitors. Without them (e.g. over some remote sessions),
+/// monitors keep the names `szDevice` and the device descriptions give.
+fn display_targets() -> Vec<native::DisplayConfigTarget> {
+    native::display_config_targets().unwrap_or_default()
+}
+
 pub fn enumerate_monitors() -> Result<Vec<PhysicalMonitor>> {
     let enumerator = native::MonitorEnumerator::enumerate()?;
+    let targets = display_targets();
     let mut monitors = Vec::new();
 
     for hmonitor in enumerator.monitors {
         let physical_monitors = native::get_physical_monitors(hmonitor)?;
 
-        for pm in &physical_monitors {
-            match PhysicalMonitor::new(hmonitor, pm) {
+        for (index, pm) in physical_monitors.iter().enumerate() {
+            match PhysicalMonitor::new(hmonitor, index as u32, pm, &targets) {
                 Ok(monitor) => monitors.push(monitor),
                 Err(e) => eprintln!("Warning: Failed to create monitor: {}", e),
             }
@@ -167,23 +951,806 @@ pub fn enumerate_monitors() -> Result<Vec<PhysicalMonitor>> {
     Ok(monitors)
 }
 
-pub fn find_monitor(device_name: &str) -> Result<PhysicalMonitor> {
-    let monitors = enumerate_monitors()?;
+/// `monitors` grouped by the adapter (GPU) they are attached to. Adapters are in the order their
+/// first monitor appears, and each adapter's monitors keep their order.
+pub fn group_by_adapter(monitors: &[MonitorInfo]) -> Vec<(&str, Vec<&MonitorInfo>)> {
+    let mut groups: Vec<(&str, Vec<&MonitorInfo>)> = Vec::new();
+    for info in monitors {
+        match groups
+            .iter_mut()
+            .find(|(adapter, _)| *adapter == info.adapter_name)
+        {
+            Some((_, members)) => members.push(info),
+            None => groups.push((info.adapter_name.as_str(), vec![info])),
+        }
+    }
+    groups
+}
+
+/// Monitors attached to one adapter (GPU), see [`MonitorInfo::on_adapter`]
+pub fn enumerate_monitors_on_adapter(adapter_name: &str) -> Result<Vec<PhysicalMonitor>> {
+    let mut monitors = enumerate_monitors()?;
+    monitors.retain(|mon| mon.info().on_adapter(adapter_name));
+    Ok(monitors)
+}
+
+/// Info for every monitor without opening physical monitor handles, which is much cheaper than
+/// [`enumerate_monitors`] when only names and positions are needed. Entries come in the same
+/// order as [`enumerate_monitors`]; friendly names come from the monitor device descriptions,
+/// which dxva2 normally reports as well.
+pub fn list_monitor_info() -> Result<Vec<MonitorInfo>> {
+    let enumerator = native::MonitorEnumerator::enumerate()?;
+    let targets = display_targets();
+    let mut monitors = Vec::new();
 
-    monitors
+    for hmonitor in enumerator.monitors {
+        let monitor_info = native::get_monitor_info(hmonitor)?;
+        let mut descriptions = native::get_monitor_descriptions(&monitor_info.szDevice);
+        if descriptions.is_empty() {
+            // Some virtual displays have no monitor device; still list the display itself
+            descriptions.push(native::display_device_name(&monitor_info));
+        }
+
+        for (index, description) in descriptions.into_iter().enumerate() {
+            monitors.push(MonitorInfo::from_native(
+                &monitor_info,
+                index as u32,
+                description,
+                &targets,
+            ));
+        }
+    }
+
+    Ok(monitors)
+}
+
+/// Bounds of every display and whether it is the primary one, in enumeration order. Much cheaper
+/// to read than [`list_monitor_info`], and changes whenever displays are connected, removed or
+/// rearranged, so it tells whether a saved [`list_monitor_info`] result is still current.
+pub fn display_layout() -> Result<Vec<(MonitorRect, bool)>> {
+    native::MonitorEnumerator::enumerate()?
+        .monitors
         .into_iter()
-        .find(|m| {
-            let info = m.info();
-            info.device_name == device_name || info.friendly_name == device_name
+        .map(|hmonitor| {
+            let info = native::get_monitor_info(hmonitor)?.monitorInfo;
+            // MONITORINFOF_PRIMARY
+            Ok((MonitorRect::from(info.rcMonitor), (info.dwFlags & 1) != 0))
         })
-        .ok_or_else(|| crate::MonitorError::MonitorNotFound(device_name.to_string()))
+        .collect()
+}
+
+/// Open the first physical monitor behind a raw HMONITOR, e.g. one handed over by other code
+pub fn open_hmonitor(hmonitor: HMONITOR) -> Result<PhysicalMonitor> {
+    native::get_monitor_info(hmonitor).map_err(|e| {
+        MonitorError::InvalidValue(format!(
+            "0x{:X} is not a valid HMONITOR ({})",
+            hmonitor as usize, e
+        ))
+    })?;
+
+    let physical_monitors = native::get_physical_monitors(hmonitor)?;
+    let targets = display_targets();
+    let mut monitors = physical_monitors
+        .iter()
+        .enumerate()
+        .map(|(index, pm)| PhysicalMonitor::new(hmonitor, index as u32, pm, &targets))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(monitors.remove(0))
+}
+
+pub fn find_monitor(device_name: &str) -> Result<PhysicalMonitor> {
+    MonitorSelector::Device(device_name.to_string()).resolve()
 }
 
 pub fn get_primary_monitor() -> Result<PhysicalMonitor> {
-    let monitors = enumerate_monitors()?;
+    MonitorSelector::Primary.resolve()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows_sys::Win32::Foundation::ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST;
+
+    #[test]
+    fn rect_converts_from_win32_and_measures() {
+        let rect = MonitorRect::from(RECT {
+            left: -1920,
+            top: 0,
+            right: 0,
+            bottom: 1080,
+        });
+        assert_eq!((rect.left, rect.right), (-1920, 0));
+        assert_eq!((rect.width(), rect.height()), (1920, 1080));
+    }
+
+    #[test]
+    fn info_json_includes_rectangle_and_work_area() {
+        let mut info = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        info.work_area.bottom = 1040;
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["monitor_rect"]["right"], 1920);
+        assert_eq!(json["work_area"]["bottom"], 1040);
+        let parsed: MonitorInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.work_area, info.work_area);
+    }
+
+    #[test]
+    fn normalized_levels_scale_over_the_reported_range() {
+        assert_eq!(normalize_level(50, 0, 100).unwrap(), 0.5);
+        assert_eq!(normalize_level(60, 20, 100).unwrap(), 0.5);
+        assert_eq!(normalize_level(10, 20, 100).unwrap(), 0.0);
+        assert_eq!(normalize_level(150, 0, 100).unwrap(), 1.0);
+        assert_eq!(normalize_level(1, 0, 3).unwrap(), 1.0 / 3.0);
+
+        assert_eq!(level_from_normalized(0.5, 0, 100).unwrap(), 50);
+        assert_eq!(level_from_normalized(0.5, 20, 100).unwrap(), 60);
+        assert_eq!(level_from_normalized(0.5, 0, 3).unwrap(), 2);
+        assert_eq!(level_from_normalized(1.5, 0, 100).unwrap(), 100);
+        assert_eq!(level_from_normalized(-0.5, 10, 100).unwrap(), 10);
+        assert_eq!(level_from_normalized(f32::NAN, 10, 100).unwrap(), 10);
+        assert_eq!(
+            level_from_normalized(1.0, 0, u32::MAX >> 8).unwrap(),
+            u32::MAX >> 8
+        );
+    }
+
+    #[test]
+    fn zero_range_is_control_unavailable() {
+        for (minimum, maximum) in [(0, 0), (50, 50), (80, 20)] {
+            assert!(matches!(
+                normalize_level(0, minimum, maximum),
+                Err(MonitorError::ControlUnavailable(_))
+            ));
+            assert!(matches!(
+                level_from_normalized(0.5, minimum, maximum),
+                Err(MonitorError::ControlUnavailable(_))
+            ));
+            assert!(matches!(
+                level_from_notch(5, 10, minimum, maximum),
+                Err(MonitorError::ControlUnavailable(_))
+            ));
+        }
+        assert!(mirrored_level(50, (0, 0), (0, 100)).is_err());
+        assert!(mirrored_level(50, (0, 100), (0, 0)).is_err());
+    }
+
+    #[test]
+    fn open_hmonitor_rejects_an_invalid_handle() {
+        let error = open_hmonitor(0xDEAD as HMONITOR).err().unwrap();
+        assert!(matches!(&error, MonitorError::InvalidValue(_)));
+        assert!(error.to_string().contains("0xDEAD is not a valid HMONITOR"));
+    }
+
+    #[test]
+    fn os_error_code_survives_dropping_a_monitor() {
+        let os_code = ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST as u32;
+        let monitor = crate::vcp::VcpMonitor::with_backend(
+            crate::backend::MockBackend::new().failing_with(vcp::codes::BRIGHTNESS, os_code),
+        );
+        let error = monitor.get_vcp_feature(vcp::codes::BRIGHTNESS).unwrap_err();
+
+        drop(PhysicalMonitor::fake(MonitorInfo::fake(
+            "\\\\.\\DISPLAY2",
+            "Generic PnP Monitor",
+        )));
+        assert_eq!(error.os_code(), Some(os_code));
+        assert!(matches!(
+            error,
+            MonitorError::Win32 {
+                function: "GetVCPFeatureAndVCPFeatureReply",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn probe_reports_only_the_supported_controls() {
+        let monitor = VcpControls::new(
+            "\\\\.\\DISPLAY1",
+            crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 40, 100),
+        );
+        let caps = ControlCapabilities::probe(&monitor, &monitor.vcp);
+        assert!(caps.brightness);
+        assert!(!caps.contrast);
+        assert!(caps.vcp);
+    }
+
+    #[test]
+    fn probe_treats_a_zero_range_as_unsupported() {
+        let monitor = VcpControls::new(
+            "\\\\.\\DISPLAY1",
+            crate::backend::MockBackend::new()
+                .with_feature(vcp::codes::CONTRAST, 0, 0)
+                .with_feature(vcp::codes::VCP_VERSION, 0x0202, 0),
+        );
+        let caps = ControlCapabilities::probe(&monitor, &monitor.vcp);
+        assert!(!caps.brightness);
+        assert!(!caps.contrast);
+        assert!(caps.vcp);
+
+        let silent = VcpControls::new("\\\\.\\DISPLAY1", crate::backend::MockBackend::new());
+        let caps = ControlCapabilities::probe(&silent, &silent.vcp);
+        assert!(!caps.brightness && !caps.contrast && !caps.vcp);
+    }
+
+    #[test]
+    fn normalized_brightness_goes_through_the_monitor() {
+        let mock = crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 25, 50);
+        let monitor = VcpControls::new("\\\\.\\DISPLAY1", mock.clone());
+
+        assert_eq!(monitor.get_brightness_normalized().unwrap(), 0.5);
+        monitor.set_brightness_normalized(0.8).unwrap();
+        assert_eq!(mock.value(vcp::codes::BRIGHTNESS), Some(40));
+        monitor.set_brightness_normalized(2.0).unwrap();
+        assert_eq!(mock.value(vcp::codes::BRIGHTNESS), Some(50));
+    }
+
+    #[test]
+    fn zero_range_monitor_reports_no_normalized_brightness() {
+        let mock = crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 0, 0);
+        let monitor = VcpControls::new("\\\\.\\DISPLAY1", mock.clone());
+
+        assert!(matches!(
+            monitor.get_brightness_normalized(),
+            Err(MonitorError::ControlUnavailable(_))
+        ));
+        assert!(monitor.set_brightness_normalized(0.5).is_err());
+        assert!(mock.writes().is_empty());
+    }
+
+    #[test]
+    fn repeat_write_sends_the_write_n_times() {
+        let mock = crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 40, 100);
+        let vcp = vcp::VcpMonitor::with_backend(mock.clone());
+
+        repeat_write(3, || vcp.set_vcp_feature(vcp::codes::BRIGHTNESS, 70)).unwrap();
+        assert_eq!(mock.writes(), vec![(vcp::codes::BRIGHTNESS, 70); 3]);
+    }
+
+    #[test]
+    fn repeat_write_always_writes_at_least_once() {
+        let mut calls = 0;
+        repeat_write(0, || {
+            calls += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn repeat_write_stops_at_the_first_error() {
+        let os_code = ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST as u32;
+        let vcp = vcp::VcpMonitor::with_backend(
+            crate::backend::MockBackend::new().failing_with(vcp::codes::BRIGHTNESS, os_code),
+        );
+
+        let mut calls = 0;
+        let err = repeat_write(3, || {
+            calls += 1;
+            vcp.set_vcp_feature(vcp::codes::BRIGHTNESS, 70)
+        })
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert_eq!(err.os_code(), Some(os_code));
+    }
+
+    fn edid(serial_number: u32, serial: Option<&str>) -> EdidSummary {
+        EdidSummary {
+            manufacturer: "DEL".to_string(),
+            product_code: 0x4211,
+            serial_number,
+            serial: serial.map(str::to_string),
+            name: Some("DELL U2723QE".to_string()),
+            manufacture_date: None,
+            native_resolution: None,
+            extension_count: 0,
+            cea: None,
+        }
+    }
+
+    #[test]
+    fn stable_id_prefers_edid_serial_then_instance_then_device() {
+        let mut info = MonitorInfo::fake("\\\\.\\DISPLAY2", "Dell U2723QE");
+        assert_eq!(info.stable_id(), "device:\\\\.\\DISPLAY2");
+
+        info.instance_name = "DISPLAY\\DELA1F4\\5&2d3b7c5&0&UID4352".to_string();
+        assert_eq!(
+            info.stable_id(),
+            "instance:DISPLAY\\DELA1F4\\5&2d3b7c5&0&UID4352"
+        );
+
+        info.edid = Some(edid(0, None));
+        assert!(info.stable_id().starts_with("instance:"));
+
+        info.edid = Some(edid(1234, None));
+        assert_eq!(info.stable_id(), "edid:DEL4211:1234");
+
+        info.edid = Some(edid(1234, Some("7XKJ3H3")));
+        assert_eq!(info.stable_id(), "edid:DEL4211:7XKJ3H3");
+    }
+
+    #[test]
+    fn stable_id_survives_a_new_device_name() {
+        let mut before = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        before.edid = Some(edid(1234, Some("7XKJ3H3")));
+        let mut after = before.clone();
+        after.device_name = "\\\\.\\DISPLAY3".to_string();
+        after.instance_name = "DISPLAY\\DELA1F4\\5&1a2b3c&0&UID512".to_string();
+
+        assert_eq!(before.stable_id(), after.stable_id());
+        assert!(before.same_panel_as(&after));
+    }
 
-    monitors
+    #[test]
+    fn snapshot_leaves_unanswered_readings_empty() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(vcp::codes::BRIGHTNESS, 40, 100)
+            .with_feature(vcp::codes::INPUT_SOURCE, 0x11, 0x1B)
+            .failing_with(
+                vcp::codes::CONTRAST,
+                ERROR_GRAPHICS_I2C_DEVICE_DOES_NOT_EXIST as u32,
+            );
+        let monitor = VcpControls::new("\\\\.\\DISPLAY1", mock);
+
+        let snapshot = MonitorSnapshot::read(&monitor, &monitor.vcp).unwrap();
+        assert_eq!(snapshot.brightness.as_ref().unwrap().current, 40);
+        assert!(snapshot.contrast.is_none());
+        assert_eq!(snapshot.input, Some(InputSource::Hdmi1));
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert!(json["contrast"].is_null());
+        assert_eq!(json["brightness"]["current"], 40);
+    }
+
+    #[test]
+    fn snapshot_fails_when_nothing_answers() {
+        let monitor = VcpControls::new("\\\\.\\DISPLAY1", crate::backend::MockBackend::new());
+        let err = MonitorSnapshot::read(&monitor, &monitor.vcp).unwrap_err();
+        assert!(matches!(err, MonitorError::ControlUnavailable(_)));
+        assert!(
+            err.to_string()
+                .contains("Generic PnP Monitor did not answer any reading")
+        );
+    }
+
+    /// Monitor whose brightness readings follow a script, repeating the last one, like a monitor
+    /// part-way through a fade. `None` is a read the monitor doesn't answer.
+    struct Fading {
+        readings: std::cell::RefCell<VecDeque<Option<u32>>>,
+        reads: std::cell::Cell<usize>,
+        info: MonitorInfo,
+    }
+
+    impl Fading {
+        fn new(readings: &[Option<u32>]) -> Self {
+            Self {
+                readings: std::cell::RefCell::new(readings.iter().copied().collect()),
+                reads: std::cell::Cell::new(0),
+                info: MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE"),
+            }
+        }
+    }
+
+    impl Monitor for Fading {
+        fn get_brightness(&self) -> Result<BrightnessInfo> {
+            self.reads.set(self.reads.get() + 1);
+            let mut readings = self.readings.borrow_mut();
+            let reading = if readings.len() > 1 {
+                readings.pop_front().unwrap()
+            } else {
+                readings[0]
+            };
+            let current = reading.ok_or(MonitorError::VcpNotSupported)?;
+            Ok(BrightnessInfo {
+                minimum: 0,
+                current,
+                maximum: 100,
+                reported: None,
+            })
+        }
+
+        fn set_brightness(&self, _level: u32) -> Result<()> {
+            unreachable!()
+        }
+
+        fn get_contrast(&self) -> Result<ContrastInfo> {
+            Err(MonitorError::VcpNotSupported)
+        }
+
+        fn set_contrast(&self, _level: u32) -> Result<()> {
+            unreachable!()
+        }
+
+        fn info(&self) -> &MonitorInfo {
+            &self.info
+        }
+    }
+
+    #[test]
+    fn tolerance_applies_in_both_directions() {
+        assert!(within_tolerance(48, 50, 2));
+        assert!(within_tolerance(52, 50, 2));
+        assert!(!within_tolerance(47, 50, 2));
+        assert!(within_tolerance(50, 50, 0));
+        assert!(!within_tolerance(0, u32::MAX, 1));
+    }
+
+    #[test]
+    fn wait_for_brightness_polls_until_the_fade_converges() {
+        let monitor = Fading::new(&[Some(20), None, Some(35), Some(48), Some(50)]);
+        let reached = wait_for_brightness(
+            &monitor,
+            50,
+            2,
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        )
+        .unwrap();
+        assert_eq!(reached, 48);
+        assert_eq!(monitor.reads.get(), 4);
+    }
+
+    #[test]
+    fn wait_for_brightness_times_out_with_the_last_reading() {
+        let monitor = Fading::new(&[Some(20), None]);
+        let err = wait_for_brightness(
+            &monitor,
+            50,
+            2,
+            Duration::from_millis(20),
+            Duration::from_millis(5),
+        )
+        .unwrap_err();
+        assert!(matches!(err, MonitorError::Timeout(_)));
+        assert!(err.to_string().contains("(last reading 20)"), "{}", err);
+
+        let silent = Fading::new(&[None]);
+        let err = wait_for_brightness(&silent, 50, 2, Duration::ZERO, Duration::from_millis(5))
+            .unwrap_err();
+        assert!(err.to_string().contains("(no reading)"), "{}", err);
+    }
+
+    /// Two monitors on an NVIDIA card and one on the integrated GPU
+    fn two_gpu_tree() -> Vec<MonitorInfo> {
+        [
+            ("\\\\.\\DISPLAY1", "NVIDIA GeForce RTX 3080"),
+            ("\\\\.\\DISPLAY2", "Intel(R) UHD Graphics 770"),
+            ("\\\\.\\DISPLAY3", "NVIDIA GeForce RTX 3080"),
+        ]
         .into_iter()
-        .find(|m| m.info().is_primary)
-        .ok_or_else(|| crate::MonitorError::MonitorNotFound("Primary monitor".to_string()))
+        .map(|(device_name, adapter_name)| {
+            let mut info = MonitorInfo::fake(device_name, "Generic PnP Monitor");
+            info.adapter_name = adapter_name.to_string();
+            info
+        })
+        .collect()
+    }
+
+    #[test]
+    fn adapter_filter_matches_part_of_the_name_in_any_case() {
+        let on = |adapter: &str| -> Vec<String> {
+            two_gpu_tree()
+                .into_iter()
+                .filter(|info| info.on_adapter(adapter))
+                .map(|info| info.device_name)
+                .collect()
+        };
+
+        assert_eq!(on("nvidia"), ["\\\\.\\DISPLAY1", "\\\\.\\DISPLAY3"]);
+        assert_eq!(on("UHD GRAPHICS"), ["\\\\.\\DISPLAY2"]);
+        assert!(on("radeon").is_empty());
+        // An unknown adapter name never matches a filter
+        assert!(!MonitorInfo::fake("\\\\.\\DISPLAY4", "Generic PnP Monitor").on_adapter("nvidia"));
+    }
+
+    #[test]
+    fn cloned_panel_is_the_same_under_two_device_names() {
+        let mut first = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        first.edid = Some(edid(1234, Some("7XKJ3H3")));
+        let mut clone = MonitorInfo::fake("\\\\.\\DISPLAY2", "Dell U2723QE");
+        clone.edid = first.edid.clone();
+        assert!(first.same_panel_as(&clone));
+
+        // Same model, different unit
+        let mut other = clone.clone();
+        other.edid = Some(edid(1234, Some("9ZQW1A2")));
+        assert!(!first.same_panel_as(&other));
+    }
+
+    #[test]
+    fn panels_without_serials_compare_by_instance() {
+        let mut first = MonitorInfo::fake("\\\\.\\DISPLAY1", "Generic PnP Monitor");
+        let mut second = MonitorInfo::fake("\\\\.\\DISPLAY2", "Generic PnP Monitor");
+        first.edid = Some(edid(0, None));
+        second.edid = Some(edid(0, None));
+        // Nothing to tell them apart by, so they count as different
+        assert!(!first.same_panel_as(&second));
+
+        first.instance_name = "DISPLAY\\GSM5B7F\\5&2d3b7c5&0&UID4352".to_string();
+        second.instance_name = first.instance_name.clone();
+        assert!(first.same_panel_as(&second));
+
+        second.instance_name = "DISPLAY\\GSM5B7F\\5&2d3b7c5&0&UID4353".to_string();
+        assert!(!first.same_panel_as(&second));
+    }
+
+    /// Run `run_bounded` over `count` monitors with `jobs` workers, returning the results and
+    /// the most monitors that were being talked to at once
+    fn bounded_scan(count: usize, jobs: usize) -> (Vec<String>, usize) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let monitors: Vec<MonitorInfo> = (1..=count)
+            .map(|i| MonitorInfo::fake(&format!("\\\\.\\DISPLAY{}", i), "Generic PnP Monitor"))
+            .collect();
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let results = run_bounded(monitors, jobs, |info| {
+            let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            active.fetch_sub(1, Ordering::SeqCst);
+            info.device_name
+        });
+        (results, peak.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn run_bounded_never_exceeds_the_job_limit() {
+        for jobs in [1, 2, 3] {
+            let (results, peak) = bounded_scan(6, jobs);
+            assert!(peak <= jobs, "{} workers active with --jobs {}", peak, jobs);
+            // Results come back in monitor order however the work was spread
+            let expected: Vec<String> = (1..=6).map(|i| format!("\\\\.\\DISPLAY{}", i)).collect();
+            assert_eq!(results, expected);
+        }
+    }
+
+    #[test]
+    fn run_bounded_uses_at_least_one_worker() {
+        let (results, peak) = bounded_scan(3, 0);
+        assert_eq!((results.len(), peak), (3, 1));
+        assert!(bounded_scan(0, 4).0.is_empty());
+    }
+
+    #[test]
+    fn notches_spread_evenly_over_the_reported_range() {
+        assert_eq!(level_from_notch(7, 10, 0, 100).unwrap(), 70);
+        assert_eq!(level_from_notch(0, 10, 0, 100).unwrap(), 0);
+        assert_eq!(level_from_notch(10, 10, 0, 100).unwrap(), 100);
+        // Rounded to the nearest raw level, offset from the minimum
+        assert_eq!(level_from_notch(1, 3, 0, 100).unwrap(), 33);
+        assert_eq!(level_from_notch(2, 3, 0, 100).unwrap(), 67);
+        assert_eq!(level_from_notch(5, 10, 20, 80).unwrap(), 50);
+
+        assert!(matches!(
+            level_from_notch(11, 10, 0, 100),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            level_from_notch(0, 0, 0, 100),
+            Err(MonitorError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn level_values_parse_raw_numbers_and_notches() {
+        assert_eq!("70".parse::<LevelValue>().unwrap(), LevelValue::Raw(70));
+        assert_eq!("7n".parse::<LevelValue>().unwrap(), LevelValue::Notch(7));
+        assert_eq!(" 7N ".parse::<LevelValue>().unwrap(), LevelValue::Notch(7));
+        for bad in ["n", "7x", "-1", "seven"] {
+            assert!(bad.parse::<LevelValue>().is_err(), "{}", bad);
+        }
+        assert_eq!(LevelValue::Notch(7).to_string(), "7n");
+        assert_eq!(LevelValue::Raw(70).to_string(), "70");
+    }
+
+    #[test]
+    fn debouncer_passes_on_settled_changes_once() {
+        let settle = Duration::from_millis(500);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut debouncer = Debouncer::new(settle);
+
+        // The first reading is passed on once it has held for the settle time
+        assert_eq!(debouncer.observe(50, at(0)), None);
+        assert_eq!(debouncer.observe(50, at(500)), Some(50));
+        assert_eq!(debouncer.observe(50, at(1000)), None);
+
+        // Stepping through levels restarts the wait; only the level it stops at is passed on
+        assert_eq!(debouncer.observe(60, at(1100)), None);
+        assert_eq!(debouncer.observe(70, at(1400)), None);
+        assert_eq!(debouncer.observe(80, at(1700)), None);
+        assert_eq!(debouncer.observe(80, at(2100)), None);
+        assert_eq!(debouncer.observe(80, at(2200)), Some(80));
+
+        // Going back to the last value passed on is not a change
+        assert_eq!(debouncer.observe(70, at(2300)), None);
+        assert_eq!(debouncer.observe(80, at(2400)), None);
+        assert_eq!(debouncer.observe(80, at(3000)), None);
+    }
+
+    #[test]
+    fn mirroring_scales_to_the_target_range_and_skips_matching_targets() {
+        let mock = crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 10, 50);
+        let target = VcpControls::new("\\\\.\\DISPLAY2", mock.clone());
+
+        assert_eq!(mirror_brightness_to(&target, 80, (0, 100)).unwrap(), 40);
+        assert_eq!(mock.writes(), vec![(vcp::codes::BRIGHTNESS, 40)]);
+
+        // Already at the mirrored level: nothing is written
+        assert_eq!(mirror_brightness_to(&target, 80, (0, 100)).unwrap(), 40);
+        assert_eq!(mock.writes().len(), 1);
+
+        let silent = VcpControls::new("\\\\.\\DISPLAY3", crate::backend::MockBackend::new());
+        assert!(mirror_brightness_to(&silent, 80, (0, 100)).is_err());
+    }
+
+    #[test]
+    fn max_overrides_take_precedence_over_the_reported_maximum_in_scaling() {
+        let mock = crate::backend::MockBackend::new().with_feature(vcp::codes::BRIGHTNESS, 40, 255);
+        let mut monitor = VcpControls::new("\\\\.\\DISPLAY1", mock.clone());
+        assert!((monitor.get_brightness_normalized().unwrap() - 40.0 / 255.0).abs() < 1e-6);
+
+        monitor.vcp = vcp::VcpMonitor::with_backend(mock.clone())
+            .with_max_overrides(BTreeMap::from([(vcp::codes::BRIGHTNESS, 100)]));
+        assert!((monitor.get_brightness_normalized().unwrap() - 0.4).abs() < 1e-6);
+        monitor.set_brightness_normalized(0.5).unwrap();
+        assert_eq!(mock.writes(), vec![(vcp::codes::BRIGHTNESS, 50)]);
+
+        // Levels above the override are clamped to it
+        monitor.set_brightness(180).unwrap();
+        assert_eq!(monitor.get_brightness().unwrap().current, 100);
+
+        let physical = PhysicalMonitor::fake(MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850"))
+            .with_max_overrides(BTreeMap::from([(vcp::codes::CONTRAST, 100)]));
+        assert_eq!(physical.maximum(vcp::codes::CONTRAST, 0), 100);
+        assert_eq!(physical.maximum(vcp::codes::BRIGHTNESS, 255), 255);
+    }
+
+    fn path_target(
+        source: &str,
+        friendly_name: &str,
+        instance: &str,
+    ) -> native::DisplayConfigTarget {
+        native::DisplayConfigTarget {
+            source_device: source.to_string(),
+            friendly_name: friendly_name.to_string(),
+            device_path: format!(
+                "\\\\?\\DISPLAY#{}#{{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}}",
+                instance
+            ),
+        }
+    }
+
+    #[test]
+    fn display_paths_are_matched_by_instance_id_then_position() {
+        let targets = [
+            path_target(
+                "\\\\.\\DISPLAY1",
+                "DELL U2723DE",
+                "DELA1B2#5&2b0b5b5e&0&UID4353",
+            ),
+            path_target(
+                "\\\\.\\DISPLAY2",
+                "LG 27GL850",
+                "GSM5B7F#5&2b0b5b5e&0&UID4354",
+            ),
+            path_target(
+                "\\\\.\\DISPLAY2",
+                "LG 27GL850",
+                "GSM5B7F#5&2b0b5b5e&0&UID4355",
+            ),
+        ];
+
+        // The instance ID wins, case-insensitively, whatever device name and position say
+        let found = correlate_target(
+            &targets,
+            "\\\\.\\DISPLAY9",
+            "display\\gsm5b7f\\5&2b0b5b5e&0&uid4355",
+            0,
+        );
+        assert_eq!(found, Some(&targets[2]));
+
+        // Without one, cloned monitors are matched by their position on the source
+        assert_eq!(
+            correlate_target(&targets, "\\\\.\\DISPLAY2", "", 1),
+            Some(&targets[2])
+        );
+        assert_eq!(
+            correlate_target(&targets, "\\\\.\\DISPLAY2", "DISPLAY\\ACR0001\\1", 0),
+            Some(&targets[1])
+        );
+        assert_eq!(correlate_target(&targets, "\\\\.\\DISPLAY2", "", 2), None);
+        assert_eq!(correlate_target(&[], "\\\\.\\DISPLAY1", "", 0), None);
+    }
+
+    #[test]
+    fn brightness_control_prefers_luminance_and_probes_lazily() {
+        fn missing() -> Result<()> {
+            Err(MonitorError::VcpNotSupported)
+        }
+        let unprobed = || -> Result<()> { panic!("control probed after one was chosen") };
+        assert_eq!(
+            BrightnessControl::select(false, || Ok(()), unprobed).unwrap(),
+            Some(BrightnessControl::Luminance)
+        );
+        assert_eq!(
+            BrightnessControl::select(false, missing, || Ok(())).unwrap(),
+            Some(BrightnessControl::BacklightWhite)
+        );
+        assert_eq!(
+            BrightnessControl::select(true, unprobed, || Ok(())).unwrap(),
+            Some(BrightnessControl::BacklightWhite)
+        );
+        assert_eq!(
+            BrightnessControl::select(false, missing, missing).unwrap(),
+            None
+        );
+        assert_eq!(
+            BrightnessControl::select(true, unprobed, || Err(MonitorError::UnsupportedOperation(
+                "no 0x6B".to_string()
+            )))
+            .unwrap(),
+            None
+        );
+
+        assert_eq!(BrightnessControl::Luminance.code(), vcp::codes::BRIGHTNESS);
+        assert_eq!(
+            BrightnessControl::BacklightWhite.code(),
+            vcp::codes::BACKLIGHT_WHITE
+        );
+    }
+
+    #[test]
+    fn brightness_control_is_not_switched_on_a_busy_channel() {
+        let unprobed = || -> Result<()> { panic!("fell back to the backlight level") };
+        assert!(matches!(
+            BrightnessControl::select(
+                false,
+                || Err(MonitorError::ChannelBusy("GetVCPFeatureAndVCPFeatureReply")),
+                unprobed,
+            ),
+            Err(MonitorError::ChannelBusy(_))
+        ));
+        assert!(matches!(
+            BrightnessControl::select(
+                false,
+                || Err(MonitorError::VcpNotSupported),
+                || Err(MonitorError::ChannelBusy("GetVCPFeatureAndVCPFeatureReply")),
+            ),
+            Err(MonitorError::ChannelBusy(_))
+        ));
+    }
+
+    #[test]
+    fn monitors_group_by_adapter_in_first_seen_order() {
+        let mut monitors = two_gpu_tree();
+        monitors.push(MonitorInfo::fake("\\\\.\\DISPLAY4", "Generic PnP Monitor"));
+
+        let groups: Vec<(&str, Vec<&str>)> = group_by_adapter(&monitors)
+            .into_iter()
+            .map(|(adapter, members)| {
+                let devices = members
+                    .iter()
+                    .map(|info| info.device_name.as_str())
+                    .collect();
+                (adapter, devices)
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (
+                    "NVIDIA GeForce RTX 3080",
+                    vec!["\\\\.\\DISPLAY1", "\\\\.\\DISPLAY3"]
+                ),
+                ("Intel(R) UHD Graphics 770", vec!["\\\\.\\DISPLAY2"]),
+                ("", vec!["\\\\.\\DISPLAY4"]),
+            ]
+        );
+        assert!(group_by_adapter(&[]).is_empty());
+    }
 }
@@ -1,6 +1,34 @@
+// HMONITOR/HANDLE parameters are opaque OS handles that are only passed through to Windows
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
 use crate::Result;
+use windows_sys::Win32::Devices::Display::{
+    DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME, DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+    DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SOURCE_DEVICE_NAME,
+    DISPLAYCONFIG_TARGET_DEVICE_NAME, DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes,
+    QDC_ONLY_ACTIVE_PATHS, QueryDisplayConfig,
+};
 use windows_sys::Win32::Foundation::*;
 use windows_sys::Win32::Graphics::Gdi::*;
+use windows_sys::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows_sys::Win32::Security::{
+    GetTokenInformation, PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES, TOKEN_QUERY, TOKEN_USER,
+    TokenUser,
+};
+use windows_sys::Win32::Storage::FileSystem::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, FlushFileBuffers, PIPE_ACCESS_DUPLEX, ReadFile, WriteFile,
+};
+use windows_sys::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_MESSAGE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_MESSAGE, PIPE_WAIT,
+};
+use windows_sys::Win32::System::Registry::{HKEY_LOCAL_MACHINE, RRF_RT_REG_BINARY, RegGetValueW};
+use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+/// `EnumDisplayDevicesW` flag to return the device interface path in `DeviceID`
+const EDD_GET_DEVICE_INTERFACE_NAME: u32 = 0x1;
 
 pub mod dxva2 {
     use super::*;
@@ -81,15 +109,19 @@ pub struct PHYSICAL_MONITOR {
 
 impl PHYSICAL_MONITOR {
     pub fn description(&self) -> String {
-        let len = self
-            .sz_physical_monitor_description
-            .iter()
-            .position(|&c| c == 0)
-            .unwrap_or(self.sz_physical_monitor_description.len());
-        String::from_utf16_lossy(&self.sz_physical_monitor_description[..len])
+        wide_to_string(&self.sz_physical_monitor_description)
     }
 }
 
+/// Build an error from `GetLastError`.
+///
+/// Must be called immediately after the failing API call: anything in between (including a `Drop`
+/// that destroys another physical monitor handle) may overwrite the thread's last-error value.
+pub fn last_error(function: &'static str) -> crate::MonitorError {
+    let code = unsafe { GetLastError() };
+    crate::MonitorError::Win32 { function, code }
+}
+
 pub struct MonitorEnumerator {
     pub monitors: Vec<HMONITOR>,
 }
@@ -107,7 +139,7 @@ impl MonitorEnumerator {
             );
 
             if result == 0 {
-                return Err(crate::MonitorError::EnumerationFailed);
+                return Err(last_error("EnumDisplayMonitors"));
             }
         }
 
@@ -133,7 +165,7 @@ pub fn get_monitor_info(hmonitor: HMONITOR) -> Result<MONITORINFOEXW> {
 
         let result = GetMonitorInfoW(hmonitor, &mut info.monitorInfo as *mut _ as *mut _);
         if result == 0 {
-            return Err(crate::MonitorError::EnumerationFailed);
+            return Err(last_error("GetMonitorInfoW"));
         }
 
         Ok(info)
@@ -145,7 +177,10 @@ pub fn get_physical_monitors(hmonitor: HMONITOR) -> Result<Vec<PHYSICAL_MONITOR>
         let mut count = 0u32;
         let result = dxva2::GetNumberOfPhysicalMonitorsFromHMONITOR(hmonitor, &mut count);
 
-        if result == 0 || count == 0 {
+        if result == 0 {
+            return Err(last_error("GetNumberOfPhysicalMonitorsFromHMONITOR"));
+        }
+        if count == 0 {
             return Err(crate::MonitorError::PhysicalMonitorHandleFailed);
         }
 
@@ -161,7 +196,7 @@ pub fn get_physical_monitors(hmonitor: HMONITOR) -> Result<Vec<PHYSICAL_MONITOR>
             dxva2::GetPhysicalMonitorsFromHMONITOR(hmonitor, count, physical_monitors.as_mut_ptr());
 
         if result == 0 {
-            return Err(crate::MonitorError::PhysicalMonitorHandleFailed);
+            return Err(last_error("GetPhysicalMonitorsFromHMONITOR"));
         }
 
         Ok(physical_monitors)
@@ -172,8 +207,516 @@ pub fn destroy_physical_monitor(handle: HANDLE) -> Result<()> {
     unsafe {
         let result = dxva2::DestroyPhysicalMonitor(handle);
         if result == 0 {
-            return Err(crate::MonitorError::EnumerationFailed);
+            return Err(last_error("DestroyPhysicalMonitor"));
         }
         Ok(())
     }
 }
+
+fn wide_to_string(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// GDI device name (e.g. `\\.\DISPLAY1`) from a `get_monitor_info` result
+pub fn display_device_name(info: &MONITORINFOEXW) -> String {
+    wide_to_string(&info.szDevice)
+}
+
+/// Descriptions of the monitors attached to a display device such as `\\.\DISPLAY1`, in the
+/// same order as the physical monitors dxva2 reports for it
+pub fn get_monitor_descriptions(display_device: &[u16]) -> Vec<String> {
+    let mut descriptions = Vec::new();
+
+    unsafe {
+        loop {
+            let mut device: DISPLAY_DEVICEW = std::mem::zeroed();
+            device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+
+            let index = descriptions.len() as u32;
+            if EnumDisplayDevicesW(display_device.as_ptr(), index, &mut device, 0) == 0 {
+                break;
+            }
+            descriptions.push(wide_to_string(&device.DeviceString));
+        }
+    }
+
+    descriptions
+}
+
+/// Description of the adapter (GPU) driving a display device such as `\\.\DISPLAY1`, e.g.
+/// `NVIDIA GeForce RTX 3080`
+pub fn get_adapter_name(display_device: &[u16]) -> Option<String> {
+    let display_device = wide_to_string(display_device);
+
+    unsafe {
+        let mut index = 0;
+        loop {
+            let mut device: DISPLAY_DEVICEW = std::mem::zeroed();
+            device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+
+            // A null device enumerates the display devices of every adapter
+            if EnumDisplayDevicesW(std::ptr::null(), index, &mut device, 0) == 0 {
+                return None;
+            }
+            if wide_to_string(&device.DeviceName) == display_device {
+                return Some(wide_to_string(&device.DeviceString));
+            }
+            index += 1;
+        }
+    }
+}
+
+/// Device instance ID (e.g. `DISPLAY\DEL41A9\5&2b0b5b5e&0&UID4353`) of the `index`-th monitor
+/// attached to a display device such as `\\.\DISPLAY1`
+pub fn get_monitor_instance_id(display_device: &[u16], index: u32) -> Option<String> {
+    let interface = unsafe {
+        let mut device: DISPLAY_DEVICEW = std::mem::zeroed();
+        device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+
+        let result = EnumDisplayDevicesW(
+            display_device.as_ptr(),
+            index,
+            &mut device,
+            EDD_GET_DEVICE_INTERFACE_NAME,
+        );
+        if result == 0 {
+            return None;
+        }
+
+        wide_to_string(&device.DeviceID)
+    };
+
+    instance_id_from_interface(&interface)
+}
+
+/// Device instance ID from a monitor device interface path:
+/// `\\?\DISPLAY#DEL41A9#5&2b0b5b5e&0&UID4353#{e6f07b5f-...}` -> `DISPLAY\DEL41A9\5&...`
+pub fn instance_id_from_interface(interface: &str) -> Option<String> {
+    let mut parts = interface.strip_prefix(r"\\?\")?.split('#');
+    let (class, model, instance) = (parts.next()?, parts.next()?, parts.next()?);
+    Some(format!(r"{}\{}\{}", class, model, instance))
+}
+
+/// Read the EDID Windows caches in the device's registry key
+pub fn read_edid(instance_id: &str) -> Result<Vec<u8>> {
+    let subkey = to_wide(&format!(
+        r"SYSTEM\CurrentControlSet\Enum\{}\Device Parameters",
+        instance_id
+    ));
+    let value = to_wide("EDID");
+
+    unsafe {
+        let mut size = 0u32;
+        let code = RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            subkey.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_BINARY,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut size,
+        );
+        if code != ERROR_SUCCESS {
+            return Err(crate::MonitorError::Win32 {
+                function: "RegGetValueW",
+                code,
+            });
+        }
+
+        let mut data = vec![0u8; size as usize];
+        let code = RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            subkey.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_BINARY,
+            std::ptr::null_mut(),
+            data.as_mut_ptr().cast(),
+            &mut size,
+        );
+        if code != ERROR_SUCCESS {
+            return Err(crate::MonitorError::Win32 {
+                function: "RegGetValueW",
+                code,
+            });
+        }
+
+        data.truncate(size as usize);
+        Ok(data)
+    }
+}
+
+/// Every active display path, in the order `QueryDisplayConfig` returns them
+fn display_config_paths() -> Result<Vec<DISPLAYCONFIG_PATH_INFO>> {
+    unsafe {
+        loop {
+            let (mut path_count, mut mode_count) = (0u32, 0u32);
+            let code = GetDisplayConfigBufferSizes(
+                QDC_ONLY_ACTIVE_PATHS,
+                &mut path_count,
+                &mut mode_count,
+            );
+            if code != ERROR_SUCCESS {
+                return Err(crate::MonitorError::Win32 {
+                    function: "GetDisplayConfigBufferSizes",
+                    code,
+                });
+            }
+
+            let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+            let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+            let code = QueryDisplayConfig(
+                QDC_ONLY_ACTIVE_PATHS,
+                &mut path_count,
+                paths.as_mut_ptr(),
+                &mut mode_count,
+                modes.as_mut_ptr(),
+                std::ptr::null_mut(),
+            );
+            match code {
+                ERROR_SUCCESS => {
+                    paths.truncate(path_count as usize);
+                    return Ok(paths);
+                }
+                // The topology changed between the two calls; size the buffers again
+                ERROR_INSUFFICIENT_BUFFER => continue,
+                code => {
+                    return Err(crate::MonitorError::Win32 {
+                        function: "QueryDisplayConfig",
+                        code,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// GDI device name (e.g. `\\.\DISPLAY2`) of the source of a display path
+fn path_source_name(path: &DISPLAYCONFIG_PATH_INFO) -> Result<String> {
+    unsafe {
+        let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME::default();
+        source.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME;
+        source.header.size = std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32;
+        source.header.adapterId = path.sourceInfo.adapterId;
+        source.header.id = path.sourceInfo.id;
+
+        let code = DisplayConfigGetDeviceInfo(&mut source.header);
+        if code != 0 {
+            return Err(crate::MonitorError::Win32 {
+                function: "DisplayConfigGetDeviceInfo",
+                code: code as u32,
+            });
+        }
+        Ok(wide_to_string(&source.viewGdiDeviceName))
+    }
+}
+
+/// GDI device name (e.g. `\\.\DISPLAY2`) of the source of every active display path, in the order
+/// `QueryDisplayConfig` returns the paths
+pub fn display_config_sources() -> Result<Vec<String>> {
+    display_config_paths()?
+        .iter()
+        .map(path_source_name)
+        .collect()
+}
+
+/// The source and target (monitor) of one active display path, as `QueryDisplayConfig` and
+/// `DisplayConfigGetDeviceInfo` report them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayConfigTarget {
+    /// GDI device name of the source, e.g. `\\.\DISPLAY2`
+    pub source_device: String,
+    /// Monitor name from its EDID, e.g. `DELL U2723DE`; empty for some built-in panels
+    pub friendly_name: String,
+    /// Monitor device interface path, e.g. `\\?\DISPLAY#DELA1B2#5&2b0b5b5e&0&UID4353#{...}`
+    pub device_path: String,
+}
+
+/// Source and target names of every active display path, in `QueryDisplayConfig` order
+pub fn display_config_targets() -> Result<Vec<DisplayConfigTarget>> {
+    display_config_paths()?
+        .iter()
+        .map(|path| unsafe {
+            let mut target = DISPLAYCONFIG_TARGET_DEVICE_NAME::default();
+            target.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME;
+            target.header.size = std::mem::size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32;
+            target.header.adapterId = path.targetInfo.adapterId;
+            target.header.id = path.targetInfo.id;
+
+            let code = DisplayConfigGetDeviceInfo(&mut target.header);
+            if code != 0 {
+                return Err(crate::MonitorError::Win32 {
+                    function: "DisplayConfigGetDeviceInfo",
+                    code: code as u32,
+                });
+            }
+            Ok(DisplayConfigTarget {
+                source_device: path_source_name(path)?,
+                friendly_name: wide_to_string(&target.monitorFriendlyDeviceName),
+                device_path: wide_to_string(&target.monitorDevicePath),
+            })
+        })
+        .collect()
+}
+
+/// Capabilities GDI reports through a display device's device context
+pub struct DeviceContextCaps {
+    /// `GetDeviceCaps(TECHNOLOGY)`, `DT_RASDISPLAY` for a monitor
+    pub technology: u32,
+    /// `GetDeviceCaps(COLORMGMTCAPS)`, with `CM_GAMMA_RAMP` set when gamma ramps are supported
+    pub color_management: u32,
+}
+
+impl DeviceContextCaps {
+    pub fn is_raster_display(&self) -> bool {
+        self.technology == DT_RASDISPLAY
+    }
+
+    pub fn supports_gamma_ramp(&self) -> bool {
+        self.color_management & CM_GAMMA_RAMP != 0
+    }
+}
+
+/// Create a device context for a display device such as `\\.\DISPLAY1` with `CreateDCW`, read
+/// its capabilities, and release it again
+pub fn probe_device_context(display_device: &str) -> Result<DeviceContextCaps> {
+    let wide = to_wide(display_device);
+    unsafe {
+        let hdc = CreateDCW(
+            wide.as_ptr(),
+            wide.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+        );
+        if hdc.is_null() {
+            return Err(last_error("CreateDCW"));
+        }
+
+        let caps = DeviceContextCaps {
+            technology: GetDeviceCaps(hdc, TECHNOLOGY as i32) as u32,
+            color_management: GetDeviceCaps(hdc, COLORMGMTCAPS as i32) as u32,
+        };
+        DeleteDC(hdc);
+        Ok(caps)
+    }
+}
+
+/// String form (`S-1-5-21-...`) of the SID of the user this process runs as
+fn current_user_sid() -> Result<String> {
+    let mut token = std::ptr::null_mut();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+        return Err(last_error("OpenProcessToken"));
+    }
+    let sid = token_user_sid(token);
+    unsafe {
+        CloseHandle(token);
+    }
+    sid
+}
+
+fn token_user_sid(token: HANDLE) -> Result<String> {
+    let mut size = 0u32;
+    unsafe {
+        GetTokenInformation(token, TokenUser, std::ptr::null_mut(), 0, &mut size);
+    }
+    // u64 elements keep the TOKEN_USER at the start of the buffer aligned
+    let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+    if unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            buffer.as_mut_ptr().cast(),
+            size,
+            &mut size,
+        )
+    } == 0
+    {
+        return Err(last_error("GetTokenInformation"));
+    }
+    let user = unsafe { &*(buffer.as_ptr() as *const TOKEN_USER) };
+
+    let mut string = std::ptr::null_mut();
+    if unsafe { ConvertSidToStringSidW(user.User.Sid, &mut string) } == 0 {
+        return Err(last_error("ConvertSidToStringSidW"));
+    }
+    let sid = unsafe {
+        let len = (0..).take_while(|&i| *string.add(i) != 0).count();
+        let sid = String::from_utf16_lossy(std::slice::from_raw_parts(string, len));
+        LocalFree(string.cast());
+        sid
+    };
+    Ok(sid)
+}
+
+/// Security descriptor whose DACL gives the current user, and nobody else, access to an
+/// object. Freed on drop.
+struct CurrentUserOnly(PSECURITY_DESCRIPTOR);
+
+impl CurrentUserOnly {
+    fn new() -> Result<Self> {
+        // Protected DACL with a single generic-all ACE for the user's SID
+        let sddl = to_wide(&format!("D:P(A;;GA;;;{})", current_user_sid()?));
+        let mut descriptor = std::ptr::null_mut();
+        if unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                std::ptr::null_mut(),
+            )
+        } == 0
+        {
+            return Err(last_error(
+                "ConvertStringSecurityDescriptorToSecurityDescriptorW",
+            ));
+        }
+        Ok(Self(descriptor))
+    }
+}
+
+impl Drop for CurrentUserOnly {
+    fn drop(&mut self) {
+        unsafe {
+            LocalFree(self.0);
+        }
+    }
+}
+
+/// Server end of a message-mode named pipe such as `\\.\pipe\monitorconfig`, serving one
+/// client at a time
+pub struct NamedPipe {
+    handle: HANDLE,
+}
+
+/// Buffer size requested for each direction of the pipe; longer messages still arrive whole
+const PIPE_BUFFER_SIZE: u32 = 64 * 1024;
+
+impl NamedPipe {
+    /// Create the pipe. Only the current user can open it, and only from this machine.
+    ///
+    /// Fails with `ERROR_ACCESS_DENIED` if a pipe of that name already exists, so another
+    /// process can't have created it first and be listening in place of this one.
+    pub fn create(name: &str) -> Result<Self> {
+        let wide = to_wide(name);
+        let descriptor = CurrentUserOnly::new()?;
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor.0,
+            bInheritHandle: 0,
+        };
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide.as_ptr(),
+                PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                PIPE_TYPE_MESSAGE | PIPE_READMODE_MESSAGE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                1,
+                PIPE_BUFFER_SIZE,
+                PIPE_BUFFER_SIZE,
+                0,
+                &attributes,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(last_error("CreateNamedPipeW"));
+        }
+        Ok(Self { handle })
+    }
+
+    /// Block until a client connects
+    pub fn connect(&self) -> Result<()> {
+        let result = unsafe { ConnectNamedPipe(self.handle, std::ptr::null_mut()) };
+        // A client that connected between create/disconnect and this call is not an error
+        if result == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            return Err(last_error("ConnectNamedPipe"));
+        }
+        Ok(())
+    }
+
+    /// Read one whole message, or `None` once the client has disconnected
+    pub fn read_message(&self) -> Result<Option<Vec<u8>>> {
+        let mut message = Vec::new();
+        let mut buffer = vec![0u8; PIPE_BUFFER_SIZE as usize];
+
+        loop {
+            let mut read = 0u32;
+            let result = unsafe {
+                ReadFile(
+                    self.handle,
+                    buffer.as_mut_ptr(),
+                    buffer.len() as u32,
+                    &mut read,
+                    std::ptr::null_mut(),
+                )
+            };
+            message.extend_from_slice(&buffer[..read as usize]);
+
+            if result != 0 {
+                return Ok(Some(message));
+            }
+            match unsafe { GetLastError() } {
+                // The rest of the message follows in the next read
+                ERROR_MORE_DATA => continue,
+                ERROR_BROKEN_PIPE => return Ok(None),
+                _ => return Err(last_error("ReadFile")),
+            }
+        }
+    }
+
+    pub fn write_message(&self, message: &[u8]) -> Result<()> {
+        let mut written = 0u32;
+        let result = unsafe {
+            WriteFile(
+                self.handle,
+                message.as_ptr(),
+                message.len() as u32,
+                &mut written,
+                std::ptr::null_mut(),
+            )
+        };
+        if result == 0 {
+            return Err(last_error("WriteFile"));
+        }
+        Ok(())
+    }
+
+    /// Drop the current client, after it has had the chance to read every reply
+    pub fn disconnect(&self) -> Result<()> {
+        unsafe {
+            FlushFileBuffers(self.handle);
+            if DisconnectNamedPipe(self.handle) == 0 {
+                return Err(last_error("DisconnectNamedPipe"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for NamedPipe {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_ids_are_taken_from_interface_paths() {
+        assert_eq!(
+            instance_id_from_interface(
+                r"\\?\DISPLAY#DEL41A9#5&2b0b5b5e&0&UID4353#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}"
+            )
+            .as_deref(),
+            Some(r"DISPLAY\DEL41A9\5&2b0b5b5e&0&UID4353")
+        );
+        assert_eq!(instance_id_from_interface(r"DISPLAY#DEL41A9#1"), None);
+        assert_eq!(instance_id_from_interface(r"\\?\DISPLAY#DEL41A9"), None);
+    }
+}
@@ -0,0 +1,881 @@
+use crate::{
+    MonitorError, Result, capabilities,
+    config::Config,
+    decode::InputSource,
+    monitor::MonitorInfo,
+    vcp::{self, VcpMonitor, codes},
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path::Path, thread, time::Duration};
+
+/// VCP codes captured by `save-profile` when no codes are given
+pub const DEFAULT_PROFILE_CODES: &[u8] = &[
+    codes::BRIGHTNESS,
+    codes::CONTRAST,
+    codes::COLOR_TEMPERATURE,
+    codes::RED_GAIN,
+    codes::GREEN_GAIN,
+    codes::BLUE_GAIN,
+];
+
+/// VCP codes kept across an input switch by `set-input --preserve`
+pub const PRESERVED_INPUT_CODES: &[u8] = &[codes::BRIGHTNESS, codes::CONTRAST];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileValue {
+    pub code: u8,
+    pub value: u32,
+}
+
+/// A code whose value differs between two profiles. `None` means the code is missing from that
+/// side, e.g. because the monitor didn't answer it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileChange {
+    pub code: u8,
+    pub baseline: Option<u32>,
+    pub current: Option<u32>,
+}
+
+/// Why a profile value can't be applied as-is to a monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum ProfileIssue {
+    /// The monitor doesn't answer the code
+    Unsupported { code: u8 },
+    /// The code is read-only status or information
+    ReadOnly { code: u8 },
+    /// The value is above the maximum the monitor reports for the code
+    OutOfRange { code: u8, value: u32, maximum: u32 },
+    /// The monitor's capabilities list the values the code accepts, and this isn't one of them
+    NotAllowed {
+        code: u8,
+        value: u32,
+        allowed: Vec<u8>,
+    },
+}
+
+impl ProfileIssue {
+    pub fn code(&self) -> u8 {
+        match self {
+            ProfileIssue::Unsupported { code }
+            | ProfileIssue::ReadOnly { code }
+            | ProfileIssue::OutOfRange { code, .. }
+            | ProfileIssue::NotAllowed { code, .. } => *code,
+        }
+    }
+}
+
+impl fmt::Display for ProfileIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileIssue::Unsupported { .. } => write!(f, "not supported by the monitor"),
+            ProfileIssue::ReadOnly { .. } => write!(f, "read-only"),
+            ProfileIssue::OutOfRange { value, maximum, .. } => {
+                write!(f, "{} is above the maximum of {}", value, maximum)
+            }
+            ProfileIssue::NotAllowed { value, allowed, .. } => {
+                let allowed: Vec<_> = allowed.iter().map(|v| format!("0x{:02X}", v)).collect();
+                write!(
+                    f,
+                    "0x{:02X} is not one of the values the monitor lists ({})",
+                    value,
+                    allowed.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Saved set of VCP values that can be re-applied to a monitor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitorProfile {
+    pub values: Vec<ProfileValue>,
+    /// Monitor the values were captured from, as `list --json` shows it. Only context for
+    /// matching and debugging; applying a profile writes `values` alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<MonitorInfo>,
+}
+
+impl MonitorProfile {
+    /// Read `codes` from the monitor; codes the monitor doesn't answer are left out
+    pub fn capture(vcp_mon: &VcpMonitor, codes: &[u8]) -> Self {
+        let values = codes
+            .iter()
+            .filter_map(|&code| {
+                vcp_mon
+                    .get_vcp_feature(code)
+                    .ok()
+                    .map(|response| ProfileValue {
+                        code,
+                        value: response.current_value,
+                    })
+            })
+            .collect();
+
+        Self {
+            values,
+            monitor: None,
+        }
+    }
+
+    /// The current values of a scan, e.g. one loaded with [`vcp::load_scan`]
+    pub fn from_responses(responses: &[vcp::VcpFeatureResponse]) -> Self {
+        let values = responses
+            .iter()
+            .map(|response| ProfileValue {
+                code: response.vcp_code,
+                value: response.current_value,
+            })
+            .collect();
+
+        Self {
+            values,
+            monitor: None,
+        }
+    }
+
+    /// Record `info` as the monitor the values came from
+    pub fn with_monitor(mut self, info: &MonitorInfo) -> Self {
+        self.monitor = Some(info.clone());
+        self
+    }
+
+    /// Whether the profile was captured from a panel other than `info`; `false` when the profile
+    /// doesn't say where it came from
+    pub fn from_other_monitor(&self, info: &MonitorInfo) -> bool {
+        self.monitor
+            .as_ref()
+            .is_some_and(|source| !source.same_panel_as(info))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| MonitorError::Profile(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| MonitorError::Profile(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn value(&self, code: u8) -> Option<u32> {
+        self.values.iter().find(|v| v.code == code).map(|v| v.value)
+    }
+
+    /// Write this profile's value for `code` back, leaving every other code alone, as
+    /// `revert-vcp` does. Returns the value written, or `None` if the profile has none for `code`.
+    pub fn revert(&self, vcp_mon: &VcpMonitor, code: u8) -> Result<Option<u32>> {
+        let Some(value) = self.value(code) else {
+            return Ok(None);
+        };
+        vcp_mon.set_vcp_feature(code, value)?;
+        Ok(Some(value))
+    }
+
+    /// Codes whose value in `current` differs from this profile, in this profile's order followed
+    /// by codes only `current` has
+    pub fn diff(&self, current: &MonitorProfile) -> Vec<ProfileChange> {
+        let changed = self.values.iter().filter_map(|v| {
+            let now = current.value(v.code);
+            (now != Some(v.value)).then_some(ProfileChange {
+                code: v.code,
+                baseline: Some(v.value),
+                current: now,
+            })
+        });
+        let added = current
+            .values
+            .iter()
+            .filter(|v| self.value(v.code).is_none())
+            .map(|v| ProfileChange {
+                code: v.code,
+                baseline: None,
+                current: Some(v.value),
+            });
+
+        changed.chain(added).collect()
+    }
+
+    /// Codes in this profile that hold a setting, leaving out read-only ones such as usage time
+    pub fn adjustable_codes(&self) -> Vec<u8> {
+        self.values
+            .iter()
+            .map(|v| v.code)
+            .filter(|code| !vcp::READ_ONLY_CODES.contains(code))
+            .collect()
+    }
+
+    /// Settings on the monitor that differ from this profile as a baseline. Only
+    /// [adjustable codes](Self::adjustable_codes) count; counters like usage time always move.
+    pub fn drift(&self, vcp_mon: &VcpMonitor) -> Vec<ProfileChange> {
+        let codes = self.adjustable_codes();
+        let current = MonitorProfile::capture(vcp_mon, &codes);
+        self.diff(&current)
+            .into_iter()
+            .filter(|change| codes.contains(&change.code))
+            .collect()
+    }
+
+    /// Check every value against what the monitor reports, without writing anything. Codes
+    /// with a list of allowed values in the capabilities string are checked against that list,
+    /// the others against the maximum the monitor reports.
+    pub fn validate(&self, vcp_mon: &VcpMonitor) -> Vec<ProfileIssue> {
+        let caps = vcp_mon
+            .get_capabilities()
+            .ok()
+            .and_then(|caps| capabilities::parse_capabilities(&caps).ok());
+
+        self.values
+            .iter()
+            .filter_map(|v| {
+                let code = v.code;
+                if vcp::READ_ONLY_CODES.contains(&code) {
+                    return Some(ProfileIssue::ReadOnly { code });
+                }
+                let Ok(maximum) = vcp_mon.max_for_code(code) else {
+                    return Some(ProfileIssue::Unsupported { code });
+                };
+
+                let allowed = caps
+                    .as_ref()
+                    .and_then(|caps| caps.vcp_code(code))
+                    .filter(|cap| !cap.values.is_empty());
+                match allowed {
+                    Some(cap) => {
+                        let listed = u8::try_from(v.value).is_ok_and(|b| cap.values.contains(&b));
+                        (!listed).then(|| ProfileIssue::NotAllowed {
+                            code,
+                            value: v.value,
+                            allowed: cap.values.clone(),
+                        })
+                    }
+                    None => (v.value > maximum).then_some(ProfileIssue::OutOfRange {
+                        code,
+                        value: v.value,
+                        maximum,
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// Write every value in order, continuing past failures and reporting all failed codes
+    pub fn apply(&self, vcp_mon: &VcpMonitor) -> Result<()> {
+        self.apply_with_progress(vcp_mon, |_, _| {})
+    }
+
+    /// Like [`Self::apply`], calling `progress(done, total)` after every write, failed or not
+    pub fn apply_with_progress(
+        &self,
+        vcp_mon: &VcpMonitor,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        let total = self.values.len();
+        let failed: Vec<String> = self
+            .values
+            .iter()
+            .enumerate()
+            .filter(|(i, v)| {
+                let failed = vcp_mon.set_vcp_feature(v.code, v.value).is_err();
+                progress(i + 1, total);
+                failed
+            })
+            .map(|(_, v)| format!("0x{:02X}", v.code))
+            .collect();
+
+        if !failed.is_empty() {
+            return Err(MonitorError::UnsupportedOperation(format!(
+                "failed to apply VCP codes {}",
+                failed.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Like [`Self::apply`], but read every code first and write only those whose current value
+    /// differs from the profile, to save DDC/CI traffic. Codes that can't be read are written.
+    /// Returns the values that were written.
+    pub fn apply_minimal(&self, vcp_mon: &VcpMonitor) -> Result<MonitorProfile> {
+        let codes: Vec<u8> = self.values.iter().map(|v| v.code).collect();
+        let current = Self::capture(vcp_mon, &codes);
+        let values = self
+            .diff(&current)
+            .into_iter()
+            .filter_map(|change| {
+                change.baseline.map(|value| ProfileValue {
+                    code: change.code,
+                    value,
+                })
+            })
+            .collect();
+
+        let changed = Self {
+            values,
+            monitor: None,
+        };
+        changed.apply(vcp_mon)?;
+        Ok(changed)
+    }
+}
+
+/// One monitor's entry in a [`Backup`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorBackup {
+    /// [`MonitorInfo::stable_id`] at backup time, used to find the monitor again on restore
+    pub stable_id: String,
+    /// Names and EDID, for reference; restore goes by `stable_id` only
+    pub info: MonitorInfo,
+    pub profile: MonitorProfile,
+}
+
+/// Profiles and identity of every monitor, as written by `backup-all`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Backup {
+    pub monitors: Vec<MonitorBackup>,
+}
+
+impl Backup {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| MonitorError::Profile(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| MonitorError::Profile(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Pair each saved monitor with the index of the current monitor that has the same stable
+    /// id. Saved monitors with no current match are returned separately, in backup order.
+    pub fn match_monitors<'a>(
+        &'a self,
+        current: &[MonitorInfo],
+    ) -> (Vec<(&'a MonitorBackup, usize)>, Vec<&'a MonitorBackup>) {
+        let mut matched = Vec::new();
+        let mut unmatched = Vec::new();
+        for saved in &self.monitors {
+            match current
+                .iter()
+                .position(|info| info.stable_id() == saved.stable_id)
+            {
+                Some(index) => matched.push((saved, index)),
+                None => unmatched.push(saved),
+            }
+        }
+        (matched, unmatched)
+    }
+}
+
+/// Switch inputs, then restore brightness and contrast once the monitor has settled, for monitors
+/// that load per-input values on a switch. Returns the restored values; codes the monitor didn't
+/// answer before the switch are left alone.
+pub fn switch_input_preserving(
+    vcp_mon: &VcpMonitor,
+    input: InputSource,
+    settle: Duration,
+) -> Result<MonitorProfile> {
+    let saved = MonitorProfile::capture(vcp_mon, PRESERVED_INPUT_CODES);
+    vcp_mon.set_input_source(input)?;
+    thread::sleep(settle);
+    saved.apply(vcp_mon)?;
+    Ok(saved)
+}
+
+/// Profiles to apply when a monitor switches to a given input, as configured in `input_profiles`
+pub struct InputProfileMap {
+    profiles: Vec<(InputSource, MonitorProfile)>,
+    last_input: Option<InputSource>,
+}
+
+impl InputProfileMap {
+    /// Parse the configured input names and load every referenced profile up front, so a typo
+    /// or missing file is reported at startup rather than on the first input switch
+    pub fn from_config(config: &Config) -> Result<Self> {
+        if config.input_profiles.is_empty() {
+            return Err(MonitorError::Config(
+                "no input_profiles configured".to_string(),
+            ));
+        }
+
+        let mut profiles = Vec::new();
+        for (name, path) in &config.input_profiles {
+            let input: InputSource = name.parse()?;
+            let profile = MonitorProfile::load(&config.resolve(path))?;
+            profiles.push((input, profile));
+        }
+
+        Ok(Self {
+            profiles,
+            last_input: None,
+        })
+    }
+
+    pub fn profile_for(&self, input: InputSource) -> Option<&MonitorProfile> {
+        self.profiles
+            .iter()
+            .find(|(mapped, _)| *mapped == input)
+            .map(|(_, profile)| profile)
+    }
+
+    /// Record the latest input reading. Returns the profile to apply when the input changed to a
+    /// mapped one; the first reading only establishes the baseline.
+    pub fn observe(&mut self, input: InputSource) -> Option<&MonitorProfile> {
+        let previous = self.last_input.replace(input);
+        match previous {
+            Some(previous) if previous != input => self.profile_for(input),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Empty scratch directory for one test, under the system temp directory
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("monitorconfig-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn profile(brightness: u32) -> MonitorProfile {
+        MonitorProfile {
+            values: vec![ProfileValue {
+                code: codes::BRIGHTNESS,
+                value: brightness,
+            }],
+            monitor: None,
+        }
+    }
+
+    fn input_config(dir: &Path, mappings: &[(&str, &str)]) -> Config {
+        let mut config = Config::default();
+        for (input, file) in mappings {
+            config
+                .input_profiles
+                .insert(input.to_string(), dir.join(file));
+        }
+        config
+    }
+
+    #[test]
+    fn input_profiles_apply_only_on_a_change_to_a_mapped_input() {
+        let dir = scratch_dir("input-change");
+        profile(80).save(&dir.join("game.json")).unwrap();
+        profile(30).save(&dir.join("work.json")).unwrap();
+        let config = input_config(&dir, &[("hdmi1", "game.json"), ("dp1", "work.json")]);
+        let mut profiles = InputProfileMap::from_config(&config).unwrap();
+
+        // The first reading is only the baseline
+        assert!(profiles.observe(InputSource::DisplayPort1).is_none());
+        assert!(profiles.observe(InputSource::DisplayPort1).is_none());
+        let applied = profiles.observe(InputSource::Hdmi1).unwrap();
+        assert_eq!(applied.value(codes::BRIGHTNESS), Some(80));
+        assert!(profiles.observe(InputSource::Hdmi1).is_none());
+        assert!(profiles.observe(InputSource::Vga1).is_none());
+        let applied = profiles.observe(InputSource::DisplayPort1).unwrap();
+        assert_eq!(applied.value(codes::BRIGHTNESS), Some(30));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn input_profiles_are_validated_at_startup() {
+        let dir = scratch_dir("input-validate");
+        profile(80).save(&dir.join("game.json")).unwrap();
+
+        assert!(matches!(
+            InputProfileMap::from_config(&Config::default()),
+            Err(MonitorError::Config(_))
+        ));
+        assert!(matches!(
+            InputProfileMap::from_config(&input_config(&dir, &[("hdmi1", "missing.json")])),
+            Err(MonitorError::Profile(_))
+        ));
+        assert!(matches!(
+            InputProfileMap::from_config(&input_config(&dir, &[("thunderbolt", "game.json")])),
+            Err(MonitorError::InvalidValue(_))
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn diff_lists_changed_missing_and_added_codes() {
+        let baseline = MonitorProfile {
+            values: vec![
+                ProfileValue {
+                    code: codes::BRIGHTNESS,
+                    value: 75,
+                },
+                ProfileValue {
+                    code: codes::CONTRAST,
+                    value: 75,
+                },
+                ProfileValue {
+                    code: codes::RED_GAIN,
+                    value: 50,
+                },
+            ],
+            monitor: None,
+        };
+        let current = MonitorProfile {
+            values: vec![
+                ProfileValue {
+                    code: codes::BRIGHTNESS,
+                    value: 40,
+                },
+                ProfileValue {
+                    code: codes::CONTRAST,
+                    value: 75,
+                },
+                ProfileValue {
+                    code: codes::BLUE_GAIN,
+                    value: 50,
+                },
+            ],
+            monitor: None,
+        };
+
+        assert_eq!(
+            baseline.diff(&current),
+            vec![
+                ProfileChange {
+                    code: codes::BRIGHTNESS,
+                    baseline: Some(75),
+                    current: Some(40)
+                },
+                ProfileChange {
+                    code: codes::RED_GAIN,
+                    baseline: Some(50),
+                    current: None
+                },
+                ProfileChange {
+                    code: codes::BLUE_GAIN,
+                    baseline: None,
+                    current: Some(50)
+                },
+            ]
+        );
+        assert!(baseline.diff(&baseline).is_empty());
+    }
+
+    #[test]
+    fn drift_ignores_read_only_counters() {
+        let baseline = MonitorProfile {
+            values: vec![
+                ProfileValue {
+                    code: codes::BRIGHTNESS,
+                    value: 75,
+                },
+                ProfileValue {
+                    code: codes::CONTRAST,
+                    value: 75,
+                },
+                ProfileValue {
+                    code: codes::USAGE_TIME,
+                    value: 100,
+                },
+            ],
+            monitor: None,
+        };
+        let monitor = VcpMonitor::with_backend(
+            crate::backend::MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 40, 100)
+                .with_feature(codes::CONTRAST, 75, 100)
+                .with_feature(codes::USAGE_TIME, 2_500, 0),
+        );
+
+        assert_eq!(
+            baseline.adjustable_codes(),
+            vec![codes::BRIGHTNESS, codes::CONTRAST]
+        );
+        assert_eq!(
+            baseline.drift(&monitor),
+            vec![ProfileChange {
+                code: codes::BRIGHTNESS,
+                baseline: Some(75),
+                current: Some(40)
+            }]
+        );
+    }
+
+    #[test]
+    fn input_switch_captures_then_restores_brightness_and_contrast() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 70, 100)
+            .with_feature(codes::CONTRAST, 60, 100)
+            .with_feature(codes::INPUT_SOURCE, 0x0F, 0x1B);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+
+        let restored =
+            switch_input_preserving(&vcp_mon, InputSource::Hdmi1, Duration::ZERO).unwrap();
+        assert_eq!(restored.value(codes::BRIGHTNESS), Some(70));
+        assert_eq!(restored.value(codes::CONTRAST), Some(60));
+        assert_eq!(
+            mock.writes(),
+            vec![
+                (codes::INPUT_SOURCE, 0x11),
+                (codes::BRIGHTNESS, 70),
+                (codes::CONTRAST, 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn input_switch_leaves_unanswered_codes_alone() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 70, 100)
+            .with_feature(codes::INPUT_SOURCE, 0x0F, 0x1B);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+
+        let restored =
+            switch_input_preserving(&vcp_mon, InputSource::Hdmi1, Duration::ZERO).unwrap();
+        assert_eq!(restored.value(codes::CONTRAST), None);
+        assert_eq!(
+            mock.writes(),
+            vec![(codes::INPUT_SOURCE, 0x11), (codes::BRIGHTNESS, 70)]
+        );
+    }
+
+    #[test]
+    fn failed_input_switch_restores_nothing() {
+        let mock = crate::backend::MockBackend::new().with_feature(codes::BRIGHTNESS, 70, 100);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+
+        assert!(switch_input_preserving(&vcp_mon, InputSource::Hdmi1, Duration::ZERO).is_err());
+        assert!(mock.writes().is_empty());
+    }
+
+    fn profile_of(values: &[(u8, u32)]) -> MonitorProfile {
+        MonitorProfile {
+            values: values
+                .iter()
+                .map(|&(code, value)| ProfileValue { code, value })
+                .collect(),
+            monitor: None,
+        }
+    }
+
+    #[test]
+    fn validation_reports_unsupported_and_out_of_range_codes() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 40, 100)
+            .with_feature(codes::CONTRAST, 50, 75);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+
+        let issues = profile_of(&[
+            (codes::BRIGHTNESS, 80),
+            (codes::RED_GAIN, 50),
+            (codes::CONTRAST, 90),
+        ])
+        .validate(&vcp_mon);
+        assert_eq!(
+            issues,
+            [
+                ProfileIssue::Unsupported {
+                    code: codes::RED_GAIN
+                },
+                ProfileIssue::OutOfRange {
+                    code: codes::CONTRAST,
+                    value: 90,
+                    maximum: 75
+                },
+            ]
+        );
+        // Validation never writes
+        assert!(mock.writes().is_empty());
+    }
+
+    #[test]
+    fn validation_uses_listed_values_and_flags_read_only_codes() {
+        let vcp_mon = VcpMonitor::with_backend(
+            crate::backend::MockBackend::new()
+                .with_feature(codes::INPUT_SOURCE, 0x0F, 0xFF)
+                .with_feature(codes::USAGE_TIME, 1200, 0)
+                .with_capabilities("(vcp(60(0F 11 12) C0))"),
+        );
+
+        let issues = profile_of(&[
+            (codes::INPUT_SOURCE, 0x11),
+            (codes::INPUT_SOURCE, 0x1B),
+            (codes::USAGE_TIME, 0),
+        ])
+        .validate(&vcp_mon);
+        assert_eq!(
+            issues,
+            [
+                ProfileIssue::NotAllowed {
+                    code: codes::INPUT_SOURCE,
+                    value: 0x1B,
+                    allowed: vec![0x0F, 0x11, 0x12]
+                },
+                ProfileIssue::ReadOnly {
+                    code: codes::USAGE_TIME
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn minimal_apply_writes_only_differing_codes() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 40, 100)
+            .with_feature(codes::CONTRAST, 50, 100)
+            .with_feature(codes::AUDIO_VOLUME, 10, 100);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+
+        let written = profile_of(&[
+            (codes::BRIGHTNESS, 40),
+            (codes::CONTRAST, 70),
+            (codes::AUDIO_VOLUME, 10),
+        ])
+        .apply_minimal(&vcp_mon)
+        .unwrap();
+        assert_eq!(
+            written.values,
+            [ProfileValue {
+                code: codes::CONTRAST,
+                value: 70
+            }]
+        );
+        assert_eq!(mock.writes(), vec![(codes::CONTRAST, 70)]);
+
+        // Applying again finds nothing left to change
+        let written = profile_of(&[(codes::CONTRAST, 70)])
+            .apply_minimal(&vcp_mon)
+            .unwrap();
+        assert!(written.values.is_empty());
+        assert_eq!(mock.writes().len(), 1);
+    }
+
+    #[test]
+    fn apply_progress_counts_every_write_including_failures() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 40, 100)
+            .with_feature(codes::CONTRAST, 50, 100);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+
+        let mut progress = Vec::new();
+        let result = profile_of(&[
+            (codes::BRIGHTNESS, 60),
+            (codes::RED_GAIN, 50),
+            (codes::CONTRAST, 70),
+        ])
+        .apply_with_progress(&vcp_mon, |done, total| progress.push((done, total)));
+        assert!(result.is_err());
+        assert_eq!(progress, [(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn revert_writes_only_the_one_saved_code() {
+        let mock = crate::backend::MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 90, 100)
+            .with_feature(codes::CONTRAST, 20, 100);
+        let vcp_mon = VcpMonitor::with_backend(mock.clone());
+        let baseline = profile_of(&[(codes::BRIGHTNESS, 50), (codes::CONTRAST, 75)]);
+
+        assert_eq!(
+            baseline.revert(&vcp_mon, codes::CONTRAST).unwrap(),
+            Some(75)
+        );
+        assert_eq!(mock.writes(), vec![(codes::CONTRAST, 75)]);
+
+        assert_eq!(baseline.revert(&vcp_mon, codes::RED_GAIN).unwrap(), None);
+        assert_eq!(mock.writes().len(), 1);
+    }
+
+    fn backup_of(info: &MonitorInfo, brightness: u32) -> MonitorBackup {
+        MonitorBackup {
+            stable_id: info.stable_id(),
+            info: info.clone(),
+            profile: profile(brightness),
+        }
+    }
+
+    #[test]
+    fn backups_match_current_monitors_by_stable_id() {
+        let dell = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        let lg = MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850");
+        let gone = MonitorInfo::fake("\\\\.\\DISPLAY3", "BenQ PD2700U");
+        let backup = Backup {
+            monitors: vec![
+                backup_of(&lg, 40),
+                backup_of(&gone, 50),
+                backup_of(&dell, 60),
+            ],
+        };
+
+        let dir = scratch_dir("backup-all");
+        let path = dir.join("backup.json");
+        backup.save(&path).unwrap();
+        let backup = Backup::load(&path).unwrap();
+
+        let (matched, unmatched) = backup.match_monitors(&[dell, lg]);
+        let matched: Vec<_> = matched
+            .iter()
+            .map(|(saved, index)| (saved.info.friendly_name.as_str(), *index))
+            .collect();
+        assert_eq!(matched, [("LG 27GL850", 1), ("Dell U2723DE", 0)]);
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].info.friendly_name, "BenQ PD2700U");
+        assert_eq!(unmatched[0].profile.value(codes::BRIGHTNESS), Some(50));
+    }
+
+    #[test]
+    fn saved_profiles_keep_the_source_monitor() {
+        let dir = scratch_dir("profile-source");
+        let path = dir.join("profile.json");
+        let mut info = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        info.instance_name = "DISPLAY\\DELA1B2\\UID4353".to_string();
+
+        profile(80).with_monitor(&info).save(&path).unwrap();
+        let loaded = MonitorProfile::load(&path).unwrap();
+        let source = loaded.monitor.as_ref().unwrap();
+        assert_eq!(source.friendly_name, "Dell U2723DE");
+        assert_eq!(source.instance_name, info.instance_name);
+        assert_eq!(loaded.value(codes::BRIGHTNESS), Some(80));
+
+        // Only the captured values are in the file when there is no source
+        profile(80).save(&path).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("monitor"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn profiles_without_a_source_still_load() {
+        let dir = scratch_dir("profile-legacy");
+        let path = dir.join("profile.json");
+        fs::write(&path, r#"{ "values": [{ "code": 16, "value": 80 }] }"#).unwrap();
+
+        let loaded = MonitorProfile::load(&path).unwrap();
+        assert!(loaded.monitor.is_none());
+        assert!(!loaded.from_other_monitor(&MonitorInfo::fake("\\\\.\\DISPLAY1", "Any")));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn from_other_monitor_compares_panels() {
+        let mut source = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723DE");
+        source.instance_name = "DISPLAY\\DELA1B2\\UID4353".to_string();
+        let saved = profile(80).with_monitor(&source);
+
+        // The same panel on another display number is not another monitor
+        let mut moved = MonitorInfo::fake("\\\\.\\DISPLAY2", "Dell U2723DE");
+        moved.instance_name = source.instance_name.clone();
+        assert!(!saved.from_other_monitor(&moved));
+
+        let mut other = MonitorInfo::fake("\\\\.\\DISPLAY1", "LG 27GL850");
+        other.instance_name = "DISPLAY\\GSM5B7F\\UID4354".to_string();
+        assert!(saved.from_other_monitor(&other));
+    }
+}
@@ -0,0 +1,469 @@
+use crate::{
+    MonitorError, Result, monitor,
+    monitor::{Monitor, MonitorInfo, MonitorRect, PhysicalMonitor},
+    selector::MonitorSelector,
+};
+use std::{
+    collections::HashSet,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Where a [`MonitorRegistry`] gets its monitors from. Only the native implementation exists
+/// outside tests; tests substitute their own to control what each enumeration returns.
+pub(crate) trait DisplaySource: Send + Sync {
+    /// Every monitor, with a physical handle opened for each
+    fn enumerate(&self) -> Result<Vec<PhysicalMonitor>>;
+    /// Every monitor's info, without opening physical handles
+    fn list_info(&self) -> Result<Vec<MonitorInfo>>;
+    /// Bounds and primary flag of every display, see [`monitor::display_layout`]
+    fn display_layout(&self) -> Result<Vec<(MonitorRect, bool)>>;
+}
+
+/// Monitors as Windows enumerates them
+struct NativeDisplays;
+
+impl DisplaySource for NativeDisplays {
+    fn enumerate(&self) -> Result<Vec<PhysicalMonitor>> {
+        monitor::enumerate_monitors()
+    }
+
+    fn list_info(&self) -> Result<Vec<MonitorInfo>> {
+        monitor::list_monitor_info()
+    }
+
+    fn display_layout(&self) -> Result<Vec<(MonitorRect, bool)>> {
+        monitor::display_layout()
+    }
+}
+
+/// Long-lived view of the connected monitors, intended for GUI and daemon consumers.
+///
+/// The registry is `Send + Sync`, so it can be wrapped in an `Arc` and shared between threads.
+/// All native access (enumeration and calls made through [`MonitorRegistry::with_monitor`]) is
+/// serialized through a single internal lock.
+///
+/// A monitor unplugged after enumeration leaves a stale handle behind. The first call that fails
+/// because of it marks the monitor as disconnected, and it and every later call to that monitor
+/// fail with [`MonitorError::MonitorDisconnected`] until [`MonitorRegistry::refresh`].
+pub struct MonitorRegistry {
+    source: Box<dyn DisplaySource>,
+    monitors: Mutex<Vec<PhysicalMonitor>>,
+    /// Stable IDs of monitors found to be disconnected since the last refresh
+    disconnected: Mutex<HashSet<String>>,
+    /// Last result of [`MonitorRegistry::list_info`], reused for `info_ttl`
+    info_cache: Mutex<Option<InfoCache>>,
+    info_ttl: Duration,
+}
+
+/// Info-only enumeration kept by [`MonitorRegistry::list_info`], with the display layout it was
+/// taken under
+struct InfoCache {
+    taken: Instant,
+    layout: Vec<(MonitorRect, bool)>,
+    monitors: Vec<MonitorInfo>,
+}
+
+impl MonitorRegistry {
+    /// Create a registry populated with the currently connected monitors
+    pub fn new() -> Result<Self> {
+        Self::with_source(NativeDisplays)
+    }
+
+    /// Create a registry populated from `source`
+    pub(crate) fn with_source(source: impl DisplaySource + 'static) -> Result<Self> {
+        let registry = Self {
+            source: Box::new(source),
+            monitors: Mutex::new(Vec::new()),
+            disconnected: Mutex::new(HashSet::new()),
+            info_cache: Mutex::new(None),
+            info_ttl: Duration::ZERO,
+        };
+        registry.refresh()?;
+        Ok(registry)
+    }
+
+    /// Reuse the result of [`MonitorRegistry::list_info`] for up to `ttl`, for callers that poll
+    /// it often, such as a status bar. Zero, the default, enumerates on every call.
+    pub fn with_info_ttl(mut self, ttl: Duration) -> Self {
+        self.info_ttl = ttl;
+        self
+    }
+
+    /// Re-enumerate monitors, replacing (and releasing the handles of) all previous entries
+    pub fn refresh(&self) -> Result<()> {
+        let mut monitors = self.lock();
+        *monitors = self.source.enumerate()?;
+        self.lock_disconnected().clear();
+        self.invalidate_info();
+        Ok(())
+    }
+
+    /// Info for every monitor connected now, from [`monitor::list_monitor_info`]. Unlike
+    /// [`MonitorRegistry::all`], this opens no handles and also sees monitors connected or
+    /// removed since the last refresh.
+    ///
+    /// With an info TTL set, a result younger than the TTL is returned again, as long as the
+    /// display layout (see [`monitor::display_layout`]) is still the same: connecting, removing
+    /// or rearranging displays invalidates it straight away.
+    pub fn list_info(&self) -> Result<Vec<MonitorInfo>> {
+        let _monitors = self.lock();
+        let layout = self.source.display_layout()?;
+        let mut cache = self.lock_info_cache();
+        if let Some(cached) = cache.as_ref()
+            && cached.taken.elapsed() < self.info_ttl
+            && cached.layout == layout
+        {
+            return Ok(cached.monitors.clone());
+        }
+
+        let monitors = self.source.list_info()?;
+        *cache = (!self.info_ttl.is_zero()).then(|| InfoCache {
+            taken: Instant::now(),
+            layout,
+            monitors: monitors.clone(),
+        });
+        Ok(monitors)
+    }
+
+    /// Drop the result [`MonitorRegistry::list_info`] keeps, so its next call enumerates again
+    pub fn invalidate_info(&self) {
+        *self.lock_info_cache() = None;
+    }
+
+    /// Info for every monitor currently held by the registry
+    pub fn all(&self) -> Vec<MonitorInfo> {
+        self.lock().iter().map(|m| m.info().clone()).collect()
+    }
+
+    /// Whether a call to `monitor` failed because it was disconnected since the last refresh
+    pub fn is_disconnected(&self, monitor: &MonitorInfo) -> bool {
+        self.lock_disconnected().contains(&monitor.stable_id())
+    }
+
+    /// Info for the monitor `selector` picks
+    pub fn get(&self, selector: &MonitorSelector) -> Result<MonitorInfo> {
+        self.with_monitor(selector, |m| Ok(m.info().clone()))
+    }
+
+    /// Run `f` against the monitor `selector` picks out of the registry's monitors (see
+    /// [`MonitorSelector::position`]) while holding the registry lock, so no other thread can
+    /// talk to the hardware at the same time
+    pub fn with_monitor<T>(
+        &self,
+        selector: &MonitorSelector,
+        f: impl FnOnce(&PhysicalMonitor) -> Result<T>,
+    ) -> Result<T> {
+        let monitors = self.lock();
+        let index = selector.position(&monitors)?;
+        let mon = &monitors[index];
+        let stable_id = mon.info().stable_id();
+        let disconnected = || MonitorError::MonitorDisconnected(mon.info().friendly_name.clone());
+
+        // Don't send anything through a handle already known to be stale
+        if self.lock_disconnected().contains(&stable_id) {
+            return Err(disconnected());
+        }
+        f(mon).map_err(|e| {
+            if e.is_disconnect() {
+                self.lock_disconnected().insert(stable_id);
+                disconnected()
+            } else {
+                e
+            }
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<PhysicalMonitor>> {
+        // A panic in a caller's closure must not make the registry unusable
+        self.monitors.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// When both locks are needed, this one is taken second, so the two can't deadlock
+    fn lock_disconnected(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.disconnected.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Like the disconnected set, taken after the monitor lock and never together with the
+    /// disconnected set
+    fn lock_info_cache(&self) -> MutexGuard<'_, Option<InfoCache>> {
+        self.info_cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Displays whose monitors a test changes between enumerations, counting how often
+/// physical handles were opened
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct FakeDisplays {
+    names: std::sync::Arc<Mutex<Vec<&'static str>>>,
+    pub(crate) enumerations: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    pub(crate) info_listings: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(test)]
+impl FakeDisplays {
+    pub(crate) fn new(names: &[&'static str]) -> Self {
+        let displays = Self::default();
+        displays.connect(names);
+        displays
+    }
+
+    pub(crate) fn connect(&self, names: &[&'static str]) {
+        *self.names.lock().unwrap() = names.to_vec();
+    }
+
+    fn infos(&self) -> Vec<MonitorInfo> {
+        self.names
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, name)| MonitorInfo::fake(&format!("\\\\.\\DISPLAY{}", i + 1), name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl DisplaySource for FakeDisplays {
+    fn enumerate(&self) -> Result<Vec<PhysicalMonitor>> {
+        self.enumerations
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(self
+            .infos()
+            .into_iter()
+            .map(PhysicalMonitor::fake)
+            .collect())
+    }
+
+    fn list_info(&self) -> Result<Vec<MonitorInfo>> {
+        self.info_listings
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(self.infos())
+    }
+
+    fn display_layout(&self) -> Result<Vec<(MonitorRect, bool)>> {
+        Ok(self
+            .infos()
+            .iter()
+            .map(|info| (info.monitor_rect, info.is_primary))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, atomic::Ordering};
+    use windows_sys::Win32::Foundation::ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS;
+
+    fn device(name: &str) -> MonitorSelector {
+        MonitorSelector::Device(name.to_string())
+    }
+
+    fn friendly_names(infos: &[MonitorInfo]) -> Vec<&str> {
+        infos
+            .iter()
+            .map(|info| info.friendly_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn refresh_replaces_stale_entries() {
+        let displays = FakeDisplays::new(&["Dell U2723DE", "LG 27GL850"]);
+        let registry = MonitorRegistry::with_source(displays.clone()).unwrap();
+        assert_eq!(displays.enumerations.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            friendly_names(&registry.all()),
+            ["Dell U2723DE", "LG 27GL850"]
+        );
+
+        displays.connect(&["BenQ PD2700U"]);
+        // Nothing changes until the registry is refreshed
+        assert_eq!(registry.all().len(), 2);
+
+        registry.refresh().unwrap();
+        assert_eq!(displays.enumerations.load(Ordering::SeqCst), 2);
+        assert_eq!(friendly_names(&registry.all()), ["BenQ PD2700U"]);
+        assert!(matches!(
+            registry.get(&device("LG 27GL850")),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+        assert_eq!(
+            registry.get(&device("BenQ PD2700U")).unwrap().device_name,
+            "\\\\.\\DISPLAY1"
+        );
+    }
+
+    #[test]
+    fn monitors_are_picked_by_any_selector() {
+        let registry =
+            MonitorRegistry::with_source(FakeDisplays::new(&["Dell U2723DE", "LG 27GL850"]))
+                .unwrap();
+        let picked = |selector: MonitorSelector| registry.get(&selector).map(|i| i.device_name);
+
+        assert_eq!(picked(device("lg")).unwrap(), "\\\\.\\DISPLAY2");
+        assert!(matches!(
+            picked(MonitorSelector::ExactDevice("lg".to_string())),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+        assert_eq!(
+            picked(MonitorSelector::ExactDevice("LG 27GL850".to_string())).unwrap(),
+            "\\\\.\\DISPLAY2"
+        );
+        // None of the fakes is the primary monitor
+        assert!(matches!(
+            picked(MonitorSelector::Primary),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn registry_can_be_shared_between_threads() {
+        let registry =
+            Arc::new(MonitorRegistry::with_source(FakeDisplays::new(&["Dell"])).unwrap());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let registry = Arc::clone(&registry);
+                std::thread::spawn(move || {
+                    registry.refresh().unwrap();
+                    registry.get(&device("Dell")).unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().friendly_name, "Dell");
+        }
+    }
+
+    #[test]
+    fn list_info_opens_no_physical_handles() {
+        let displays = FakeDisplays::new(&["Dell U2723DE", "LG 27GL850"]);
+        let registry = MonitorRegistry::with_source(displays.clone()).unwrap();
+        assert_eq!(displays.enumerations.load(Ordering::SeqCst), 1);
+
+        displays.connect(&["Dell U2723DE", "LG 27GL850", "BenQ PD2700U"]);
+        let infos = registry.list_info().unwrap();
+        assert_eq!(
+            friendly_names(&infos),
+            ["Dell U2723DE", "LG 27GL850", "BenQ PD2700U"]
+        );
+        assert_eq!(displays.info_listings.load(Ordering::SeqCst), 1);
+        assert_eq!(displays.enumerations.load(Ordering::SeqCst), 1);
+        // The handles held by the registry are untouched
+        assert_eq!(registry.all().len(), 2);
+    }
+
+    #[test]
+    fn disconnect_marks_the_monitor_until_refresh() {
+        let registry = MonitorRegistry::with_source(FakeDisplays::new(&["Dell"])).unwrap();
+        let unplugged = || -> Result<()> {
+            Err(MonitorError::Win32 {
+                function: "GetVCPFeatureAndVCPFeatureReply",
+                code: ERROR_GRAPHICS_MONITOR_NO_LONGER_EXISTS as u32,
+            })
+        };
+
+        assert!(matches!(
+            registry.with_monitor(&device("Dell"), |_| unplugged()),
+            Err(MonitorError::MonitorDisconnected(_))
+        ));
+        let info = registry.get(&device("Dell"));
+        assert!(matches!(info, Err(MonitorError::MonitorDisconnected(_))));
+
+        // Known-stale handles aren't used again
+        let mut called = false;
+        let _ = registry.with_monitor(&device("Dell"), |_| {
+            called = true;
+            Ok(())
+        });
+        assert!(!called);
+
+        registry.refresh().unwrap();
+        assert_eq!(registry.get(&device("Dell")).unwrap().friendly_name, "Dell");
+    }
+
+    #[test]
+    fn other_errors_dont_mark_the_monitor_disconnected() {
+        let registry = MonitorRegistry::with_source(FakeDisplays::new(&["Dell"])).unwrap();
+        assert!(matches!(
+            registry.with_monitor(&device("Dell"), |_| -> Result<()> {
+                Err(MonitorError::VcpNotSupported)
+            }),
+            Err(MonitorError::VcpNotSupported)
+        ));
+        let info = registry.get(&device("Dell")).unwrap();
+        assert!(!registry.is_disconnected(&info));
+    }
+
+    #[test]
+    fn info_is_cached_for_the_ttl_while_the_layout_holds() {
+        let displays = FakeDisplays::new(&["Dell U2723DE", "LG 27GL850"]);
+        let listings = || displays.info_listings.load(Ordering::SeqCst);
+
+        // Without a TTL every call enumerates
+        let registry = MonitorRegistry::with_source(displays.clone()).unwrap();
+        registry.list_info().unwrap();
+        registry.list_info().unwrap();
+        assert_eq!(listings(), 2);
+
+        let registry = MonitorRegistry::with_source(displays.clone())
+            .unwrap()
+            .with_info_ttl(Duration::from_secs(60));
+        registry.list_info().unwrap();
+        // Same layout, so a rename isn't noticed until the TTL runs out
+        displays.connect(&["Dell U2723DE", "BenQ PD2700U"]);
+        assert_eq!(
+            friendly_names(&registry.list_info().unwrap()),
+            ["Dell U2723DE", "LG 27GL850"]
+        );
+        assert_eq!(listings(), 3);
+
+        // Connecting a display changes the layout
+        displays.connect(&["Dell U2723DE", "BenQ PD2700U", "LG 27GL850"]);
+        assert_eq!(registry.list_info().unwrap().len(), 3);
+        assert_eq!(listings(), 4);
+
+        registry.invalidate_info();
+        registry.list_info().unwrap();
+        registry.refresh().unwrap();
+        registry.list_info().unwrap();
+        assert_eq!(listings(), 6);
+    }
+
+    #[test]
+    fn cached_info_expires_after_the_ttl() {
+        let displays = FakeDisplays::new(&["Dell U2723DE"]);
+        let registry = MonitorRegistry::with_source(displays.clone())
+            .unwrap()
+            .with_info_ttl(Duration::from_millis(20));
+
+        registry.list_info().unwrap();
+        registry.list_info().unwrap();
+        assert_eq!(displays.info_listings.load(Ordering::SeqCst), 1);
+        std::thread::sleep(Duration::from_millis(30));
+        registry.list_info().unwrap();
+        assert_eq!(displays.info_listings.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn cached_info_can_be_read_from_many_threads() {
+        let displays = FakeDisplays::new(&["Dell U2723DE", "LG 27GL850"]);
+        let registry = Arc::new(
+            MonitorRegistry::with_source(displays.clone())
+                .unwrap()
+                .with_info_ttl(Duration::from_secs(60)),
+        );
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let registry = Arc::clone(&registry);
+                std::thread::spawn(move || registry.list_info().unwrap().len())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 2);
+        }
+        // The first caller enumerates; the rest wait for it and share the result
+        assert_eq!(displays.info_listings.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    MonitorError, Result, monitor,
+    monitor::{Monitor, MonitorInfo, PhysicalMonitor},
+    native,
+};
+use windows_sys::Win32::Graphics::Gdi::HMONITOR;
+
+/// Which monitor a command targets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MonitorSelector {
+    #[default]
+    Primary,
+    /// Device name, friendly name or stable ID; names can also match loosely, see
+    /// [`position_by_name`]
+    Device(String),
+    /// Device name, friendly name or stable ID, matched exactly (`--exact`)
+    ExactDevice(String),
+    /// Number shown for the monitor in Windows display settings (1-based)
+    DisplayNumber(u32),
+    /// Raw HMONITOR obtained outside this tool; bypasses enumeration
+    Handle(usize),
+}
+
+impl MonitorSelector {
+    /// Enumerate monitors and return the selected one; a raw handle is opened directly
+    pub fn resolve(&self) -> Result<PhysicalMonitor> {
+        match self {
+            MonitorSelector::Handle(handle) => monitor::open_hmonitor(*handle as HMONITOR),
+            _ => self.select(monitor::enumerate_monitors()?),
+        }
+    }
+
+    /// Pick the selected monitor out of `monitors`; the others are dropped and their handles
+    /// released
+    pub fn select(&self, mut monitors: Vec<PhysicalMonitor>) -> Result<PhysicalMonitor> {
+        let index = self.position(&monitors)?;
+        Ok(monitors.swap_remove(index))
+    }
+
+    /// Index of the selected monitor in `monitors`
+    pub fn position(&self, monitors: &[PhysicalMonitor]) -> Result<usize> {
+        match self {
+            MonitorSelector::Primary => monitors
+                .iter()
+                .position(|m| m.info().is_primary)
+                .ok_or_else(|| MonitorError::MonitorNotFound("Primary monitor".to_string())),
+            MonitorSelector::Device(name) => {
+                let infos: Vec<_> = monitors.iter().map(|m| m.info()).collect();
+                position_by_name(&infos, name)
+            }
+            MonitorSelector::ExactDevice(name) => monitors
+                .iter()
+                .position(|m| m.info().matches(name))
+                .ok_or_else(|| MonitorError::MonitorNotFound(name.clone())),
+            MonitorSelector::DisplayNumber(number) => {
+                let sources = native::display_config_sources()?;
+                let device = device_for_display_number(&sources, *number)?;
+                monitors
+                    .iter()
+                    .position(|m| m.info().device_name == device)
+                    .ok_or_else(|| MonitorError::MonitorNotFound(format!("Display {}", number)))
+            }
+            MonitorSelector::Handle(handle) => monitors
+                .iter()
+                .position(|m| m.hmonitor() as usize == *handle)
+                .ok_or_else(|| MonitorError::MonitorNotFound(format!("HMONITOR 0x{:X}", handle))),
+        }
+    }
+}
+
+/// Index of the monitor in `infos` that `name` identifies.
+///
+/// An exact match on device name, friendly name or stable ID wins. Otherwise `name` is matched
+/// with [`MonitorInfo::matches_partially`] and must pick out a single panel; the entries a cloned
+/// monitor shows up as count as one, and the first is used. Several panels are an error that
+/// lists them.
+pub fn position_by_name(infos: &[&MonitorInfo], name: &str) -> Result<usize> {
+    if let Some(index) = infos.iter().position(|info| info.matches(name)) {
+        return Ok(index);
+    }
+
+    let candidates: Vec<usize> = (0..infos.len())
+        .filter(|&index| infos[index].matches_partially(name))
+        .collect();
+    let Some(&first) = candidates.first() else {
+        return Err(MonitorError::MonitorNotFound(name.to_string()));
+    };
+    if candidates
+        .iter()
+        .all(|&index| index == first || infos[index].same_panel_as(infos[first]))
+    {
+        return Ok(first);
+    }
+
+    let listed: Vec<String> = candidates
+        .iter()
+        .map(|&index| {
+            format!(
+                "{} ({})",
+                infos[index].friendly_name, infos[index].device_name
+            )
+        })
+        .collect();
+    Err(MonitorError::AmbiguousMonitor(format!(
+        "'{}' matches {}; use more of the name, the device name or the stable ID",
+        name,
+        listed.join(", ")
+    )))
+}
+
+/// Map a display-settings number to a GDI device name, given the sources of the active display
+/// paths in `QueryDisplayConfig` order.
+///
+/// Windows display settings numbers monitors by their position in the active path list, which
+/// doesn't follow the `\\.\DISPLAYn` names or `list`'s enumeration order. Cloned monitors share a
+/// source, so only its first path gets a number here.
+pub fn device_for_display_number(sources: &[String], number: u32) -> Result<&str> {
+    let mut unique: Vec<&str> = Vec::new();
+    for source in sources {
+        if !unique.contains(&source.as_str()) {
+            unique.push(source);
+        }
+    }
+
+    number
+        .checked_sub(1)
+        .and_then(|index| unique.get(index as usize))
+        .copied()
+        .ok_or_else(|| {
+            MonitorError::MonitorNotFound(format!(
+                "Display {} (Windows reports {} active display{})",
+                number,
+                unique.len(),
+                if unique.len() == 1 { "" } else { "s" }
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::MonitorInfo;
+
+    fn monitors() -> Vec<PhysicalMonitor> {
+        let mut primary = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        primary.is_primary = true;
+        vec![
+            PhysicalMonitor::fake(MonitorInfo::fake("\\\\.\\DISPLAY2", "LG 27GL850")),
+            PhysicalMonitor::fake(primary),
+            PhysicalMonitor::fake(MonitorInfo::fake("\\\\.\\DISPLAY3", "Dell P2422H")),
+        ]
+    }
+
+    fn selected(selector: MonitorSelector) -> Result<String> {
+        selector
+            .select(monitors())
+            .map(|m| m.info().device_name.clone())
+    }
+
+    #[test]
+    fn select_finds_the_primary_and_named_monitors() {
+        assert_eq!(
+            selected(MonitorSelector::Primary).unwrap(),
+            "\\\\.\\DISPLAY1"
+        );
+        assert_eq!(
+            selected(MonitorSelector::Device("LG 27GL850".to_string())).unwrap(),
+            "\\\\.\\DISPLAY2"
+        );
+        assert_eq!(
+            selected(MonitorSelector::Device("\\\\.\\DISPLAY3".to_string())).unwrap(),
+            "\\\\.\\DISPLAY3"
+        );
+        assert_eq!(
+            selected(MonitorSelector::ExactDevice("Dell P2422H".to_string())).unwrap(),
+            "\\\\.\\DISPLAY3"
+        );
+        assert!(matches!(
+            selected(MonitorSelector::ExactDevice("dell p2422h".to_string())),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+        assert!(matches!(
+            selected(MonitorSelector::Handle(0x10001)),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn partial_names_must_pick_out_one_panel() {
+        // A single partial match is used even when the monitor can't be told apart from others
+        assert_eq!(
+            selected(MonitorSelector::Device("lg".to_string())).unwrap(),
+            "\\\\.\\DISPLAY2"
+        );
+        assert_eq!(
+            selected(MonitorSelector::Device("p2422".to_string())).unwrap(),
+            "\\\\.\\DISPLAY3"
+        );
+        assert_eq!(
+            selected(MonitorSelector::Device("\\\\.\\display3".to_string())).unwrap(),
+            "\\\\.\\DISPLAY3"
+        );
+
+        match selected(MonitorSelector::Device("dell".to_string())) {
+            Err(MonitorError::AmbiguousMonitor(message)) => {
+                assert!(message.contains("Dell U2723QE (\\\\.\\DISPLAY1)"));
+                assert!(message.contains("Dell P2422H (\\\\.\\DISPLAY3)"));
+            }
+            other => panic!("expected an ambiguous match, got {:?}", other),
+        }
+        assert!(matches!(
+            selected(MonitorSelector::Device("benq".to_string())),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn display_numbers_count_cloned_sources_once() {
+        let sources: Vec<String> = ["\\\\.\\DISPLAY2", "\\\\.\\DISPLAY1", "\\\\.\\DISPLAY1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(
+            device_for_display_number(&sources, 1).unwrap(),
+            "\\\\.\\DISPLAY2"
+        );
+        assert_eq!(
+            device_for_display_number(&sources, 2).unwrap(),
+            "\\\\.\\DISPLAY1"
+        );
+        assert!(device_for_display_number(&sources, 3).is_err());
+        assert!(device_for_display_number(&sources, 0).is_err());
+    }
+
+    #[test]
+    fn a_cloned_panel_matches_once() {
+        let mut first = MonitorInfo::fake("\\\\.\\DISPLAY1", "Dell U2723QE");
+        first.instance_name = "DISPLAY\\DELA1B2\\5&2b0b5b5e&0&UID4353".to_string();
+        let mut clone = MonitorInfo::fake("\\\\.\\DISPLAY2", "Dell U2723QE");
+        clone.instance_name = first.instance_name.clone();
+        let other = MonitorInfo::fake("\\\\.\\DISPLAY3", "LG 27GL850");
+
+        assert_eq!(
+            position_by_name(&[&other, &first, &clone], "u2723").unwrap(),
+            1
+        );
+        assert!(position_by_name(&[&other, &first, &clone], "27").is_err());
+    }
+}
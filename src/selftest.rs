@@ -0,0 +1,436 @@
+use crate::{
+    Result, monitor,
+    vcp::{VcpFeatureResponse, VcpMonitor, codes},
+};
+use serde::Serialize;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Pause between reads while waiting for a written value to show up
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Most writes [`SelfTestReport::recommend`] asks for; a monitor that needs more has a
+/// connection problem that repeating won't hide
+pub const MAX_TUNED_REPEAT: u32 = 5;
+
+/// Most busy-channel attempts [`SelfTestReport::recommend`] asks for
+pub const MAX_TUNED_RETRY: u32 = 5;
+
+/// Shortest retry delay [`SelfTestReport::recommend`] asks for: DDC/CI hosts are to leave 50 ms
+/// between commands
+const MIN_TUNED_RETRY_DELAY_MS: u64 = 50;
+
+/// Outcome of one write/verify cycle of [`run`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum CycleOutcome {
+    /// The monitor reported the written value `settle_ms` after the write
+    Verified { settle_ms: u64 },
+    /// The write went through, but the monitor still reported another value at the timeout
+    NotApplied { reported: u32 },
+    /// The write or a read-back failed
+    Failed { error: String },
+}
+
+/// Result of a [`run`]: what happened on each cycle, and the brightness that was restored
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    /// Brightness before the test, written back at the end
+    pub original: u32,
+    pub cycles: Vec<CycleOutcome>,
+    /// Longest a single successful brightness read took
+    pub slowest_read_ms: u64,
+}
+
+impl SelfTestReport {
+    /// Number of cycles where the monitor reported the written value in time
+    pub fn verified(&self) -> usize {
+        self.settle_times().count()
+    }
+
+    /// Share of cycles that verified, 0.0-1.0; 0.0 without cycles
+    pub fn success_rate(&self) -> f64 {
+        if self.cycles.is_empty() {
+            return 0.0;
+        }
+        self.verified() as f64 / self.cycles.len() as f64
+    }
+
+    /// Share of cycles where a write or read failed outright, 0.0-1.0; 0.0 without cycles
+    pub fn failure_rate(&self) -> f64 {
+        if self.cycles.is_empty() {
+            return 0.0;
+        }
+        let failed = self
+            .cycles
+            .iter()
+            .filter(|cycle| matches!(cycle, CycleOutcome::Failed { .. }))
+            .count();
+        failed as f64 / self.cycles.len() as f64
+    }
+
+    /// Median time the verified cycles took to report the written value
+    pub fn typical_settle(&self) -> Option<Duration> {
+        let mut times: Vec<_> = self.settle_times().collect();
+        times.sort_unstable();
+        times.get(times.len() / 2).copied()
+    }
+
+    /// Longest time a verified cycle took to report the written value
+    pub fn slowest_settle(&self) -> Option<Duration> {
+        self.settle_times().max()
+    }
+
+    /// Settle delay, write count and busy-channel retries for the configuration file, or `None`
+    /// if no cycle verified.
+    ///
+    /// The delay is the slowest settle time with half again as headroom, rounded up to 10 ms. The
+    /// write count is the lowest that brings the chance of every write being missed below 1% at
+    /// the measured failure rate, capped at [`MAX_TUNED_REPEAT`]. The retry count does the same
+    /// for transfers that failed outright, between 2 and [`MAX_TUNED_RETRY`] attempts, so it
+    /// assumes the test ran without retries. The retry delay is the slowest read rounded up to
+    /// 10 ms, so another application's transfer can finish, and at least 50 ms.
+    pub fn recommend(&self) -> Option<Tuning> {
+        let slowest = self.slowest_settle()?.as_millis() as u64;
+
+        Some(Tuning {
+            settle_delay_ms: (slowest * 3 / 2).div_ceil(10) * 10,
+            repeat: attempts_for(1.0 - self.success_rate(), 1, MAX_TUNED_REPEAT),
+            retry: attempts_for(self.failure_rate(), 2, MAX_TUNED_RETRY),
+            retry_delay_ms: (self.slowest_read_ms.div_ceil(10) * 10).max(MIN_TUNED_RETRY_DELAY_MS),
+        })
+    }
+
+    fn settle_times(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.cycles.iter().filter_map(|cycle| match cycle {
+            CycleOutcome::Verified { settle_ms } => Some(Duration::from_millis(*settle_ms)),
+            _ => None,
+        })
+    }
+}
+
+/// Fewest attempts, from `min` up to `max`, that bring the chance of every one failing below 1%
+/// when each fails with probability `failing`
+fn attempts_for(failing: f64, min: u32, max: u32) -> u32 {
+    (min..max)
+        .find(|&attempts| failing.powi(attempts as i32) <= 0.01)
+        .unwrap_or(max)
+}
+
+/// Per-monitor settings recommended from a [`SelfTestReport`], named as in the configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Tuning {
+    pub settle_delay_ms: u64,
+    pub repeat: u32,
+    pub retry: u32,
+    pub retry_delay_ms: u64,
+}
+
+/// The two brightness levels the test alternates between: a tenth of the range either side of
+/// `original`, or both on one side near the ends of the range. Neither is `original`, so a write
+/// the monitor ignores can't pass for a verified one, and the screen barely changes. Only a 0-1
+/// range leaves no choice but to alternate with `original`.
+pub fn test_levels(original: u32, maximum: u32) -> Result<[u32; 2]> {
+    monitor::check_range(0, maximum)?;
+    let original = original.min(maximum);
+    if maximum == 1 {
+        return Ok([1 - original, original]);
+    }
+
+    let step = (maximum / 10).max(1);
+    Ok(
+        match (original.checked_sub(step), original + step <= maximum) {
+            (Some(low), true) => [low, original + step],
+            (None, _) => [original + step, original + 2 * step],
+            (Some(low), false) => [low, original - 2 * step],
+        },
+    )
+}
+
+/// Write brightness `cycles` times, alternating between the [`test_levels`], and after each
+/// write read it back until the monitor reports the written value or `timeout` passes.
+///
+/// The original brightness is always written back afterwards, also when a cycle fails. Failing
+/// cycles are recorded rather than stopping the test; only failing to read the original
+/// brightness, or to restore it, is an error.
+pub fn run(vcp: &VcpMonitor, cycles: u32, timeout: Duration) -> Result<SelfTestReport> {
+    let mut slowest_read = Duration::ZERO;
+    let response = timed_read(vcp, &mut slowest_read)?;
+    let original = response.current_value;
+    let levels = test_levels(original, response.maximum_value)?;
+
+    let outcomes = (0..cycles as usize)
+        .map(|cycle| run_cycle(vcp, levels[cycle % 2], timeout, &mut slowest_read))
+        .collect();
+
+    vcp.set_vcp_feature(codes::BRIGHTNESS, original)?;
+    Ok(SelfTestReport {
+        original,
+        cycles: outcomes,
+        slowest_read_ms: slowest_read.as_millis() as u64,
+    })
+}
+
+/// Read brightness, raising `slowest` to the time the read took if it succeeded
+fn timed_read(vcp: &VcpMonitor, slowest: &mut Duration) -> Result<VcpFeatureResponse> {
+    let started = Instant::now();
+    let response = vcp.get_vcp_feature(codes::BRIGHTNESS)?;
+    *slowest = (*slowest).max(started.elapsed());
+    Ok(response)
+}
+
+fn run_cycle(
+    vcp: &VcpMonitor,
+    value: u32,
+    timeout: Duration,
+    slowest_read: &mut Duration,
+) -> CycleOutcome {
+    if let Err(e) = vcp.set_vcp_feature(codes::BRIGHTNESS, value) {
+        return CycleOutcome::Failed {
+            error: e.to_string(),
+        };
+    }
+
+    let written = Instant::now();
+    loop {
+        let reported = match timed_read(vcp, slowest_read) {
+            Ok(response) => response.current_value,
+            Err(e) => {
+                return CycleOutcome::Failed {
+                    error: e.to_string(),
+                };
+            }
+        };
+        let elapsed = written.elapsed();
+        if reported == value {
+            return CycleOutcome::Verified {
+                settle_ms: elapsed.as_millis() as u64,
+            };
+        }
+        if elapsed >= timeout {
+            return CycleOutcome::NotApplied { reported };
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Result of `persistence-test`: whether a brightness written over DDC/CI was still set after the
+/// monitor was switched off and on again
+#[derive(Debug, Clone, Serialize)]
+pub struct PersistenceReport {
+    /// Brightness before the test, written back at the end
+    pub original: u32,
+    /// Brightness written before the power cycle
+    pub test_value: u32,
+    /// Brightness the monitor reported once it was back on
+    pub reported: u32,
+}
+
+impl PersistenceReport {
+    /// Whether the monitor still reported the test value after the power cycle
+    pub fn persisted(&self) -> bool {
+        self.reported == self.test_value
+    }
+
+    /// What the result means for keeping settings changed over DDC/CI
+    pub fn advice(&self) -> &'static str {
+        if self.persisted() {
+            "The monitor keeps DDC/CI changes when switched off; save-settings isn't needed"
+        } else {
+            "The monitor forgets DDC/CI changes when switched off; run save-settings after \
+             changing settings to keep them"
+        }
+    }
+}
+
+/// Read brightness until the monitor reports `value` or `timeout` passes, returning whether it did
+pub fn wait_for_brightness(vcp: &VcpMonitor, value: u32, timeout: Duration) -> Result<bool> {
+    let written = Instant::now();
+    loop {
+        if vcp.get_vcp_feature(codes::BRIGHTNESS)?.current_value == value {
+            return Ok(true);
+        }
+        if written.elapsed() >= timeout {
+            return Ok(false);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+
+    fn report(cycles: Vec<CycleOutcome>) -> SelfTestReport {
+        SelfTestReport {
+            original: 50,
+            cycles,
+            slowest_read_ms: 40,
+        }
+    }
+
+    #[test]
+    fn test_levels_avoid_the_original_and_stay_in_range() {
+        assert_eq!(test_levels(50, 100).unwrap(), [40, 60]);
+        assert_eq!(test_levels(5, 100).unwrap(), [15, 25]);
+        assert_eq!(test_levels(95, 100).unwrap(), [85, 75]);
+        // Reported levels above the maximum are treated as the maximum
+        assert_eq!(test_levels(120, 100).unwrap(), [90, 80]);
+        assert_eq!(test_levels(2, 5).unwrap(), [1, 3]);
+        assert_eq!(test_levels(0, 1).unwrap(), [1, 0]);
+        assert!(test_levels(0, 0).is_err());
+    }
+
+    #[test]
+    fn run_alternates_levels_and_restores_the_original() {
+        let mock = MockBackend::new().with_feature(codes::BRIGHTNESS, 50, 100);
+        let vcp = VcpMonitor::with_backend(mock.clone());
+
+        let report = run(&vcp, 3, Duration::from_millis(100)).unwrap();
+        assert_eq!(report.original, 50);
+        assert_eq!(report.verified(), 3);
+        assert_eq!(
+            mock.writes(),
+            vec![
+                (codes::BRIGHTNESS, 40),
+                (codes::BRIGHTNESS, 60),
+                (codes::BRIGHTNESS, 40),
+                (codes::BRIGHTNESS, 50)
+            ]
+        );
+        assert_eq!(mock.value(codes::BRIGHTNESS), Some(50));
+    }
+
+    #[test]
+    fn ignored_writes_are_not_applied_cycles() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 50, 100)
+            .ignoring_writes();
+        let vcp = VcpMonitor::with_backend(mock);
+
+        let report = run(&vcp, 2, Duration::ZERO).unwrap();
+        assert_eq!(
+            report.cycles,
+            [
+                CycleOutcome::NotApplied { reported: 50 },
+                CycleOutcome::NotApplied { reported: 50 }
+            ]
+        );
+        assert_eq!(report.success_rate(), 0.0);
+        assert_eq!(report.recommend(), None);
+
+        // Without brightness there is nothing to test
+        assert!(
+            run(
+                &VcpMonitor::with_backend(MockBackend::new()),
+                2,
+                Duration::ZERO
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn recommendations_follow_the_slowest_settle_and_failure_rate() {
+        let verified = |settle_ms| CycleOutcome::Verified { settle_ms };
+        let failed = CycleOutcome::Failed {
+            error: "timeout".to_string(),
+        };
+
+        let all_verified = report(vec![verified(20), verified(40), verified(30)]);
+        assert_eq!(
+            all_verified.typical_settle(),
+            Some(Duration::from_millis(30))
+        );
+        assert_eq!(
+            all_verified.recommend(),
+            Some(Tuning {
+                settle_delay_ms: 60,
+                repeat: 1,
+                retry: 2,
+                retry_delay_ms: 50
+            })
+        );
+
+        // Half the writes missed: 1% odds of missing them all takes 7 writes, capped at 5
+        let unreliable = report(vec![verified(33), failed.clone()]);
+        assert_eq!(unreliable.success_rate(), 0.5);
+        assert_eq!(
+            unreliable.recommend(),
+            Some(Tuning {
+                settle_delay_ms: 50,
+                repeat: MAX_TUNED_REPEAT,
+                retry: MAX_TUNED_RETRY,
+                retry_delay_ms: 50
+            })
+        );
+
+        let mostly = report(vec![verified(10), verified(10), verified(10), failed]);
+        assert_eq!(mostly.recommend().unwrap().repeat, 4);
+        assert_eq!(mostly.recommend().unwrap().retry, 4);
+
+        // Writes the monitor ignored count against repeat, not retry
+        let ignored = CycleOutcome::NotApplied { reported: 50 };
+        let ignoring = report(vec![verified(10), ignored.clone(), ignored]);
+        assert_eq!(ignoring.failure_rate(), 0.0);
+        assert_eq!(ignoring.recommend().unwrap().repeat, 5);
+        assert_eq!(ignoring.recommend().unwrap().retry, 2);
+    }
+
+    #[test]
+    fn retry_delay_follows_the_slowest_read() {
+        let slow = SelfTestReport {
+            slowest_read_ms: 73,
+            ..report(vec![CycleOutcome::Verified { settle_ms: 10 }])
+        };
+        assert_eq!(slow.recommend().unwrap().retry_delay_ms, 80);
+
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 50, 100)
+            .slow_to_read(codes::BRIGHTNESS, Duration::from_millis(30));
+        let vcp = VcpMonitor::with_backend(mock);
+        let report = run(&vcp, 1, Duration::from_millis(100)).unwrap();
+        assert!(report.slowest_read_ms >= 30);
+    }
+
+    #[test]
+    fn persistence_reports_compare_the_test_value() {
+        let kept = PersistenceReport {
+            original: 50,
+            test_value: 40,
+            reported: 40,
+        };
+        assert!(kept.persisted());
+        assert!(kept.advice().contains("isn't needed"));
+
+        let forgotten = PersistenceReport {
+            reported: 50,
+            ..kept
+        };
+        assert!(!forgotten.persisted());
+        assert!(forgotten.advice().contains("run save-settings"));
+    }
+
+    #[test]
+    fn waiting_for_brightness_reports_whether_it_arrived() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 50, 100)
+            .settling_after(Duration::from_millis(30));
+        let vcp = VcpMonitor::with_backend(mock);
+        vcp.set_vcp_feature(codes::BRIGHTNESS, 40).unwrap();
+
+        assert!(!wait_for_brightness(&vcp, 40, Duration::ZERO).unwrap());
+        assert!(wait_for_brightness(&vcp, 40, Duration::from_secs(1)).unwrap());
+        assert!(
+            wait_for_brightness(
+                &VcpMonitor::with_backend(MockBackend::new()),
+                40,
+                Duration::ZERO
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use crate::{
+    MonitorError, MonitorRegistry, Result,
+    monitor::{Monitor, MonitorInfo},
+    native::NamedPipe,
+    selector::MonitorSelector,
+    vcp,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+/// Pipe `serve` listens on unless told otherwise
+pub const DEFAULT_PIPE_NAME: &str = r"\\.\pipe\monitorconfig";
+
+/// The request could not be parsed as JSON or lacks a method
+pub const PARSE_ERROR: i32 = -32700;
+/// No method by that name
+pub const METHOD_NOT_FOUND: i32 = -32601;
+/// The params don't fit the method
+pub const INVALID_PARAMS: i32 = -32602;
+/// The method ran and the monitor or Windows reported an error
+pub const MONITOR_ERROR: i32 = -32000;
+/// The monitor was unplugged since the last `refresh`; refresh and retry
+pub const MONITOR_DISCONNECTED: i32 = -32001;
+
+/// One request message: `{"id": 1, "method": "get_brightness", "params": {"device": "..."}}`.
+///
+/// `id` is echoed back unchanged so clients can match replies; `params` may be omitted for
+/// methods without required parameters.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Request {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// One reply message, carrying exactly one of `result` and `error`
+#[derive(Debug, Clone, Serialize)]
+pub struct Response {
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ResponseError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl ResponseError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<MonitorError> for ResponseError {
+    fn from(e: MonitorError) -> Self {
+        let code = match e {
+            MonitorError::MonitorDisconnected(_) => MONITOR_DISCONNECTED,
+            _ => MONITOR_ERROR,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+/// Monitor selection shared by every per-monitor method; no device means the primary monitor
+#[derive(Deserialize)]
+struct TargetParams {
+    device: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LevelParams {
+    device: Option<String>,
+    value: u32,
+}
+
+#[derive(Deserialize)]
+struct GetVcpParams {
+    device: Option<String>,
+    code: u8,
+}
+
+#[derive(Deserialize)]
+struct SetVcpParams {
+    device: Option<String>,
+    code: u8,
+    value: u32,
+}
+
+/// Listed monitor, as in `list --json`
+#[derive(Serialize)]
+struct ListedMonitor {
+    #[serde(flatten)]
+    info: MonitorInfo,
+    stable_id: String,
+    /// A request to the monitor found it unplugged since the last refresh
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disconnected: bool,
+}
+
+/// Monitor handles kept open across requests, so each request skips enumeration.
+///
+/// Handles go stale when monitors are plugged or unplugged; clients call `refresh` to
+/// re-enumerate. Requests to an unplugged monitor fail with [`MONITOR_DISCONNECTED`] until then.
+pub struct Session {
+    registry: MonitorRegistry,
+    read_only: bool,
+    exact: bool,
+}
+
+impl Session {
+    /// Open every monitor. With `read_only`, methods that change monitor settings are refused.
+    /// With `exact`, `device` must be a device name, friendly name or stable ID exactly.
+    pub fn new(read_only: bool, exact: bool) -> Result<Self> {
+        Ok(Self::with_registry(
+            MonitorRegistry::new()?,
+            read_only,
+            exact,
+        ))
+    }
+
+    fn with_registry(registry: MonitorRegistry, read_only: bool, exact: bool) -> Self {
+        Self {
+            registry,
+            read_only,
+            exact,
+        }
+    }
+
+    /// Answer one raw request message with a serialized [`Response`]
+    pub fn handle_message(&self, message: &[u8]) -> Vec<u8> {
+        let response = match serde_json::from_slice::<Request>(message) {
+            Ok(request) => self.dispatch(&request),
+            Err(e) => Response {
+                id: Value::Null,
+                result: None,
+                error: Some(ResponseError::new(PARSE_ERROR, e.to_string())),
+            },
+        };
+        // Responses only hold strings, numbers and already-serialized values
+        serde_json::to_vec(&response).unwrap_or_default()
+    }
+
+    pub fn dispatch(&self, request: &Request) -> Response {
+        let (result, error) = match self.call(&request.method, &request.params) {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Response {
+            id: request.id.clone(),
+            result,
+            error,
+        }
+    }
+
+    fn call(&self, method: &str, params: &Value) -> std::result::Result<Value, ResponseError> {
+        match method {
+            "list" => to_value(self.list()),
+            "refresh" => {
+                self.registry.refresh()?;
+                to_value(self.list())
+            }
+            "get_brightness" => {
+                let params: TargetParams = parse_params(params)?;
+                to_value(self.with_target(params.device, |mon| mon.get_brightness())?)
+            }
+            "set_brightness" => {
+                self.check_writable(method)?;
+                let params: LevelParams = parse_params(params)?;
+                self.with_target(params.device, |mon| mon.set_brightness(params.value))?;
+                Ok(Value::Null)
+            }
+            "get_contrast" => {
+                let params: TargetParams = parse_params(params)?;
+                to_value(self.with_target(params.device, |mon| mon.get_contrast())?)
+            }
+            "set_contrast" => {
+                self.check_writable(method)?;
+                let params: LevelParams = parse_params(params)?;
+                self.with_target(params.device, |mon| mon.set_contrast(params.value))?;
+                Ok(Value::Null)
+            }
+            "get_vcp" => {
+                let params: GetVcpParams = parse_params(params)?;
+                to_value(self.with_target(params.device, |mon| {
+                    vcp::VcpMonitor::new(mon.handle()).get_vcp_feature(params.code)
+                })?)
+            }
+            "set_vcp" => {
+                self.check_writable(method)?;
+                let params: SetVcpParams = parse_params(params)?;
+                self.with_target(params.device, |mon| {
+                    vcp::VcpMonitor::new(mon.handle()).set_vcp_feature(params.code, params.value)
+                })?;
+                Ok(Value::Null)
+            }
+            "capabilities" => {
+                let params: TargetParams = parse_params(params)?;
+                to_value(self.with_target(params.device, |mon| {
+                    vcp::VcpMonitor::new(mon.handle()).get_capabilities()
+                })?)
+            }
+            _ => Err(ResponseError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{}'", method),
+            )),
+        }
+    }
+
+    fn list(&self) -> Vec<ListedMonitor> {
+        self.registry
+            .all()
+            .into_iter()
+            .map(|info| ListedMonitor {
+                stable_id: info.stable_id(),
+                disconnected: self.registry.is_disconnected(&info),
+                info,
+            })
+            .collect()
+    }
+
+    fn with_target<T>(
+        &self,
+        device: Option<String>,
+        f: impl FnOnce(&crate::monitor::PhysicalMonitor) -> Result<T>,
+    ) -> Result<T> {
+        let selector = match device {
+            Some(device) if self.exact => MonitorSelector::ExactDevice(device),
+            Some(device) => MonitorSelector::Device(device),
+            None => MonitorSelector::Primary,
+        };
+        self.registry.with_monitor(&selector, f)
+    }
+
+    fn check_writable(&self, method: &str) -> Result<()> {
+        if self.read_only {
+            return Err(MonitorError::ReadOnlyMode(method.to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: &Value) -> std::result::Result<T, ResponseError> {
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        params => params.clone(),
+    };
+    serde_json::from_value(params).map_err(|e| ResponseError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value(value: impl Serialize) -> std::result::Result<Value, ResponseError> {
+    serde_json::to_value(value).map_err(|e| MonitorError::from(e).into())
+}
+
+/// Answer requests on the named pipe `pipe_name` until the process is stopped. Clients are
+/// served one at a time; a client that misbehaves is disconnected without stopping the server.
+pub fn serve(pipe_name: &str, session: &Session, silent: bool) -> Result<()> {
+    let pipe = NamedPipe::create(pipe_name)?;
+    if !silent {
+        eprintln!("Listening on {}", pipe_name);
+    }
+
+    loop {
+        pipe.connect()?;
+        if let Err(e) = serve_client(&pipe, session)
+            && !silent
+        {
+            eprintln!("Client error: {}", e);
+        }
+        pipe.disconnect()?;
+    }
+}
+
+fn serve_client(pipe: &NamedPipe, session: &Session) -> Result<()> {
+    while let Some(message) = pipe.read_message()? {
+        pipe.write_message(&session.handle_message(&message))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::FakeDisplays;
+    use serde_json::json;
+
+    fn session(displays: &FakeDisplays, read_only: bool) -> Session {
+        let registry = MonitorRegistry::with_source(displays.clone()).unwrap();
+        Session::with_registry(registry, read_only, false)
+    }
+
+    /// Send `request` through the message layer and parse the reply
+    fn send(session: &Session, request: Value) -> Value {
+        let reply = session.handle_message(request.to_string().as_bytes());
+        serde_json::from_slice(&reply).unwrap()
+    }
+
+    #[test]
+    fn malformed_messages_are_parse_errors() {
+        let session = session(&FakeDisplays::new(&["Dell U2723QE"]), false);
+        for message in [&b"not json"[..], br#"{"id": 1}"#] {
+            let reply: Value = serde_json::from_slice(&session.handle_message(message)).unwrap();
+            assert_eq!(reply["id"], Value::Null);
+            assert_eq!(reply["error"]["code"], PARSE_ERROR);
+            assert!(reply.get("result").is_none());
+        }
+    }
+
+    #[test]
+    fn unknown_methods_are_reported_with_the_request_id() {
+        let session = session(&FakeDisplays::new(&["Dell U2723QE"]), false);
+        let reply = send(&session, json!({"id": "a7", "method": "get_sharpness"}));
+        assert_eq!(reply["id"], "a7");
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(reply["error"]["message"], "unknown method 'get_sharpness'");
+    }
+
+    #[test]
+    fn list_and_refresh_report_the_monitors() {
+        let displays = FakeDisplays::new(&["Dell U2723QE", "LG 27GL850"]);
+        let session = session(&displays, false);
+
+        let reply = send(&session, json!({"id": 1, "method": "list"}));
+        let listed = reply["result"].as_array().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1]["friendly_name"], "LG 27GL850");
+        assert_eq!(listed[1]["stable_id"], "device:\\\\.\\DISPLAY2");
+        assert!(listed[1].get("disconnected").is_none());
+
+        displays.connect(&["BenQ PD2700U"]);
+        let reply = send(&session, json!({"id": 2, "method": "refresh"}));
+        assert_eq!(reply["result"][0]["friendly_name"], "BenQ PD2700U");
+        assert_eq!(reply["result"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn params_that_dont_fit_the_method_are_invalid() {
+        let session = session(&FakeDisplays::new(&["Dell U2723QE"]), false);
+        for request in [
+            json!({"id": 1, "method": "get_vcp", "params": {"device": "Dell U2723QE"}}),
+            json!({"id": 2, "method": "get_vcp", "params": {"code": 300}}),
+            json!({"id": 3, "method": "set_brightness", "params": {"value": "high"}}),
+        ] {
+            let reply = send(&session, request.clone());
+            assert_eq!(reply["error"]["code"], INVALID_PARAMS, "{}", request);
+        }
+    }
+
+    #[test]
+    fn read_only_sessions_refuse_writes_before_touching_a_monitor() {
+        let session = session(&FakeDisplays::new(&["Dell U2723QE"]), true);
+        for method in ["set_brightness", "set_contrast", "set_vcp"] {
+            let reply = send(
+                &session,
+                json!({"id": 1, "method": method, "params": {"device": "nowhere", "value": 1}}),
+            );
+            assert_eq!(reply["error"]["code"], MONITOR_ERROR);
+            let message = reply["error"]["message"].as_str().unwrap();
+            assert!(message.contains(method), "{}", message);
+            assert!(!message.contains("nowhere"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn requests_for_missing_monitors_fail() {
+        let session = session(&FakeDisplays::new(&["Dell U2723QE"]), false);
+        let reply = send(
+            &session,
+            json!({"id": 1, "method": "get_brightness", "params": {"device": "BenQ"}}),
+        );
+        assert_eq!(reply["error"]["code"], MONITOR_ERROR);
+
+        // Without a device the primary monitor is used, and none of the fakes is primary
+        let reply = send(&session, json!({"id": 2, "method": "get_brightness"}));
+        assert_eq!(reply["error"]["code"], MONITOR_ERROR);
+        assert!(
+            reply["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Primary monitor")
+        );
+    }
+
+    #[test]
+    fn disconnects_have_their_own_error_code() {
+        let error = ResponseError::from(MonitorError::MonitorDisconnected("Dell".to_string()));
+        assert_eq!(error.code, MONITOR_DISCONNECTED);
+        let error = ResponseError::from(MonitorError::VcpNotSupported);
+        assert_eq!(error.code, MONITOR_ERROR);
+    }
+
+    #[test]
+    fn exact_sessions_match_device_names_only_exactly() {
+        let displays = FakeDisplays::new(&["Dell U2723QE", "LG 27GL850"]);
+        let registry = MonitorRegistry::with_source(displays.clone()).unwrap();
+        let exact = Session::with_registry(registry, false, true);
+        let loose = session(&displays, false);
+        let target = |session: &Session, device: &str| {
+            session.with_target(Some(device.to_string()), |mon| {
+                Ok(mon.info().device_name.clone())
+            })
+        };
+
+        assert_eq!(target(&loose, "lg").unwrap(), "\\\\.\\DISPLAY2");
+        assert!(matches!(
+            target(&exact, "lg"),
+            Err(MonitorError::MonitorNotFound(_))
+        ));
+        assert!(target(&exact, "\\\\.\\display2").is_err());
+        assert_eq!(target(&exact, "LG 27GL850").unwrap(), "\\\\.\\DISPLAY2");
+        assert_eq!(
+            target(&exact, "device:\\\\.\\DISPLAY1").unwrap(),
+            "\\\\.\\DISPLAY1"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+/// Spaces between two columns
+const COLUMN_GAP: usize = 2;
+
+/// Plain-text table whose columns are as wide as their widest cell, so long friendly names or
+/// descriptions don't push later columns out of line.
+///
+/// Widths are counted in characters. The last column is not padded, and a separator line is
+/// drawn under the headers.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    right_aligned: Vec<usize>,
+}
+
+impl Table {
+    pub fn new<I, S>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Right-align `column`, e.g. for numbers
+    pub fn align_right(mut self, column: usize) -> Self {
+        self.right_aligned.push(column);
+        self
+    }
+
+    /// Append a row; missing trailing cells are left blank
+    pub fn add_row<I, S>(&mut self, cells: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.rows
+            .push(cells.into_iter().map(|cell| cell.to_string()).collect());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Width of each column: the character count of its longest cell, header included
+    pub fn column_widths(&self) -> Vec<usize> {
+        let columns = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain([self.headers.len()])
+            .max()
+            .unwrap_or(0);
+
+        (0..columns)
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .chain([&self.headers])
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// The rows as they appear in the table, without the header and separator, for output that
+    /// puts other lines between them
+    pub fn row_lines(&self) -> Vec<String> {
+        let widths = self.column_widths();
+        self.rows
+            .iter()
+            .map(|row| self.format_row(row, &widths))
+            .collect()
+    }
+
+    fn write_row(&self, f: &mut fmt::Formatter, cells: &[String], widths: &[usize]) -> fmt::Result {
+        writeln!(f, "{}", self.format_row(cells, widths))
+    }
+
+    fn format_row(&self, cells: &[String], widths: &[usize]) -> String {
+        let mut line = String::new();
+        for (column, width) in widths.iter().enumerate() {
+            let cell = cells.get(column).map_or("", String::as_str);
+            let last = column + 1 == widths.len();
+            if column > 0 {
+                line.push_str(&" ".repeat(COLUMN_GAP));
+            }
+            if self.right_aligned.contains(&column) {
+                line.push_str(&format!("{:>width$}", cell, width = width));
+            } else if last {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:<width$}", cell, width = width));
+            }
+        }
+        line.trim_end().to_string()
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let widths = self.column_widths();
+        self.write_row(f, &self.headers, &widths)?;
+
+        let total = widths.iter().sum::<usize>() + COLUMN_GAP * widths.len().saturating_sub(1);
+        writeln!(f, "{}", "-".repeat(total))?;
+
+        for row in &self.rows {
+            self.write_row(f, row, &widths)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_entries_are_as_wide_as_the_headers() {
+        let mut table = Table::new(["Device Name", "Brightness"]);
+        table.add_row(["A", "5"]);
+        assert_eq!(table.column_widths(), [11, 10]);
+        assert_eq!(
+            table.to_string(),
+            "Device Name  Brightness\n-----------------------\nA            5\n"
+        );
+    }
+
+    #[test]
+    fn long_entries_widen_their_column() {
+        let mut table = Table::new(["Name", "Max"]).align_right(1);
+        table.add_row(["Generic PnP Monitor (DELL U2723QE, 27 inch)", "100"]);
+        table.add_row(["LG", "5"]);
+        assert_eq!(table.column_widths(), [43, 3]);
+
+        let text = table.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[2], "Generic PnP Monitor (DELL U2723QE, 27 inch)  100");
+        assert_eq!(lines[3], format!("LG{}    5", " ".repeat(41)));
+        assert_eq!(lines[2].len(), lines[3].len());
+    }
+
+    #[test]
+    fn widths_count_characters_and_missing_cells() {
+        let mut table = Table::new(["Input"]);
+        table.add_row(["Entrée HDMI", "extra"]);
+        table.add_row(Vec::<String>::new());
+        assert_eq!(table.column_widths(), [11, 5]);
+        assert_eq!(table.row_lines(), ["Entrée HDMI  extra", ""]);
+    }
+
+    #[test]
+    fn row_lines_line_up_without_the_header() {
+        let mut table = Table::new(["", ""]);
+        table.add_row(["\\\\.\\DISPLAY1", "DELL U2723QE"]);
+        table.add_row(["\\\\.\\DISPLAY10", "LG"]);
+        assert_eq!(
+            table.row_lines(),
+            ["\\\\.\\DISPLAY1   DELL U2723QE", "\\\\.\\DISPLAY10  LG"]
+        );
+    }
+}
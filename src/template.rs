@@ -0,0 +1,134 @@
+use crate::{MonitorError, Result};
+
+enum Segment<'a> {
+    Text(String),
+    Field(&'a str),
+}
+
+/// Split `template` into literal text and `{name}` placeholders; `{{` and `}}` are literal braces
+fn parse(template: &str) -> Result<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        text.push_str(&rest[..i]);
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+
+        if let Some(after) = rest.strip_prefix(brace) {
+            text.push_str(brace);
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            return Err(MonitorError::InvalidValue(format!(
+                "unmatched '}}' in template '{}'",
+                template
+            )));
+        }
+
+        let end = rest.find('}').ok_or_else(|| {
+            MonitorError::InvalidValue(format!("unclosed '{{' in template '{}'", template))
+        })?;
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut text)));
+        }
+        segments.push(Segment::Field(rest[..end].trim()));
+        rest = &rest[end + 1..];
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+fn unknown_field(name: &str, names: impl Iterator<Item = impl AsRef<str>>) -> MonitorError {
+    let names: Vec<_> = names.map(|n| format!("{{{}}}", n.as_ref())).collect();
+    MonitorError::InvalidValue(format!(
+        "unknown template field '{{{}}}' (available: {})",
+        name,
+        names.join(", ")
+    ))
+}
+
+/// Check a template against the field names a command provides, so mistakes are reported before
+/// the monitor is queried
+pub fn validate(template: &str, names: &[&str]) -> Result<()> {
+    for segment in parse(template)? {
+        if let Segment::Field(name) = segment
+            && !names.contains(&name)
+        {
+            return Err(unknown_field(name, names.iter()));
+        }
+    }
+    Ok(())
+}
+
+/// Replace every `{name}` in `template` with the matching value from `fields`
+pub fn render(template: &str, fields: &[(&str, String)]) -> Result<String> {
+    let mut output = String::new();
+    for segment in parse(template)? {
+        match segment {
+            Segment::Text(text) => output.push_str(&text),
+            Segment::Field(name) => {
+                let (_, value) = fields
+                    .iter()
+                    .find(|(field, _)| *field == name)
+                    .ok_or_else(|| unknown_field(name, fields.iter().map(|(field, _)| field)))?;
+                output.push_str(value);
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<(&'static str, String)> {
+        vec![("current", "40".to_string()), ("max", "100".to_string())]
+    }
+
+    #[test]
+    fn render_substitutes_named_fields() {
+        assert_eq!(render("{current}/{max}", &fields()).unwrap(), "40/100");
+        assert_eq!(
+            render("Brightness: { current }%", &fields()).unwrap(),
+            "Brightness: 40%"
+        );
+        assert_eq!(render("no fields", &fields()).unwrap(), "no fields");
+        assert_eq!(render("", &fields()).unwrap(), "");
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(render("{{{current}}}", &fields()).unwrap(), "{40}");
+        assert_eq!(render("}}{{", &fields()).unwrap(), "}{");
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected_with_the_available_ones() {
+        let err = render("{current}/{maximum}", &fields()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            MonitorError::InvalidValue(
+                "unknown template field '{maximum}' (available: {current}, {max})".to_string()
+            )
+            .to_string()
+        );
+        assert!(validate("{current}/{max}", &["current", "max"]).is_ok());
+        assert!(validate("{min}", &["current", "max"]).is_err());
+    }
+
+    #[test]
+    fn unbalanced_braces_are_rejected() {
+        for template in ["{current", "current}", "{current}}"] {
+            let err = render(template, &fields()).unwrap_err();
+            assert!(matches!(err, MonitorError::InvalidValue(_)), "{}", template);
+        }
+    }
+}
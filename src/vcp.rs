@@ -1,5 +1,18 @@
-use crate::{MonitorError, Result};
-use serde::{Deserialize, Serialize};
+use crate::{
+    MonitorError, Result,
+    backend::{Backend, NativeBackend, RetryPolicy},
+    capabilities::{self, Capabilities},
+    decode, monitor,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
 use windows_sys::Win32::Foundation::HANDLE;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,168 +21,1478 @@ pub struct VcpFeatureResponse {
     pub current_value: u32,
     pub maximum_value: u32,
     pub code_type: VcpCodeType,
+    /// Value the monitor reported when it was above the maximum; `current_value` then holds
+    /// the maximum instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported_value: Option<u32>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// Serialized by variant name. Deserialization also accepts the numeric MC_VCP_CODE_TYPE value
+/// (0 or 1), which is what other DDC/CI tools and the PowerShell module emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum VcpCodeType {
     SetParameter = 0,
     Momentary = 1,
 }
 
+impl<'de> Deserialize<'de> for VcpCodeType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct CodeTypeVisitor;
+
+        impl de::Visitor<'_> for CodeTypeVisitor {
+            type Value = VcpCodeType;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("\"SetParameter\", \"Momentary\", 0 or 1")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Self::Value, E> {
+                match v {
+                    "SetParameter" => Ok(VcpCodeType::SetParameter),
+                    "Momentary" => Ok(VcpCodeType::Momentary),
+                    _ => Err(E::unknown_variant(v, &["SetParameter", "Momentary"])),
+                }
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Self::Value, E> {
+                match v {
+                    0 => Ok(VcpCodeType::SetParameter),
+                    1 => Ok(VcpCodeType::Momentary),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Self::Value, E> {
+                match u64::try_from(v) {
+                    Ok(v) => self.visit_u64(v),
+                    Err(_) => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(CodeTypeVisitor)
+    }
+}
+
+/// How a VCP code is meant to be driven
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "values")]
+pub enum CodeClass {
+    /// Any value from 0 up to the reported maximum
+    Continuous,
+    /// One of the values advertised in the capabilities string
+    Discrete(Vec<u8>),
+    /// Momentary code that triggers an action rather than storing a value
+    Action,
+    /// Status or information code that can only be read
+    ReadOnly,
+}
+
+impl CodeClass {
+    /// Whether the code holds a value the user can adjust
+    pub fn is_writable(&self) -> bool {
+        matches!(self, CodeClass::Continuous | CodeClass::Discrete(_))
+    }
+}
+
+impl fmt::Display for CodeClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            CodeClass::Continuous => "Continuous",
+            CodeClass::Discrete(_) => "Discrete",
+            CodeClass::Action => "Action",
+            CodeClass::ReadOnly => "Read-only",
+        })
+    }
+}
+
+/// How long the read of one code took during a scan, whether or not the monitor supports it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ProbeTiming {
+    pub code: u8,
+    pub supported: bool,
+    #[serde(
+        rename = "elapsed_ms",
+        serialize_with = "serialize_millis",
+        deserialize_with = "deserialize_millis"
+    )]
+    pub elapsed: Duration,
+}
+
+fn serialize_millis<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+fn deserialize_millis<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Duration, D::Error> {
+    let millis = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(millis / 1000.0).map_err(de::Error::custom)
+}
+
+/// Number of codes a full scan reads (0x00-0xFF)
+pub const SCAN_CODE_COUNT: usize = 256;
+
+/// Progress of a scan, saved to disk so an interrupted scan of a slow monitor can continue where
+/// it stopped instead of starting over
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanState {
+    /// [Stable ID](crate::monitor::MonitorInfo::stable_id) of the scanned monitor, so a state
+    /// file isn't resumed against another monitor
+    pub monitor: String,
+    /// Next code to probe; 256 once every code has been probed
+    pub next_code: u16,
+    pub features: Vec<VcpFeatureResponse>,
+    pub probes: Vec<ProbeTiming>,
+}
+
+impl ScanState {
+    pub fn new(monitor: impl Into<String>) -> Self {
+        Self {
+            monitor: monitor.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The saved state at `path`, or `None` if no scan has been saved there yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|e| {
+            MonitorError::InvalidValue(format!("scan state {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the state to `path`, replacing it in one step so an interruption mid-write can't
+    /// leave a truncated file behind
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_string(self)?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_code > u16::from(u8::MAX)
+    }
+}
+
+/// A scan saved to a file, in any of the forms `scan-vcp` writes
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedScan {
+    /// `scan-vcp --json`
+    Entries(Vec<ScanEntry>),
+    /// `scan-vcp --json --timings`
+    Timed { entries: Vec<ScanEntry> },
+    /// `scan-vcp --resume` state file
+    State(ScanState),
+}
+
+/// The codes and values of a scan saved with `scan-vcp --json` or `--resume`
+pub fn load_scan(path: &Path) -> Result<Vec<VcpFeatureResponse>> {
+    let contents = fs::read_to_string(path)?;
+    let scan = serde_json::from_str(&contents).map_err(|_| {
+        MonitorError::InvalidValue(format!(
+            "{} is not a scan saved with scan-vcp --json or --resume",
+            path.display()
+        ))
+    })?;
+    Ok(match scan {
+        SavedScan::Entries(entries) | SavedScan::Timed { entries } => {
+            entries.into_iter().map(|entry| entry.response).collect()
+        }
+        SavedScan::State(state) => state.features,
+    })
+}
+
+/// Supported codes found by a scan, together with the parsed capabilities if the monitor
+/// provided a usable capabilities string
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    pub features: Vec<VcpFeatureResponse>,
+    pub capabilities: Option<Capabilities>,
+    /// One entry per probed code, in probe order
+    pub probes: Vec<ProbeTiming>,
+}
+
+impl ScanReport {
+    /// Time spent reading codes, excluding the capabilities request
+    pub fn probe_time(&self) -> Duration {
+        self.probes.iter().map(|probe| probe.elapsed).sum()
+    }
+
+    /// The `count` slowest probes, slowest first
+    pub fn slowest_probes(&self, count: usize) -> Vec<ProbeTiming> {
+        let mut probes = self.probes.clone();
+        probes.sort_by_key(|probe| std::cmp::Reverse(probe.elapsed));
+        probes.truncate(count);
+        probes
+    }
+
+    pub fn entries(&self) -> Vec<ScanEntry> {
+        self.features
+            .iter()
+            .map(|response| ScanEntry {
+                response: response.clone(),
+                class: classify_code(self, response.vcp_code),
+                decoded: decode::decode_value(response.vcp_code, response.current_value),
+            })
+            .collect()
+    }
+}
+
+/// One scanned code as shown in `scan-vcp` output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanEntry {
+    #[serde(flatten)]
+    pub response: VcpFeatureResponse,
+    pub class: CodeClass,
+    /// Interpretation of the current value, for codes with a known decoder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<String>,
+}
+
+/// Classify a code using the reply type from the scan and the value list from the capabilities.
+/// Codes missing from the scan are classified from the capabilities alone.
+pub fn classify_code(report: &ScanReport, code: u8) -> CodeClass {
+    if READ_ONLY_CODES.contains(&code) {
+        return CodeClass::ReadOnly;
+    }
+
+    let momentary = report
+        .features
+        .iter()
+        .any(|f| f.vcp_code == code && matches!(f.code_type, VcpCodeType::Momentary));
+    if momentary {
+        return CodeClass::Action;
+    }
+
+    match report
+        .capabilities
+        .as_ref()
+        .and_then(|caps| caps.vcp_code(code))
+    {
+        Some(cap) if !cap.values.is_empty() => CodeClass::Discrete(cap.values.clone()),
+        _ => CodeClass::Continuous,
+    }
+}
+
 // Common VCP codes
 pub mod codes {
+    pub const RESTORE_LUMINANCE_CONTRAST: u8 = 0x05;
     pub const BRIGHTNESS: u8 = 0x10;
     pub const CONTRAST: u8 = 0x12;
+    pub const USER_COLOR_TEMPERATURE_INCREMENT: u8 = 0x0B;
+    pub const USER_COLOR_TEMPERATURE: u8 = 0x0C;
     pub const COLOR_TEMPERATURE: u8 = 0x14;
     pub const RED_GAIN: u8 = 0x16;
     pub const GREEN_GAIN: u8 = 0x18;
     pub const BLUE_GAIN: u8 = 0x1A;
     pub const POWER_MODE: u8 = 0xD6;
+    pub const PERFORMANCE_PRESERVATION: u8 = 0x54;
     pub const INPUT_SOURCE: u8 = 0x60;
     pub const AUDIO_VOLUME: u8 = 0x62;
+    pub const SPEAKER_SELECT: u8 = 0x63;
+    pub const AMBIENT_LIGHT_SENSOR: u8 = 0x66;
+    pub const BACKLIGHT_WHITE: u8 = 0x6B;
     pub const AUDIO_MUTE: u8 = 0x8D;
+    /// First of the six consecutive six-axis saturation codes (0x59-0x5E)
+    pub const SIX_AXIS_SATURATION: u8 = 0x59;
+    /// First of the six consecutive six-axis hue codes (0x9B-0xA0)
+    pub const SIX_AXIS_HUE: u8 = 0x9B;
+    pub const SUBPIXEL_LAYOUT: u8 = 0xB2;
+    pub const MONITOR_STATUS: u8 = 0xB7;
+    pub const LINK_CONTROL: u8 = 0xBE;
+    pub const USAGE_TIME: u8 = 0xC0;
+    pub const OSD: u8 = 0xCA;
+    pub const DISPLAY_DESCRIPTOR_LENGTH: u8 = 0xC2;
+    pub const DISPLAY_DESCRIPTOR: u8 = 0xC3;
+    pub const DISPLAY_DESCRIPTOR_ENABLE: u8 = 0xC4;
+    pub const AUX_DISPLAY_SIZE: u8 = 0xCE;
+    pub const AUX_DISPLAY_DATA: u8 = 0xCF;
+    pub const OUTPUT_SELECT: u8 = 0xD0;
+    pub const ASSET_TAG: u8 = 0xD2;
+    pub const SCAN_MODE: u8 = 0xDA;
+    pub const VCP_VERSION: u8 = 0xDF;
 }
 
+/// One axis of six-axis color, in the order MCCS assigns its codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorAxis {
+    Red,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Magenta,
+}
+
+impl ColorAxis {
+    pub const ALL: [ColorAxis; 6] = [
+        ColorAxis::Red,
+        ColorAxis::Yellow,
+        ColorAxis::Green,
+        ColorAxis::Cyan,
+        ColorAxis::Blue,
+        ColorAxis::Magenta,
+    ];
+
+    /// Code of this axis in a control whose red axis is `base`
+    pub fn code(self, base: u8) -> u8 {
+        base + self as u8
+    }
+}
+
+impl fmt::Display for ColorAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorAxis::Red => "Red",
+            ColorAxis::Yellow => "Yellow",
+            ColorAxis::Green => "Green",
+            ColorAxis::Cyan => "Cyan",
+            ColorAxis::Blue => "Blue",
+            ColorAxis::Magenta => "Magenta",
+        };
+        f.pad(name)
+    }
+}
+
+/// Values of a six-axis saturation or hue control. `None` marks an axis the monitor didn't
+/// answer when reading, or one to leave alone when writing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SixAxisValues {
+    pub red: Option<u32>,
+    pub yellow: Option<u32>,
+    pub green: Option<u32>,
+    pub cyan: Option<u32>,
+    pub blue: Option<u32>,
+    pub magenta: Option<u32>,
+}
+
+impl SixAxisValues {
+    pub fn get(&self, axis: ColorAxis) -> Option<u32> {
+        match axis {
+            ColorAxis::Red => self.red,
+            ColorAxis::Yellow => self.yellow,
+            ColorAxis::Green => self.green,
+            ColorAxis::Cyan => self.cyan,
+            ColorAxis::Blue => self.blue,
+            ColorAxis::Magenta => self.magenta,
+        }
+    }
+
+    pub fn set(&mut self, axis: ColorAxis, value: Option<u32>) {
+        let slot = match axis {
+            ColorAxis::Red => &mut self.red,
+            ColorAxis::Yellow => &mut self.yellow,
+            ColorAxis::Green => &mut self.green,
+            ColorAxis::Cyan => &mut self.cyan,
+            ColorAxis::Blue => &mut self.blue,
+            ColorAxis::Magenta => &mut self.magenta,
+        };
+        *slot = value;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        ColorAxis::ALL.iter().all(|&axis| self.get(axis).is_none())
+    }
+}
+
+/// Outcome for one axis of a grouped six-axis write
+#[derive(Debug, Clone, Serialize)]
+pub struct AxisWrite {
+    pub axis: ColorAxis,
+    pub requested: u32,
+    /// Value written after clamping to the axis maximum; `None` if the axis failed
+    pub written: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AxisWrite {
+    pub fn clamped(&self) -> bool {
+        self.written
+            .is_some_and(|written| written != self.requested)
+    }
+}
+
+/// Continuous level controls, whose current value should never be above the reported maximum
+pub const LEVEL_CODES: &[u8] = &[
+    0x10, // Brightness
+    0x12, // Contrast
+    0x16, // Video Gain: Red
+    0x18, // Video Gain: Green
+    0x1A, // Video Gain: Blue
+    0x59, 0x5A, 0x5B, 0x5C, 0x5D, 0x5E, // Six-axis saturation
+    0x62, // Audio Speaker Volume
+    0x6C, // Video Black Level: Red
+    0x6E, // Video Black Level: Green
+    0x70, // Video Black Level: Blue
+    0x87, // Sharpness
+    0x8A, // Color Saturation
+    0x9B, 0x9C, 0x9D, 0x9E, 0x9F, 0xA0, // Six-axis hue
+];
+
+/// `current` limited to `maximum`, along with `current` itself if it had to be clamped. Some
+/// firmware reports a level above its own maximum, which would put percentages over 100%; the
+/// caller decides whether to tell the user.
+pub fn clamp_reported_level(current: u32, maximum: u32) -> (u32, Option<u32>) {
+    if current <= maximum {
+        (current, None)
+    } else {
+        (maximum, Some(current))
+    }
+}
+
+/// Codes `scan-vcp --reconcile` reads besides the advertised ones: controls and status codes most
+/// monitors implement, so a capabilities string that leaves them out is caught
+pub const COMMON_CODES: &[u8] = &[
+    0x10, // Brightness
+    0x12, // Contrast
+    0x14, // Select Color Preset
+    0x16, // Video Gain: Red
+    0x18, // Video Gain: Green
+    0x1A, // Video Gain: Blue
+    0x60, // Input Source
+    0x62, // Audio Speaker Volume
+    0x8D, // Audio Mute
+    0xAC, // Horizontal Frequency
+    0xAE, // Vertical Frequency
+    0xB6, // Display Technology Type
+    0xC0, // Display Usage Time
+    0xC8, // Display Controller Type
+    0xC9, // Display Firmware Level
+    0xCA, // OSD / Button Control
+    0xD6, // Power Mode
+    0xDF, // VCP Version
+];
+
+/// How the codes a capabilities string advertises compare with the codes the monitor answers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Reconciliation {
+    /// Advertised codes the monitor answered
+    pub confirmed: Vec<u8>,
+    /// Advertised codes the monitor didn't answer
+    pub advertised_unresponsive: Vec<u8>,
+    /// Codes the monitor answered without advertising them
+    pub responsive_unadvertised: Vec<u8>,
+}
+
+impl Reconciliation {
+    /// Sort the `answered` codes, which may include codes that weren't advertised, against the
+    /// `advertised` ones. Each list comes out in code order without duplicates.
+    pub fn new(advertised: &[u8], answered: &[u8]) -> Self {
+        let advertised: BTreeSet<u8> = advertised.iter().copied().collect();
+        let answered: BTreeSet<u8> = answered.iter().copied().collect();
+        Self {
+            confirmed: advertised.intersection(&answered).copied().collect(),
+            advertised_unresponsive: advertised.difference(&answered).copied().collect(),
+            responsive_unadvertised: answered.difference(&advertised).copied().collect(),
+        }
+    }
+
+    /// Whether the capabilities string and the monitor agree on every probed code
+    pub fn is_consistent(&self) -> bool {
+        self.advertised_unresponsive.is_empty() && self.responsive_unadvertised.is_empty()
+    }
+}
+
+/// Codes that MCCS defines as read-only status or information
+pub const READ_ONLY_CODES: &[u8] = &[
+    0x0B, // User Color Temperature Increment
+    0x52, // Active Control
+    0x73, // LUT Size
+    0xAC, // Horizontal Frequency
+    0xAE, // Vertical Frequency
+    0xB2, // Flat Panel Sub-Pixel Layout
+    0xB6, // Display Technology Type
+    0xB7, // Monitor Status
+    0xC0, // Display Usage Time
+    0xC2, // Display Descriptor Length
+    0xC6, // Application Enable Key
+    0xC8, // Display Controller Type
+    0xC9, // Display Firmware Level
+    0xCE, // Auxiliary Display Size
+    0xDF, // VCP Version
+];
+
 pub struct VcpMonitor {
-    handle: HANDLE,
+    backend: Box<dyn Backend>,
+    settle_delay: Duration,
+    cache: VcpCache,
+}
+
+/// Maximum values already read from a monitor. A monitor's maximum for a code doesn't change
+/// while it stays connected, so each [`VcpMonitor`] remembers them instead of re-reading.
+///
+/// Configured overrides take the place of the reported maximum and are known without a read.
+#[derive(Debug, Default)]
+pub struct VcpCache {
+    maximums: RefCell<BTreeMap<u8, u32>>,
+    overrides: BTreeMap<u8, u32>,
+}
+
+impl VcpCache {
+    /// The maximum for `code`: its override, else the remembered one if it has been read
+    pub fn maximum(&self, code: u8) -> Option<u32> {
+        self.overrides
+            .get(&code)
+            .copied()
+            .or_else(|| self.maximums.borrow().get(&code).copied())
+    }
+
+    fn record(&self, response: &VcpFeatureResponse) {
+        self.maximums
+            .borrow_mut()
+            .insert(response.vcp_code, response.maximum_value);
+    }
 }
 
 impl VcpMonitor {
     pub fn new(handle: HANDLE) -> Self {
-        Self { handle }
+        Self::with_retry(handle, RetryPolicy::default())
+    }
+
+    /// Like [`Self::new`], retrying calls that fail on a busy DDC/CI channel as `retry` says
+    pub fn with_retry(handle: HANDLE, retry: RetryPolicy) -> Self {
+        Self {
+            backend: Box::new(NativeBackend::new(handle, retry)),
+            settle_delay: Duration::ZERO,
+            cache: VcpCache::default(),
+        }
+    }
+
+    /// Drive a monitor through any [`Backend`], e.g. a [`MockBackend`](crate::MockBackend) in
+    /// tests
+    #[cfg(any(test, feature = "testing"))]
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            settle_delay: Duration::ZERO,
+            cache: VcpCache::default(),
+        }
+    }
+
+    /// Wait this long after a write before reading it back in [`Self::set_and_verify`]; slow
+    /// monitors report the old value for a while
+    pub fn with_settle_delay(mut self, delay: Duration) -> Self {
+        self.settle_delay = delay;
+        self
+    }
+
+    /// Use these maximums, keyed by VCP code, in place of what the monitor reports. For firmware
+    /// that reports e.g. 0 or 255 for a 0-100 control; levels are then clamped and scaled
+    /// against the override.
+    pub fn with_max_overrides(mut self, overrides: BTreeMap<u8, u32>) -> Self {
+        self.cache.overrides = overrides;
+        self
     }
 
     pub fn get_vcp_feature(&self, vcp_code: u8) -> Result<VcpFeatureResponse> {
-        unsafe {
-            let mut code_type = 0u32;
-            let mut current_value = 0u32;
-            let mut maximum_value = 0u32;
-
-            let result = crate::native::dxva2::GetVCPFeatureAndVCPFeatureReply(
-                self.handle,
-                vcp_code,
-                &mut code_type,
-                &mut current_value,
-                &mut maximum_value,
-            );
+        let mut response = self.backend.get_vcp_feature(vcp_code)?;
+        if let Some(&maximum) = self.cache.overrides.get(&vcp_code) {
+            response.maximum_value = maximum;
+        }
+        if LEVEL_CODES.contains(&vcp_code) {
+            (response.current_value, response.reported_value) =
+                clamp_reported_level(response.current_value, response.maximum_value);
+        }
+        self.cache.record(&response);
+        Ok(response)
+    }
+
+    /// Maximum value for `vcp_code`, read from the monitor only the first time it is needed.
+    /// Any earlier [`Self::get_vcp_feature`] of the code also fills the cache.
+    pub fn max_for_code(&self, vcp_code: u8) -> Result<u32> {
+        match self.cache.maximum(vcp_code) {
+            Some(maximum) => Ok(maximum),
+            None => Ok(self.get_vcp_feature(vcp_code)?.maximum_value),
+        }
+    }
+
+    /// Maximums read so far
+    pub fn cache(&self) -> &VcpCache {
+        &self.cache
+    }
 
-            if result == 0 {
-                return Err(MonitorError::VcpNotSupported);
+    pub fn set_vcp_feature(&self, vcp_code: u8, value: u32) -> Result<()> {
+        self.backend.set_vcp_feature(vcp_code, value)
+    }
+
+    /// Write `value` and read it back. Returns whether the monitor now reports the written value;
+    /// many monitors acknowledge writes to features they don't actually implement.
+    pub fn set_and_verify(&self, vcp_code: u8, value: u32) -> Result<bool> {
+        self.set_vcp_feature(vcp_code, value)?;
+        if !self.settle_delay.is_zero() {
+            thread::sleep(self.settle_delay);
+        }
+        let response = self.get_vcp_feature(vcp_code)?;
+        Ok(response.current_value == value)
+    }
+
+    /// Write `value`, wait `settle`, and read it back, repeating until the monitor reports a value
+    /// within `tolerance` of it or `max_attempts` writes (at least one) have been made. For
+    /// monitors where a single write doesn't stick.
+    ///
+    /// Returns the value the monitor reported last; if the attempts ran out, it is outside the
+    /// tolerance.
+    pub fn set_vcp_until_stable(
+        &self,
+        vcp_code: u8,
+        value: u32,
+        max_attempts: u32,
+        settle: Duration,
+        tolerance: u32,
+    ) -> Result<u32> {
+        let mut reported = 0;
+        for _ in 0..max_attempts.max(1) {
+            self.set_vcp_feature(vcp_code, value)?;
+            thread::sleep(settle);
+            reported = self.get_vcp_feature(vcp_code)?.current_value;
+            if monitor::within_tolerance(reported, value, tolerance) {
+                break;
             }
+        }
+        Ok(reported)
+    }
 
-            Ok(VcpFeatureResponse {
-                vcp_code,
-                current_value,
-                maximum_value,
-                code_type: if code_type == 0 {
-                    VcpCodeType::SetParameter
-                } else {
-                    VcpCodeType::Momentary
-                },
-            })
+    /// Turn the ambient-light-sensor auto brightness (VCP 0x66) on or off and report whether the
+    /// monitor applied it
+    pub fn set_auto_brightness(&self, enabled: bool) -> Result<bool> {
+        let value = if enabled { 0x02 } else { 0x01 };
+        self.set_and_verify(codes::AMBIENT_LIGHT_SENSOR, value)
+    }
+
+    /// Read the active input (VCP 0x60)
+    pub fn get_input_source(&self) -> Result<decode::InputSource> {
+        let response = self.get_vcp_feature(codes::INPUT_SOURCE).map_err(|e| {
+            unsupported_as(e, "monitor does not report its input source (VCP 0x60)")
+        })?;
+        Ok(decode::InputSource::from_value(response.current_value))
+    }
+
+    /// Poll the input source (VCP 0x60) every `interval` until it is `input`, e.g. while a KVM
+    /// switch changes over. Failed reads are retried, since many monitors don't answer during
+    /// the switch, but a monitor that is disconnected or doesn't report its input fails at once.
+    /// Fails with [`MonitorError::Timeout`] after `timeout`.
+    pub fn wait_for_input(
+        &self,
+        input: decode::InputSource,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<decode::InputSource> {
+        let deadline = Instant::now() + timeout;
+        let mut last = None;
+
+        loop {
+            match self.get_input_source() {
+                Ok(current) if current.value() == input.value() => return Ok(current),
+                Ok(current) => last = Some(current),
+                Err(e)
+                    if e.is_disconnect() || matches!(e, MonitorError::UnsupportedOperation(_)) =>
+                {
+                    return Err(e);
+                }
+                Err(_) => {}
+            }
+
+            if Instant::now() + interval > deadline {
+                let last = last.map_or("no reading".to_string(), |v| format!("last reading {}", v));
+                return Err(MonitorError::Timeout(format!(
+                    "input did not switch to {} within {:.1}s ({})",
+                    input,
+                    timeout.as_secs_f64(),
+                    last
+                )));
+            }
+            thread::sleep(interval);
         }
     }
 
-    pub fn set_vcp_feature(&self, vcp_code: u8, value: u32) -> Result<()> {
-        unsafe {
-            let result = crate::native::dxva2::SetVCPFeature(self.handle, vcp_code, value);
+    /// Read which output a display with several outputs is driving (VCP 0xD0). Output values use
+    /// the same table as inputs.
+    pub fn get_output_select(&self) -> Result<decode::InputSource> {
+        let response = self.get_vcp_feature(codes::OUTPUT_SELECT)?;
+        Ok(decode::InputSource::from_value(response.current_value))
+    }
+
+    /// Select the output to drive (VCP 0xD0). Few displays have more than one output, so support
+    /// is checked with a read first rather than sending a write the display would ignore.
+    pub fn set_output_select(&self, output: decode::InputSource) -> Result<()> {
+        self.get_output_select()?;
+        self.set_vcp_feature(codes::OUTPUT_SELECT, u32::from(output.value()))
+    }
 
-            if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "SetVCPFeature failed".to_string(),
-                ));
+    /// Read the scan mode (VCP 0xDA)
+    pub fn get_scan_mode(&self) -> Result<decode::ScanMode> {
+        let response = self.get_vcp_feature(codes::SCAN_MODE).map_err(|e| {
+            unsupported_as(e, "monitor does not support scan mode control (VCP 0xDA)")
+        })?;
+        Ok(decode::ScanMode::from_value(response.current_value))
+    }
+
+    /// Set the scan mode (VCP 0xDA)
+    pub fn set_scan_mode(&self, mode: decode::ScanMode) -> Result<()> {
+        self.set_vcp_feature(codes::SCAN_MODE, u32::from(mode.value()))
+    }
+
+    /// Read the custom color temperature: the increment (VCP 0x0B) and multiplier (VCP 0x0C)
+    pub fn get_user_color_temperature(&self) -> Result<decode::UserColorTemperature> {
+        let unsupported = |e| {
+            unsupported_as(
+                e,
+                "monitor does not support a user color temperature (VCP 0x0B/0x0C)",
+            )
+        };
+        let increment = self
+            .get_vcp_feature(codes::USER_COLOR_TEMPERATURE_INCREMENT)
+            .map_err(unsupported)?;
+        let multiplier = self
+            .get_vcp_feature(codes::USER_COLOR_TEMPERATURE)
+            .map_err(unsupported)?;
+        Ok(decode::UserColorTemperature {
+            increment: increment.current_value,
+            multiplier: multiplier.current_value,
+            maximum: multiplier.maximum_value,
+        })
+    }
+
+    /// Set the custom color temperature to the step nearest `kelvin`, see
+    /// [`decode::UserColorTemperature::multiplier_for`]. Returns the temperature as set.
+    pub fn set_user_color_temperature(&self, kelvin: u32) -> Result<decode::UserColorTemperature> {
+        let current = self.get_user_color_temperature()?;
+        let multiplier = current.multiplier_for(kelvin)?;
+        self.set_vcp_feature(codes::USER_COLOR_TEMPERATURE, multiplier)?;
+        Ok(decode::UserColorTemperature {
+            multiplier,
+            ..current
+        })
+    }
+
+    /// Set the red, green and blue gains (VCP 0x16, 0x18, 0x1A). Every gain is checked against
+    /// its channel's maximum before any is written, so a bad value leaves all three unchanged.
+    pub fn set_rgb_gains(&self, gains: [u32; 3]) -> Result<()> {
+        let codes = [codes::RED_GAIN, codes::GREEN_GAIN, codes::BLUE_GAIN];
+        for (code, gain) in codes.iter().zip(gains) {
+            let maximum = self.max_for_code(*code)?;
+            if gain > maximum {
+                return Err(MonitorError::InvalidValue(format!(
+                    "{} {} is above the maximum of {}",
+                    get_vcp_code_info(*code).map_or("gain", |info| info.name),
+                    gain,
+                    maximum
+                )));
             }
+        }
+        for (code, gain) in codes.iter().zip(gains) {
+            self.set_vcp_feature(*code, gain)?;
+        }
+        Ok(())
+    }
 
-            Ok(())
+    /// Gains for a `#RRGGBB` triplet, each byte scaled to its channel's maximum
+    pub fn rgb_gains_for(&self, triplet: decode::HexTriplet) -> Result<[u32; 3]> {
+        Ok([
+            decode::HexTriplet::scale(triplet.red, self.max_for_code(codes::RED_GAIN)?),
+            decode::HexTriplet::scale(triplet.green, self.max_for_code(codes::GREEN_GAIN)?),
+            decode::HexTriplet::scale(triplet.blue, self.max_for_code(codes::BLUE_GAIN)?),
+        ])
+    }
+
+    /// Read the on-screen display state (VCP 0xCA)
+    pub fn get_osd_state(&self) -> Result<decode::OsdState> {
+        let response = self
+            .get_vcp_feature(codes::OSD)
+            .map_err(|e| unsupported_as(e, "monitor does not report its OSD state (VCP 0xCA)"))?;
+        Ok(decode::OsdState::from_value(response.current_value))
+    }
+
+    /// Whether the monitor reports its OSD as enabled, which monitors that track it use while
+    /// the user is in the menu. Others report it whenever the menu may be opened, so callers
+    /// that defer writes on it should let the user opt in.
+    pub fn is_osd_active(&self) -> Result<bool> {
+        Ok(self.get_osd_state()? == decode::OsdState::Enabled)
+    }
+
+    /// Read which set of speakers is active (VCP 0x63)
+    pub fn get_speaker_select(&self) -> Result<decode::SpeakerSelect> {
+        let response = self.get_vcp_feature(codes::SPEAKER_SELECT).map_err(|e| {
+            unsupported_as(e, "monitor does not support speaker selection (VCP 0x63)")
+        })?;
+        Ok(decode::SpeakerSelect::from_value(response.current_value))
+    }
+
+    /// Select the active set of speakers (VCP 0x63)
+    pub fn set_speaker_select(&self, speakers: decode::SpeakerSelect) -> Result<()> {
+        self.set_vcp_feature(codes::SPEAKER_SELECT, u32::from(speakers.value()))
+    }
+
+    /// Read the performance-preservation feature bitmask (VCP 0x54)
+    pub fn get_performance_preservation(&self) -> Result<decode::PerformancePreservation> {
+        let response = self
+            .get_vcp_feature(codes::PERFORMANCE_PRESERVATION)
+            .map_err(|e| {
+                unsupported_as(
+                    e,
+                    "monitor does not support performance preservation (VCP 0x54)",
+                )
+            })?;
+        Ok(decode::PerformancePreservation::from_value(
+            response.current_value,
+        ))
+    }
+
+    /// Write the whole performance-preservation bitmask (VCP 0x54)
+    pub fn set_performance_preservation(
+        &self,
+        features: decode::PerformancePreservation,
+    ) -> Result<()> {
+        self.set_vcp_feature(codes::PERFORMANCE_PRESERVATION, u32::from(features.bits()))
+    }
+
+    /// Read all six saturation axes (VCP 0x59-0x5E)
+    pub fn get_six_axis_saturation(&self) -> Result<SixAxisValues> {
+        self.get_six_axis(codes::SIX_AXIS_SATURATION, "saturation (VCP 0x59-0x5E)")
+    }
+
+    /// Write the saturation axes set in `values` (VCP 0x59-0x5E); see [`Self::set_six_axis`]
+    pub fn set_six_axis_saturation(&self, values: &SixAxisValues) -> Vec<AxisWrite> {
+        self.set_six_axis(codes::SIX_AXIS_SATURATION, values)
+    }
+
+    /// Read all six hue axes (VCP 0x9B-0xA0)
+    pub fn get_six_axis_hue(&self) -> Result<SixAxisValues> {
+        self.get_six_axis(codes::SIX_AXIS_HUE, "hue (VCP 0x9B-0xA0)")
+    }
+
+    /// Write the hue axes set in `values` (VCP 0x9B-0xA0); see [`Self::set_six_axis`]
+    pub fn set_six_axis_hue(&self, values: &SixAxisValues) -> Vec<AxisWrite> {
+        self.set_six_axis(codes::SIX_AXIS_HUE, values)
+    }
+
+    /// Read the six axes starting at `base`. Axes the monitor doesn't answer are `None`; only a
+    /// monitor answering none of them is an error.
+    fn get_six_axis(&self, base: u8, what: &str) -> Result<SixAxisValues> {
+        let mut values = SixAxisValues::default();
+        for axis in ColorAxis::ALL {
+            let value = self
+                .get_vcp_feature(axis.code(base))
+                .ok()
+                .map(|response| response.current_value);
+            values.set(axis, value);
+        }
+
+        if values.is_empty() {
+            return Err(MonitorError::UnsupportedOperation(format!(
+                "monitor does not support six-axis {}",
+                what
+            )));
         }
+        Ok(values)
+    }
+
+    /// Write each axis set in `values`, clamped to the maximum the monitor reports for it. A
+    /// failing axis doesn't stop the others; every attempted axis gets an [`AxisWrite`].
+    fn set_six_axis(&self, base: u8, values: &SixAxisValues) -> Vec<AxisWrite> {
+        ColorAxis::ALL
+            .into_iter()
+            .filter_map(|axis| {
+                let requested = values.get(axis)?;
+                let code = axis.code(base);
+                let result = self.max_for_code(code).and_then(|maximum| {
+                    let value = requested.min(maximum);
+                    self.set_vcp_feature(code, value).map(|()| value)
+                });
+                Some(match result {
+                    Ok(value) => AxisWrite {
+                        axis,
+                        requested,
+                        written: Some(value),
+                        error: None,
+                    },
+                    Err(e) => AxisWrite {
+                        axis,
+                        requested,
+                        written: None,
+                        error: Some(e.to_string()),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Read the LCD sub-pixel layout (VCP 0xB2)
+    pub fn get_subpixel_layout(&self) -> Result<decode::SubPixelLayout> {
+        let response = self.get_vcp_feature(codes::SUBPIXEL_LAYOUT).map_err(|e| {
+            unsupported_as(e, "monitor does not report its sub-pixel layout (VCP 0xB2)")
+        })?;
+        Ok(decode::SubPixelLayout::from_value(response.current_value))
+    }
+
+    /// Read the DPVL video mode and packet status (VCP 0xB7). Only DPVL displays implement it.
+    pub fn get_monitor_status(&self) -> Result<decode::MonitorStatus> {
+        let response = self
+            .get_vcp_feature(codes::MONITOR_STATUS)
+            .map_err(|e| unsupported_as(e, "monitor does not report a DPVL status (VCP 0xB7)"))?;
+        Ok(decode::MonitorStatus::from_value(response.current_value))
+    }
+
+    /// Read the DVI link status (VCP 0xBE)
+    pub fn get_link_status(&self) -> Result<decode::LinkStatus> {
+        let response = self
+            .get_vcp_feature(codes::LINK_CONTROL)
+            .map_err(|e| unsupported_as(e, "monitor does not report its link status (VCP 0xBE)"))?;
+        Ok(decode::LinkStatus::from_value(response.current_value))
+    }
+
+    /// Read the speaker volume (VCP 0x62)
+    pub fn get_volume(&self) -> Result<VcpFeatureResponse> {
+        self.get_vcp_feature(codes::AUDIO_VOLUME)
+            .map_err(|e| unsupported_as(e, VOLUME_UNSUPPORTED))
+    }
+
+    /// Set the speaker volume (VCP 0x62), checked against the monitor's maximum
+    pub fn set_volume(&self, value: u32) -> Result<()> {
+        let maximum = self
+            .max_for_code(codes::AUDIO_VOLUME)
+            .map_err(|e| unsupported_as(e, VOLUME_UNSUPPORTED))?;
+        check_level(codes::AUDIO_VOLUME, value, maximum)?;
+        self.set_vcp_feature(codes::AUDIO_VOLUME, value)
+    }
+
+    /// Read the white backlight level (VCP 0x6B), which some monitors adjust for brightness
+    /// instead of luminance (VCP 0x10)
+    pub fn get_backlight_white(&self) -> Result<VcpFeatureResponse> {
+        self.get_vcp_feature(codes::BACKLIGHT_WHITE)
+    }
+
+    /// Set the white backlight level (VCP 0x6B), checked against the monitor's maximum
+    pub fn set_backlight_white(&self, value: u32) -> Result<()> {
+        let maximum = self.max_for_code(codes::BACKLIGHT_WHITE)?;
+        check_level(codes::BACKLIGHT_WHITE, value, maximum)?;
+        self.set_vcp_feature(codes::BACKLIGHT_WHITE, value)
+    }
+
+    /// Fade brightness (VCP 0x10) to `target` over `duration`; see [`Self::fade_vcp`]
+    pub fn fade_brightness(&self, target: u32, duration: Duration, steps: u32) -> Result<()> {
+        self.fade_vcp(codes::BRIGHTNESS, target, duration, steps)
+    }
+
+    /// Fade the speaker volume (VCP 0x62) to `target` over `duration`, avoiding a sudden jump in
+    /// loudness; see [`Self::fade_vcp`]
+    pub fn fade_volume(&self, target: u32, duration: Duration, steps: u32) -> Result<()> {
+        self.max_for_code(codes::AUDIO_VOLUME)
+            .map_err(|e| unsupported_as(e, VOLUME_UNSUPPORTED))?;
+        self.fade_vcp(codes::AUDIO_VOLUME, target, duration, steps)
+    }
+
+    /// Move a continuous control from its current value to `target` in up to `steps` writes
+    /// spread evenly over `duration` (see [`fade_steps`]). `target` is checked against the
+    /// monitor's maximum before anything is written.
+    pub fn fade_vcp(&self, code: u8, target: u32, duration: Duration, steps: u32) -> Result<()> {
+        self.fade_vcp_with_progress(code, target, duration, steps, |_, _| {})
+    }
+
+    /// Like [`Self::fade_vcp`], calling `progress(done, total)` after every write
+    pub fn fade_vcp_with_progress(
+        &self,
+        code: u8,
+        target: u32,
+        duration: Duration,
+        steps: u32,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        let current = self.get_vcp_feature(code)?;
+        check_level(code, target, current.maximum_value)?;
+
+        let values = fade_steps(current.current_value, target, steps);
+        let interval = duration / values.len().max(1) as u32;
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                thread::sleep(interval);
+            }
+            self.set_vcp_feature(code, *value)?;
+            progress(i + 1, values.len());
+        }
+
+        Ok(())
+    }
+
+    /// Read the display's power-on hours (VCP 0xC0)
+    pub fn get_usage_hours(&self) -> Result<u32> {
+        let response = self
+            .get_vcp_feature(codes::USAGE_TIME)
+            .map_err(|e| unsupported_as(e, "monitor does not report its usage time (VCP 0xC0)"))?;
+        Ok(decode::usage_hours(
+            response.current_value,
+            response.maximum_value,
+        ))
+    }
+
+    /// Switch to another input (VCP 0x60)
+    pub fn set_input_source(&self, input: decode::InputSource) -> Result<()> {
+        self.set_vcp_feature(codes::INPUT_SOURCE, u32::from(input.value()))
+    }
+
+    /// Switch the display power mode (VCP 0xD6)
+    pub fn set_power_mode(&self, mode: decode::PowerMode) -> Result<()> {
+        self.set_vcp_feature(codes::POWER_MODE, u32::from(mode.value()))
     }
 
     /// Scan all VCP codes (0x00-0xFF) and return the ones supported by the monitor
     /// Similar to PowerShell's Get-MonitorVCPResponse -All
     pub fn scan_vcp_features(&self) -> Vec<VcpFeatureResponse> {
-        let mut features = Vec::new();
+        self.scan_vcp_features_with_progress(|_, _| {})
+    }
 
-        for code in 0u8..=255u8 {
-            if let Ok(response) = self.get_vcp_feature(code) {
-                features.push(response);
+    /// Like [`Self::scan_vcp_features`], calling `progress(done, total)` after every code
+    pub fn scan_vcp_features_with_progress(
+        &self,
+        progress: impl FnMut(usize, usize),
+    ) -> Vec<VcpFeatureResponse> {
+        self.probe_all_codes(progress).0
+    }
+
+    /// Read every code, timing each read
+    fn probe_all_codes(
+        &self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> (Vec<VcpFeatureResponse>, Vec<ProbeTiming>) {
+        let mut state = ScanState::default();
+        // The checkpoint only reports progress, so nothing can fail
+        let _ = self.probe_remaining_codes(&mut state, |state| {
+            progress(state.next_code as usize, SCAN_CODE_COUNT);
+            Ok(())
+        });
+        (state.features, state.probes)
+    }
+
+    /// Read the codes from `state.next_code` on, recording each in `state` and handing the
+    /// updated state to `checkpoint` after every read
+    fn probe_remaining_codes(
+        &self,
+        state: &mut ScanState,
+        mut checkpoint: impl FnMut(&ScanState) -> Result<()>,
+    ) -> Result<()> {
+        while !state.is_complete() {
+            let code = state.next_code as u8;
+            let start = Instant::now();
+            let result = self.get_vcp_feature(code);
+            state.probes.push(ProbeTiming {
+                code,
+                supported: result.is_ok(),
+                elapsed: start.elapsed(),
+            });
+
+            if let Ok(response) = result {
+                state.features.push(response);
             }
             // Silently ignore unsupported codes (similar to PowerShell behavior)
+
+            state.next_code += 1;
+            checkpoint(state)?;
         }
 
-        features
+        Ok(())
     }
 
-    pub fn get_capabilities(&self) -> Result<String> {
-        unsafe {
-            let mut length = 0u32;
-            let result =
-                crate::native::dxva2::GetCapabilitiesStringLength(self.handle, &mut length);
-
-            if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "GetCapabilitiesStringLength failed".to_string(),
-                ));
+    /// Read every code the capabilities string advertises plus the [`COMMON_CODES`], and compare
+    /// what answered with what was advertised. Much quicker than a full scan, since only those
+    /// codes are read. Only a code the monitor reports as unsupported counts as not answering;
+    /// any other failed read (a disconnect, a busy channel) stops the comparison, so it can't
+    /// show up as a false discrepancy. Fails if the capabilities string can't be read or parsed.
+    pub fn reconcile_capabilities(&self) -> Result<Reconciliation> {
+        let caps = capabilities::parse_capabilities(&self.get_capabilities()?)?;
+        let advertised: Vec<u8> = caps.vcp_codes.iter().map(|cap| cap.code).collect();
+        let probed: BTreeSet<u8> = advertised.iter().chain(COMMON_CODES).copied().collect();
+        let mut answered = Vec::new();
+        for code in probed {
+            match self.get_vcp_feature(code) {
+                Ok(_) => answered.push(code),
+                Err(e) if e.is_not_supported() => {}
+                Err(e) => return Err(e),
             }
+        }
+        Ok(Reconciliation::new(&advertised, &answered))
+    }
 
-            let mut buffer = vec![0u8; length as usize];
-            let result = crate::native::dxva2::CapabilitiesRequestAndCapabilitiesReply(
-                self.handle,
-                buffer.as_mut_ptr(),
-                length,
-            );
+    /// Scan all VCP codes and pair the results with the parsed capabilities string
+    pub fn scan_report(&self) -> ScanReport {
+        self.scan_report_with_progress(|_, _| {})
+    }
 
-            if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "CapabilitiesRequestAndCapabilitiesReply failed".to_string(),
-                ));
-            }
+    /// Like [`Self::scan_report`], calling `progress(done, total)` after every code
+    pub fn scan_report_with_progress(&self, progress: impl FnMut(usize, usize)) -> ScanReport {
+        let (features, probes) = self.probe_all_codes(progress);
+        self.finish_scan(features, probes)
+    }
+
+    /// Like [`Self::scan_report`], but continuing from a saved `state` (a fresh
+    /// [`ScanState`] scans everything). `checkpoint` is called after every code, typically to
+    /// [save](ScanState::save) the state; an error from it stops the scan.
+    pub fn scan_report_resumable(
+        &self,
+        state: &mut ScanState,
+        checkpoint: impl FnMut(&ScanState) -> Result<()>,
+    ) -> Result<ScanReport> {
+        self.probe_remaining_codes(state, checkpoint)?;
+        Ok(self.finish_scan(state.features.clone(), state.probes.clone()))
+    }
+
+    fn finish_scan(
+        &self,
+        features: Vec<VcpFeatureResponse>,
+        probes: Vec<ProbeTiming>,
+    ) -> ScanReport {
+        let capabilities = self
+            .get_capabilities()
+            .ok()
+            .and_then(|caps| capabilities::parse_capabilities(&caps).ok());
 
-            // Remove null terminators and convert to String
-            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
-            Ok(String::from_utf8_lossy(&buffer[..end]).to_string())
+        ScanReport {
+            features,
+            capabilities,
+            probes,
         }
     }
 
+    pub fn get_capabilities(&self) -> Result<String> {
+        self.backend.capabilities()
+    }
+
     pub fn save_settings(&self) -> Result<()> {
-        unsafe {
-            let result = crate::native::dxva2::SaveCurrentMonitorSettings(self.handle);
+        self.backend.save_settings()
+    }
+
+    pub fn restore_factory_defaults(&self) -> Result<()> {
+        self.backend.restore_factory_defaults()
+    }
+
+    pub fn restore_factory_color_defaults(&self) -> Result<()> {
+        self.backend.restore_factory_color_defaults()
+    }
 
-            if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "SaveCurrentMonitorSettings failed".to_string(),
-                ));
+    /// Restore only the factory brightness and contrast (VCP 0x05); any non-zero write triggers
+    /// the reset
+    pub fn reset_luminance_contrast(&self) -> Result<()> {
+        self.set_vcp_feature(codes::RESTORE_LUMINANCE_CONTRAST, 1)
+    }
+
+    /// Read `len` bytes starting at `offset` from a table-type VCP code (e.g. 0x73, 0xC3)
+    ///
+    /// Table replies arrive in fragments of at most [`TABLE_FRAGMENT_SIZE`] bytes; a short
+    /// fragment marks the end of the table.
+    pub fn table_read(&self, vcp_code: u8, offset: u16, len: usize) -> Result<Vec<u8>> {
+        check_table_range(offset, len)?;
+        let mut data = Vec::with_capacity(len);
+
+        while data.len() < len {
+            let fragment_offset = offset as usize + data.len();
+            let fragment = self.read_table_fragment(vcp_code, fragment_offset as u16)?;
+            let remaining = len - data.len();
+            data.extend_from_slice(&fragment[..fragment.len().min(remaining)]);
+
+            if fragment.len() < TABLE_FRAGMENT_SIZE {
+                break;
             }
+        }
 
-            Ok(())
+        Ok(data)
+    }
+
+    /// Write `data` starting at `offset` to a table-type VCP code, split into
+    /// [`TABLE_FRAGMENT_SIZE`]-byte fragments
+    pub fn table_write(&self, vcp_code: u8, offset: u16, data: &[u8]) -> Result<()> {
+        check_table_range(offset, data.len())?;
+        for (fragment_offset, fragment) in table_fragments(offset, data) {
+            self.write_table_fragment(vcp_code, fragment_offset, fragment)?;
+        }
+        Ok(())
+    }
+
+    /// Write the asset tag (VCP 0xD2)
+    pub fn set_asset_tag(&self, tag: &str) -> Result<()> {
+        let data = check_table_text("asset tag", tag, MAX_TABLE_TEXT_LEN)?;
+        self.table_write(codes::ASSET_TAG, 0, data)
+    }
+
+    /// Write the display descriptor (VCP 0xC3), after checking it fits the length the monitor
+    /// reports through VCP 0xC2
+    pub fn set_display_descriptor(&self, descriptor: &str) -> Result<()> {
+        let max_len = self.display_descriptor_length()?;
+        let data = check_table_text("display descriptor", descriptor, max_len)?;
+        self.table_write(codes::DISPLAY_DESCRIPTOR, 0, data)
+    }
+
+    /// Read the display descriptor (VCP 0xC3), up to the length reported through VCP 0xC2.
+    /// Padding after the text is dropped.
+    pub fn get_display_descriptor(&self) -> Result<String> {
+        let len = self.display_descriptor_length()?;
+        let data = self.table_read(codes::DISPLAY_DESCRIPTOR, 0, len)?;
+        let text = String::from_utf8_lossy(&data);
+        Ok(text.trim_end_matches(['\0', ' ']).to_string())
+    }
+
+    /// Whether the monitor shows its display descriptor while it receives no video (VCP 0xC4)
+    pub fn get_display_descriptor_enabled(&self) -> Result<bool> {
+        let response = self
+            .get_vcp_feature(codes::DISPLAY_DESCRIPTOR_ENABLE)
+            .map_err(|e| {
+                unsupported_as(
+                    e,
+                    "monitor does not support showing a display descriptor (VCP 0xC4)",
+                )
+            })?;
+        // Only bit 0 is defined
+        Ok(response.current_value & 0x01 != 0)
+    }
+
+    /// Turn display of the descriptor while there is no video on or off (VCP 0xC4)
+    pub fn set_display_descriptor_enabled(&self, enabled: bool) -> Result<()> {
+        self.get_display_descriptor_enabled()?;
+        self.set_vcp_feature(codes::DISPLAY_DESCRIPTOR_ENABLE, u32::from(enabled))
+    }
+
+    /// Read the size of the auxiliary alphanumeric display (VCP 0xCE). Few monitors have one.
+    pub fn get_aux_display_size(&self) -> Result<decode::AuxDisplaySize> {
+        let response = self
+            .get_vcp_feature(codes::AUX_DISPLAY_SIZE)
+            .map_err(|e| unsupported_as(e, "monitor has no auxiliary display (VCP 0xCE)"))?;
+        Ok(decode::AuxDisplaySize::from_value(response.current_value))
+    }
+
+    /// Show `text` on the auxiliary display (VCP 0xCF), after checking it fits the size reported
+    /// through VCP 0xCE. Returns the size.
+    pub fn set_aux_display_text(&self, text: &str) -> Result<decode::AuxDisplaySize> {
+        let size = self.get_aux_display_size()?;
+        let data = aux_display_data(text, size)?;
+        self.table_write(codes::AUX_DISPLAY_DATA, 0, &data)?;
+        Ok(size)
+    }
+
+    /// Descriptor capacity from VCP 0xC2, capped at [`MAX_TABLE_TEXT_LEN`]
+    fn display_descriptor_length(&self) -> Result<usize> {
+        let len = self
+            .get_vcp_feature(codes::DISPLAY_DESCRIPTOR_LENGTH)
+            .map_err(|e| {
+                unsupported_as(
+                    e,
+                    "monitor does not report a display descriptor length (VCP 0xC2)",
+                )
+            })?
+            .current_value as usize;
+        Ok(len.min(MAX_TABLE_TEXT_LEN))
+    }
+
+    fn read_table_fragment(&self, vcp_code: u8, offset: u16) -> Result<Vec<u8>> {
+        self.backend.read_table_fragment(vcp_code, offset)
+    }
+
+    fn write_table_fragment(&self, vcp_code: u8, offset: u16, fragment: &[u8]) -> Result<()> {
+        self.backend
+            .write_table_fragment(vcp_code, offset, fragment)
+    }
+}
+
+/// Intermediate values for fading a continuous control from `start` to `target` in up to `steps`
+/// evenly spaced writes. Always ends at `target`; steps that would repeat a value are dropped, so
+/// small changes take fewer writes. Empty if there is nothing to change.
+pub fn fade_steps(start: u32, target: u32, steps: u32) -> Vec<u32> {
+    let steps = steps.max(1);
+    let (start, target) = (i64::from(start), i64::from(target));
+    let mut values: Vec<u32> = Vec::new();
+
+    for step in 1..=i64::from(steps) {
+        let value = (start + (target - start) * step / i64::from(steps)) as u32;
+        if values.last() != Some(&value) && i64::from(value) != start {
+            values.push(value);
         }
     }
 
-    pub fn restore_factory_defaults(&self) -> Result<()> {
-        unsafe {
-            let result = crate::native::dxva2::RestoreMonitorFactoryDefaults(self.handle);
+    values
+}
+
+const VOLUME_UNSUPPORTED: &str = "monitor does not support audio volume (VCP 0x62)";
+
+/// For a failed read: [`MonitorError::UnsupportedOperation`] with `message` if the monitor
+/// doesn't implement the code, otherwise the error as it is, so a disconnect or a busy channel
+/// still reaches the caller
+fn unsupported_as(error: MonitorError, message: &str) -> MonitorError {
+    if error.is_not_supported() {
+        MonitorError::UnsupportedOperation(message.to_string())
+    } else {
+        error
+    }
+}
+
+fn check_level(code: u8, value: u32, maximum: u32) -> Result<()> {
+    if value > maximum {
+        return Err(MonitorError::InvalidValue(format!(
+            "{} is above the maximum of {} for VCP code 0x{:02X}",
+            value, maximum, code
+        )));
+    }
+    Ok(())
+}
+
+/// Maximum payload of a single DDC/CI table read or write fragment
+pub const TABLE_FRAGMENT_SIZE: usize = 32;
+
+/// Split `data` into `(offset, fragment)` pairs of at most [`TABLE_FRAGMENT_SIZE`] bytes
+pub fn table_fragments(offset: u16, data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    data.chunks(TABLE_FRAGMENT_SIZE)
+        .enumerate()
+        .map(move |(i, chunk)| (offset + (i * TABLE_FRAGMENT_SIZE) as u16, chunk))
+}
+
+/// Largest capabilities string accepted from a monitor; real ones are a few hundred bytes
+pub const MAX_CAPABILITIES_LEN: u32 = 64 * 1024;
+
+/// Check the length a monitor reports for its capabilities string before allocating for it.
+///
+/// Zero means the monitor has no capabilities string to give; anything over
+/// [`MAX_CAPABILITIES_LEN`] is treated as a firmware bug rather than allocated.
+pub fn check_capabilities_length(length: u32) -> Result<usize> {
+    match length {
+        0 => Err(MonitorError::UnsupportedOperation(
+            "monitor reported an empty capabilities string".to_string(),
+        )),
+        len if len > MAX_CAPABILITIES_LEN => Err(MonitorError::CapabilitiesTooLarge(len)),
+        len => Ok(len as usize),
+    }
+}
+
+/// Upper bound MCCS places on the display descriptor; also applied to the asset tag
+pub const MAX_TABLE_TEXT_LEN: usize = 256;
+
+/// Validate text destined for a table-type code: printable ASCII of at most `max_len` bytes
+pub fn check_table_text<'a>(what: &str, text: &'a str, max_len: usize) -> Result<&'a [u8]> {
+    if !text.bytes().all(|b| b.is_ascii_graphic() || b == b' ') {
+        return Err(MonitorError::InvalidValue(format!(
+            "{} must be printable ASCII",
+            what
+        )));
+    }
+    if text.len() > max_len {
+        return Err(MonitorError::InvalidValue(format!(
+            "{} is {} bytes; the monitor accepts at most {}",
+            what,
+            text.len(),
+            max_len
+        )));
+    }
+    Ok(text.as_bytes())
+}
+
+/// Table data for the auxiliary display: `text`, checked to fit `size` and padded with spaces
+/// to the full display so characters from longer earlier text are cleared
+pub fn aux_display_data(text: &str, size: decode::AuxDisplaySize) -> Result<Vec<u8>> {
+    let capacity = size.capacity();
+    let mut data = check_table_text("auxiliary display text", text, capacity)?.to_vec();
+    data.resize(capacity, b' ');
+    Ok(data)
+}
+
+/// Table offsets are 16-bit, so a transfer must not run past 0xFFFF
+fn check_table_range(offset: u16, len: usize) -> Result<()> {
+    if offset as usize + len > u16::MAX as usize + 1 {
+        return Err(MonitorError::InvalidValue(format!(
+            "table transfer of {} bytes at offset {} exceeds the 16-bit offset range",
+            len, offset
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn table_unsupported(vcp_code: u8) -> MonitorError {
+    MonitorError::UnsupportedOperation(format!(
+        "VCP code 0x{:02X} requires DDC/CI table transfers, which the Windows Monitor Configuration API does not provide",
+        vcp_code
+    ))
+}
 
-            if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "RestoreMonitorFactoryDefaults failed".to_string(),
-                ));
-            }
+/// Broad grouping of the known VCP codes, for narrowing down `list-vcp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VcpCategory {
+    /// Color presets, gains, black levels, gamma, hue and saturation
+    Color,
+    /// Position, size, distortion, orientation and scaling
+    Geometry,
+    /// Speaker, microphone and audio processing
+    Audio,
+    /// Power mode and auxiliary power
+    Power,
+    /// Luminance, contrast, sharpness, backlight and picture modes
+    Image,
+    /// Read-only status, timing and identification
+    Info,
+    /// Manufacturer-specific codes (0xE0-0xFF)
+    Oem,
+    /// Input and output selection, OSD, resets and protocol housekeeping
+    Control,
+}
 
-            Ok(())
+impl VcpCategory {
+    pub const ALL: [VcpCategory; 8] = [
+        VcpCategory::Color,
+        VcpCategory::Geometry,
+        VcpCategory::Audio,
+        VcpCategory::Power,
+        VcpCategory::Image,
+        VcpCategory::Info,
+        VcpCategory::Oem,
+        VcpCategory::Control,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VcpCategory::Color => "color",
+            VcpCategory::Geometry => "geometry",
+            VcpCategory::Audio => "audio",
+            VcpCategory::Power => "power",
+            VcpCategory::Image => "image",
+            VcpCategory::Info => "info",
+            VcpCategory::Oem => "oem",
+            VcpCategory::Control => "control",
         }
     }
+}
 
-    pub fn restore_factory_color_defaults(&self) -> Result<()> {
-        unsafe {
-            let result = crate::native::dxva2::RestoreMonitorFactoryColorDefaults(self.handle);
+impl fmt::Display for VcpCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
 
-            if result == 0 {
-                return Err(crate::MonitorError::UnsupportedOperation(
-                    "RestoreMonitorFactoryColorDefaults failed".to_string(),
-                ));
-            }
+impl std::str::FromStr for VcpCategory {
+    type Err = MonitorError;
 
-            Ok(())
+    /// Accepts the category names case-insensitively; `status` is an alias of `info`
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized = s.to_ascii_lowercase();
+        if normalized == "status" {
+            return Ok(VcpCategory::Info);
         }
+        VcpCategory::ALL
+            .into_iter()
+            .find(|category| category.name() == normalized)
+            .ok_or_else(|| {
+                let names: Vec<_> = VcpCategory::ALL.iter().map(|c| c.name()).collect();
+                MonitorError::InvalidValue(format!(
+                    "unknown category '{}' (expected one of {})",
+                    s,
+                    names.join(", ")
+                ))
+            })
     }
 }
 
@@ -178,932 +1501,2435 @@ pub struct VcpFeatureInfo {
     pub code: u8,
     pub name: &'static str,
     pub description: &'static str,
+    pub category: VcpCategory,
+}
+
+/// Known codes in `category`, in code order
+pub fn known_codes_in(category: VcpCategory) -> impl Iterator<Item = &'static VcpFeatureInfo> {
+    KNOWN_VCP_CODES
+        .iter()
+        .filter(move |info| info.category == category)
 }
 
+/// Every VCP code with a known name, sorted by code and without duplicates, so
+/// [`get_vcp_code_info`] can binary search it. The build fails if an edit breaks the order.
 pub const KNOWN_VCP_CODES: &[VcpFeatureInfo] = &[
     VcpFeatureInfo {
         code: 0x00,
         name: "Code Page",
         description: "Returns the Code Page ID number Byte SL.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x01,
         name: "Degauss",
         description: "Causes a CRT display to perform a degauss cycle.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x02,
         name: "New Control Value",
         description: "Indicates that a displays MCCS VCP Code register value has changed.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x03,
         name: "Soft Controls",
         description: "Allows applications running on the host to use control buttons on the display.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x04,
         name: "Restore Factory Defaults",
         description: "Restore all factory presets including luminance / contrast, geometry, color and TV defaults.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x05,
         name: "Restore Factory Luminance / Contrast Defaults",
         description: "Restores factory defaults for luminance and contrast adjustments.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x06,
         name: "Restore Factory Geometry Defaults",
         description: "Restore factory defaults for geometry adjustments.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x08,
         name: "Restore Factory Color Defaults",
         description: "Restore factory defaults for color settings.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x0A,
         name: "Restore Factory TV Defaults",
         description: "Restore factory defaults for TV functions.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x0B,
         name: "User Color Temperature Increment",
         description: "Sets the minimum increment in which the display can adjust the color temperature.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x0C,
         name: "User Color Temperature",
         description: "Multiplier of the value set in 0x0B",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x0E,
         name: "Clock",
         description: "Video sampling clock frequency",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: codes::BRIGHTNESS,
         name: "Luminance",
         description: "Luminance of the image (Brightness control).",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x11,
         name: "Flesh Tone Enhancement",
         description: "This control allows for selection of contrast enhancement algorithms using a bitmask.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: codes::CONTRAST,
         name: "Contrast",
         description: "Contrast of the image.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x13,
         name: "Backlight Control",
         description: "This VCP code has been deprecated.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: codes::COLOR_TEMPERATURE,
         name: "Select Color Preset",
         description: "Select a specified color temperature.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: codes::RED_GAIN,
         name: "Video Gain (Drive): Red",
         description: "Sets the luminance of red pixels.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x17,
         name: "User Color Vision Compensation",
         description: "Sets the degree of compensation. Intended to help people that see red colors poorly.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: codes::GREEN_GAIN,
         name: "Video Gain (Drive): Green",
         description: "Sets the luminance of green pixels.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: codes::BLUE_GAIN,
         name: "Video Gain (Drive): Blue",
         description: "Sets the luminance of blue pixels.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x1C,
         name: "Focus",
         description: "Sets the focus of the image.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x1E,
         name: "Auto Setup",
         description: "Perform auto setup function (H/V position, clock, clock phase, A/D converter, etc.)",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x1F,
         name: "Auto Color Setup",
         description: "Perform auto color setup function (R / G / B gain and offset, A/D setup, etc.)",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x20,
         name: "Horizontal Position (Phase)",
         description: "Moves the image left and right on the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x22,
         name: "Horizontal Size",
         description: "Sets the width of the image.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x24,
         name: "Horizontal Pincushion",
         description: "Makes the left/right sides of the image more/less convex.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x26,
         name: "Horizontal Pincushion Balance",
         description: "Increasing (decreasing) this value will move the center section of the image toward the right (left) side of the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x28,
         name: "Horizontal Convergence R/B",
         description: "Increasing (decreasing) this value will shift the red pixels to the right (left) across the image and the blue pixels left (right) across the image with respect to the green pixels.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x29,
         name: "Horizontal Convergence M/G",
         description: "Increasing (decreasing) this value will shift the magenta pixels to the right (left) across the image and the green pixels left (right) across the image with respect to the magenta pixels",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x2A,
         name: "Horizontal Linearity",
         description: "Increasing (decreasing) this value will increase (decrease) the density of pixels in the image center",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x2C,
         name: "Horizontal Linearity Balance",
         description: "Increasing (decreasing) this value shifts the density of pixels from the left (right) side to the right (left) side of the image.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x2E,
         name: "Gray Scale Expansion",
         description: "Expands the gray scale either in the near white region or the near black region (or both).",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x30,
         name: "Vertical Position (Phase)",
         description: "Increasing (decreasing) this value moves the image toward the top (bottom) edge of the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x32,
         name: "Vertical Size",
         description: "Increasing (decreasing) this value will increase (decrease) the height of the image",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x34,
         name: "Vertical Pincushion",
         description: "Increasing (decreasing) this value will cause the top and bottom edges of the image to become more (less) convex.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x36,
         name: "Vertical Pincushion Balance",
         description: "Increasing (decreasing) this value will move the center section of the image toward the top (bottom) edge of the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x38,
         name: "Vertical Convergence R/B",
         description: "Increasing (decreasing) this value shifts the red pixels up (down) across the image and the blue pixels down (up) across the image with respect to the green pixels.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x39,
         name: "Vertical Convergence M/G",
         description: "Increasing (decreasing) this value will shift the magenta pixels up (down) across the image and the green pixels down (up) across the image with respect to the magenta pixels",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x3A,
         name: "Vertical Linearity",
         description: "Increasing (decreasing) this value will increase (decrease) the density of scan lines in the image center.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x3C,
         name: "Vertical Linearity Balance",
         description: "Increasing (decreasing) this value shifts the density of scan lines from the top (bottom) end to the bottom (top) end of the image",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x3E,
         name: "Clock Phase",
         description: "Increasing (decreasing) this value will increase (decrease) the phase shift of the sampling clock.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x40,
         name: "Horizontal Parallelogram",
         description: "Increasing (decreasing) this value shifts the top section of the image to the right (left) with respect to the bottom section of the image",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x41,
         name: "Vertical Parallelogram",
         description: "Increasing (decreasing) this value shifts the top section of the image to the right (left) with respect to the bottom section of the image.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x42,
         name: "Horizontal Keystone",
         description: "Increasing (decreasing) this value will increase (decrease) the horizontal size at the top of the image with respect to the horizontal size at the bottom of the image",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x43,
         name: "Vertical Keystone",
         description: "Increasing (decreasing) this value will increase (decrease) the vertical size at the left of the image with respect to the vertical size at the right of the image",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x44,
         name: "Rotation",
         description: "Increasing (decreasing) this value rotates the image (counter) clockwise about the center point of the screen.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x46,
         name: "Top Corner Flare",
         description: "Increasing (decreasing) this value will increase (decrease) the distance between the left and right sides at the top of the image.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x48,
         name: "Top Corner Hook",
         description: "Increasing (decreasing) this value moves the top of the image to the right (left).",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x4A,
         name: "Bottom Corner Flare",
         description: "Increasing (decreasing) this value will increase (decrease) the distance between the left and right sides at the bottom of the image",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x4C,
         name: "Bottom Corner Hook",
         description: "Increasing (decreasing) this value moves the bottom of the image to the right (left).",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x52,
         name: "Active Control",
         description: "All VCP Codes that have new values must be added to this FIFO in the order they occur and VCP 02h must be set to = 02h when this FIFO is NOT empty.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0x54,
         name: "Performance Preservation",
         description: "This command provides the capability to control up to 16 features aimed at maintaining the performance of a display.",
+        category: VcpCategory::Power,
     },
     VcpFeatureInfo {
         code: 0x56,
         name: "Horizontal Moir",
         description: "Increasing (decreasing) this value controls the horizontal picture moir cancellation.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x58,
         name: "Vertical Moir",
         description: "Increasing (decreasing) this value controls the vertical picture moir cancellation.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x59,
         name: "6 Axis Saturation Control: Red",
         description: "Adjust the red saturation for 6-axis color.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x5A,
         name: "6 Axis Saturation Control: Yellow",
         description: "Adjust the yellow saturation for 6-axis color.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x5B,
         name: "6 Axis Saturation Control: Green",
         description: "Adjust the green saturation for 6-axis color.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x5C,
         name: "6 Axis Saturation Control: Cyan",
         description: "Adjust the cyan saturation for 6-axis color.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x5D,
         name: "6 Axis Saturation Control: Blue",
         description: "Adjust the blue saturation for 6-axis color.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x5E,
         name: "6 Axis Saturation Control: Magenta",
         description: "Adjust the magenta saturation for 6-axis color.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: codes::INPUT_SOURCE,
         name: "Input Select",
         description: "Adjusts the active input on the display.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: codes::AUDIO_VOLUME,
         name: "Audio: Speaker Volume",
         description: "Allows the volume to be adjusted.",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
-        code: 0x63,
+        code: codes::SPEAKER_SELECT,
         name: "Speaker Select",
         description: "Selects the active speakers on the display",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x64,
         name: "Audio: Microphone Volume",
         description: "Sets the microphone gain.",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x65,
         name: "Audio: Jack Connection Status",
         description: "This bitmask allows the source to determine the capabilities as well as the current configuration of speakers/lineout connected to a display, or active in an audio only device",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x66,
         name: "Ambient Light Sensor",
         description: "Used to control the action of an ambient light sensor",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x6B,
         name: "Backlight Level: White",
         description: "Sets the White backlight level of the image.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x6C,
         name: "Video Black Level: Red",
         description: "Sets the black level of the red video.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x6D,
         name: "Backlight Level: Red",
         description: "Sets the Red backlight level of the image.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x6E,
         name: "Video Black Level: Green",
         description: "Sets the black level of the green video.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x6F,
         name: "Backlight Level: Green",
         description: "Sets the Green backlight level of the image.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x70,
         name: "Video Black Level: Blue",
         description: "Sets the black level of the blue video",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x71,
         name: "Backlight Level: Blue",
         description: "Sets the Blue backlight level of the image.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x72,
         name: "Gamma",
         description: "This VCP code has two distinct modes, it may be used to select an absolute (within a defined tolerance) value for gamma, or it may be used to select a value of gamma relative to the default gamma of the display",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x73,
         name: "LUT Size",
         description: "Provides the size (number of entries and number of bits / entry) for the Red / Green and Blue LUT in the display",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x74,
         name: "Single Point LUT Operation",
         description: "Allows a single point within a displays color LUT (look up table) to be loaded.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x75,
         name: "Block LUT Operation",
         description: "Provides an efficient method for loading multiple values into a displays LUT.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x76,
         name: "Remote Procedure Call",
         description: "Allows initiation of a routine / macro resident in the display.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x78,
         name: "Display Identification on Data Operation",
         description: "This command allows a selected block (128 bytes) of Display Identification Data (e.g., EDID or DisplayID) to be read.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x7C,
         name: "Adjust Zoom",
         description: "Sets the zoom function of the projection lens.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x82,
         name: "Horizontal Mirror (Flip)",
         description: "This VCP code allows the image to be mirrored horizontally.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x84,
         name: "Vertical Mirror (Flip)",
         description: "This VCP code allows the image to be mirrored vertically.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x86,
         name: "Display Scaling",
         description: "Changing this value will affect the scaling (input versus output) function of the display. NOTE: This VCP code can be used to scale up or down to the maximum screen size.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x87,
         name: "Sharpness",
         description: "Allows one of a range of algorithms to be selected to suit the type of image being displayed and/or personal preference.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x88,
         name: "Velocity Scan Modulation",
         description: "Increasing (decreasing) this value will increase (decrease) the velocity modulation of the horizontal scan as a function of a change in the luminance level.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x8A,
         name: "Color Saturation",
         description: "Increasing this control increases the amplitude of the color difference components of the video signal.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x8B,
         name: "TVChannel Up / Down",
         description: "Used to increment / decrement between TV-channels, the exact behavior is implementation specific (e.g. increment / decrement to next numeric channel or increment / decrement to next channel with a signal).",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0x8C,
         name: "TV-Sharpness",
         description: "Increasing this control increases the amplitude of the high frequency components of the video signal.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: codes::AUDIO_MUTE,
         name: "Audio Mute / Screen Blank",
         description: "Provides for the audio to be muted or un-muted.",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x8E,
         name: "TV-Contrast",
         description: "Increasing (decreasing) this control increases (decreases) the ratio between whites and blacks in the video.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x8F,
         name: "Audio Treble",
         description: "Allows control of the high frequency component of the audio.",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x90,
         name: "Hue",
         description: "Also known as tint Increasing (decreasing) this control increases (decreases) the wavelength of the color component of the video signal.",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x91,
         name: "Audio Bass",
         description: "Allows control of the low frequency component of the audio.",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x92,
         name: "TV-Black Level / Luminance",
         description: "Increasing this control increases the black level of the video, resulting in an increase of the luminance level of the video.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x93,
         name: "Audio Balance L / R",
         description: "This control affects the left right balance of audio output. Increasing (decreasing) the value will cause the balance to move to the right (left).",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x94,
         name: "Audio Processor Mode",
         description: "This control allows one of several audio processing modes to be selected.",
+        category: VcpCategory::Audio,
     },
     VcpFeatureInfo {
         code: 0x95,
         name: "Window Position (TL_X)",
         description: "Defines the top left X pixel of an area of the image. Specified in coordinates of incoming image before any scaling etc. in the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x96,
         name: "Window Position (TL_Y)",
         description: "Defines the top left Y pixel of an area of the image. Specified in coordinates of incoming image before any scaling etc. in the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x97,
         name: "Window Position (BR_X)",
         description: "Defines the bottom right X pixel of an area of the image. Specified in co-ordinates of the incoming image before any scaling etc. in the display.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x98,
         name: "Window Position (BR_Y)",
         description: "Defines the bottom right Y pixel of an area of the image. Specified in co-ordinates of the incoming image before any processing (e.g. scaling) in the display",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0x9A,
         name: "Window Background",
         description: "Changes the contrast ratio between the area of the window and the rest of the desktop",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0x9B,
         name: "6 Axis Hue Control: Red",
         description: "Adjust the red hue for 6-axis color",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x9C,
         name: "6 Axis Hue Control: Yellow",
         description: "Adjust the yellow hue for 6-axis color",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x9D,
         name: "6 Axis Hue Control: Green",
         description: "Adjust the green hue for 6-axis color",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x9E,
         name: "6 Axis Hue Control: Cyan",
         description: "Adjust the cyan hue for 6-axis color",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0x9F,
         name: "6 Axis Hue Control: Blue",
         description: "Adjust the blue hue for 6-axis color",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0xA0,
         name: "6 Axis Hue Control: Magenta",
         description: "Adjust the magenta hue for 6-axis color",
+        category: VcpCategory::Color,
     },
     VcpFeatureInfo {
         code: 0xA2,
         name: "Auto Setup On / Off",
         description: "Turn on / off the auto setup function (periodic or event driven)",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xA4,
         name: "Window Mask Control",
         description: "Data size: Write / Read = 10 bytes This code has two sets of functions:",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xA5,
         name: "Window Select",
         description: "Change the selected window (as defined by 95h 98h).",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xA6,
         name: "Window Size",
         description: "Increasing (decreasing) this value will increase (decrease) the size of the window called out by VCP A5",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xA7,
         name: "Window Transparency",
         description: "Increasing (decreasing) this value will increase (decrease) the transparency of the window called out by A5",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xAA,
         name: "Screen Orientation",
         description: "Indicates the orientation of the screen",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xAC,
         name: "Horizontal Frequency",
         description: "Horizontal synchronization signal frequency in Hz as determined by the display.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xAE,
         name: "Vertical Frequency",
         description: "Vertical synchronization signal frequency in 0.01Hz as determined by the display",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB0,
         name: "Settings",
         description: "Store/Restore the user saved values for current mode",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xB2,
         name: "Flat Panel Sub-Pixel Layout",
         description: "Indicates the type of LCD sub-pixel structure",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB4,
         name: "Source Timing Mode",
         description: "Indicates the timing mode being sent by the host.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB5,
         name: "Source Color Coding",
         description: "Allows the host to specify the color coding method that is being used.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB6,
         name: "Display Technology Type",
         description: "Indicates the base technology type.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB7,
         name: "Monitor Status",
         description: "Video mode and status of a DPVL capable monitor.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB8,
         name: "Packet Count",
         description: "Counter for the DPVL packets received (valid and invalid ones). This value counts from 00 00h to FF FFh and then rolls over to 00 00h. The host can reset the value to 00 00h",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xB9,
         name: "Monitor X Origin",
         description: "The X origin of the monitor in the virtual screen. The support of this command indicates the multi-display support of the display. If a display supports this command, the monitor must also support Monitor Y Origin command",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xBA,
         name: "Monitor Y Origin",
         description: "The Y origin of the display in the virtual screen. The support of this command indicates the multi-display support of the display. If a display supports this command, the monitor must also support Monitor X Origin command",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xBB,
         name: "Header Error Count",
         description: "Error Counter for the DPVL header. The counter value saturates at FF FFh. Host can reset to 00 00h.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xBC,
         name: "Body CRC Error Count",
         description: "CRC error Counter for the DPVL body (containing video data). The counter value saturates at FF FFh. The Host can reset to 00 00h",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xBD,
         name: "Client ID",
         description: "Assigned identification number for the monitor. Valid range is 0000h to FF FEh FF FFh is reserved for broadcast.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xBE,
         name: "Link Control",
         description: "Indicates the status of the DVI link",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xC0,
         name: "Display Usage Time",
         description: "Returns the current value (in hours) of active power on time accumulated by the display in the ML, SH and SL bytes",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xC2,
         name: "Display Descriptor Length",
         description: "Returns the length (in bytes) of non-volatile storage in the display available for writing a display descriptor the maximum descriptor length is 256 bytes",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xC3,
         name: "Transmit Display Descriptor",
         description: "Allows a display descriptor (up to maximum length defined by the display (see code C2h) to be written (read) to (from) nonvolatile storage in the display.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xC4,
         name: "Enable Display of Display Descriptor",
         description: "If enabled, the display descriptor written to the display using VCP code C3h must be displayed when no video is being received.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xC6,
         name: "Application Enable Key",
         description: "A 2-byte value used to allow an application to only operate with known products. The display manufacturer and application author agree to a code such that application will only run when a valid code is present in the display.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xC7,
         name: "Display Enable Key",
         description: "This VCP code has been deprecated. It must NOT be implemented in new designs!",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xC8,
         name: "Display Controller ID",
         description: "Contains the ID for the display controller. 1st byte is parsed as the OEM ID, next 3 bytes is a unique chip ID assigned by the OEM.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xC9,
         name: "Display Firmware Level",
         description: "Contains the firmware version of the display. 1st byte is parsed as the revision number. 2nd byte is the major version. 3rd and 4th are unused.",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
-        code: 0xCA,
+        code: codes::OSD,
         name: "OSD / Button Control",
         description: "Sets and indicates the current operational state of the display OSD and buttons",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xCC,
         name: "OSD Language",
         description: "Allows the host to select the display OSD language.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xCD,
         name: "Status Indicators (Host)",
         description: "This command provides the capability to control up to 16 LED (or similar) indicators which may be used to indicate aspects of the host system status",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xCE,
         name: "Auxiliary Display Size",
         description: "An auxiliary display is a small alphanumeric display associated with the primary display and able to be accessed via the primary display",
+        category: VcpCategory::Info,
     },
     VcpFeatureInfo {
         code: 0xCF,
         name: "Auxiliary Display Data",
         description: "An auxiliary display is a small alphanumeric display associated with the primary display and able to be accessed via the primary display.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xD0,
         name: "Output Select",
         description: "A one byte write/read (Byte 0), allows the host to set (write) one and only one source to output and identify (read) the current output setting",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xD2,
         name: "Asset Tag",
         description: "This VCP codes allows an Asset Tag to be written to a display or read from a display. It also allows for control by the display manufacturer of which applications may write an asset tag.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xD4,
         name: "Stereo Video Mode",
         description: "Used to select the video mode with respect to 2D or 3D video.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: codes::POWER_MODE,
         name: "Power Mode",
         description: "Controls the power mode of the display. 0 = Reserved, 1 = On, 2 = Standby, 3 = Suspend, 4 and 5 = Off",
+        category: VcpCategory::Power,
     },
     VcpFeatureInfo {
         code: 0xD7,
         name: "Auxiliary Power Output",
         description: "Controls output of an auxiliary power output from a display to a host device.",
+        category: VcpCategory::Power,
     },
     VcpFeatureInfo {
         code: 0xDA,
         name: "Scan Mode",
         description: "Controls the scan characteristics.",
+        category: VcpCategory::Geometry,
     },
     VcpFeatureInfo {
         code: 0xDB,
         name: "Image Mode",
         description: "Controls aspects of the displayed image",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0xDC,
         name: "Display Application",
         description: "Select an image preset like Standard, Movie, Games, etc.",
+        category: VcpCategory::Image,
     },
     VcpFeatureInfo {
         code: 0xDE,
         name: "Scratch Pad",
         description: "Provides 2 bytes of volatile storage for use of software application(s) leading to more efficient operation.",
+        category: VcpCategory::Control,
     },
     VcpFeatureInfo {
         code: 0xDF,
         name: "VCP Version",
         description: "Defines the version number of the MCCS standard recognized by the display.",
+        category: VcpCategory::Info,
     },
     // OEM-specific codes (0xE0-0xFF range) - Manufacturer-specific implementations
     VcpFeatureInfo {
         code: 0xE0,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE1,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE2,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE3,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE4,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE5,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE6,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE7,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE8,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xE9,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xEA,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xEB,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xEC,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xED,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xEE,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xEF,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF0,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF1,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF2,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF3,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF4,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF5,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF6,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF7,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF8,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xF9,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xFA,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xFB,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xFC,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xFD,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xFE,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
     VcpFeatureInfo {
         code: 0xFF,
         name: "OEM specific",
         description: "Manufacturer-specific VCP code",
+        category: VcpCategory::Oem,
     },
 ];
 
+const _: () = assert!(
+    is_strictly_sorted(KNOWN_VCP_CODES),
+    "KNOWN_VCP_CODES must be sorted by code, with each code listed once"
+);
+
+/// Whether every entry's code is greater than the one before it
+const fn is_strictly_sorted(table: &[VcpFeatureInfo]) -> bool {
+    let mut i = 1;
+    while i < table.len() {
+        if table[i - 1].code >= table[i].code {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 pub fn get_vcp_code_info(code: u8) -> Option<&'static VcpFeatureInfo> {
-    KNOWN_VCP_CODES.iter().find(|info| info.code == code)
+    KNOWN_VCP_CODES
+        .binary_search_by_key(&code, |info| info.code)
+        .ok()
+        .map(|index| &KNOWN_VCP_CODES[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+
+    fn scanned(code: u8, code_type: VcpCodeType) -> VcpFeatureResponse {
+        VcpFeatureResponse {
+            vcp_code: code,
+            current_value: 0,
+            maximum_value: 100,
+            code_type,
+            reported_value: None,
+        }
+    }
+
+    #[test]
+    fn classify_code_distinguishes_code_kinds() {
+        let report = ScanReport {
+            features: vec![
+                scanned(codes::BRIGHTNESS, VcpCodeType::SetParameter),
+                scanned(codes::INPUT_SOURCE, VcpCodeType::SetParameter),
+                scanned(codes::RESTORE_LUMINANCE_CONTRAST, VcpCodeType::Momentary),
+            ],
+            capabilities: Some(
+                capabilities::parse_capabilities("(vcp(05 10 60(0F 11 12)))").unwrap(),
+            ),
+            probes: Vec::new(),
+        };
+
+        assert_eq!(
+            classify_code(&report, codes::BRIGHTNESS),
+            CodeClass::Continuous
+        );
+        assert_eq!(
+            classify_code(&report, codes::INPUT_SOURCE),
+            CodeClass::Discrete(vec![0x0F, 0x11, 0x12])
+        );
+        assert_eq!(
+            classify_code(&report, codes::RESTORE_LUMINANCE_CONTRAST),
+            CodeClass::Action
+        );
+        assert_eq!(classify_code(&report, 0xC0), CodeClass::ReadOnly);
+    }
+
+    #[test]
+    fn classify_code_without_capabilities_is_continuous() {
+        let report = ScanReport {
+            features: vec![scanned(codes::INPUT_SOURCE, VcpCodeType::SetParameter)],
+            capabilities: None,
+            probes: Vec::new(),
+        };
+        assert_eq!(
+            classify_code(&report, codes::INPUT_SOURCE),
+            CodeClass::Continuous
+        );
+        assert!(CodeClass::Discrete(vec![1]).is_writable());
+        assert!(!CodeClass::Action.is_writable());
+    }
+
+    #[test]
+    fn scan_entries_serialize_decoded_only_for_known_codes() {
+        let mut input = scanned(codes::INPUT_SOURCE, VcpCodeType::SetParameter);
+        input.current_value = 0x0F;
+        let report = ScanReport {
+            features: vec![input, scanned(codes::BRIGHTNESS, VcpCodeType::SetParameter)],
+            capabilities: None,
+            probes: Vec::new(),
+        };
+
+        let entries = serde_json::to_value(report.entries()).unwrap();
+        assert_eq!(entries[0]["decoded"], "DisplayPort 1");
+        assert_eq!(entries[0]["vcp_code"], codes::INPUT_SOURCE);
+        assert!(entries[1].get("decoded").is_none());
+    }
+
+    #[test]
+    fn writable_entries_exclude_actions_and_status_codes() {
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 50, 100)
+                .with_feature(codes::INPUT_SOURCE, 0x11, 0x12)
+                .with_action(codes::RESTORE_LUMINANCE_CONTRAST)
+                .with_feature(0xC0, 1200, 0xFFFF)
+                .with_feature(codes::VCP_VERSION, 0x0202, 0)
+                .with_capabilities("(vcp(05 10 60(0F 11) C0 DF))"),
+        );
+
+        let report = monitor.scan_report();
+        assert_eq!(report.features.len(), 5);
+        let writable: Vec<u8> = report
+            .entries()
+            .into_iter()
+            .filter(|entry| entry.class.is_writable())
+            .map(|entry| entry.response.vcp_code)
+            .collect();
+        assert_eq!(writable, vec![codes::BRIGHTNESS, codes::INPUT_SOURCE]);
+    }
+
+    #[test]
+    fn feature_responses_round_trip_through_json() {
+        for code_type in [VcpCodeType::SetParameter, VcpCodeType::Momentary] {
+            let response = scanned(codes::BRIGHTNESS, code_type);
+            let json = serde_json::to_string(&response).unwrap();
+            let parsed: VcpFeatureResponse = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.code_type, code_type);
+            assert_eq!(parsed.vcp_code, response.vcp_code);
+            assert_eq!(parsed.maximum_value, response.maximum_value);
+        }
+        assert_eq!(
+            serde_json::to_value(VcpCodeType::Momentary).unwrap(),
+            "Momentary"
+        );
+    }
+
+    #[test]
+    fn code_type_accepts_numeric_values() {
+        let parse = |json: &str| serde_json::from_str::<VcpCodeType>(json);
+        assert_eq!(parse("0").unwrap(), VcpCodeType::SetParameter);
+        assert_eq!(parse("1").unwrap(), VcpCodeType::Momentary);
+        assert!(parse("2").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("\"Toggle\"").is_err());
+    }
+
+    #[test]
+    fn input_source_is_read_and_decoded() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new().with_feature(
+            codes::INPUT_SOURCE,
+            0x0F,
+            0x12,
+        ));
+        assert_eq!(
+            monitor.get_input_source().unwrap(),
+            decode::InputSource::DisplayPort1
+        );
+    }
+
+    #[test]
+    fn input_source_unsupported_only_when_the_monitor_says_so() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            monitor.get_input_source(),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+
+        let transmit_error =
+            windows_sys::Win32::Foundation::ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32;
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new().failing_with(codes::INPUT_SOURCE, transmit_error),
+        );
+        assert_eq!(
+            monitor.get_input_source().unwrap_err().os_code(),
+            Some(transmit_error)
+        );
+    }
+
+    #[test]
+    fn link_status_is_unsupported_without_vcp_0xbe() {
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::LINK_CONTROL, 1, 1));
+        assert_eq!(
+            monitor.get_link_status().unwrap(),
+            decode::LinkStatus::ShutdownEnabled
+        );
+
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            monitor.get_link_status(),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+    }
+
+    #[test]
+    fn auto_brightness_reports_whether_the_monitor_applied_it() {
+        let mock = MockBackend::new().with_feature(codes::AMBIENT_LIGHT_SENSOR, 0x01, 0x02);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        assert!(monitor.set_auto_brightness(true).unwrap());
+        assert_eq!(mock.value(codes::AMBIENT_LIGHT_SENSOR), Some(0x02));
+        assert!(monitor.set_auto_brightness(false).unwrap());
+        assert_eq!(mock.value(codes::AMBIENT_LIGHT_SENSOR), Some(0x01));
+
+        let ignoring = MockBackend::new()
+            .with_feature(codes::AMBIENT_LIGHT_SENSOR, 0x01, 0x02)
+            .ignoring_writes();
+        let monitor = VcpMonitor::with_backend(ignoring.clone());
+        assert!(!monitor.set_auto_brightness(true).unwrap());
+        assert_eq!(ignoring.writes(), vec![(codes::AMBIENT_LIGHT_SENSOR, 0x02)]);
+    }
+
+    #[test]
+    fn auto_brightness_without_a_sensor_fails() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(monitor.set_auto_brightness(true).is_err());
+    }
+
+    #[test]
+    fn table_fragments_split_at_fragment_size() {
+        let data = [0xAB; 70];
+        let fragments: Vec<(u16, usize)> = table_fragments(10, &data)
+            .map(|(offset, fragment)| (offset, fragment.len()))
+            .collect();
+        assert_eq!(fragments, vec![(10, 32), (42, 32), (74, 6)]);
+    }
+
+    #[test]
+    fn table_read_joins_fragments_from_offset() {
+        let data: Vec<u8> = (0..100).collect();
+        let monitor = VcpMonitor::with_backend(MockBackend::new().with_table(0x73, &data));
+
+        assert_eq!(monitor.table_read(0x73, 0, 100).unwrap(), data);
+        assert_eq!(monitor.table_read(0x73, 5, 40).unwrap(), data[5..45]);
+    }
+
+    #[test]
+    fn table_read_stops_at_short_fragment() {
+        let data: Vec<u8> = (0..40).collect();
+        let monitor = VcpMonitor::with_backend(MockBackend::new().with_table(0x73, &data));
+
+        assert_eq!(monitor.table_read(0x73, 0, 200).unwrap(), data);
+        assert!(monitor.table_read(0x73, 64, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn table_write_sends_fragments_at_their_offsets() {
+        let mock = MockBackend::new().with_table(codes::ASSET_TAG, &[]);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        let data: Vec<u8> = (0..70).collect();
+
+        monitor.table_write(codes::ASSET_TAG, 4, &data).unwrap();
+
+        assert_eq!(
+            mock.table_writes(),
+            vec![
+                (codes::ASSET_TAG, 4, 32),
+                (codes::ASSET_TAG, 36, 32),
+                (codes::ASSET_TAG, 68, 6)
+            ]
+        );
+        assert_eq!(mock.table(codes::ASSET_TAG).unwrap()[4..], data[..]);
+    }
+
+    #[test]
+    fn table_transfer_past_16_bit_offsets_is_rejected() {
+        let mock = MockBackend::new().with_table(0x73, &[]);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.table_write(0x73, u16::MAX, &[1, 2]),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            monitor.table_read(0x73, u16::MAX - 1, 3),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(mock.table_writes().is_empty());
+        assert!(monitor.table_write(0x73, u16::MAX, &[1]).is_ok());
+    }
+
+    #[test]
+    fn asset_tag_is_written_as_table_data() {
+        let mock = MockBackend::new().with_table(codes::ASSET_TAG, &[]);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        monitor.set_asset_tag("IT-004213").unwrap();
+        assert_eq!(mock.table(codes::ASSET_TAG).unwrap(), b"IT-004213");
+    }
+
+    #[test]
+    fn table_text_must_be_printable_ascii_within_the_limit() {
+        assert_eq!(check_table_text("tag", "Room 3", 10).unwrap(), b"Room 3");
+        assert!(check_table_text("tag", "tab\there", 10).is_err());
+        assert!(check_table_text("tag", "café", 10).is_err());
+        assert!(check_table_text("tag", "12345678901", 10).is_err());
+    }
+
+    #[test]
+    fn display_descriptor_is_checked_against_reported_length() {
+        let mock = MockBackend::new()
+            .with_feature(codes::DISPLAY_DESCRIPTOR_LENGTH, 8, 255)
+            .with_table(codes::DISPLAY_DESCRIPTOR, &[]);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.set_display_descriptor("Room 3.14 left"),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(mock.table_writes().is_empty());
+
+        monitor.set_display_descriptor("Room 3").unwrap();
+        assert_eq!(mock.table(codes::DISPLAY_DESCRIPTOR).unwrap(), b"Room 3");
+    }
+
+    #[test]
+    fn table_text_is_validated_before_table_writes_fail_as_unsupported() {
+        let mock = MockBackend::new()
+            .with_feature(codes::DISPLAY_DESCRIPTOR_LENGTH, 8, 255)
+            .without_table_transfers();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.set_asset_tag("tab\there"),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            monitor.set_asset_tag("IT-004213"),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xD2")
+        ));
+        assert!(matches!(
+            monitor.set_display_descriptor("Room 3.14 left"),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            monitor.set_display_descriptor("Room 3"),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xC3")
+        ));
+        assert!(mock.table_writes().is_empty());
+    }
+
+    #[test]
+    fn scan_times_every_probe_and_ranks_the_slow_ones() {
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 40, 100)
+                .with_feature(codes::CONTRAST, 50, 100)
+                .slow_to_read(codes::CONTRAST, Duration::from_millis(40))
+                .slow_to_read(0x20, Duration::from_millis(20)),
+        );
+
+        let report = monitor.scan_report();
+        assert_eq!(report.probes.len(), SCAN_CODE_COUNT);
+        assert!(
+            report
+                .probes
+                .iter()
+                .enumerate()
+                .all(|(i, p)| p.code as usize == i)
+        );
+        assert!(report.probes[codes::BRIGHTNESS as usize].supported);
+        assert!(!report.probes[0x20].supported);
+
+        let slowest: Vec<u8> = report.slowest_probes(2).iter().map(|p| p.code).collect();
+        assert_eq!(slowest, vec![codes::CONTRAST, 0x20]);
+        assert!(report.probes[codes::CONTRAST as usize].elapsed >= Duration::from_millis(40));
+        assert!(report.probe_time() >= Duration::from_millis(60));
+    }
+
+    #[test]
+    fn probe_timing_json_is_in_milliseconds() {
+        let probe = ProbeTiming {
+            code: codes::BRIGHTNESS,
+            supported: true,
+            elapsed: Duration::from_micros(12_500),
+        };
+
+        let json = serde_json::to_value(probe).unwrap();
+        assert_eq!(json["elapsed_ms"], 12.5);
+        let parsed: ProbeTiming = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.elapsed, probe.elapsed);
+    }
+
+    #[test]
+    fn output_select_reads_and_writes_input_values() {
+        let mock = MockBackend::new().with_feature(codes::OUTPUT_SELECT, 0x0F, 0x12);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert_eq!(
+            monitor.get_output_select().unwrap(),
+            decode::InputSource::DisplayPort1
+        );
+        monitor
+            .set_output_select(decode::InputSource::Hdmi1)
+            .unwrap();
+        assert_eq!(mock.writes(), vec![(codes::OUTPUT_SELECT, 0x11)]);
+        assert_eq!(
+            monitor.get_output_select().unwrap(),
+            decode::InputSource::Hdmi1
+        );
+    }
+
+    #[test]
+    fn output_select_is_cleanly_unsupported() {
+        let mock = MockBackend::new();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.get_output_select(),
+            Err(MonitorError::VcpNotSupported)
+        ));
+        assert!(matches!(
+            monitor.set_output_select(decode::InputSource::Hdmi1),
+            Err(MonitorError::VcpNotSupported)
+        ));
+        // Nothing is sent to a display without the code
+        assert!(mock.writes().is_empty());
+    }
+
+    #[test]
+    fn luminance_contrast_reset_writes_code_05() {
+        let mock = MockBackend::new().with_action(codes::RESTORE_LUMINANCE_CONTRAST);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        monitor.reset_luminance_contrast().unwrap();
+        assert_eq!(mock.writes(), vec![(0x05, 1)]);
+    }
+
+    #[test]
+    fn subpixel_layout_reads_code_b2() {
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::SUBPIXEL_LAYOUT, 3, 8));
+        assert_eq!(
+            monitor.get_subpixel_layout().unwrap(),
+            decode::SubPixelLayout::BgrVertical
+        );
+
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            monitor.get_subpixel_layout(),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xB2")
+        ));
+    }
+
+    #[test]
+    fn capabilities_length_guards() {
+        assert!(matches!(
+            check_capabilities_length(0),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+        assert!(matches!(
+            check_capabilities_length(MAX_CAPABILITIES_LEN + 1),
+            Err(MonitorError::CapabilitiesTooLarge(len)) if len == MAX_CAPABILITIES_LEN + 1
+        ));
+        assert!(matches!(
+            check_capabilities_length(u32::MAX),
+            Err(MonitorError::CapabilitiesTooLarge(u32::MAX))
+        ));
+        assert_eq!(check_capabilities_length(412).unwrap(), 412);
+        assert_eq!(
+            check_capabilities_length(MAX_CAPABILITIES_LEN).unwrap(),
+            MAX_CAPABILITIES_LEN as usize
+        );
+    }
+
+    #[test]
+    fn performance_preservation_round_trips_through_code_54() {
+        let mock = MockBackend::new().with_feature(codes::PERFORMANCE_PRESERVATION, 0, 0xFFFF);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        let mut features = monitor.get_performance_preservation().unwrap();
+        assert!(features.enabled().is_empty());
+        features.set(4, true).unwrap();
+        monitor.set_performance_preservation(features).unwrap();
+
+        assert_eq!(mock.writes(), vec![(codes::PERFORMANCE_PRESERVATION, 0x10)]);
+        assert_eq!(
+            monitor.get_performance_preservation().unwrap().enabled(),
+            [4]
+        );
+    }
+
+    #[test]
+    fn six_axis_read_leaves_unanswered_axes_empty() {
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(0x59, 50, 100) // red
+                .with_feature(0x5B, 60, 100) // green
+                .with_feature(0x5D, 70, 100), // blue
+        );
+
+        let values = monitor.get_six_axis_saturation().unwrap();
+        assert_eq!(
+            values,
+            SixAxisValues {
+                red: Some(50),
+                green: Some(60),
+                blue: Some(70),
+                ..SixAxisValues::default()
+            }
+        );
+        assert!(matches!(
+            monitor.get_six_axis_hue(),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0x9B-0xA0")
+        ));
+    }
+
+    #[test]
+    fn six_axis_write_clamps_and_reports_each_axis() {
+        let mock = MockBackend::new()
+            .with_feature(0x9B, 50, 100) // red
+            .with_feature(0x9C, 50, 64); // yellow
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        let mut values = SixAxisValues::default();
+        values.set(ColorAxis::Red, Some(80));
+        values.set(ColorAxis::Yellow, Some(90));
+        values.set(ColorAxis::Cyan, Some(10));
+        let writes = monitor.set_six_axis_hue(&values);
+
+        let outcome: Vec<(ColorAxis, u32, Option<u32>, bool)> = writes
+            .iter()
+            .map(|w| (w.axis, w.requested, w.written, w.error.is_some()))
+            .collect();
+        assert_eq!(
+            outcome,
+            [
+                (ColorAxis::Red, 80, Some(80), false),
+                (ColorAxis::Yellow, 90, Some(64), false),
+                (ColorAxis::Cyan, 10, None, true),
+            ]
+        );
+        // Axes not set in `values` are left alone
+        assert_eq!(mock.writes(), vec![(0x9B, 80), (0x9C, 64)]);
+    }
+
+    #[test]
+    fn scan_state_saves_and_loads() {
+        let dir = std::env::temp_dir().join(format!("monitorconfig-scan-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scan.json");
+        let _ = fs::remove_file(&path);
+        assert!(ScanState::load(&path).unwrap().is_none());
+
+        let mut state = ScanState::new("edid:DEL4211:7XKJ3H3");
+        state.next_code = 0x11;
+        state
+            .features
+            .push(scanned(codes::BRIGHTNESS, VcpCodeType::SetParameter));
+        state.save(&path).unwrap();
+
+        let loaded = ScanState::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.monitor, "edid:DEL4211:7XKJ3H3");
+        assert_eq!(loaded.next_code, 0x11);
+        assert!(!loaded.is_complete());
+        assert_eq!(load_scan(&path).unwrap().len(), 1);
+
+        fs::write(&path, "{").unwrap();
+        assert!(matches!(
+            ScanState::load(&path),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn interrupted_scan_resumes_where_it_stopped() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 40, 100)
+            .with_feature(codes::INPUT_SOURCE, 0x0F, 0x1B);
+        let monitor = VcpMonitor::with_backend(mock);
+
+        // Interrupted after probing 0x00-0x1F
+        let mut state = ScanState::new("device:\\\\.\\DISPLAY1");
+        let interrupted = monitor.scan_report_resumable(&mut state, |state| {
+            if state.next_code == 0x20 {
+                return Err(MonitorError::Timeout("interrupted".to_string()));
+            }
+            Ok(())
+        });
+        assert!(matches!(interrupted, Err(MonitorError::Timeout(_))));
+        assert_eq!(state.next_code, 0x20);
+        assert_eq!(state.features.len(), 1);
+
+        let mut checkpoints = 0;
+        let report = monitor
+            .scan_report_resumable(&mut state, |_| {
+                checkpoints += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(checkpoints, SCAN_CODE_COUNT - 0x20);
+        assert!(state.is_complete());
+        assert_eq!(report.probes.len(), SCAN_CODE_COUNT);
+        let codes: Vec<u8> = report.features.iter().map(|f| f.vcp_code).collect();
+        assert_eq!(codes, [codes::BRIGHTNESS, codes::INPUT_SOURCE]);
+
+        // A complete state has nothing left to probe
+        let report = monitor
+            .scan_report_resumable(&mut state, |_| unreachable!())
+            .unwrap();
+        assert_eq!(report.features.len(), 2);
+    }
+
+    #[test]
+    fn fade_steps_spread_evenly_and_end_on_target() {
+        assert_eq!(fade_steps(0, 100, 4), [25, 50, 75, 100]);
+        assert_eq!(fade_steps(80, 20, 3), [60, 40, 20]);
+        // More steps than values: each value is written once
+        assert_eq!(fade_steps(10, 13, 10), [11, 12, 13]);
+        assert_eq!(fade_steps(10, 60, 0), [60]);
+        assert!(fade_steps(50, 50, 5).is_empty());
+    }
+
+    #[test]
+    fn fade_core_drives_any_continuous_code() {
+        for code in [codes::BRIGHTNESS, codes::AUDIO_VOLUME] {
+            let mock = MockBackend::new().with_feature(code, 20, 100);
+            let monitor = VcpMonitor::with_backend(mock.clone());
+
+            let mut progress = Vec::new();
+            monitor
+                .fade_vcp_with_progress(code, 80, Duration::ZERO, 3, |done, total| {
+                    progress.push((done, total))
+                })
+                .unwrap();
+            assert_eq!(mock.writes(), vec![(code, 40), (code, 60), (code, 80)]);
+            assert_eq!(progress, [(1, 3), (2, 3), (3, 3)]);
+        }
+    }
+
+    #[test]
+    fn fade_checks_the_target_before_writing() {
+        let mock = MockBackend::new().with_feature(codes::AUDIO_VOLUME, 20, 50);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        assert!(matches!(
+            monitor.fade_volume(80, Duration::ZERO, 3),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(mock.writes().is_empty());
+
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            monitor.fade_volume(30, Duration::ZERO, 3),
+            Err(MonitorError::UnsupportedOperation(message)) if message == VOLUME_UNSUPPORTED
+        ));
+    }
+
+    #[test]
+    fn display_descriptor_enable_reads_only_bit_0() {
+        let mock = MockBackend::new().with_feature(codes::DISPLAY_DESCRIPTOR_ENABLE, 0x02, 1);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(!monitor.get_display_descriptor_enabled().unwrap());
+        monitor.set_display_descriptor_enabled(true).unwrap();
+        assert!(monitor.get_display_descriptor_enabled().unwrap());
+        assert_eq!(mock.writes(), vec![(codes::DISPLAY_DESCRIPTOR_ENABLE, 1)]);
+    }
+
+    #[test]
+    fn display_descriptor_enable_is_not_written_when_unsupported() {
+        let mock = MockBackend::new();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.set_display_descriptor_enabled(true),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+        assert!(mock.writes().is_empty());
+    }
+
+    #[test]
+    fn display_descriptor_read_drops_padding() {
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::DISPLAY_DESCRIPTOR_LENGTH, 12, 255)
+                .with_table(codes::DISPLAY_DESCRIPTOR, b"Room 3  \0\0\0\0extra"),
+        );
+        assert_eq!(monitor.get_display_descriptor().unwrap(), "Room 3");
+    }
+
+    #[test]
+    fn display_descriptor_needs_table_transfers_but_its_flag_does_not() {
+        let mock = MockBackend::new()
+            .with_feature(codes::DISPLAY_DESCRIPTOR_LENGTH, 8, 255)
+            .with_feature(codes::DISPLAY_DESCRIPTOR_ENABLE, 0, 1)
+            .without_table_transfers();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.get_display_descriptor(),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xC3")
+        ));
+        assert!(matches!(
+            monitor.set_display_descriptor("Room 3.14 left"),
+            Err(MonitorError::InvalidValue(message)) if message.contains('8')
+        ));
+        assert!(matches!(
+            monitor.set_display_descriptor("Room 3"),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+
+        monitor.set_display_descriptor_enabled(true).unwrap();
+        assert!(monitor.get_display_descriptor_enabled().unwrap());
+        assert_eq!(mock.writes(), vec![(codes::DISPLAY_DESCRIPTOR_ENABLE, 1)]);
+    }
+
+    #[test]
+    fn max_for_code_reads_once_and_caches() {
+        let mock = MockBackend::new().with_feature(codes::BRIGHTNESS, 40, 80);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(monitor.cache().maximum(codes::BRIGHTNESS).is_none());
+        assert_eq!(monitor.max_for_code(codes::BRIGHTNESS).unwrap(), 80);
+        assert_eq!(monitor.max_for_code(codes::BRIGHTNESS).unwrap(), 80);
+        assert_eq!(mock.reads(), [codes::BRIGHTNESS]);
+        assert_eq!(monitor.cache().maximum(codes::BRIGHTNESS), Some(80));
+
+        // Failed reads aren't cached
+        assert!(monitor.max_for_code(codes::CONTRAST).is_err());
+        assert!(monitor.max_for_code(codes::CONTRAST).is_err());
+        assert_eq!(mock.reads().len(), 3);
+    }
+
+    #[test]
+    fn max_overrides_need_no_read() {
+        let mock = MockBackend::new().with_feature(codes::BRIGHTNESS, 40, 255);
+        let monitor = VcpMonitor::with_backend(mock.clone())
+            .with_max_overrides(BTreeMap::from([(codes::BRIGHTNESS, 100)]));
+
+        assert_eq!(monitor.max_for_code(codes::BRIGHTNESS).unwrap(), 100);
+        assert!(mock.reads().is_empty());
+        assert_eq!(
+            monitor
+                .get_vcp_feature(codes::BRIGHTNESS)
+                .unwrap()
+                .maximum_value,
+            100
+        );
+    }
+
+    #[test]
+    fn monitor_status_is_cleanly_unsupported_on_normal_monitors() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            monitor.get_monitor_status(),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xB7")
+        ));
+
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::MONITOR_STATUS, 1, 7));
+        assert!(monitor.get_monitor_status().unwrap().dpvl_mode);
+    }
+
+    #[test]
+    fn scan_progress_is_monotonic_and_reaches_the_total() {
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::BRIGHTNESS, 50, 100));
+        let mut progress = Vec::new();
+        monitor.scan_vcp_features_with_progress(|done, total| progress.push((done, total)));
+
+        assert_eq!(progress.len(), SCAN_CODE_COUNT);
+        assert!(progress.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(progress.iter().all(|&(_, total)| total == SCAN_CODE_COUNT));
+        assert_eq!(progress.last(), Some(&(SCAN_CODE_COUNT, SCAN_CODE_COUNT)));
+    }
+
+    #[test]
+    fn category_filter_returns_only_that_categorys_codes() {
+        let audio: Vec<u8> = known_codes_in(VcpCategory::Audio)
+            .map(|info| info.code)
+            .collect();
+        assert_eq!(
+            audio,
+            [
+                codes::AUDIO_VOLUME,
+                codes::SPEAKER_SELECT,
+                0x64,
+                0x65,
+                codes::AUDIO_MUTE,
+                0x8F,
+                0x91,
+                0x93,
+                0x94
+            ]
+        );
+        let power: Vec<u8> = known_codes_in(VcpCategory::Power)
+            .map(|info| info.code)
+            .collect();
+        assert_eq!(power, [0x54, codes::POWER_MODE, 0xD7]);
+
+        // Every known code is in exactly one category
+        let total: usize = VcpCategory::ALL
+            .iter()
+            .map(|&category| known_codes_in(category).count())
+            .sum();
+        assert_eq!(total, KNOWN_VCP_CODES.len());
+    }
+
+    #[test]
+    fn categories_parse_case_insensitively_with_a_status_alias() {
+        for category in VcpCategory::ALL {
+            assert_eq!(category.name().parse::<VcpCategory>().unwrap(), category);
+        }
+        assert_eq!("Audio".parse::<VcpCategory>().unwrap(), VcpCategory::Audio);
+        assert_eq!("STATUS".parse::<VcpCategory>().unwrap(), VcpCategory::Info);
+        assert!(matches!(
+            "lighting".parse::<VcpCategory>(),
+            Err(MonitorError::InvalidValue(message)) if message.contains("geometry")
+        ));
+    }
+
+    #[test]
+    fn until_stable_rewrites_until_the_value_reads_back() {
+        // Read back straight away, the first write still shows the old value; the second sticks
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 20, 100)
+            .settling_after(Duration::from_secs(60));
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        assert_eq!(
+            monitor
+                .set_vcp_until_stable(codes::BRIGHTNESS, 70, 3, Duration::ZERO, 0)
+                .unwrap(),
+            70
+        );
+        assert_eq!(mock.writes().len(), 2);
+
+        // Waiting out the settle time, one write is enough
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 20, 100)
+            .settling_after(Duration::from_millis(30));
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        assert_eq!(
+            monitor
+                .set_vcp_until_stable(codes::BRIGHTNESS, 70, 3, Duration::from_millis(40), 0)
+                .unwrap(),
+            70
+        );
+        assert_eq!(mock.writes(), vec![(codes::BRIGHTNESS, 70)]);
+
+        // A monitor that never applies it gets every attempt, and its value is reported
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 20, 100)
+            .ignoring_writes();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        assert_eq!(
+            monitor
+                .set_vcp_until_stable(codes::BRIGHTNESS, 70, 3, Duration::ZERO, 0)
+                .unwrap(),
+            20
+        );
+        assert_eq!(mock.writes().len(), 3);
+    }
+
+    #[test]
+    fn until_stable_accepts_values_within_tolerance_and_writes_at_least_once() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 48, 100)
+            .ignoring_writes();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert_eq!(
+            monitor
+                .set_vcp_until_stable(codes::BRIGHTNESS, 50, 0, Duration::ZERO, 2)
+                .unwrap(),
+            48
+        );
+        assert_eq!(mock.writes().len(), 1);
+        assert!(
+            VcpMonitor::with_backend(MockBackend::new())
+                .set_vcp_until_stable(codes::BRIGHTNESS, 50, 3, Duration::ZERO, 0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn backlight_white_writes_are_checked_against_its_maximum() {
+        let mock = MockBackend::new().with_feature(codes::BACKLIGHT_WHITE, 30, 80);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert_eq!(monitor.get_backlight_white().unwrap().current_value, 30);
+        monitor.set_backlight_white(80).unwrap();
+        assert!(matches!(
+            monitor.set_backlight_white(81),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert_eq!(mock.writes(), vec![(codes::BACKLIGHT_WHITE, 80)]);
+    }
+
+    #[test]
+    fn scan_mode_reads_writes_and_reports_unsupported() {
+        let mock = MockBackend::new().with_feature(codes::SCAN_MODE, 0x00, 0x03);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert_eq!(monitor.get_scan_mode().unwrap(), decode::ScanMode::Normal);
+        monitor.set_scan_mode(decode::ScanMode::Underscan).unwrap();
+        assert_eq!(
+            monitor.get_scan_mode().unwrap(),
+            decode::ScanMode::Underscan
+        );
+        assert_eq!(mock.writes(), vec![(codes::SCAN_MODE, 0x01)]);
+
+        assert!(matches!(
+            VcpMonitor::with_backend(MockBackend::new()).get_scan_mode(),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xDA")
+        ));
+    }
+
+    #[test]
+    fn aux_display_text_is_padded_to_the_display() {
+        let size = decode::AuxDisplaySize::from_value(0b01_001000);
+        assert_eq!(aux_display_data("OK", size).unwrap(), b"OK      ");
+        assert!(aux_display_data("far too long", size).is_err());
+    }
+
+    #[test]
+    fn aux_display_text_is_written_as_table_data() {
+        let mock = MockBackend::new()
+            .with_feature(codes::AUX_DISPLAY_SIZE, 0b10_000100, 0xFF)
+            .with_table(codes::AUX_DISPLAY_DATA, &[]);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        let size = monitor.set_aux_display_text("BUILD").unwrap();
+        assert_eq!(size.capacity(), 8);
+        assert_eq!(mock.table(codes::AUX_DISPLAY_DATA).unwrap(), b"BUILD   ");
+    }
+
+    #[test]
+    fn aux_display_text_is_checked_against_0xce_before_the_write_fails_as_unsupported() {
+        let mock = MockBackend::new()
+            .with_feature(codes::AUX_DISPLAY_SIZE, 0b01_001000, 0xFF)
+            .without_table_transfers();
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.set_aux_display_text("far too long"),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            monitor.set_aux_display_text("OK"),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0xCF")
+        ));
+        assert!(mock.table_writes().is_empty());
+    }
+
+    #[test]
+    fn aux_display_size_without_display_is_unsupported() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            monitor.get_aux_display_size(),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+    }
+
+    #[test]
+    fn set_and_verify_waits_for_the_settle_delay_before_reading() {
+        let settling = || {
+            MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 40, 100)
+                .settling_after(Duration::from_millis(50))
+        };
+
+        let impatient = VcpMonitor::with_backend(settling());
+        assert!(!impatient.set_and_verify(codes::BRIGHTNESS, 75).unwrap());
+
+        let patient =
+            VcpMonitor::with_backend(settling()).with_settle_delay(Duration::from_millis(80));
+        let start = Instant::now();
+        assert!(patient.set_and_verify(codes::BRIGHTNESS, 75).unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+
+    #[test]
+    fn clamp_reported_level_keeps_the_reported_value() {
+        assert_eq!(clamp_reported_level(50, 100), (50, None));
+        assert_eq!(clamp_reported_level(100, 100), (100, None));
+        assert_eq!(clamp_reported_level(120, 100), (100, Some(120)));
+    }
+
+    #[test]
+    fn level_reads_above_the_maximum_are_clamped() {
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 120, 100)
+                .with_feature(codes::CONTRAST, 80, 100),
+        );
+
+        let brightness = monitor.get_vcp_feature(codes::BRIGHTNESS).unwrap();
+        assert_eq!(brightness.current_value, 100);
+        assert_eq!(brightness.reported_value, Some(120));
+
+        let contrast = monitor.get_vcp_feature(codes::CONTRAST).unwrap();
+        assert_eq!(contrast.current_value, 80);
+        assert_eq!(contrast.reported_value, None);
+    }
+
+    #[test]
+    fn non_level_reads_above_the_maximum_are_kept() {
+        // Input source values are identifiers, not bounded by the maximum
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::INPUT_SOURCE, 0x11, 3));
+        let input = monitor.get_vcp_feature(codes::INPUT_SOURCE).unwrap();
+        assert_eq!(input.current_value, 0x11);
+        assert_eq!(input.reported_value, None);
+    }
+
+    #[test]
+    fn reported_value_is_only_serialized_when_clamped() {
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::BRIGHTNESS, 120, 100));
+        let clamped =
+            serde_json::to_value(monitor.get_vcp_feature(codes::BRIGHTNESS).unwrap()).unwrap();
+        assert_eq!(clamped["reported_value"], 120);
+
+        let monitor =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::BRIGHTNESS, 50, 100));
+        let plain =
+            serde_json::to_value(monitor.get_vcp_feature(codes::BRIGHTNESS).unwrap()).unwrap();
+        assert!(plain.get("reported_value").is_none());
+    }
+
+    #[test]
+    fn saved_scans_load_from_every_scan_vcp_output() {
+        let dir =
+            std::env::temp_dir().join(format!("monitorconfig-scan-diff-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scan.json");
+        let report = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 40, 100)
+                .with_feature(codes::INPUT_SOURCE, 0x0F, 0x1B),
+        )
+        .scan_report();
+        let values = |responses: Vec<VcpFeatureResponse>| -> Vec<(u8, u32)> {
+            responses
+                .iter()
+                .map(|response| (response.vcp_code, response.current_value))
+                .collect()
+        };
+        let expected = [(codes::BRIGHTNESS, 40), (codes::INPUT_SOURCE, 0x0F)];
+
+        // scan-vcp --json
+        fs::write(&path, serde_json::to_string(&report.entries()).unwrap()).unwrap();
+        assert_eq!(values(load_scan(&path).unwrap()), expected);
+
+        // scan-vcp --json --timings
+        let timed = serde_json::json!({ "entries": report.entries(), "probes": report.probes });
+        fs::write(&path, timed.to_string()).unwrap();
+        assert_eq!(values(load_scan(&path).unwrap()), expected);
+
+        fs::write(&path, r#"{ "brightness": 40 }"#).unwrap();
+        assert!(matches!(
+            load_scan(&path),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn user_color_temperature_reads_both_codes_and_writes_the_multiplier() {
+        let mock = MockBackend::new()
+            .with_feature(codes::USER_COLOR_TEMPERATURE_INCREMENT, 100, 100)
+            .with_feature(codes::USER_COLOR_TEMPERATURE, 35, 70);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert_eq!(monitor.get_user_color_temperature().unwrap().kelvin(), 6500);
+        let set = monitor.set_user_color_temperature(5030).unwrap();
+        assert_eq!(set.multiplier, 20);
+        assert_eq!(set.to_string(), "5000 K");
+        assert!(monitor.set_user_color_temperature(10_500).is_err());
+        assert_eq!(mock.writes(), vec![(codes::USER_COLOR_TEMPERATURE, 20)]);
+
+        // Both codes are needed
+        let monitor = VcpMonitor::with_backend(MockBackend::new().with_feature(
+            codes::USER_COLOR_TEMPERATURE,
+            35,
+            70,
+        ));
+        assert!(matches!(
+            monitor.get_user_color_temperature(),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+    }
+
+    #[test]
+    fn speaker_select_reads_writes_and_reports_unsupported() {
+        let mock = MockBackend::new().with_feature(codes::SPEAKER_SELECT, 0x00, 0x03);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert_eq!(
+            monitor.get_speaker_select().unwrap(),
+            decode::SpeakerSelect::Front
+        );
+        monitor
+            .set_speaker_select(decode::SpeakerSelect::Rear)
+            .unwrap();
+        assert_eq!(
+            monitor.get_speaker_select().unwrap(),
+            decode::SpeakerSelect::Rear
+        );
+        assert_eq!(mock.writes(), vec![(codes::SPEAKER_SELECT, 0x02)]);
+
+        assert!(matches!(
+            VcpMonitor::with_backend(MockBackend::new()).get_speaker_select(),
+            Err(MonitorError::UnsupportedOperation(message)) if message.contains("0x63")
+        ));
+    }
+
+    #[test]
+    fn wait_for_input_polls_until_the_switch_lands() {
+        let mock = MockBackend::new()
+            .with_feature(codes::INPUT_SOURCE, 0x0F, 0xFF)
+            .settling_after(Duration::from_millis(40));
+        let monitor = VcpMonitor::with_backend(mock.clone());
+        monitor.set_vcp_feature(codes::INPUT_SOURCE, 0x11).unwrap();
+
+        let reached = monitor
+            .wait_for_input(
+                decode::InputSource::Hdmi1,
+                Duration::from_secs(1),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+        assert_eq!(reached, decode::InputSource::Hdmi1);
+        // Polled while the old input was still reported
+        assert!(mock.reads().len() > 1);
+    }
+
+    #[test]
+    fn wait_for_input_times_out_with_the_last_reading() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new().with_feature(
+            codes::INPUT_SOURCE,
+            0x0F,
+            0xFF,
+        ));
+        match monitor.wait_for_input(decode::InputSource::Hdmi1, Duration::ZERO, Duration::ZERO) {
+            Err(MonitorError::Timeout(message)) => assert!(message.contains("last reading")),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+
+        let transmit_error =
+            windows_sys::Win32::Foundation::ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32;
+        let failing = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::INPUT_SOURCE, 0x0F, 0xFF)
+                .failing_with(codes::INPUT_SOURCE, transmit_error),
+        );
+        match failing.wait_for_input(decode::InputSource::Hdmi1, Duration::ZERO, Duration::ZERO) {
+            Err(MonitorError::Timeout(message)) => assert!(message.contains("no reading")),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn get_vcp_code_info_finds_every_known_code() {
+        for code in 0..=u8::MAX {
+            let expected = KNOWN_VCP_CODES.iter().find(|info| info.code == code);
+            assert_eq!(
+                get_vcp_code_info(code).map(|info| info.name),
+                expected.map(|info| info.name),
+                "code 0x{:02X}",
+                code
+            );
+        }
+        assert_eq!(
+            get_vcp_code_info(codes::BRIGHTNESS).unwrap().code,
+            codes::BRIGHTNESS
+        );
+        assert!(get_vcp_code_info(0x07).is_none());
+    }
+
+    #[test]
+    fn strict_sort_rejects_unordered_and_repeated_codes() {
+        let entry = |code| VcpFeatureInfo {
+            code,
+            name: "test",
+            description: "",
+            category: VcpCategory::Info,
+        };
+        assert!(is_strictly_sorted(&[]));
+        assert!(is_strictly_sorted(&[entry(0x10), entry(0x12)]));
+        assert!(!is_strictly_sorted(&[entry(0x12), entry(0x10)]));
+        assert!(!is_strictly_sorted(&[entry(0x10), entry(0x10)]));
+        assert!(is_strictly_sorted(KNOWN_VCP_CODES));
+    }
+
+    #[test]
+    fn osd_is_active_only_when_reported_enabled() {
+        let open =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::OSD, 0x02, 0xFF));
+        assert_eq!(open.get_osd_state().unwrap(), decode::OsdState::Enabled);
+        assert!(open.is_osd_active().unwrap());
+
+        let closed =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::OSD, 0x01, 0xFF));
+        assert!(!closed.is_osd_active().unwrap());
+
+        let unknown =
+            VcpMonitor::with_backend(MockBackend::new().with_feature(codes::OSD, 0xFF, 0xFF));
+        assert!(!unknown.is_osd_active().unwrap());
+
+        let silent = VcpMonitor::with_backend(MockBackend::new());
+        assert!(matches!(
+            silent.get_osd_state(),
+            Err(MonitorError::UnsupportedOperation(_))
+        ));
+    }
+
+    #[test]
+    fn reconciliation_splits_codes_in_order() {
+        let reconciliation = Reconciliation::new(&[0x60, 0x10, 0x12, 0x10], &[0x12, 0xD6, 0x10]);
+        assert_eq!(reconciliation.confirmed, vec![0x10, 0x12]);
+        assert_eq!(reconciliation.advertised_unresponsive, vec![0x60]);
+        assert_eq!(reconciliation.responsive_unadvertised, vec![0xD6]);
+        assert!(!reconciliation.is_consistent());
+        assert!(Reconciliation::new(&[0x10], &[0x10]).is_consistent());
+    }
+
+    #[test]
+    fn reconcile_reads_advertised_and_common_codes() {
+        let mock = MockBackend::new()
+            .with_feature(codes::BRIGHTNESS, 50, 100)
+            .with_feature(codes::CONTRAST, 50, 100)
+            .with_feature(0xE9, 1, 2)
+            .with_capabilities("(vcp(10 60 E9))");
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        let reconciliation = monitor.reconcile_capabilities().unwrap();
+        assert_eq!(reconciliation.confirmed, vec![codes::BRIGHTNESS, 0xE9]);
+        assert_eq!(
+            reconciliation.advertised_unresponsive,
+            vec![codes::INPUT_SOURCE]
+        );
+        assert_eq!(
+            reconciliation.responsive_unadvertised,
+            vec![codes::CONTRAST]
+        );
+
+        // Only the advertised and common codes are read, not the whole range
+        let mut read: Vec<u8> = mock.reads();
+        read.sort_unstable();
+        read.dedup();
+        let mut expected: Vec<u8> = COMMON_CODES.iter().copied().chain([0xE9]).collect();
+        expected.sort_unstable();
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn reconcile_stops_on_errors_other_than_unsupported() {
+        let transmit_error =
+            windows_sys::Win32::Foundation::ERROR_GRAPHICS_I2C_ERROR_TRANSMITTING_DATA as u32;
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::BRIGHTNESS, 50, 100)
+                .failing_with(codes::INPUT_SOURCE, transmit_error)
+                .with_capabilities("(vcp(10 60))"),
+        );
+        assert!(monitor.reconcile_capabilities().is_err());
+
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        assert!(monitor.reconcile_capabilities().is_err());
+    }
+
+    #[test]
+    fn rgb_gains_scale_each_channel_to_its_maximum() {
+        let monitor = VcpMonitor::with_backend(
+            MockBackend::new()
+                .with_feature(codes::RED_GAIN, 50, 100)
+                .with_feature(codes::GREEN_GAIN, 50, 255)
+                .with_feature(codes::BLUE_GAIN, 50, 200),
+        );
+        let triplet: decode::HexTriplet = "#FF8000".parse().unwrap();
+        assert_eq!(monitor.rgb_gains_for(triplet).unwrap(), [100, 128, 0]);
+    }
+
+    #[test]
+    fn rgb_gains_are_all_checked_before_any_is_written() {
+        let mock = MockBackend::new()
+            .with_feature(codes::RED_GAIN, 50, 100)
+            .with_feature(codes::GREEN_GAIN, 50, 100)
+            .with_feature(codes::BLUE_GAIN, 50, 100);
+        let monitor = VcpMonitor::with_backend(mock.clone());
+
+        assert!(matches!(
+            monitor.set_rgb_gains([90, 95, 101]),
+            Err(MonitorError::InvalidValue(_))
+        ));
+        assert!(mock.writes().is_empty());
+
+        monitor.set_rgb_gains([90, 95, 100]).unwrap();
+        assert_eq!(
+            mock.writes(),
+            vec![
+                (codes::RED_GAIN, 90),
+                (codes::GREEN_GAIN, 95),
+                (codes::BLUE_GAIN, 100)
+            ]
+        );
+    }
+
+    #[test]
+    fn wait_for_input_gives_up_when_inputs_are_unsupported() {
+        let monitor = VcpMonitor::with_backend(MockBackend::new());
+        let result = monitor.wait_for_input(
+            decode::InputSource::Hdmi1,
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        );
+        assert!(matches!(result, Err(MonitorError::UnsupportedOperation(_))));
+    }
 }